@@ -0,0 +1,17 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler Source_sampler;
+layout(set = 0, binding = 2) uniform Saturation_saturation {
+    float Saturation;
+};
+
+void main() {
+    vec4 source = texture(sampler2D(Source, Source_sampler), v_Uv);
+    float luminance = dot(source.rgb, vec3(0.2126, 0.7152, 0.0722));
+    o_Target = vec4(mix(vec3(luminance), source.rgb, Saturation), source.a);
+}
@@ -0,0 +1,21 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler Source_sampler;
+layout(set = 0, binding = 2) uniform Vignette_color {
+    vec4 Color;
+};
+layout(set = 0, binding = 3) uniform Vignette_intensity {
+    float Intensity;
+};
+
+void main() {
+    vec4 source = texture(sampler2D(Source, Source_sampler), v_Uv);
+    float distance = length(v_Uv - vec2(0.5)) * 1.4142;
+    float vignette = smoothstep(0.4, 1.0, distance) * Intensity;
+    o_Target = vec4(mix(source.rgb, Color.rgb, vignette), source.a);
+}
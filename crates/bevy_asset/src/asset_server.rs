@@ -1,17 +1,28 @@
 use crate::{
     path::{AssetPath, AssetPathId, SourcePathId},
+    processor::{meta_path, ErasedAssetProcessor, ProcessedAssets},
     Asset, AssetIo, AssetIoError, AssetLifecycle, AssetLifecycleChannel, AssetLifecycleEvent,
-    AssetLoader, Assets, Handle, HandleId, HandleUntyped, LabelId, LoadContext, LoadState,
-    RefChange, RefChangeChannel, SourceInfo, SourceMeta,
+    AssetLoader, Assets, ErasedAssetSaver, Handle, HandleId, HandleUntyped, LabelId, LoadContext,
+    LoadState, OverlayAssetIo, QueuedSave, RefChange, RefChangeChannel, SavedFile, SourceInfo,
+    SourceMeta,
 };
 use anyhow::Result;
+use bevy_app::EventWriter;
 use bevy_ecs::system::Res;
 use bevy_log::warn;
 use bevy_tasks::TaskPool;
-use bevy_utils::{HashMap, Uuid};
-use crossbeam_channel::TryRecvError;
+use bevy_utils::{HashMap, HashSet, Uuid};
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
 use parking_lot::RwLock;
-use std::{collections::hash_map::Entry, path::Path, sync::Arc};
+use serde::Serialize;
+use std::{
+    collections::hash_map::Entry,
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
 use thiserror::Error;
 
 /// Errors that occur while loading assets with an AssetServer
@@ -27,6 +38,16 @@ pub enum AssetServerError {
     AssetLoaderError(anyhow::Error),
     #[error("encountered an error while reading an asset: {0}")]
     AssetIoError(#[from] AssetIoError),
+    #[error("no `AssetIo` is mounted for the asset source: {0}")]
+    MissingAssetSource(String),
+    #[error("no `EmbeddedAssetIo` is mounted for the asset source: {0}")]
+    MissingEmbeddedAssetIo(String),
+    #[error("no `AssetProcessor` is registered with the name: {0}")]
+    MissingAssetProcessor(String),
+    #[error("encountered an error while processing an asset: {0}")]
+    AssetProcessorError(anyhow::Error),
+    #[error("no `AssetSaver` for this asset type found{}", format_missing_asset_ext(.extensions))]
+    MissingAssetSaver { extensions: Vec<String> },
 }
 
 fn format_missing_asset_ext(exts: &[String]) -> String {
@@ -41,6 +62,15 @@ fn format_missing_asset_ext(exts: &[String]) -> String {
     }
 }
 
+/// Sent when an asset fails to load. Assets that depend on it report [LoadState::Failed] from
+/// [AssetServer::get_load_state_with_dependencies].
+#[derive(Debug)]
+pub struct AssetLoadFailed {
+    pub id: HandleId,
+    pub path: AssetPath<'static>,
+    pub error: AssetServerError,
+}
+
 #[derive(Default)]
 pub(crate) struct AssetRefCounter {
     pub(crate) channel: Arc<RefChangeChannel>,
@@ -48,14 +78,30 @@ pub(crate) struct AssetRefCounter {
 }
 
 pub struct AssetServerInternal {
-    pub(crate) asset_io: Box<dyn AssetIo>,
+    /// The `AssetIo` of every mounted source, keyed by source name. `None` is the default source.
+    pub(crate) asset_io: RwLock<HashMap<Option<String>, Arc<dyn AssetIo>>>,
+    pub(crate) watching_for_changes: AtomicBool,
     pub(crate) asset_ref_counter: AssetRefCounter,
     pub(crate) asset_sources: Arc<RwLock<HashMap<SourcePathId, SourceInfo>>>,
     pub(crate) asset_lifecycles: Arc<RwLock<HashMap<Uuid, Box<dyn AssetLifecycle>>>>,
     loaders: RwLock<Vec<Arc<Box<dyn AssetLoader>>>>,
+    pub(crate) savers: RwLock<Vec<Arc<dyn ErasedAssetSaver>>>,
+    /// Assets to save, by type, see [AssetServer::save]
+    pub(crate) queued_saves: RwLock<HashMap<Uuid, Vec<QueuedSave>>>,
+    /// Asset sources that were overwritten by [AssetServer::save], by source and path
+    pub(crate) saved_files: RwLock<HashMap<(Option<String>, PathBuf), SavedFile>>,
+    pub(crate) processors: RwLock<HashMap<String, Arc<dyn ErasedAssetProcessor>>>,
+    pub(crate) processed_assets: RwLock<Option<Arc<ProcessedAssets>>>,
     extension_to_loader_index: RwLock<HashMap<String, usize>>,
     handle_to_path: Arc<RwLock<HashMap<HandleId, AssetPath<'static>>>>,
-    task_pool: TaskPool,
+    /// Loaded assets, by type, that haven't sent [AssetLoadedWithDependencies] yet because some
+    /// of their dependencies are still loading.
+    ///
+    /// [AssetLoadedWithDependencies]: crate::AssetLoadedWithDependencies
+    waiting_for_dependencies: RwLock<HashMap<Uuid, HashSet<AssetPathId>>>,
+    load_failure_sender: Sender<AssetLoadFailed>,
+    load_failure_receiver: Receiver<AssetLoadFailed>,
+    pub(crate) task_pool: TaskPool,
 }
 
 /// Loads assets from the filesystem on background threads
@@ -77,20 +123,81 @@ impl AssetServer {
     }
 
     pub fn with_boxed_io(asset_io: Box<dyn AssetIo>, task_pool: TaskPool) -> Self {
+        let mut sources = HashMap::default();
+        sources.insert(None, Arc::from(asset_io));
+        let (load_failure_sender, load_failure_receiver) = crossbeam_channel::unbounded();
         AssetServer {
             server: Arc::new(AssetServerInternal {
+                asset_io: RwLock::new(sources),
+                watching_for_changes: AtomicBool::new(false),
                 loaders: Default::default(),
+                savers: Default::default(),
+                queued_saves: Default::default(),
+                saved_files: Default::default(),
+                processors: Default::default(),
+                processed_assets: Default::default(),
                 extension_to_loader_index: Default::default(),
                 asset_sources: Default::default(),
                 asset_ref_counter: Default::default(),
                 handle_to_path: Default::default(),
                 asset_lifecycles: Default::default(),
+                waiting_for_dependencies: Default::default(),
+                load_failure_sender,
+                load_failure_receiver,
                 task_pool,
-                asset_io,
             }),
         }
     }
 
+    /// Mounts `asset_io` as the given source, so paths like `source://path/to/file.ext` are loaded
+    /// from it. `None` mounts it over the default source.
+    ///
+    /// If the source already exists, `asset_io` is layered on top of it in an [OverlayAssetIo]:
+    /// files it contains shadow the ones from earlier mounts.
+    pub fn mount<T: AssetIo>(&self, source: Option<&str>, asset_io: T) {
+        self.mount_boxed(source, Box::new(asset_io));
+    }
+
+    pub fn mount_boxed(&self, source: Option<&str>, asset_io: Box<dyn AssetIo>) {
+        let asset_io: Arc<dyn AssetIo> = Arc::from(asset_io);
+        if self.server.watching_for_changes.load(Ordering::Relaxed) {
+            if let Err(err) = asset_io.watch_for_changes() {
+                warn!(
+                    "failed to watch asset source {:?} for changes: {}",
+                    source, err
+                );
+            }
+        }
+
+        let mut sources = self.server.asset_io.write();
+        let mounted = match sources.remove(&source.map(|source| source.to_string())) {
+            Some(existing) => {
+                let mut overlay = match existing.downcast_ref::<OverlayAssetIo>() {
+                    Some(overlay) => overlay.clone(),
+                    None => {
+                        let mut overlay = OverlayAssetIo::new();
+                        overlay.mount(existing);
+                        overlay
+                    }
+                };
+                overlay.mount(asset_io);
+                Arc::new(overlay)
+            }
+            None => asset_io,
+        };
+        sources.insert(source.map(|source| source.to_string()), mounted);
+    }
+
+    /// Returns the `AssetIo` mounted for the given source, or the default one for `None`.
+    pub fn get_asset_io(&self, source: Option<&str>) -> Result<Arc<dyn AssetIo>, AssetServerError> {
+        self.server
+            .asset_io
+            .read()
+            .get(&source.map(|source| source.to_string()))
+            .cloned()
+            .ok_or_else(|| AssetServerError::MissingAssetSource(source.unwrap_or("").to_string()))
+    }
+
     pub(crate) fn register_asset_type<T: Asset>(&self) -> Assets<T> {
         self.server.asset_lifecycles.write().insert(
             T::TYPE_UUID,
@@ -115,7 +222,12 @@ impl AssetServer {
     }
 
     pub fn watch_for_changes(&self) -> Result<(), AssetServerError> {
-        self.server.asset_io.watch_for_changes()?;
+        self.server
+            .watching_for_changes
+            .store(true, Ordering::Relaxed);
+        for asset_io in self.server.asset_io.read().values() {
+            asset_io.watch_for_changes()?;
+        }
         Ok(())
     }
 
@@ -143,7 +255,7 @@ impl AssetServer {
             })
     }
 
-    fn get_path_asset_loader<P: AsRef<Path>>(
+    fn get_path_asset_loader<P: AsRef<std::path::Path>>(
         &self,
         path: P,
     ) -> Result<Arc<Box<dyn AssetLoader>>, AssetServerError> {
@@ -212,18 +324,63 @@ impl AssetServer {
         load_state
     }
 
+    /// Returns the load state of an asset and, recursively, of the assets it depends on. This is
+    /// [LoadState::Loaded] only once all of them are loaded, and [LoadState::Failed] if any of
+    /// them failed to load.
+    pub fn get_load_state_with_dependencies<H: Into<HandleId>>(&self, handle: H) -> LoadState {
+        match handle.into() {
+            HandleId::AssetPathId(id) => {
+                let asset_sources = self.server.asset_sources.read();
+                get_recursive_load_state(
+                    &asset_sources,
+                    id.source_path_id(),
+                    &mut HashSet::default(),
+                )
+            }
+            HandleId::Id(_, _) => LoadState::NotLoaded,
+        }
+    }
+
+    pub fn get_group_load_state_with_dependencies(
+        &self,
+        handles: impl IntoIterator<Item = HandleId>,
+    ) -> LoadState {
+        let mut load_state = LoadState::Loaded;
+        for handle_id in handles {
+            match self.get_load_state_with_dependencies(handle_id) {
+                LoadState::Loaded => continue,
+                LoadState::Loading => {
+                    load_state = LoadState::Loading;
+                }
+                LoadState::Failed => return LoadState::Failed,
+                LoadState::NotLoaded => return LoadState::NotLoaded,
+            }
+        }
+
+        load_state
+    }
+
     pub fn load<'a, T: Asset, P: Into<AssetPath<'a>>>(&self, path: P) -> Handle<T> {
         self.load_untyped(path).typed()
     }
 
-    // TODO: properly set failed LoadState in all failure cases
+    /// Loads an asset with settings for its [AssetLoader], see [AssetPath::with_settings]. Loading
+    /// the same path with different settings creates separate assets.
+    pub fn load_with_settings<'a, T: Asset, P: Into<AssetPath<'a>>, S: Serialize>(
+        &self,
+        path: P,
+        settings: &S,
+    ) -> Handle<T> {
+        self.load(path.into().with_settings(settings))
+    }
+
     async fn load_async<'a, P: Into<AssetPath<'a>>>(
         &self,
         path: P,
         force: bool,
     ) -> Result<AssetPathId, AssetServerError> {
         let asset_path: AssetPath = path.into();
-        let asset_loader = self.get_path_asset_loader(asset_path.path())?;
+        let asset_io = self.get_asset_io(asset_path.source())?;
         let asset_path_id: AssetPathId = asset_path.get_id();
 
         // load metadata and update source info. this is done in a scope to ensure we release the
@@ -237,7 +394,10 @@ impl AssetServer {
                     committed_assets: Default::default(),
                     load_state: LoadState::NotLoaded,
                     meta: None,
+                    source: asset_path.source().map(ToString::to_string),
                     path: asset_path.path().to_owned(),
+                    settings: asset_path.settings().map(ToString::to_string),
+                    read_paths: Vec::new(),
                     version: 0,
                 }),
             };
@@ -258,28 +418,36 @@ impl AssetServer {
             source_info.version
         };
 
-        // load the asset bytes
-        let bytes = match self.server.asset_io.load_path(asset_path.path()).await {
-            Ok(bytes) => bytes,
+        // load the asset bytes, or its processed artifact, and find the loader for them
+        let loaded = self
+            .read_asset_source(&asset_path, &*asset_io)
+            .await
+            .and_then(|source| {
+                let asset_loader = self.get_path_asset_loader(&source.loader_path)?;
+                Ok((source, asset_loader))
+            });
+        let (source, asset_loader) = match loaded {
+            Ok(loaded) => loaded,
             Err(err) => {
                 let mut asset_sources = self.server.asset_sources.write();
                 let source_info = asset_sources
                     .get_mut(&asset_path_id.source_path_id())
                     .expect("`AssetSource` should exist at this point.");
                 source_info.load_state = LoadState::Failed;
-                return Err(AssetServerError::AssetIoError(err));
+                return Err(err);
             }
         };
 
         // load the asset source using the corresponding AssetLoader
         let mut load_context = LoadContext::new(
-            asset_path.path(),
+            &asset_path,
+            source.meta,
             &self.server.asset_ref_counter.channel,
-            &*self.server.asset_io,
+            &*asset_io,
             version,
         );
         asset_loader
-            .load(&bytes, &mut load_context)
+            .load(&source.bytes, &mut load_context)
             .await
             .map_err(AssetServerError::AssetLoaderError)?;
 
@@ -303,9 +471,15 @@ impl AssetServer {
         // TODO: queue free old assets
         source_info.asset_types.clear();
 
-        source_info.meta = Some(SourceMeta {
-            assets: load_context.get_asset_metas(),
-        });
+        let mut meta = load_context
+            .meta
+            .as_ref()
+            .and_then(|meta| meta.as_deref())
+            .and_then(|meta| SourceMeta::from_bytes(meta).ok())
+            .unwrap_or_default();
+        meta.assets = load_context.get_asset_metas();
+        source_info.meta = Some(meta);
+        source_info.read_paths = std::mem::take(&mut *load_context.read_paths.lock());
 
         // load asset dependencies and prepare asset type hashmap
         for (label, loaded_asset) in load_context.labeled_assets.iter_mut() {
@@ -317,10 +491,27 @@ impl AssetServer {
             }
         }
 
-        self.server
-            .asset_io
-            .watch_path_for_changes(asset_path.path())
-            .unwrap();
+        for read_path in source_info.read_paths.iter() {
+            let _ = asset_io.watch_path_for_changes(read_path);
+        }
+        // editing the meta file reprocesses the asset, or changes the settings its loader read.
+        // most assets don't have one
+        if self.server.processed_assets.read().is_some()
+            || load_context.read_meta.load(Ordering::Relaxed)
+        {
+            let _ = asset_io.watch_path_for_changes(&meta_path(asset_path.path()));
+        }
+        // processed artifacts are loaded without their sources, which may not exist. the asset
+        // itself loaded fine, so failing to watch it doesn't fail the load
+        if source.read_source {
+            if let Err(err) = asset_io.watch_path_for_changes(asset_path.path()) {
+                warn!(
+                    "failed to watch {:?} for changes: {}",
+                    asset_path.path(),
+                    err
+                );
+            }
+        }
         self.create_assets_in_load_context(&mut load_context);
         Ok(asset_path_id)
     }
@@ -341,36 +532,54 @@ impl AssetServer {
         self.server
             .task_pool
             .spawn(async move {
-                if let Err(err) = server.load_async(owned_path, force).await {
+                if let Err(err) = server.load_async(owned_path.clone(), force).await {
                     warn!("{}", err);
+                    server.fail_load(owned_path, err);
                 }
             })
             .detach();
         asset_path.into()
     }
 
-    pub fn load_folder<P: AsRef<Path>>(
+    fn fail_load(&self, path: AssetPath<'static>, error: AssetServerError) {
+        let id = path.get_id();
+        if let Some(source_info) = self
+            .server
+            .asset_sources
+            .write()
+            .get_mut(&id.source_path_id())
+        {
+            source_info.load_state = LoadState::Failed;
+        }
+        let _ = self.server.load_failure_sender.send(AssetLoadFailed {
+            id: id.into(),
+            path,
+            error,
+        });
+    }
+
+    pub fn load_folder<'a, P: Into<AssetPath<'a>>>(
         &self,
         path: P,
     ) -> Result<Vec<HandleUntyped>, AssetServerError> {
-        let path = path.as_ref();
-        if !self.server.asset_io.is_directory(path) {
+        let path: AssetPath = path.into();
+        let asset_io = self.get_asset_io(path.source())?;
+        if !asset_io.is_directory(path.path()) {
             return Err(AssetServerError::AssetFolderNotADirectory(
-                path.to_str().unwrap().to_string(),
+                path.path().to_str().unwrap().to_string(),
             ));
         }
 
         let mut handles = Vec::new();
-        for child_path in self.server.asset_io.read_directory(path.as_ref())? {
-            if self.server.asset_io.is_directory(&child_path) {
-                handles.extend(self.load_folder(&child_path)?);
+        for child_path in asset_io.read_directory(path.path())? {
+            let child_path = AssetPath::new(child_path, None).with_source(path.source());
+            if asset_io.is_directory(child_path.path()) {
+                handles.extend(self.load_folder(child_path)?);
             } else {
-                if self.get_path_asset_loader(&child_path).is_err() {
+                if self.get_path_asset_loader(child_path.path()).is_err() {
                     continue;
                 }
-                let handle =
-                    self.load_untyped(child_path.to_str().expect("Path should be a valid string."));
-                handles.push(handle);
+                handles.push(self.load_untyped(child_path));
             }
         }
 
@@ -431,8 +640,9 @@ impl AssetServer {
                 .take()
                 .expect("Asset should exist at this point.");
             if let Some(asset_lifecycle) = asset_lifecycles.get(&asset_value.type_uuid()) {
-                let asset_path =
-                    AssetPath::new_ref(&load_context.path, label.as_ref().map(|l| l.as_str()));
+                let asset_path = AssetPath::new_ref(load_context.path, label.as_deref())
+                    .with_source(load_context.source)
+                    .with_serialized_settings(load_context.settings);
                 asset_lifecycle.create_asset(asset_path.into(), asset_value, load_context.version);
             } else {
                 panic!(
@@ -467,6 +677,12 @@ impl AssetServer {
                                 if source_info.is_loaded() {
                                     source_info.load_state = LoadState::Loaded;
                                 }
+                                self.server
+                                    .waiting_for_dependencies
+                                    .write()
+                                    .entry(T::TYPE_UUID)
+                                    .or_default()
+                                    .insert(id);
                             }
                         }
                     }
@@ -492,13 +708,165 @@ impl AssetServer {
                 Err(TryRecvError::Disconnected) => panic!("AssetChannel disconnected."),
             }
         }
+        drop(asset_sources_guard);
+
+        let mut waiting_for_dependencies = self.server.waiting_for_dependencies.write();
+        let waiting = match waiting_for_dependencies.get_mut(&T::TYPE_UUID) {
+            Some(waiting) if !waiting.is_empty() => waiting,
+            _ => return,
+        };
+        let asset_sources = self.server.asset_sources.read();
+        waiting.retain(|id| {
+            match get_recursive_load_state(
+                &asset_sources,
+                id.source_path_id(),
+                &mut HashSet::default(),
+            ) {
+                LoadState::Loaded => {
+                    assets.send_loaded_with_dependencies((*id).into());
+                    false
+                }
+                LoadState::Loading => true,
+                LoadState::NotLoaded | LoadState::Failed => false,
+            }
+        });
+    }
+}
+
+/// Combines the load state of a source with the load states of its dependencies. Dependencies
+/// that are already visited count as loaded, so cycles and shared dependencies are fine.
+fn get_recursive_load_state(
+    asset_sources: &HashMap<SourcePathId, SourceInfo>,
+    source_path_id: SourcePathId,
+    visited: &mut HashSet<SourcePathId>,
+) -> LoadState {
+    if !visited.insert(source_path_id) {
+        return LoadState::Loaded;
+    }
+    let source_info = match asset_sources.get(&source_path_id) {
+        Some(source_info) => source_info,
+        None => return LoadState::NotLoaded,
+    };
+    // dependencies are only known once the source itself is loaded
+    if source_info.load_state != LoadState::Loaded {
+        return source_info.load_state;
     }
+
+    let mut load_state = LoadState::Loaded;
+    let dependencies = source_info
+        .meta
+        .iter()
+        .flat_map(|meta| meta.assets.iter())
+        .flat_map(|asset| asset.dependencies.iter());
+    for dependency in dependencies {
+        let source_path_id = dependency.get_id().source_path_id();
+        match get_recursive_load_state(asset_sources, source_path_id, visited) {
+            LoadState::Loaded => {}
+            LoadState::Failed => return LoadState::Failed,
+            // dependencies are queued when their dependant loads, so they may not be known yet
+            LoadState::Loading | LoadState::NotLoaded => load_state = LoadState::Loading,
+        }
+    }
+    load_state
+}
+
+/// Sends an [AssetLoadFailed] event for each asset that failed to load.
+pub fn asset_load_failed_system(
+    asset_server: Res<AssetServer>,
+    mut events: EventWriter<AssetLoadFailed>,
+) {
+    events.send_batch(asset_server.server.load_failure_receiver.try_iter());
 }
 
 pub fn free_unused_assets_system(asset_server: Res<AssetServer>) {
     asset_server.free_unused_assets();
 }
 
+/// Reloads the assets that changed in any of the mounted sources.
+pub fn filesystem_watcher_system(asset_server: Res<AssetServer>) {
+    let sources: Vec<(Option<String>, Arc<dyn AssetIo>)> = asset_server
+        .server
+        .asset_io
+        .read()
+        .iter()
+        .map(|(source, asset_io)| (source.clone(), asset_io.clone()))
+        .collect();
+    let mut changed_paths = Vec::new();
+    for (source, asset_io) in sources {
+        for mut path in asset_io.poll_changed_paths() {
+            if path.extension().map_or(false, |ext| ext == "meta") {
+                path.set_extension("");
+            }
+            changed_paths.push((source.clone(), path));
+        }
+    }
+    let changed_paths = asset_server.filter_saved_paths(changed_paths);
+    if changed_paths.is_empty() {
+        return;
+    }
+    for asset_path in asset_server.get_assets_to_reload(&changed_paths) {
+        let _ = asset_server.load_untracked(asset_path, true);
+    }
+}
+
+impl AssetServer {
+    /// Returns the assets to reload when the given files change: the assets loaded from them, in
+    /// every variant of loader settings, the assets whose loaders read them, and recursively
+    /// every asset that depends on one of those.
+    pub(crate) fn get_assets_to_reload(
+        &self,
+        changed_paths: &[(Option<String>, PathBuf)],
+    ) -> Vec<AssetPath<'static>> {
+        let asset_sources = self.server.asset_sources.read();
+        let mut reload = HashSet::default();
+        let mut asset_paths = Vec::new();
+        for (source, path) in changed_paths {
+            let mut found = false;
+            for (source_path_id, info) in asset_sources.iter() {
+                if info.source == *source && (info.path == *path || info.read_paths.contains(path))
+                {
+                    found = true;
+                    reload.insert(*source_path_id);
+                }
+            }
+            if !found {
+                asset_paths.push(AssetPath::new(path.clone(), None).with_source(source.clone()));
+            }
+        }
+
+        // propagate through the dependency graph until no more dependants are found
+        loop {
+            let dependants: Vec<SourcePathId> = asset_sources
+                .iter()
+                .filter(|(source_path_id, info)| {
+                    !reload.contains(*source_path_id)
+                        && info
+                            .meta
+                            .iter()
+                            .flat_map(|meta| meta.assets.iter())
+                            .flat_map(|asset| asset.dependencies.iter())
+                            .any(|dependency| {
+                                reload.contains(&dependency.get_id().source_path_id())
+                            })
+                })
+                .map(|(source_path_id, _)| *source_path_id)
+                .collect();
+            if dependants.is_empty() {
+                break;
+            }
+            reload.extend(dependants);
+        }
+
+        asset_paths.extend(reload.iter().map(|source_path_id| {
+            let info = &asset_sources[source_path_id];
+            AssetPath::new(info.path.clone(), None)
+                .with_source(info.source.clone())
+                .with_serialized_settings(info.settings.clone())
+        }));
+        asset_paths
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
@@ -537,18 +905,7 @@ mod test {
     fn setup() -> AssetServer {
         use crate::FileAssetIo;
 
-        let asset_server = AssetServer {
-            server: Arc::new(AssetServerInternal {
-                loaders: Default::default(),
-                extension_to_loader_index: Default::default(),
-                asset_sources: Default::default(),
-                asset_ref_counter: Default::default(),
-                handle_to_path: Default::default(),
-                asset_lifecycles: Default::default(),
-                task_pool: Default::default(),
-                asset_io: Box::new(FileAssetIo::new(&".")),
-            }),
-        };
+        let asset_server = AssetServer::new(FileAssetIo::new(&"."), Default::default());
         asset_server.add_loader::<FakePngLoader>(FakePngLoader);
         asset_server.add_loader::<FakeMultipleDotLoader>(FakeMultipleDotLoader);
         asset_server
@@ -623,4 +980,354 @@ mod test {
         let t = asset_server.get_path_asset_loader("test.test.png");
         assert_eq!(t.unwrap().extensions()[0], "test.png");
     }
+
+    fn insert_source(
+        asset_server: &AssetServer,
+        path: &str,
+        load_state: LoadState,
+        dependencies: &[&str],
+        read_paths: &[&str],
+    ) {
+        let meta = SourceMeta {
+            assets: vec![crate::AssetMeta {
+                label: None,
+                dependencies: dependencies
+                    .iter()
+                    .map(|path| AssetPath::from(*path).to_owned())
+                    .collect(),
+                type_uuid: Uuid::nil(),
+            }],
+            ..Default::default()
+        };
+        asset_server.server.asset_sources.write().insert(
+            AssetPath::from(path).get_id().source_path_id(),
+            SourceInfo {
+                meta: Some(meta),
+                source: None,
+                path: path.into(),
+                settings: None,
+                read_paths: read_paths.iter().map(PathBuf::from).collect(),
+                asset_types: Default::default(),
+                load_state,
+                committed_assets: Default::default(),
+                version: 1,
+            },
+        );
+    }
+
+    #[test]
+    fn load_state_with_dependencies() {
+        let asset_server = setup();
+        let set_source = |path, load_state, dependencies: &[&str]| {
+            insert_source(&asset_server, path, load_state, dependencies, &[])
+        };
+        let scene: HandleId = AssetPath::from("scene.gltf").into();
+
+        set_source(
+            "scene.gltf",
+            LoadState::Loaded,
+            &["a.png", "scene.gltf#Mesh0"],
+        );
+        assert_eq!(asset_server.get_load_state(scene), LoadState::Loaded);
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(scene),
+            LoadState::Loading
+        );
+
+        set_source("a.png", LoadState::Loaded, &["scene.gltf"]);
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(scene),
+            LoadState::Loaded
+        );
+
+        set_source("a.png", LoadState::Failed, &[]);
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(scene),
+            LoadState::Failed
+        );
+    }
+
+    #[test]
+    fn load_state_of_nested_dependencies() {
+        let asset_server = setup();
+        let set_source = |path, load_state, dependencies: &[&str]| {
+            insert_source(&asset_server, path, load_state, dependencies, &[])
+        };
+        let level: HandleId = AssetPath::from("level.ron").into();
+        let prefab: HandleId = AssetPath::from("prefab.ron").into();
+
+        set_source("level.ron", LoadState::Loaded, &["prefab.ron"]);
+        set_source("prefab.ron", LoadState::Loaded, &["mat.ron"]);
+        set_source("mat.ron", LoadState::Loaded, &["texture.png"]);
+        set_source("texture.png", LoadState::Loading, &[]);
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(level),
+            LoadState::Loading
+        );
+
+        set_source("texture.png", LoadState::Loaded, &[]);
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(level),
+            LoadState::Loaded
+        );
+
+        // a failure anywhere down the chain fails every asset above it
+        set_source("texture.png", LoadState::Failed, &[]);
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(level),
+            LoadState::Failed
+        );
+        assert_eq!(
+            asset_server.get_group_load_state_with_dependencies(vec![level, prefab]),
+            LoadState::Failed
+        );
+        assert_eq!(
+            asset_server.get_load_state(level),
+            LoadState::Loaded,
+            "the level itself still loaded"
+        );
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    #[test]
+    fn loaded_with_dependencies_events() {
+        use crate::{
+            AddAsset, AssetLoadedWithDependencies, AssetPlugin, AssetServerSettings, LoadedAsset,
+        };
+        use bevy_app::{App, Events};
+        use bevy_reflect::TypeUuid;
+        use bevy_tasks::IoTaskPool;
+
+        #[derive(TypeUuid)]
+        #[uuid = "3a8c3a8e-43c3-4c2e-9d1f-6f0a3e6d4c11"]
+        struct DepAsset;
+
+        /// Loads a list of paths, one per line, that the asset depends on
+        struct DepLoader;
+        impl AssetLoader for DepLoader {
+            fn load<'a>(
+                &'a self,
+                bytes: &'a [u8],
+                load_context: &'a mut LoadContext,
+            ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+                Box::pin(async move {
+                    let dependencies = std::str::from_utf8(bytes)?
+                        .lines()
+                        .map(|path| AssetPath::from(path).to_owned())
+                        .collect();
+                    load_context.set_default_asset(
+                        LoadedAsset::new(DepAsset).with_dependencies(dependencies),
+                    );
+                    Ok(())
+                })
+            }
+
+            fn extensions(&self) -> &[&str] {
+                &["dep"]
+            }
+        }
+
+        let root = std::env::temp_dir().join(format!("bevy_asset_deps_{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.dep"), "b.dep").unwrap();
+        std::fs::write(root.join("b.dep"), "c.dep").unwrap();
+        std::fs::write(root.join("c.dep"), "").unwrap();
+        std::fs::write(root.join("broken.dep"), "c.dep\nmissing.dep").unwrap();
+
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .insert_resource(AssetServerSettings {
+                asset_folder: root.to_str().unwrap().to_string(),
+                ..Default::default()
+            })
+            .add_plugin(AssetPlugin)
+            .add_asset::<DepAsset>()
+            .add_asset_loader(DepLoader);
+        let asset_server = app.world().get_resource::<AssetServer>().unwrap().clone();
+        let a: Handle<DepAsset> = asset_server.load("a.dep");
+        let broken: Handle<DepAsset> = asset_server.load("broken.dep");
+
+        let mut loaded = Vec::new();
+        let mut failed = Vec::new();
+        let mut loaded_reader = app
+            .world()
+            .get_resource::<Events<AssetLoadedWithDependencies<DepAsset>>>()
+            .unwrap()
+            .get_reader();
+        let mut failed_reader = app
+            .world()
+            .get_resource::<Events<AssetLoadFailed>>()
+            .unwrap()
+            .get_reader();
+        for _ in 0..500 {
+            app.app.update();
+            let world = app.world();
+            let events = world
+                .get_resource::<Events<AssetLoadedWithDependencies<DepAsset>>>()
+                .unwrap();
+            loaded.extend(loaded_reader.iter(events).map(|event| event.handle.id));
+            let events = world.get_resource::<Events<AssetLoadFailed>>().unwrap();
+            failed.extend(failed_reader.iter(events).map(|event| event.id));
+            if asset_server.get_load_state_with_dependencies(&a) == LoadState::Loaded
+                && asset_server.get_load_state_with_dependencies(&broken) == LoadState::Failed
+            {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(&a),
+            LoadState::Loaded
+        );
+        assert_eq!(
+            asset_server.get_load_state_with_dependencies(&broken),
+            LoadState::Failed
+        );
+        // every asset of the chain is reported once its own dependencies are loaded
+        for path in ["a.dep", "b.dep", "c.dep"].iter() {
+            let id = HandleId::from(AssetPath::from(*path));
+            assert_eq!(loaded.iter().filter(|loaded| **loaded == id).count(), 1);
+        }
+        assert!(!loaded.contains(&broken.id));
+        assert_eq!(failed, vec![HandleId::from(AssetPath::from("missing.dep"))]);
+    }
+
+    #[cfg(all(
+        feature = "filesystem_watcher",
+        not(target_arch = "wasm32"),
+        not(target_os = "android")
+    ))]
+    #[test]
+    fn meta_file_changes_reload_assets() {
+        use crate::{AddAsset, AssetPlugin, AssetServerSettings, Assets, LoadedAsset};
+        use bevy_app::{App, AppBuilder};
+        use bevy_reflect::TypeUuid;
+        use bevy_tasks::IoTaskPool;
+        use serde::Deserialize;
+
+        #[derive(TypeUuid)]
+        #[uuid = "90144ba8-b5d4-46f7-8d2c-463f6eaa6517"]
+        struct ScaledAsset(u32);
+
+        #[derive(Default, Deserialize)]
+        struct ScaleSettings {
+            scale: u32,
+        }
+
+        /// Loads a number, multiplied by the scale in its loader settings
+        struct ScaledLoader;
+        impl AssetLoader for ScaledLoader {
+            fn load<'a>(
+                &'a self,
+                bytes: &'a [u8],
+                load_context: &'a mut LoadContext,
+            ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+                Box::pin(async move {
+                    let settings: ScaleSettings = load_context.settings().await?;
+                    let value: u32 = std::str::from_utf8(bytes)?.trim().parse()?;
+                    load_context
+                        .set_default_asset(LoadedAsset::new(ScaledAsset(value * settings.scale)));
+                    Ok(())
+                })
+            }
+
+            fn extensions(&self) -> &[&str] {
+                &["scaled"]
+            }
+        }
+
+        let root = std::env::temp_dir().join(format!("bevy_asset_settings_{}", std::process::id()));
+        let write_meta = |scale: u32| {
+            let meta = format!(
+                "(processor: None, loader_settings: Some((scale: {})))",
+                scale
+            );
+            std::fs::write(root.join("a.scaled.meta"), meta).unwrap();
+        };
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join("a.scaled"), "3").unwrap();
+        write_meta(2);
+
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .insert_resource(AssetServerSettings {
+                asset_folder: root.to_str().unwrap().to_string(),
+                ..Default::default()
+            })
+            .add_plugin(AssetPlugin)
+            .add_asset::<ScaledAsset>()
+            .add_asset_loader(ScaledLoader);
+        let asset_server = app.world().get_resource::<AssetServer>().unwrap().clone();
+        asset_server.watch_for_changes().unwrap();
+        let handle: Handle<ScaledAsset> = asset_server.load("a.scaled");
+        let wait_for = |app: &mut AppBuilder, expected: u32| {
+            for _ in 0..500 {
+                app.app.update();
+                let assets = app.world().get_resource::<Assets<ScaledAsset>>().unwrap();
+                if assets.get(&handle).map(|asset| asset.0) == Some(expected) {
+                    return true;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            false
+        };
+
+        let loaded = wait_for(&mut app, 6);
+        // processing is disabled, so only the loader reads the meta file
+        write_meta(5);
+        let reloaded = loaded && wait_for(&mut app, 15);
+        std::fs::remove_dir_all(&root).unwrap();
+
+        assert!(
+            loaded,
+            "the asset should load with the settings of its meta file"
+        );
+        assert!(reloaded, "editing the meta file should reload the asset");
+    }
+
+    #[test]
+    fn reload_dependants() {
+        let asset_server = setup();
+        insert_source(
+            &asset_server,
+            "scene.gltf",
+            LoadState::Loaded,
+            &[],
+            &["scene.bin"],
+        );
+        insert_source(&asset_server, "texture.png", LoadState::Loaded, &[], &[]);
+        insert_source(
+            &asset_server,
+            "mat.ron",
+            LoadState::Loaded,
+            &["texture.png"],
+            &[],
+        );
+        insert_source(
+            &asset_server,
+            "prefab.ron",
+            LoadState::Loaded,
+            &["mat.ron"],
+            &[],
+        );
+        let reload = |path: &str| {
+            let mut paths: Vec<String> = asset_server
+                .get_assets_to_reload(&[(None, PathBuf::from(path))])
+                .iter()
+                .map(|asset_path| asset_path.path().to_str().unwrap().to_string())
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(
+            reload("texture.png"),
+            ["mat.ron", "prefab.ron", "texture.png"]
+        );
+        assert_eq!(reload("mat.ron"), ["mat.ron", "prefab.ron"]);
+        assert_eq!(reload("scene.bin"), ["scene.gltf"]);
+        assert_eq!(reload("other.png"), ["other.png"]);
+    }
 }
@@ -1,6 +1,7 @@
 use crate::{
-    update_asset_storage_system, Asset, AssetLoader, AssetServer, AssetStage, Handle, HandleId,
-    RefChange,
+    save_queued_assets_system, update_asset_storage_system, Asset, AssetIo, AssetLoader,
+    AssetProcessor, AssetSaver, AssetServer, AssetServerError, AssetStage, EmbeddedAssetIo, Handle,
+    HandleId, RefChange, EMBEDDED_SOURCE,
 };
 use bevy_app::{AppBuilder, EventWriter, Events};
 use bevy_ecs::{
@@ -46,11 +47,29 @@ impl<T: Asset> Debug for AssetEvent<T> {
     }
 }
 
+/// Sent once an asset of type `T` loaded by the [AssetServer] and all of its dependencies,
+/// recursively, are loaded. See [AssetServer::get_load_state_with_dependencies].
+pub struct AssetLoadedWithDependencies<T: Asset> {
+    pub handle: Handle<T>,
+}
+
+impl<T: Asset> Debug for AssetLoadedWithDependencies<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct(&format!(
+            "AssetLoadedWithDependencies<{}>",
+            std::any::type_name::<T>()
+        ))
+        .field("handle", &self.handle.id)
+        .finish()
+    }
+}
+
 /// Stores Assets of a given type and tracks changes to them.
 #[derive(Debug)]
 pub struct Assets<T: Asset> {
     assets: HashMap<HandleId, T>,
     events: Events<AssetEvent<T>>,
+    loaded_with_dependencies: Events<AssetLoadedWithDependencies<T>>,
     pub(crate) ref_change_sender: Sender<RefChange>,
 }
 
@@ -59,6 +78,7 @@ impl<T: Asset> Assets<T> {
         Assets {
             assets: HashMap::default(),
             events: Events::default(),
+            loaded_with_dependencies: Events::default(),
             ref_change_sender,
         }
     }
@@ -173,11 +193,20 @@ impl<T: Asset> Assets<T> {
         self.assets.shrink_to_fit()
     }
 
+    pub(crate) fn send_loaded_with_dependencies(&mut self, id: HandleId) {
+        self.loaded_with_dependencies
+            .send(AssetLoadedWithDependencies {
+                handle: Handle::weak(id),
+            });
+    }
+
     pub fn asset_event_system(
         mut events: EventWriter<AssetEvent<T>>,
+        mut loaded_with_dependencies: EventWriter<AssetLoadedWithDependencies<T>>,
         mut assets: ResMut<Assets<T>>,
     ) {
-        events.send_batch(assets.events.drain())
+        events.send_batch(assets.events.drain());
+        loaded_with_dependencies.send_batch(assets.loaded_with_dependencies.drain());
     }
 
     pub fn len(&self) -> usize {
@@ -200,6 +229,25 @@ pub trait AddAsset {
     fn add_asset_loader<T>(&mut self, loader: T) -> &mut Self
     where
         T: AssetLoader;
+    fn add_asset_source<T>(&mut self, source: Option<&str>, asset_io: T) -> &mut Self
+    where
+        T: AssetIo;
+    fn add_embedded_asset(
+        &mut self,
+        namespace: &str,
+        path: &str,
+        bytes: &'static [u8],
+        root: &str,
+    ) -> Result<&mut Self, AssetServerError>;
+    fn add_asset_processor<T>(&mut self, processor: T) -> &mut Self
+    where
+        T: AssetProcessor;
+    fn init_asset_saver<T>(&mut self) -> &mut Self
+    where
+        T: AssetSaver + FromWorld;
+    fn add_asset_saver<T>(&mut self, saver: T) -> &mut Self
+    where
+        T: AssetSaver;
 }
 
 impl AddAsset for AppBuilder {
@@ -221,8 +269,13 @@ impl AddAsset for AppBuilder {
                 AssetStage::LoadAssets,
                 update_asset_storage_system::<T>.system(),
             )
+            .add_system_to_stage(
+                AssetStage::AssetEvents,
+                save_queued_assets_system::<T>.system(),
+            )
             .register_type::<Handle<T>>()
             .add_event::<AssetEvent<T>>()
+            .add_event::<AssetLoadedWithDependencies<T>>()
     }
 
     fn init_asset_loader<T>(&mut self) -> &mut Self
@@ -243,4 +296,66 @@ impl AddAsset for AppBuilder {
             .add_loader(loader);
         self
     }
+
+    /// Mounts `asset_io` as a named asset source, see [AssetServer::mount].
+    fn add_asset_source<T>(&mut self, source: Option<&str>, asset_io: T) -> &mut Self
+    where
+        T: AssetIo,
+    {
+        self.world()
+            .get_resource::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .mount(source, asset_io);
+        self
+    }
+
+    /// Registers an asset compiled into the binary, see [embedded_asset!](crate::embedded_asset).
+    /// This fails if no [EmbeddedAssetIo] is mounted as the embedded source.
+    fn add_embedded_asset(
+        &mut self,
+        namespace: &str,
+        path: &str,
+        bytes: &'static [u8],
+        root: &str,
+    ) -> Result<&mut Self, AssetServerError> {
+        let asset_io = self
+            .world()
+            .get_resource::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .get_asset_io(Some(EMBEDDED_SOURCE))?;
+        EmbeddedAssetIo::find(asset_io.as_ref())
+            .ok_or_else(|| AssetServerError::MissingEmbeddedAssetIo(EMBEDDED_SOURCE.to_string()))?
+            .add_asset(namespace, path, bytes, root);
+        Ok(self)
+    }
+
+    fn add_asset_processor<T>(&mut self, processor: T) -> &mut Self
+    where
+        T: AssetProcessor,
+    {
+        self.world()
+            .get_resource::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .add_processor(processor);
+        self
+    }
+
+    fn init_asset_saver<T>(&mut self) -> &mut Self
+    where
+        T: AssetSaver + FromWorld,
+    {
+        let result = T::from_world(self.world_mut());
+        self.add_asset_saver(result)
+    }
+
+    fn add_asset_saver<T>(&mut self, saver: T) -> &mut Self
+    where
+        T: AssetSaver,
+    {
+        self.world()
+            .get_resource::<AssetServer>()
+            .expect("AssetServer does not exist. Consider adding it as a resource.")
+            .add_saver(saver);
+        self
+    }
 }
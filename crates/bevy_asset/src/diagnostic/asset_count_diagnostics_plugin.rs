@@ -3,20 +3,68 @@ use bevy_app::prelude::*;
 use bevy_diagnostic::{Diagnostic, DiagnosticId, Diagnostics};
 use bevy_ecs::system::{IntoSystem, Res, ResMut};
 
-/// Adds "asset count" diagnostic to an App
-#[derive(Default)]
+/// Adds an "asset count" diagnostic to an App, and an "asset memory" diagnostic if the memory
+/// used by an asset is measured with [AssetCountDiagnosticsPlugin::with_memory_usage].
 pub struct AssetCountDiagnosticsPlugin<T: Asset> {
-    marker: std::marker::PhantomData<T>,
+    memory_usage: Option<AssetMemoryUsage<T>>,
+}
+
+impl<T: Asset> Default for AssetCountDiagnosticsPlugin<T> {
+    fn default() -> Self {
+        Self { memory_usage: None }
+    }
+}
+
+/// Measures the memory used by an asset, for the "asset memory" diagnostic with the given id
+pub struct AssetMemoryUsage<T: Asset> {
+    pub diagnostic_id: DiagnosticId,
+    pub measure: fn(&T) -> usize,
+}
+
+impl<T: Asset> Clone for AssetMemoryUsage<T> {
+    fn clone(&self) -> Self {
+        Self {
+            diagnostic_id: self.diagnostic_id,
+            measure: self.measure,
+        }
+    }
 }
 
 impl<T: Asset> Plugin for AssetCountDiagnosticsPlugin<T> {
     fn build(&self, app: &mut AppBuilder) {
         app.add_startup_system(Self::setup_system.system())
             .add_system(Self::diagnostic_system.system());
+        if let Some(memory_usage) = &self.memory_usage {
+            app.insert_resource(memory_usage.clone())
+                .add_startup_system(Self::memory_setup_system.system())
+                .add_system(Self::memory_diagnostic_system.system());
+        }
     }
 }
 
 impl<T: Asset> AssetCountDiagnosticsPlugin<T> {
+    /// Adds an "asset memory" diagnostic with the given id, which sums `memory_usage` over every
+    /// asset. Only the asset type knows how much memory it owns on the heap, so it has to be
+    /// measured by hand:
+    ///
+    /// ```ignore
+    /// AssetCountDiagnosticsPlugin::<Texture>::default().with_memory_usage(
+    ///     DiagnosticId::from_u128(0x81f5_3a1e_0d6c_4a7b_9e24_c8b0_57d3_f619),
+    ///     |texture| texture.data.len(),
+    /// )
+    /// ```
+    pub fn with_memory_usage(
+        mut self,
+        diagnostic_id: DiagnosticId,
+        memory_usage: fn(&T) -> usize,
+    ) -> Self {
+        self.memory_usage = Some(AssetMemoryUsage {
+            diagnostic_id,
+            measure: memory_usage,
+        });
+        self
+    }
+
     pub fn diagnostic_id() -> DiagnosticId {
         DiagnosticId(T::TYPE_UUID)
     }
@@ -29,7 +77,82 @@ impl<T: Asset> AssetCountDiagnosticsPlugin<T> {
         ));
     }
 
+    pub fn memory_setup_system(
+        mut diagnostics: ResMut<Diagnostics>,
+        memory_usage: Res<AssetMemoryUsage<T>>,
+    ) {
+        diagnostics.add(
+            Diagnostic::new(
+                memory_usage.diagnostic_id,
+                format!("asset_memory {}", std::any::type_name::<T>()),
+                20,
+            )
+            .with_suffix("B"),
+        );
+    }
+
     pub fn diagnostic_system(mut diagnostics: ResMut<Diagnostics>, assets: Res<Assets<T>>) {
         diagnostics.add_measurement(Self::diagnostic_id(), assets.len() as f64);
     }
+
+    pub fn memory_diagnostic_system(
+        mut diagnostics: ResMut<Diagnostics>,
+        assets: Res<Assets<T>>,
+        memory_usage: Res<AssetMemoryUsage<T>>,
+    ) {
+        let bytes: usize = assets
+            .iter()
+            .map(|(_, asset)| (memory_usage.measure)(asset))
+            .sum();
+        diagnostics.add_measurement(memory_usage.diagnostic_id, bytes as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddAsset, AssetPlugin};
+    use bevy_reflect::TypeUuid;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+
+    #[derive(TypeUuid)]
+    #[uuid = "0b2a4c55-3f6e-4d0e-a3c2-74f1f5e9c8d2"]
+    struct Blob(Vec<u8>);
+
+    const BLOB_MEMORY: DiagnosticId =
+        DiagnosticId::from_u128(0x2f4e_9b71_6c0d_4a83_b5e2_1d7f_08c3_96aa);
+
+    fn app(plugin: AssetCountDiagnosticsPlugin<Blob>) -> App {
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .init_resource::<Diagnostics>()
+            .add_plugin(AssetPlugin)
+            .add_asset::<Blob>()
+            .add_plugin(plugin);
+        let mut assets = app.world_mut().get_resource_mut::<Assets<Blob>>().unwrap();
+        std::mem::forget(assets.add(Blob(vec![0; 10])));
+        std::mem::forget(assets.add(Blob(vec![0; 32])));
+        let mut app = app.app;
+        app.update();
+        app
+    }
+
+    #[test]
+    fn count_assets() {
+        let app = app(AssetCountDiagnosticsPlugin::default());
+        let diagnostics = app.world.get_resource::<Diagnostics>().unwrap();
+        let count = AssetCountDiagnosticsPlugin::<Blob>::diagnostic_id();
+        assert_eq!(diagnostics.get(count).unwrap().value(), Some(2.0));
+        // memory is only measured when the plugin is told how
+        assert!(diagnostics.get(BLOB_MEMORY).is_none());
+        assert_eq!(diagnostics.iter().count(), 1);
+    }
+
+    #[test]
+    fn measure_asset_memory() {
+        let app = app(AssetCountDiagnosticsPlugin::default()
+            .with_memory_usage(BLOB_MEMORY, |blob| blob.0.len()));
+        let diagnostics = app.world.get_resource::<Diagnostics>().unwrap();
+        assert_eq!(diagnostics.get(BLOB_MEMORY).unwrap().value(), Some(42.0));
+    }
 }
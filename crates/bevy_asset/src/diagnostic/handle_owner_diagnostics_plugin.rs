@@ -0,0 +1,346 @@
+use crate::{AssetPath, AssetServer, HandleId, LabelId, ReflectHandle};
+use bevy_app::prelude::*;
+use bevy_ecs::{
+    entity::Entity,
+    reflect::{ReflectComponent, ReflectResource},
+    system::IntoExclusiveSystem,
+    world::{Mut, World},
+};
+use bevy_reflect::{Reflect, ReflectRef, TypeRegistry, TypeRegistryArc};
+use bevy_utils::{HashMap, HashSet};
+use std::fmt::Write;
+
+/// Tracks which entities and resources hold strong handles, in [HandleOwners]. This walks every
+/// reflected component and resource each frame, so it is meant for debugging why assets stay
+/// loaded.
+#[derive(Default)]
+pub struct HandleOwnerDiagnosticsPlugin;
+
+impl Plugin for HandleOwnerDiagnosticsPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.init_resource::<HandleOwners>().add_system_to_stage(
+            CoreStage::Last,
+            update_handle_owners_system.exclusive_system(),
+        );
+    }
+}
+
+/// Something in the [World] that holds a strong handle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandleOwner {
+    Component {
+        entity: Entity,
+        component: &'static str,
+    },
+    Resource {
+        resource: &'static str,
+    },
+}
+
+/// The owners of every strong handle held by a reflected component or resource, as found by the
+/// [HandleOwnerDiagnosticsPlugin].
+///
+/// Handles held elsewhere, such as by other assets or by systems, aren't found. Assets that are
+/// only held like that are listed by [HandleOwners::unowned_assets], which is a good place to
+/// start looking for leaks.
+#[derive(Debug, Default)]
+pub struct HandleOwners {
+    owners: HashMap<HandleId, Vec<HandleOwner>>,
+}
+
+impl HandleOwners {
+    pub fn get<H: Into<HandleId>>(&self, handle: H) -> &[HandleOwner] {
+        self.owners
+            .get(&handle.into())
+            .map_or(&[], |owners| owners.as_slice())
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (HandleId, &[HandleOwner])> {
+        self.owners
+            .iter()
+            .map(|(id, owners)| (*id, owners.as_slice()))
+    }
+
+    /// Finds the owners of the strong handles in every reflected component and resource of
+    /// `world`.
+    pub fn update(&mut self, world: &World, type_registry: &TypeRegistry) {
+        self.owners.clear();
+        let mut handles = Vec::new();
+        for archetype in world.archetypes().iter() {
+            for component_id in archetype.components() {
+                let registration = match world
+                    .components()
+                    .get_info(component_id)
+                    .and_then(|info| info.type_id())
+                    .and_then(|type_id| type_registry.get(type_id))
+                {
+                    Some(registration) => registration,
+                    None => continue,
+                };
+                let reflect_component = match registration.data::<ReflectComponent>() {
+                    Some(reflect_component) => reflect_component,
+                    None => continue,
+                };
+                for entity in archetype.entities() {
+                    if let Some(component) = reflect_component.reflect_component(world, *entity) {
+                        collect_strong_handles(component, type_registry, &mut handles);
+                    }
+                    for id in handles.drain(..) {
+                        self.owners
+                            .entry(id)
+                            .or_default()
+                            .push(HandleOwner::Component {
+                                entity: *entity,
+                                component: registration.name(),
+                            });
+                    }
+                }
+            }
+        }
+
+        for registration in type_registry.iter() {
+            let resource = registration
+                .data::<ReflectResource>()
+                .and_then(|reflect_resource| reflect_resource.reflect_resource(world));
+            if let Some(resource) = resource {
+                collect_strong_handles(resource, type_registry, &mut handles);
+            }
+            for id in handles.drain(..) {
+                self.owners
+                    .entry(id)
+                    .or_default()
+                    .push(HandleOwner::Resource {
+                        resource: registration.name(),
+                    });
+            }
+        }
+    }
+
+    /// Returns the assets that are kept loaded by strong handles, none of which are held by a
+    /// reflected component or resource.
+    pub fn unowned_assets(&self, asset_server: &AssetServer) -> Vec<HandleId> {
+        let ref_counts = asset_server.server.asset_ref_counter.ref_counts.read();
+        let mut unowned: Vec<HandleId> = ref_counts
+            .iter()
+            .filter(|(id, count)| **count > 0 && !self.owners.contains_key(id))
+            .map(|(id, _)| *id)
+            .collect();
+        unowned.sort();
+        unowned
+    }
+}
+
+fn collect_strong_handles(
+    value: &dyn Reflect,
+    type_registry: &TypeRegistry,
+    handles: &mut Vec<HandleId>,
+) {
+    if let Some(reflect_handle) =
+        type_registry.get_type_data::<ReflectHandle>(value.any().type_id())
+    {
+        handles.extend(reflect_handle.strong_id(value));
+        return;
+    }
+    match value.reflect_ref() {
+        ReflectRef::Struct(value) => {
+            for field in value.iter_fields() {
+                collect_strong_handles(field, type_registry, handles);
+            }
+        }
+        ReflectRef::TupleStruct(value) => {
+            for field in value.iter_fields() {
+                collect_strong_handles(field, type_registry, handles);
+            }
+        }
+        ReflectRef::Tuple(value) => {
+            for field in value.iter_fields() {
+                collect_strong_handles(field, type_registry, handles);
+            }
+        }
+        ReflectRef::List(value) => {
+            for item in value.iter() {
+                collect_strong_handles(item, type_registry, handles);
+            }
+        }
+        ReflectRef::Map(value) => {
+            for (key, value) in value.iter() {
+                collect_strong_handles(key, type_registry, handles);
+                collect_strong_handles(value, type_registry, handles);
+            }
+        }
+        ReflectRef::Value(_) => {}
+    }
+}
+
+pub fn update_handle_owners_system(world: &mut World) {
+    world.resource_scope(|world, mut handle_owners: Mut<HandleOwners>| {
+        let type_registry = world.get_resource::<TypeRegistryArc>().unwrap();
+        handle_owners.update(world, &type_registry.read());
+    });
+}
+
+impl AssetServer {
+    /// Returns the number of strong handles to an asset, as of the last time unused assets were
+    /// freed.
+    pub fn get_ref_count<H: Into<HandleId>>(&self, handle: H) -> usize {
+        self.server
+            .asset_ref_counter
+            .ref_counts
+            .read()
+            .get(&handle.into())
+            .cloned()
+            .unwrap_or(0)
+    }
+
+    /// Describes which assets keep which others loaded, in the Graphviz DOT format. Assets point
+    /// at the dependencies their loaders declared, and the owners of their strong handles point
+    /// at them if `handle_owners` are given. Loaded assets without a known owner are red.
+    pub fn dependency_graph(&self, handle_owners: Option<&HandleOwners>) -> String {
+        let asset_sources = self.server.asset_sources.read();
+        let ref_counts = self.server.asset_ref_counter.ref_counts.read();
+        let describe = |id: HandleId| -> String {
+            let id = match id {
+                HandleId::AssetPathId(id) => id,
+                HandleId::Id(_, _) => return format!("{:?}", id),
+            };
+            let source_info = match asset_sources.get(&id.source_path_id()) {
+                Some(source_info) => source_info,
+                None => return format!("{:?}", id),
+            };
+            let mut description = String::new();
+            if let Some(source) = &source_info.source {
+                let _ = write!(description, "{}://", source);
+            }
+            let _ = write!(description, "{}", source_info.path.display());
+            let label = source_info
+                .meta
+                .iter()
+                .flat_map(|meta| meta.assets.iter())
+                .find_map(|asset| {
+                    asset
+                        .label
+                        .as_deref()
+                        .filter(|label| LabelId::from(Some(*label)) == id.label_id())
+                });
+            if let Some(label) = label {
+                let _ = write!(description, "#{}", label);
+            }
+            description
+        };
+
+        let mut graph = String::from("digraph assets {\n");
+        let mut owned = HashSet::default();
+        if let Some(handle_owners) = handle_owners {
+            for (id, owners) in handle_owners.iter() {
+                owned.insert(id);
+                for owner in owners {
+                    let owner = match owner {
+                        HandleOwner::Component { entity, component } => {
+                            format!("{:?} {}", entity, component)
+                        }
+                        HandleOwner::Resource { resource } => resource.to_string(),
+                    };
+                    let _ = writeln!(graph, "    {:?} [shape=box];", owner);
+                    let _ = writeln!(graph, "    {:?} -> {:?};", owner, describe(id));
+                }
+            }
+        }
+
+        let mut ids: Vec<HandleId> = ref_counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.sort();
+        for id in ids {
+            let color = if handle_owners.is_some() && !owned.contains(&id) {
+                " color=red"
+            } else {
+                ""
+            };
+            let description = describe(id);
+            let label = format!("{} ({} refs)", description, ref_counts[&id]);
+            let _ = writeln!(graph, "    {:?} [label={:?}{}];", description, label, color);
+        }
+
+        for source_info in asset_sources.values() {
+            for asset in source_info.meta.iter().flat_map(|meta| meta.assets.iter()) {
+                if asset.dependencies.is_empty() {
+                    continue;
+                }
+                let asset_path = AssetPath::new_ref(&source_info.path, asset.label.as_deref())
+                    .with_source(source_info.source.as_deref())
+                    .with_serialized_settings(source_info.settings.as_deref());
+                let from = describe(asset_path.into());
+                for dependency in asset.dependencies.iter() {
+                    let to = describe(dependency.get_id().into());
+                    let _ = writeln!(graph, "    {:?} -> {:?};", from, to);
+                }
+            }
+        }
+        graph.push_str("}\n");
+        graph
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddAsset, AssetPlugin, Assets, Handle};
+    use bevy_reflect::TypeUuid;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+
+    #[derive(TypeUuid)]
+    #[uuid = "44115972-f31b-46e5-be5c-2b9aece6a52f"]
+    struct Blob;
+
+    #[derive(Default, Reflect)]
+    #[reflect(Resource)]
+    struct Held {
+        blobs: Vec<Handle<Blob>>,
+    }
+
+    #[test]
+    fn find_handle_owners() {
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Blob>()
+            .add_plugin(HandleOwnerDiagnosticsPlugin)
+            .register_type::<Held>();
+
+        let mut assets = app.world_mut().get_resource_mut::<Assets<Blob>>().unwrap();
+        let held = assets.add(Blob);
+        let spawned = assets.add(Blob);
+        let leaked = assets.add(Blob);
+        std::mem::forget(leaked.clone());
+        let weak = spawned.clone_weak();
+        let entity = app.world_mut().spawn().insert(spawned).id();
+        app.world_mut().spawn().insert(weak);
+        app.insert_resource(Held {
+            blobs: vec![held.clone()],
+        });
+        app.app.update();
+
+        let world = &app.app.world;
+        let handle_owners = world.get_resource::<HandleOwners>().unwrap();
+        assert_eq!(
+            handle_owners.get(&held),
+            &[HandleOwner::Resource {
+                resource: std::any::type_name::<Held>()
+            }]
+        );
+        assert_eq!(
+            handle_owners.get(world.get::<Handle<Blob>>(entity).unwrap()),
+            &[HandleOwner::Component {
+                entity,
+                component: std::any::type_name::<Handle<Blob>>()
+            }]
+        );
+        // `held` and `leaked` are also held by this test, but only `leaked` is unowned in the world
+        let asset_server = world.get_resource::<AssetServer>().unwrap();
+        assert_eq!(handle_owners.unowned_assets(asset_server), vec![leaked.id]);
+        let graph = asset_server.dependency_graph(Some(handle_owners));
+        assert_eq!(graph.matches("color=red").count(), 1);
+    }
+}
@@ -1,2 +1,4 @@
 mod asset_count_diagnostics_plugin;
-pub use asset_count_diagnostics_plugin::AssetCountDiagnosticsPlugin;
+mod handle_owner_diagnostics_plugin;
+pub use asset_count_diagnostics_plugin::{AssetCountDiagnosticsPlugin, AssetMemoryUsage};
+pub use handle_owner_diagnostics_plugin::*;
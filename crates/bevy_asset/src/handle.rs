@@ -10,7 +10,7 @@ use crate::{
     Asset, Assets,
 };
 use bevy_ecs::reflect::ReflectComponent;
-use bevy_reflect::{Reflect, ReflectDeserialize};
+use bevy_reflect::{FromType, Reflect, ReflectDeserialize};
 use bevy_utils::Uuid;
 use crossbeam_channel::{Receiver, Sender};
 use serde::{Deserialize, Serialize};
@@ -59,7 +59,7 @@ impl HandleId {
 /// Handles contain a unique id that corresponds to a specific asset in the [Assets](crate::Assets)
 /// collection.
 #[derive(Reflect)]
-#[reflect(Component)]
+#[reflect(Component, Handle)]
 pub struct Handle<T>
 where
     T: Asset,
@@ -233,6 +233,38 @@ impl<T: Asset> Clone for Handle<T> {
 unsafe impl<T: Asset> Send for Handle<T> {}
 unsafe impl<T: Asset> Sync for Handle<T> {}
 
+/// Type data of every reflected [Handle], used to find the assets referenced by reflected values
+/// of any type.
+#[derive(Clone)]
+pub struct ReflectHandle {
+    get_handle: fn(&dyn Reflect) -> Option<(HandleId, bool)>,
+}
+
+impl ReflectHandle {
+    /// Returns the id of the reflected handle, if `handle` is the type this data belongs to.
+    pub fn id(&self, handle: &dyn Reflect) -> Option<HandleId> {
+        (self.get_handle)(handle).map(|(id, _)| id)
+    }
+
+    /// Returns the id of the reflected handle if it is strong, and so keeps its asset loaded.
+    pub fn strong_id(&self, handle: &dyn Reflect) -> Option<HandleId> {
+        (self.get_handle)(handle).and_then(|(id, strong)| if strong { Some(id) } else { None })
+    }
+}
+
+impl<T: Asset> FromType<Handle<T>> for ReflectHandle {
+    fn from_type() -> Self {
+        ReflectHandle {
+            get_handle: |handle| {
+                handle
+                    .any()
+                    .downcast_ref::<Handle<T>>()
+                    .map(|handle| (handle.id, handle.is_strong()))
+            },
+        }
+    }
+}
+
 /// A non-generic version of [Handle]
 ///
 /// This allows handles to be mingled in a cross asset context. For example, storing `Handle<A>` and
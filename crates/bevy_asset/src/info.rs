@@ -1,11 +1,61 @@
 use crate::{path::AssetPath, LabelId};
 use bevy_utils::{HashMap, HashSet, Uuid};
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::path::PathBuf;
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+/// What is known about an asset source. This is also the format of the `.meta` file next to a
+/// source, such as `textures/grass.png.meta`, which selects the [AssetProcessor] that turns the
+/// source into the artifact that is loaded, and the settings of that processor and of the loader:
+///
+/// ```ron
+/// (
+///     processor: Some("TextureCompressor"),
+///     processor_settings: Some((quality: 0.8)),
+///     loader_settings: Some((is_srgb: false)),
+/// )
+/// ```
+///
+/// Processors are named by their type name, with or without its module path. The
+/// `loader_settings` are read by the asset's loader with
+/// [LoadContext::settings](crate::LoadContext::settings). `assets` is filled in once the source
+/// is loaded.
+///
+/// [AssetProcessor]: crate::AssetProcessor
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
 pub struct SourceMeta {
+    #[serde(default)]
     pub assets: Vec<AssetMeta>,
+    #[serde(default)]
+    pub processor: Option<String>,
+    #[serde(default)]
+    pub processor_settings: Option<ron::Value>,
+    #[serde(default)]
+    pub loader_settings: Option<ron::Value>,
+}
+
+impl SourceMeta {
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ron::Error> {
+        ron::de::from_bytes(bytes)
+    }
+
+    /// The settings of the processor, or the default ones if the meta file has none.
+    pub fn processor_settings<S: DeserializeOwned + Default>(&self) -> Result<S, ron::Error> {
+        Self::settings(&self.processor_settings)
+    }
+
+    /// The settings of the loader, or the default ones if the meta file has none.
+    pub fn loader_settings<S: DeserializeOwned + Default>(&self) -> Result<S, ron::Error> {
+        Self::settings(&self.loader_settings)
+    }
+
+    fn settings<S: DeserializeOwned + Default>(
+        settings: &Option<ron::Value>,
+    ) -> Result<S, ron::Error> {
+        match settings {
+            Some(settings) => settings.clone().into_rust(),
+            None => Ok(S::default()),
+        }
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -19,7 +69,14 @@ pub struct AssetMeta {
 #[derive(Clone, Debug)]
 pub struct SourceInfo {
     pub meta: Option<SourceMeta>,
+    /// The named source the asset is loaded from, or `None` for the default source.
+    pub source: Option<String>,
     pub path: PathBuf,
+    /// The loader settings the asset is loaded with, serialized to RON.
+    pub settings: Option<String>,
+    /// Other files of the same source read by the asset's loader. The asset is reloaded when
+    /// they change.
+    pub read_paths: Vec<PathBuf>,
     pub asset_types: HashMap<LabelId, Uuid>,
     pub load_state: LoadState,
     pub committed_assets: HashSet<LabelId>,
@@ -0,0 +1,332 @@
+use crate::{AssetIo, AssetIoError, OverlayAssetIo};
+use anyhow::Result;
+use bevy_utils::{BoxedFuture, HashMap};
+use parking_lot::RwLock;
+use std::path::{Path, PathBuf};
+#[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+use {
+    crate::FileAssetIo,
+    std::sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// The name of the source [EmbeddedAssetIo] is mounted as by the
+/// [AssetPlugin](crate::AssetPlugin), so embedded assets are loaded from `embedded://` paths.
+pub const EMBEDDED_SOURCE: &str = "embedded";
+
+/// Bakes a file into the binary with `include_bytes!` and registers it with the app's
+/// [EmbeddedAssetIo]. The path is relative to the crate's `Cargo.toml`, and the asset is loaded
+/// from `embedded://` followed by the package name and that path, so crates embedding files at
+/// the same relative path don't collide.
+///
+/// ```ignore
+/// embedded_asset!(app, "assets/shaders/outline.frag")?;
+/// let shader: Handle<Shader> =
+///     asset_server.load("embedded://my_crate/assets/shaders/outline.frag");
+/// ```
+#[macro_export]
+macro_rules! embedded_asset {
+    ($app: expr, $path: expr) => {
+        $crate::AddAsset::add_embedded_asset(
+            $app,
+            env!("CARGO_PKG_NAME"),
+            $path,
+            include_bytes!(concat!(env!("CARGO_MANIFEST_DIR"), "/", $path)),
+            env!("CARGO_MANIFEST_DIR"),
+        )
+    };
+}
+
+struct EmbeddedAsset {
+    bytes: &'static [u8],
+    namespace: PathBuf,
+    /// The path of the source file, relative to `root`.
+    path: PathBuf,
+    /// The directory the asset path is relative to on the machine that built the binary.
+    root: PathBuf,
+}
+
+/// Serves assets compiled into the binary, usually registered with [embedded_asset!].
+///
+/// With the file fallback enabled, which is the default in debug builds, assets whose source file
+/// still exists on disk are read from it through a [FileAssetIo] instead, so they can be edited
+/// and hot reloaded during development.
+pub struct EmbeddedAssetIo {
+    assets: RwLock<HashMap<PathBuf, EmbeddedAsset>>,
+    file_fallback: bool,
+    /// The [FileAssetIo] of every namespace, rooted where its assets were embedded from.
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    fallbacks: RwLock<HashMap<PathBuf, Arc<FileAssetIo>>>,
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    watching_for_changes: AtomicBool,
+}
+
+impl Default for EmbeddedAssetIo {
+    fn default() -> Self {
+        Self::new(cfg!(debug_assertions))
+    }
+}
+
+impl EmbeddedAssetIo {
+    pub fn new(file_fallback: bool) -> Self {
+        EmbeddedAssetIo {
+            assets: Default::default(),
+            file_fallback,
+            #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+            fallbacks: Default::default(),
+            #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+            watching_for_changes: AtomicBool::new(false),
+        }
+    }
+
+    /// Whether assets are read from their source files when those still exist.
+    pub fn file_fallback(&self) -> bool {
+        self.file_fallback
+    }
+
+    /// Returns the [EmbeddedAssetIo] of a source, which may be a layer of an [OverlayAssetIo] if
+    /// other `AssetIo`s have been mounted over it.
+    pub fn find(asset_io: &dyn AssetIo) -> Option<&EmbeddedAssetIo> {
+        if let Some(embedded) = asset_io.downcast_ref::<EmbeddedAssetIo>() {
+            return Some(embedded);
+        }
+        asset_io
+            .downcast_ref::<OverlayAssetIo>()?
+            .layers()
+            .iter()
+            .rev()
+            .find_map(|layer| Self::find(layer.as_ref()))
+    }
+
+    /// Registers `bytes` at `namespace/path`. `root` is the directory `path` was relative to when
+    /// the bytes were embedded, which is where the file fallback looks for it. Each namespace has
+    /// a single root.
+    pub fn add_asset<N: Into<PathBuf>, P: Into<PathBuf>, R: Into<PathBuf>>(
+        &self,
+        namespace: N,
+        path: P,
+        bytes: &'static [u8],
+        root: R,
+    ) {
+        let asset = EmbeddedAsset {
+            bytes,
+            namespace: namespace.into(),
+            path: path.into(),
+            root: root.into(),
+        };
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        if self.file_fallback {
+            self.add_fallback(&asset.namespace, &asset.root);
+        }
+        self.assets
+            .write()
+            .insert(asset.namespace.join(&asset.path), asset);
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    fn add_fallback(&self, namespace: &Path, root: &Path) {
+        let mut fallbacks = self.fallbacks.write();
+        if fallbacks.contains_key(namespace) {
+            return;
+        }
+        let fallback = FileAssetIo::new(root);
+        if self.watching_for_changes.load(Ordering::Relaxed) {
+            if let Err(err) = fallback.watch_for_changes() {
+                bevy_log::warn!("failed to watch {} for changes: {}", root.display(), err);
+            }
+        }
+        fallbacks.insert(namespace.to_owned(), Arc::new(fallback));
+    }
+
+    /// Returns the [FileAssetIo] that should serve `path` instead of its embedded bytes, if any,
+    /// along with the path of the source file in it.
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    fn get_fallback(&self, path: &Path) -> Option<(Arc<FileAssetIo>, PathBuf)> {
+        if !self.file_fallback {
+            return None;
+        }
+        let assets = self.assets.read();
+        let asset = assets.get(path)?;
+        if !asset.root.join(&asset.path).is_file() {
+            return None;
+        }
+        let fallback = self.fallbacks.read().get(&asset.namespace).cloned()?;
+        Some((fallback, asset.path.clone()))
+    }
+
+    fn children(&self, path: &Path) -> Vec<PathBuf> {
+        let mut children = Vec::new();
+        for asset_path in self.assets.read().keys() {
+            let child = asset_path
+                .ancestors()
+                .find(|ancestor| ancestor.parent() == Some(path));
+            if let Some(child) = child {
+                if !children.iter().any(|existing| existing == child) {
+                    children.push(child.to_owned());
+                }
+            }
+        }
+        children
+    }
+}
+
+impl AssetIo for EmbeddedAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+            if let Some((fallback, source)) = self.get_fallback(path) {
+                return fallback.load_path(&source).await;
+            }
+            self.assets
+                .read()
+                .get(path)
+                .map(|asset| asset.bytes.to_vec())
+                .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let children = self.children(path);
+        if children.is_empty() {
+            return Err(AssetIoError::NotFound(path.to_owned()));
+        }
+        Ok(Box::new(children.into_iter()))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        !self.children(path).is_empty()
+    }
+
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        if let Some((fallback, source)) = self.get_fallback(_path) {
+            fallback.watch_path_for_changes(&source)?;
+        }
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        {
+            self.watching_for_changes.store(true, Ordering::Relaxed);
+            for fallback in self.fallbacks.read().values() {
+                fallback.watch_for_changes()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Writes to the source file of an embedded asset while the file fallback serves it.
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        _bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move {
+            #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+            if let Some((fallback, source)) = self.get_fallback(path) {
+                return fallback.save_path(&source, _bytes).await;
+            }
+            Err(AssetIoError::ReadOnly(path.to_owned()))
+        })
+    }
+
+    fn poll_changed_paths(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+        for (namespace, fallback) in self.fallbacks.read().iter() {
+            changed.extend(
+                fallback
+                    .poll_changed_paths()
+                    .into_iter()
+                    .map(|path| namespace.join(path)),
+            );
+        }
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(io: &EmbeddedAssetIo, path: &str) -> Result<Vec<u8>, AssetIoError> {
+        futures_lite::future::block_on(io.load_path(Path::new(path)))
+    }
+
+    #[test]
+    fn serve_embedded_bytes() {
+        let io = EmbeddedAssetIo::new(false);
+        io.add_asset("a", "shaders/outline.frag", b"outline", "/nonexistent/a");
+        io.add_asset("a", "shaders/post/blur.frag", b"blur", "/nonexistent/a");
+        io.add_asset("b", "shaders/outline.frag", b"other", "/nonexistent/b");
+
+        assert_eq!(load(&io, "a/shaders/outline.frag").unwrap(), b"outline");
+        assert_eq!(load(&io, "b/shaders/outline.frag").unwrap(), b"other");
+        assert!(load(&io, "shaders/outline.frag").is_err());
+        assert!(load(&io, "a/shaders/missing.frag").is_err());
+        assert!(io.is_directory(Path::new("a/shaders/post")));
+        assert!(!io.is_directory(Path::new("a/shaders/outline.frag")));
+
+        let mut children: Vec<PathBuf> =
+            io.read_directory(Path::new("a/shaders")).unwrap().collect();
+        children.sort();
+        assert_eq!(
+            children,
+            vec![
+                PathBuf::from("a/shaders/outline.frag"),
+                PathBuf::from("a/shaders/post")
+            ]
+        );
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    #[test]
+    fn file_fallback() {
+        let root = env!("CARGO_MANIFEST_DIR");
+        let io = EmbeddedAssetIo::new(true);
+        io.add_asset("bevy_asset", "Cargo.toml", b"stale", root);
+        io.add_asset("bevy_asset", "deleted.txt", b"embedded", root);
+
+        let on_disk = std::fs::read(Path::new(root).join("Cargo.toml")).unwrap();
+        assert_eq!(load(&io, "bevy_asset/Cargo.toml").unwrap(), on_disk);
+        assert_eq!(load(&io, "bevy_asset/deleted.txt").unwrap(), b"embedded");
+    }
+
+    #[test]
+    fn embedded_asset_macro() {
+        use crate::{AddAsset, AssetPlugin, AssetServer};
+        use bevy_app::App;
+        use bevy_tasks::{IoTaskPool, TaskPool};
+
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin);
+        // mounting another source over the embedded one keeps it reachable
+        app.add_asset_source(Some(EMBEDDED_SOURCE), EmbeddedAssetIo::new(false));
+        crate::embedded_asset!(&mut app, "Cargo.toml").unwrap();
+
+        let asset_io = app
+            .world()
+            .get_resource::<AssetServer>()
+            .unwrap()
+            .get_asset_io(Some(EMBEDDED_SOURCE))
+            .unwrap();
+        let embedded =
+            futures_lite::future::block_on(asset_io.load_path(Path::new("bevy_asset/Cargo.toml")))
+                .unwrap();
+        assert_eq!(embedded, include_bytes!("../../Cargo.toml"));
+
+        let mut app = App::build();
+        app.insert_resource(AssetServer::new(
+            crate::FileAssetIo::new("."),
+            TaskPool::new(),
+        ));
+        assert!(crate::embedded_asset!(&mut app, "Cargo.toml").is_err());
+    }
+}
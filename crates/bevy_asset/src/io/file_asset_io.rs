@@ -1,15 +1,19 @@
-use crate::{filesystem_watcher::FilesystemWatcher, AssetIo, AssetIoError, AssetServer};
+#[cfg(feature = "filesystem_watcher")]
+use crate::filesystem_watcher::FilesystemWatcher;
+use crate::{AssetIo, AssetIoError};
 use anyhow::Result;
-use bevy_ecs::system::Res;
-use bevy_utils::{BoxedFuture, HashSet};
+use bevy_utils::BoxedFuture;
+#[cfg(feature = "filesystem_watcher")]
 use crossbeam_channel::TryRecvError;
 use fs::File;
 use io::Read;
+#[cfg(feature = "filesystem_watcher")]
 use parking_lot::RwLock;
+#[cfg(feature = "filesystem_watcher")]
+use std::sync::Arc;
 use std::{
     env, fs, io,
     path::{Path, PathBuf},
-    sync::Arc,
 };
 
 pub struct FileAssetIo {
@@ -21,6 +25,7 @@ pub struct FileAssetIo {
 impl FileAssetIo {
     pub fn new<P: AsRef<Path>>(path: P) -> Self {
         FileAssetIo {
+            #[cfg(feature = "filesystem_watcher")]
             filesystem_watcher: Default::default(),
             root_path: Self::get_root_path().join(path.as_ref()),
         }
@@ -102,42 +107,53 @@ impl AssetIo for FileAssetIo {
     fn is_directory(&self, path: &Path) -> bool {
         self.root_path.join(path).is_dir()
     }
-}
 
-#[cfg(all(
-    feature = "filesystem_watcher",
-    all(not(target_arch = "wasm32"), not(target_os = "android"))
-))]
-pub fn filesystem_watcher_system(asset_server: Res<AssetServer>) {
-    let mut changed = HashSet::default();
-    let asset_io =
-        if let Some(asset_io) = asset_server.server.asset_io.downcast_ref::<FileAssetIo>() {
-            asset_io
-        } else {
-            return;
-        };
-    let watcher = asset_io.filesystem_watcher.read();
-    if let Some(ref watcher) = *watcher {
-        loop {
-            let event = match watcher.receiver.try_recv() {
-                Ok(result) => result.unwrap(),
-                Err(TryRecvError::Empty) => break,
-                Err(TryRecvError::Disconnected) => panic!("FilesystemWatcher disconnected."),
-            };
-            if let notify::event::Event {
-                kind: notify::event::EventKind::Modify(_),
-                paths,
-                ..
-            } = event
-            {
-                for path in paths.iter() {
-                    if !changed.contains(path) {
-                        let relative_path = path.strip_prefix(&asset_io.root_path).unwrap();
-                        let _ = asset_server.load_untracked(relative_path, true);
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move {
+            let full_path = self.root_path.join(path);
+            if let Some(parent) = full_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(full_path, bytes)?;
+            Ok(())
+        })
+    }
+
+    fn poll_changed_paths(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        #[cfg(feature = "filesystem_watcher")]
+        {
+            let watcher = self.filesystem_watcher.read();
+            if let Some(ref watcher) = *watcher {
+                loop {
+                    let event = match watcher.receiver.try_recv() {
+                        Ok(result) => result.unwrap(),
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => {
+                            panic!("FilesystemWatcher disconnected.")
+                        }
+                    };
+                    if let notify::event::Event {
+                        kind: notify::event::EventKind::Modify(_),
+                        paths,
+                        ..
+                    } = event
+                    {
+                        for path in paths.iter() {
+                            if let Ok(relative_path) = path.strip_prefix(&self.root_path) {
+                                if !changed.iter().any(|changed| changed == relative_path) {
+                                    changed.push(relative_path.to_owned());
+                                }
+                            }
+                        }
                     }
                 }
-                changed.extend(paths);
             }
         }
+        changed
     }
 }
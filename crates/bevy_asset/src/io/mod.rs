@@ -1,14 +1,20 @@
 #[cfg(target_os = "android")]
 mod android_asset_io;
+mod embedded_asset_io;
 #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
 mod file_asset_io;
+mod overlay_asset_io;
+mod pack_asset_io;
 #[cfg(target_arch = "wasm32")]
 mod wasm_asset_io;
 
 #[cfg(target_os = "android")]
 pub use android_asset_io::*;
+pub use embedded_asset_io::*;
 #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
 pub use file_asset_io::*;
+pub use overlay_asset_io::*;
+pub use pack_asset_io::*;
 #[cfg(target_arch = "wasm32")]
 pub use wasm_asset_io::*;
 
@@ -30,6 +36,8 @@ pub enum AssetIoError {
     Io(#[from] io::Error),
     #[error("failed to watch path: {0}")]
     PathWatchError(PathBuf),
+    #[error("can't write to a read-only asset source: {0}")]
+    ReadOnly(PathBuf),
 }
 
 /// Handles load requests from an AssetServer
@@ -42,6 +50,22 @@ pub trait AssetIo: Downcast + Send + Sync + 'static {
     fn is_directory(&self, path: &Path) -> bool;
     fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError>;
     fn watch_for_changes(&self) -> Result<(), AssetIoError>;
+
+    /// Returns the watched paths that changed since the last call, relative to the root of this
+    /// `AssetIo`. The `AssetServer` polls every mounted source and reloads the returned paths.
+    fn poll_changed_paths(&self) -> Vec<PathBuf> {
+        Vec::new()
+    }
+
+    /// Writes `bytes` to the file at `path`, replacing it if it exists. Sources are read-only
+    /// unless they implement this.
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        _bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move { Err(AssetIoError::ReadOnly(path.to_owned())) })
+    }
 }
 
 impl_downcast!(AssetIo);
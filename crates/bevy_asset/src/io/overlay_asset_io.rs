@@ -0,0 +1,277 @@
+use crate::{AssetIo, AssetIoError};
+use anyhow::Result;
+use bevy_utils::{BoxedFuture, HashSet};
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Layers several [AssetIo]s on top of each other, such as mods over the base game content.
+///
+/// Layers mounted later shadow earlier ones: a path is loaded from the last layer that has it, and
+/// directories list the files of every layer.
+#[derive(Default, Clone)]
+pub struct OverlayAssetIo {
+    layers: Vec<Arc<dyn AssetIo>>,
+}
+
+impl OverlayAssetIo {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_layer<T: AssetIo>(mut self, asset_io: T) -> Self {
+        self.mount(Arc::new(asset_io));
+        self
+    }
+
+    /// Adds `asset_io` on top of the existing layers.
+    pub fn mount(&mut self, asset_io: Arc<dyn AssetIo>) {
+        self.layers.push(asset_io);
+    }
+
+    /// The mounted layers, from the bottom one up.
+    pub fn layers(&self) -> &[Arc<dyn AssetIo>] {
+        &self.layers
+    }
+}
+
+impl AssetIo for OverlayAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            for layer in self.layers.iter().rev() {
+                match layer.load_path(path).await {
+                    Err(AssetIoError::NotFound(_)) => continue,
+                    result => return result,
+                }
+            }
+            Err(AssetIoError::NotFound(path.to_owned()))
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let mut found = false;
+        let mut seen = HashSet::default();
+        let mut children = Vec::new();
+        for layer in self.layers.iter().filter(|layer| layer.is_directory(path)) {
+            found = true;
+            for child in layer.read_directory(path)? {
+                if seen.insert(child.clone()) {
+                    children.push(child);
+                }
+            }
+        }
+
+        if found {
+            Ok(Box::new(children.into_iter()))
+        } else {
+            Err(AssetIoError::NotFound(path.to_owned()))
+        }
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.layers.iter().any(|layer| layer.is_directory(path))
+    }
+
+    /// Watches the path in every layer that has it, so edits to the layer that shadows it and
+    /// to the layers below are both picked up. This only fails if no layer can watch the path, with
+    /// the error of the top-most layer.
+    fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+        let mut watched = false;
+        let mut first_error = None;
+        for layer in self.layers.iter().rev() {
+            match layer.watch_path_for_changes(path) {
+                Ok(()) => watched = true,
+                Err(err) => {
+                    first_error.get_or_insert(err);
+                }
+            }
+        }
+        match first_error {
+            Some(err) if !watched => Err(err),
+            _ => Ok(()),
+        }
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        for layer in self.layers.iter() {
+            layer.watch_for_changes()?;
+        }
+        Ok(())
+    }
+
+    fn poll_changed_paths(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+        for layer in self.layers.iter() {
+            for path in layer.poll_changed_paths() {
+                if !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+        changed
+    }
+
+    /// Writes to the top-most layer that isn't read-only.
+    fn save_path<'a>(
+        &'a self,
+        path: &'a Path,
+        bytes: &'a [u8],
+    ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+        Box::pin(async move {
+            for layer in self.layers.iter().rev() {
+                match layer.save_path(path, bytes).await {
+                    Err(AssetIoError::ReadOnly(_)) => continue,
+                    result => return result,
+                }
+            }
+            Err(AssetIoError::ReadOnly(path.to_owned()))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_utils::HashMap;
+    use parking_lot::Mutex;
+
+    struct MemoryAssetIo(HashMap<PathBuf, Vec<u8>>, Arc<Mutex<Vec<PathBuf>>>);
+
+    impl MemoryAssetIo {
+        fn new(files: &[(&str, &str)]) -> Self {
+            Self(
+                files
+                    .iter()
+                    .map(|(path, contents)| (PathBuf::from(path), contents.as_bytes().to_vec()))
+                    .collect(),
+                Default::default(),
+            )
+        }
+    }
+
+    impl AssetIo for MemoryAssetIo {
+        fn load_path<'a>(
+            &'a self,
+            path: &'a Path,
+        ) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+            Box::pin(async move {
+                self.0
+                    .get(path)
+                    .cloned()
+                    .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))
+            })
+        }
+
+        fn read_directory(
+            &self,
+            path: &Path,
+        ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+            let path = path.to_owned();
+            let children: Vec<PathBuf> = self
+                .0
+                .keys()
+                .filter(|child| child.parent() == Some(&path))
+                .cloned()
+                .collect();
+            Ok(Box::new(children.into_iter()))
+        }
+
+        fn is_directory(&self, path: &Path) -> bool {
+            self.0.keys().any(|child| child.parent() == Some(path))
+        }
+
+        fn watch_path_for_changes(&self, path: &Path) -> Result<(), AssetIoError> {
+            if self.0.contains_key(path) {
+                self.1.lock().push(path.to_owned());
+                Ok(())
+            } else {
+                Err(AssetIoError::PathWatchError(path.to_owned()))
+            }
+        }
+
+        fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+            Ok(())
+        }
+    }
+
+    fn overlay() -> OverlayAssetIo {
+        OverlayAssetIo::new()
+            .with_layer(MemoryAssetIo::new(&[
+                ("textures/grass.png", "base grass"),
+                ("textures/rock.png", "base rock"),
+            ]))
+            .with_layer(MemoryAssetIo::new(&[
+                ("textures/grass.png", "mod grass"),
+                ("textures/lava.png", "mod lava"),
+            ]))
+    }
+
+    #[test]
+    fn later_layers_shadow_earlier_ones() {
+        let io = overlay();
+        let load = |path: &str| {
+            futures_lite::future::block_on(io.load_path(Path::new(path)))
+                .map(|bytes| String::from_utf8(bytes).unwrap())
+        };
+        assert_eq!(load("textures/grass.png").unwrap(), "mod grass");
+        assert_eq!(load("textures/rock.png").unwrap(), "base rock");
+        assert_eq!(load("textures/lava.png").unwrap(), "mod lava");
+        assert!(matches!(
+            load("textures/sand.png"),
+            Err(AssetIoError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn directories_are_merged() {
+        let io = overlay();
+        assert!(io.is_directory(Path::new("textures")));
+        let mut children: Vec<PathBuf> =
+            io.read_directory(Path::new("textures")).unwrap().collect();
+        children.sort();
+        assert_eq!(
+            children,
+            vec![
+                PathBuf::from("textures/grass.png"),
+                PathBuf::from("textures/lava.png"),
+                PathBuf::from("textures/rock.png"),
+            ]
+        );
+    }
+
+    #[test]
+    fn paths_are_watched_in_every_layer_that_has_them() {
+        let base = MemoryAssetIo::new(&[("textures/grass.png", "base grass")]);
+        let base_watched = base.1.clone();
+        let overlay = MemoryAssetIo::new(&[
+            ("textures/grass.png", "mod grass"),
+            ("textures/lava.png", "mod lava"),
+        ]);
+        let overlay_watched = overlay.1.clone();
+        let io = OverlayAssetIo::new().with_layer(base).with_layer(overlay);
+
+        io.watch_path_for_changes(Path::new("textures/grass.png"))
+            .unwrap();
+        io.watch_path_for_changes(Path::new("textures/lava.png"))
+            .unwrap();
+        assert!(matches!(
+            io.watch_path_for_changes(Path::new("textures/sand.png")),
+            Err(AssetIoError::PathWatchError(_))
+        ));
+        assert_eq!(
+            *overlay_watched.lock(),
+            vec![
+                PathBuf::from("textures/grass.png"),
+                PathBuf::from("textures/lava.png")
+            ]
+        );
+        assert_eq!(
+            *base_watched.lock(),
+            vec![PathBuf::from("textures/grass.png")]
+        );
+    }
+}
@@ -0,0 +1,545 @@
+use crate::{AssetIo, AssetIoError};
+use anyhow::Result;
+use bevy_utils::{BoxedFuture, HashMap};
+use std::{
+    borrow::Cow,
+    convert::{TryFrom, TryInto},
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Component, Path, PathBuf},
+};
+
+const PACK_MAGIC: &[u8; 8] = b"BEVYPACK";
+const PACK_VERSION: u32 = 1;
+
+/// How an entry is stored in an asset pack
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PackCompression {
+    None,
+    /// Raw deflate. Entries that don't get smaller are stored uncompressed instead.
+    Deflate,
+}
+
+impl PackCompression {
+    fn to_byte(self) -> u8 {
+        match self {
+            PackCompression::None => 0,
+            PackCompression::Deflate => 1,
+        }
+    }
+
+    fn from_byte(byte: u8) -> io::Result<Self> {
+        match byte {
+            0 => Ok(PackCompression::None),
+            1 => Ok(PackCompression::Deflate),
+            _ => Err(invalid_pack(format!("unknown compression {}", byte))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct PackEntry {
+    offset: u64,
+    stored_size: u64,
+    size: u64,
+    compression: PackCompression,
+}
+
+enum PackData {
+    File(PathBuf),
+    Memory(Cow<'static, [u8]>),
+}
+
+/// Serves assets from a single pack file built with [PackBuilder], instead of loose files.
+///
+/// The pack can live on disk ([PackAssetIo::open]) or in memory, which includes packs embedded in
+/// the binary with `include_bytes!` ([PackAssetIo::from_static]). Only the index is read up front;
+/// entries are read and decompressed when they are loaded.
+pub struct PackAssetIo {
+    data: PackData,
+    entries: HashMap<String, PackEntry>,
+    directories: HashMap<String, Vec<PathBuf>>,
+}
+
+impl PackAssetIo {
+    /// Opens the pack at `path`, relative to the same root as [FileAssetIo](crate::FileAssetIo).
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self, AssetIoError> {
+        let path = crate::FileAssetIo::get_root_path().join(path.as_ref());
+        let mut file = File::open(&path).map_err(|err| {
+            if err.kind() == io::ErrorKind::NotFound {
+                AssetIoError::NotFound(path.clone())
+            } else {
+                err.into()
+            }
+        })?;
+        let entries = read_index(&mut file)?;
+        check_bounds(&entries, file.metadata()?.len())?;
+        Ok(Self::with_entries(PackData::File(path), entries))
+    }
+
+    pub fn from_static(bytes: &'static [u8]) -> Result<Self, AssetIoError> {
+        Self::from_data(Cow::Borrowed(bytes))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<Self, AssetIoError> {
+        Self::from_data(Cow::Owned(bytes))
+    }
+
+    fn from_data(bytes: Cow<'static, [u8]>) -> Result<Self, AssetIoError> {
+        let entries = read_index(&mut io::Cursor::new(bytes.as_ref()))?;
+        check_bounds(&entries, bytes.len() as u64)?;
+        Ok(Self::with_entries(PackData::Memory(bytes), entries))
+    }
+
+    fn with_entries(data: PackData, entries: HashMap<String, PackEntry>) -> Self {
+        let mut directories: HashMap<String, Vec<PathBuf>> = HashMap::default();
+        for path in entries.keys() {
+            let mut child = path.as_str();
+            while let Some(index) = child.rfind('/') {
+                let parent = &child[..index];
+                let children = directories.entry(parent.to_string()).or_default();
+                let is_new_directory = children.is_empty();
+                children.push(PathBuf::from(child));
+                if !is_new_directory {
+                    break;
+                }
+                child = parent;
+            }
+            if !child.contains('/') {
+                let children = directories.entry(String::new()).or_default();
+                if !children.iter().any(|existing| existing == Path::new(child)) {
+                    children.push(PathBuf::from(child));
+                }
+            }
+        }
+
+        PackAssetIo {
+            data,
+            entries,
+            directories,
+        }
+    }
+
+    /// The paths of every file in the pack.
+    pub fn paths(&self) -> impl Iterator<Item = &Path> {
+        self.entries.keys().map(Path::new)
+    }
+
+    fn read_entry(&self, entry: &PackEntry) -> io::Result<Vec<u8>> {
+        let stored = match &self.data {
+            PackData::File(path) => {
+                let mut file = File::open(path)?;
+                // the file may have been replaced since its index was read
+                check_entry_bounds(entry, file.metadata()?.len())?;
+                file.seek(SeekFrom::Start(entry.offset))?;
+                let mut stored = vec![0; entry.stored_size as usize];
+                file.read_exact(&mut stored)?;
+                Cow::Owned(stored)
+            }
+            PackData::Memory(bytes) => {
+                let start = entry.offset as usize;
+                Cow::Borrowed(&bytes[start..start + entry.stored_size as usize])
+            }
+        };
+
+        let bytes = match entry.compression {
+            PackCompression::None => stored.into_owned(),
+            PackCompression::Deflate => inflate(&stored, entry.size)?,
+        };
+        if bytes.len() as u64 != entry.size {
+            return Err(invalid_pack(
+                "entry size does not match the index".to_string(),
+            ));
+        }
+        Ok(bytes)
+    }
+}
+
+impl AssetIo for PackAssetIo {
+    fn load_path<'a>(&'a self, path: &'a Path) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+        Box::pin(async move {
+            let entry = self
+                .entries
+                .get(&pack_path(path))
+                .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))?;
+            Ok(self.read_entry(entry)?)
+        })
+    }
+
+    fn read_directory(
+        &self,
+        path: &Path,
+    ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+        let children = self
+            .directories
+            .get(&pack_path(path))
+            .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))?;
+        Ok(Box::new(children.clone().into_iter()))
+    }
+
+    fn is_directory(&self, path: &Path) -> bool {
+        self.directories.contains_key(&pack_path(path))
+    }
+
+    fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+
+    fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+        Ok(())
+    }
+}
+
+/// Builds an asset pack that can be served by [PackAssetIo].
+///
+/// ```no_run
+/// # use bevy_asset::{PackBuilder, PackCompression};
+/// # use std::fs::File;
+/// let mut builder = PackBuilder::default();
+/// builder.add_directory("assets", PackCompression::Deflate).unwrap();
+/// builder.write(&mut File::create("assets.pack").unwrap()).unwrap();
+/// ```
+#[derive(Default)]
+pub struct PackBuilder {
+    entries: Vec<(String, Vec<u8>, PackCompression)>,
+}
+
+impl PackBuilder {
+    /// Adds a file at `path`, replacing any file previously added at the same path.
+    pub fn add_file<P: AsRef<Path>>(
+        &mut self,
+        path: P,
+        bytes: Vec<u8>,
+        compression: PackCompression,
+    ) -> &mut Self {
+        let path = pack_path(path.as_ref());
+        self.entries.retain(|(existing, _, _)| *existing != path);
+        self.entries.push((path, bytes, compression));
+        self
+    }
+
+    /// Recursively adds every file in the `root` directory, with paths relative to `root`.
+    pub fn add_directory<P: AsRef<Path>>(
+        &mut self,
+        root: P,
+        compression: PackCompression,
+    ) -> io::Result<&mut Self> {
+        let root = root.as_ref();
+        let mut directories = vec![root.to_path_buf()];
+        while let Some(directory) = directories.pop() {
+            for entry in fs::read_dir(&directory)? {
+                let path = entry?.path();
+                if path.is_dir() {
+                    directories.push(path);
+                } else {
+                    let relative_path = path.strip_prefix(root).unwrap().to_owned();
+                    self.add_file(relative_path, fs::read(&path)?, compression);
+                }
+            }
+        }
+        Ok(self)
+    }
+
+    /// Writes the pack: a header, the index of every entry, then the entries' data.
+    pub fn write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        let mut entries = Vec::with_capacity(self.entries.len());
+        for (path, bytes, compression) in self.entries.iter() {
+            let compressed = match compression {
+                PackCompression::None => None,
+                PackCompression::Deflate => Some(miniz_oxide::deflate::compress_to_vec(bytes, 6))
+                    .filter(|compressed| compressed.len() < bytes.len()),
+            };
+            match compressed {
+                Some(compressed) => entries.push((
+                    path,
+                    Cow::Owned(compressed),
+                    bytes.len(),
+                    PackCompression::Deflate,
+                )),
+                None => entries.push((
+                    path,
+                    Cow::Borrowed(bytes.as_slice()),
+                    bytes.len(),
+                    PackCompression::None,
+                )),
+            }
+        }
+
+        let index_size: usize = entries
+            .iter()
+            .map(|(path, ..)| 2 + path.len() + 8 + 8 + 8 + 1)
+            .sum();
+        let mut offset = (PACK_MAGIC.len() + 4 + 4 + index_size) as u64;
+
+        writer.write_all(PACK_MAGIC)?;
+        writer.write_all(&PACK_VERSION.to_le_bytes())?;
+        writer.write_all(&(entries.len() as u32).to_le_bytes())?;
+        for (path, stored, size, compression) in entries.iter() {
+            let path_len: u16 = path
+                .len()
+                .try_into()
+                .map_err(|_| invalid_pack(format!("path is too long: {}", path)))?;
+            writer.write_all(&path_len.to_le_bytes())?;
+            writer.write_all(path.as_bytes())?;
+            writer.write_all(&offset.to_le_bytes())?;
+            writer.write_all(&(stored.len() as u64).to_le_bytes())?;
+            writer.write_all(&(*size as u64).to_le_bytes())?;
+            writer.write_all(&[compression.to_byte()])?;
+            offset += stored.len() as u64;
+        }
+        for (_, stored, ..) in entries.iter() {
+            writer.write_all(stored)?;
+        }
+        writer.flush()
+    }
+}
+
+fn read_index<R: Read>(reader: &mut R) -> io::Result<HashMap<String, PackEntry>> {
+    let mut magic = [0; 8];
+    reader.read_exact(&mut magic)?;
+    if &magic != PACK_MAGIC {
+        return Err(invalid_pack("missing pack header".to_string()));
+    }
+    let version = read_u32(reader)?;
+    if version != PACK_VERSION {
+        return Err(invalid_pack(format!("unsupported version {}", version)));
+    }
+
+    let count = read_u32(reader)?;
+    let mut entries = HashMap::default();
+    for _ in 0..count {
+        let mut path_len = [0; 2];
+        reader.read_exact(&mut path_len)?;
+        let mut path = vec![0; u16::from_le_bytes(path_len) as usize];
+        reader.read_exact(&mut path)?;
+        let path = String::from_utf8(path)
+            .map_err(|_| invalid_pack("entry path is not valid UTF-8".to_string()))?;
+        let offset = read_u64(reader)?;
+        let stored_size = read_u64(reader)?;
+        let size = read_u64(reader)?;
+        let mut compression = [0];
+        reader.read_exact(&mut compression)?;
+        entries.insert(
+            path,
+            PackEntry {
+                offset,
+                stored_size,
+                size,
+                compression: PackCompression::from_byte(compression[0])?,
+            },
+        );
+    }
+    Ok(entries)
+}
+
+/// Checks that the data of every entry is inside a pack of `pack_len` bytes, before anything is
+/// allocated for it
+fn check_bounds(entries: &HashMap<String, PackEntry>, pack_len: u64) -> io::Result<()> {
+    for (path, entry) in entries.iter() {
+        check_entry_bounds(entry, pack_len)
+            .map_err(|_| invalid_pack(format!("entry {} is out of bounds", path)))?;
+    }
+    Ok(())
+}
+
+fn check_entry_bounds(entry: &PackEntry, pack_len: u64) -> io::Result<()> {
+    match entry.offset.checked_add(entry.stored_size) {
+        Some(end) if end <= pack_len => Ok(()),
+        _ => Err(invalid_pack("entry is out of bounds".to_string())),
+    }
+}
+
+/// Decompresses a deflated entry, failing as soon as it inflates past the `size` recorded in the
+/// index. The output grows with the decompressed data, so a crafted size doesn't allocate
+/// anything up front.
+fn inflate(stored: &[u8], size: u64) -> io::Result<Vec<u8>> {
+    use miniz_oxide::inflate::{
+        core::{decompress, inflate_flags, DecompressorOxide},
+        TINFLStatus,
+    };
+
+    let size = usize::try_from(size)
+        .map_err(|_| invalid_pack("entry size does not match the index".to_string()))?;
+    let mut bytes = vec![0; size.min(stored.len().saturating_mul(2))];
+    let mut decompressor = Box::<DecompressorOxide>::default();
+    let mut in_pos = 0;
+    let mut out_pos = 0;
+    loop {
+        let (status, in_consumed, out_consumed) = {
+            let mut cursor = io::Cursor::new(bytes.as_mut_slice());
+            cursor.set_position(out_pos as u64);
+            decompress(
+                &mut decompressor,
+                &stored[in_pos..],
+                &mut cursor,
+                inflate_flags::TINFL_FLAG_USING_NON_WRAPPING_OUTPUT_BUF,
+            )
+        };
+        in_pos += in_consumed;
+        out_pos += out_consumed;
+        match status {
+            TINFLStatus::Done => {
+                bytes.truncate(out_pos);
+                return Ok(bytes);
+            }
+            TINFLStatus::HasMoreOutput if bytes.len() < size => {
+                let len = bytes.len().saturating_mul(2).max(1).min(size);
+                bytes.resize(len, 0);
+            }
+            TINFLStatus::HasMoreOutput => {
+                return Err(invalid_pack(
+                    "entry size does not match the index".to_string(),
+                ))
+            }
+            status => {
+                return Err(invalid_pack(format!(
+                    "failed to decompress entry: {:?}",
+                    status
+                )))
+            }
+        }
+    }
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Pack paths always use `/` separators, whatever the platform.
+fn pack_path(path: &Path) -> String {
+    let mut pack_path = String::new();
+    for component in path.components() {
+        if let Component::Normal(component) = component {
+            if !pack_path.is_empty() {
+                pack_path.push('/');
+            }
+            pack_path.push_str(&component.to_string_lossy());
+        }
+    }
+    pack_path
+}
+
+fn invalid_pack(message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("invalid asset pack: {}", message),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pack() -> PackAssetIo {
+        let mut builder = PackBuilder::default();
+        builder
+            .add_file("scenes/level.scn", b"level".to_vec(), PackCompression::None)
+            .add_file(
+                "textures/rpg/chars/hero.png",
+                vec![7; 1024],
+                PackCompression::Deflate,
+            )
+            .add_file(
+                "textures/rpg/tiles/grass.png",
+                b"grass".to_vec(),
+                PackCompression::Deflate,
+            );
+        let mut bytes = Vec::new();
+        builder.write(&mut bytes).unwrap();
+        PackAssetIo::from_bytes(bytes).unwrap()
+    }
+
+    fn load(io: &PackAssetIo, path: &str) -> Result<Vec<u8>, AssetIoError> {
+        futures_lite::future::block_on(io.load_path(Path::new(path)))
+    }
+
+    #[test]
+    fn load_entries() {
+        let io = pack();
+        assert_eq!(load(&io, "scenes/level.scn").unwrap(), b"level");
+        assert_eq!(
+            load(&io, "textures/rpg/chars/hero.png").unwrap(),
+            vec![7; 1024]
+        );
+        // too small to benefit from compression
+        assert_eq!(load(&io, "textures/rpg/tiles/grass.png").unwrap(), b"grass");
+        assert!(matches!(
+            load(&io, "textures/missing.png"),
+            Err(AssetIoError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn directories() {
+        let io = pack();
+        assert!(io.is_directory(Path::new("textures/rpg")));
+        assert!(io.is_directory(Path::new("")));
+        assert!(!io.is_directory(Path::new("scenes/level.scn")));
+
+        let mut children: Vec<PathBuf> = io
+            .read_directory(Path::new("textures/rpg"))
+            .unwrap()
+            .collect();
+        children.sort();
+        assert_eq!(
+            children,
+            vec![
+                PathBuf::from("textures/rpg/chars"),
+                PathBuf::from("textures/rpg/tiles")
+            ]
+        );
+
+        let mut root: Vec<PathBuf> = io.read_directory(Path::new("")).unwrap().collect();
+        root.sort();
+        assert_eq!(
+            root,
+            vec![PathBuf::from("scenes"), PathBuf::from("textures")]
+        );
+    }
+
+    #[test]
+    fn invalid_pack() {
+        assert!(PackAssetIo::from_bytes(b"not a pack".to_vec()).is_err());
+    }
+
+    fn pack_with_index(rewrite: impl FnOnce(&mut PackEntry)) -> Vec<u8> {
+        let mut builder = PackBuilder::default();
+        builder.add_file("hero.png", vec![7; 1024], PackCompression::Deflate);
+        let mut bytes = Vec::new();
+        builder.write(&mut bytes).unwrap();
+        let mut entry = read_index(&mut io::Cursor::new(bytes.as_slice()))
+            .unwrap()
+            .remove("hero.png")
+            .unwrap();
+        rewrite(&mut entry);
+        // the fields of the only entry follow the header and its path
+        let fields = PACK_MAGIC.len() + 4 + 4 + 2 + "hero.png".len();
+        bytes[fields..fields + 8].copy_from_slice(&entry.offset.to_le_bytes());
+        bytes[fields + 8..fields + 16].copy_from_slice(&entry.stored_size.to_le_bytes());
+        bytes[fields + 16..fields + 24].copy_from_slice(&entry.size.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn crafted_sizes_are_rejected() {
+        let out_of_bounds = pack_with_index(|entry| entry.stored_size = u64::MAX);
+        assert!(PackAssetIo::from_bytes(out_of_bounds).is_err());
+
+        let understated = pack_with_index(|entry| entry.size = 16);
+        let io = PackAssetIo::from_bytes(understated).unwrap();
+        assert!(load(&io, "hero.png").is_err());
+
+        let overstated = pack_with_index(|entry| entry.size = u64::MAX);
+        let io = PackAssetIo::from_bytes(overstated).unwrap();
+        assert!(load(&io, "hero.png").is_err());
+    }
+}
@@ -11,6 +11,8 @@ mod info;
 mod io;
 mod loader;
 mod path;
+mod processor;
+mod saver;
 
 pub mod prelude {
     pub use crate::{AddAsset, AssetEvent, AssetServer, Assets, Handle, HandleUntyped};
@@ -24,6 +26,8 @@ pub use info::*;
 pub use io::*;
 pub use loader::*;
 pub use path::*;
+pub use processor::*;
+pub use saver::*;
 
 use bevy_app::{prelude::Plugin, AppBuilder};
 use bevy_ecs::{
@@ -46,12 +50,17 @@ pub struct AssetPlugin;
 
 pub struct AssetServerSettings {
     pub asset_folder: String,
+    pub processing: AssetProcessingMode,
+    /// The folder processed assets are cached in, next to the asset folder
+    pub processed_asset_folder: String,
 }
 
 impl Default for AssetServerSettings {
     fn default() -> Self {
         Self {
             asset_folder: "assets".to_string(),
+            processing: AssetProcessingMode::Disabled,
+            processed_asset_folder: "processed_assets".to_string(),
         }
     }
 }
@@ -75,6 +84,27 @@ pub fn create_platform_default_asset_io(app: &mut AppBuilder) -> Box<dyn AssetIo
     Box::new(source)
 }
 
+/// Create the cache of processed assets for the platform, as configured by the
+/// [AssetServerSettings]. Returns `None` if assets aren't processed.
+pub fn create_platform_default_processed_assets(app: &mut AppBuilder) -> Option<ProcessedAssets> {
+    let settings = app
+        .world_mut()
+        .get_resource_or_insert_with(AssetServerSettings::default);
+    let folder = &settings.processed_asset_folder;
+
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    let asset_io = FileAssetIo::new(folder);
+    #[cfg(target_arch = "wasm32")]
+    let asset_io = WasmAssetIo::new(folder);
+    #[cfg(target_os = "android")]
+    let asset_io = AndroidAssetIo::new(folder);
+
+    match settings.processing {
+        AssetProcessingMode::Disabled => None,
+        mode => Some(ProcessedAssets::new(mode, Box::new(asset_io))),
+    }
+}
+
 impl Plugin for AssetPlugin {
     fn build(&self, app: &mut AppBuilder) {
         if app.world().get_resource::<AssetServer>().is_none() {
@@ -88,10 +118,18 @@ impl Plugin for AssetPlugin {
             let source = create_platform_default_asset_io(app);
 
             let asset_server = AssetServer::with_boxed_io(source, task_pool);
+            asset_server.set_processed_assets(create_platform_default_processed_assets(app));
 
             app.insert_resource(asset_server);
         }
 
+        {
+            let asset_server = app.world().get_resource::<AssetServer>().unwrap();
+            if asset_server.get_asset_io(Some(EMBEDDED_SOURCE)).is_err() {
+                asset_server.mount(Some(EMBEDDED_SOURCE), EmbeddedAssetIo::default());
+            }
+        }
+
         app.add_stage_before(
             bevy_app::CoreStage::PreUpdate,
             AssetStage::LoadAssets,
@@ -103,9 +141,14 @@ impl Plugin for AssetPlugin {
             SystemStage::parallel(),
         )
         .register_type::<HandleId>()
+        .add_event::<AssetLoadFailed>()
         .add_system_to_stage(
             bevy_app::CoreStage::PreUpdate,
             asset_server::free_unused_assets_system.system(),
+        )
+        .add_system_to_stage(
+            AssetStage::AssetEvents,
+            asset_server::asset_load_failed_system.system(),
         );
 
         #[cfg(all(
@@ -114,7 +157,7 @@ impl Plugin for AssetPlugin {
         ))]
         app.add_system_to_stage(
             AssetStage::LoadAssets,
-            io::filesystem_watcher_system.system(),
+            asset_server::filesystem_watcher_system.system(),
         );
     }
 }
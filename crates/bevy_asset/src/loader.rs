@@ -1,6 +1,6 @@
 use crate::{
-    path::AssetPath, AssetIo, AssetIoError, AssetMeta, AssetServer, Assets, Handle, HandleId,
-    RefChangeChannel,
+    path::AssetPath, processor::meta_path, AssetIo, AssetIoError, AssetMeta, AssetServer, Assets,
+    Handle, HandleId, RefChangeChannel, SourceMeta,
 };
 use anyhow::Result;
 use bevy_ecs::{
@@ -11,7 +11,12 @@ use bevy_reflect::{TypeUuid, TypeUuidDynamic};
 use bevy_utils::{BoxedFuture, HashMap};
 use crossbeam_channel::{Receiver, Sender};
 use downcast_rs::{impl_downcast, Downcast};
-use std::path::Path;
+use parking_lot::Mutex;
+use serde::de::DeserializeOwned;
+use std::{
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+};
 
 /// A loader for an asset source
 pub trait AssetLoader: Send + Sync + 'static {
@@ -76,13 +81,22 @@ pub struct LoadContext<'a> {
     pub(crate) ref_change_channel: &'a RefChangeChannel,
     pub(crate) asset_io: &'a dyn AssetIo,
     pub(crate) labeled_assets: HashMap<Option<String>, BoxedLoadedAsset>,
+    pub(crate) source: Option<&'a str>,
     pub(crate) path: &'a Path,
+    pub(crate) settings: Option<&'a str>,
+    /// The asset's meta file, or `None` if it hasn't been read yet.
+    pub(crate) meta: Option<Option<Vec<u8>>>,
+    /// The other files read with [LoadContext::read_asset_bytes].
+    pub(crate) read_paths: Mutex<Vec<PathBuf>>,
+    /// Whether [LoadContext::settings] fell back to the meta file.
+    pub(crate) read_meta: AtomicBool,
     pub(crate) version: usize,
 }
 
 impl<'a> LoadContext<'a> {
     pub(crate) fn new(
-        path: &'a Path,
+        asset_path: &'a AssetPath<'a>,
+        meta: Option<Option<Vec<u8>>>,
         ref_change_channel: &'a RefChangeChannel,
         asset_io: &'a dyn AssetIo,
         version: usize,
@@ -92,7 +106,12 @@ impl<'a> LoadContext<'a> {
             asset_io,
             labeled_assets: Default::default(),
             version,
-            path,
+            source: asset_path.source(),
+            path: asset_path.path(),
+            settings: asset_path.settings(),
+            meta,
+            read_paths: Default::default(),
+            read_meta: Default::default(),
         }
     }
 
@@ -100,6 +119,41 @@ impl<'a> LoadContext<'a> {
         &self.path
     }
 
+    /// The named source the asset is loaded from, or `None` for the default source. Reads from
+    /// [LoadContext::read_asset_bytes] resolve against the same source.
+    pub fn source(&self) -> Option<&str> {
+        self.source
+    }
+
+    /// Returns the path of the asset being loaded, or of one of its labeled sub assets.
+    pub fn asset_path<'b>(&'b self, label: Option<&'b str>) -> AssetPath<'b> {
+        AssetPath::new_ref(self.path, label)
+            .with_source(self.source)
+            .with_serialized_settings(self.settings)
+    }
+
+    /// Returns the loader settings of the asset being loaded: the settings it was loaded with
+    /// using [AssetPath::with_settings], or else the `loader_settings` of its `.meta` file, or
+    /// else the default settings.
+    pub async fn settings<S: DeserializeOwned + Default>(&self) -> Result<S, anyhow::Error> {
+        if let Some(settings) = self.settings {
+            return Ok(ron::de::from_str(settings)?);
+        }
+        self.read_meta.store(true, Ordering::Relaxed);
+        let meta = match &self.meta {
+            Some(meta) => meta.clone(),
+            None => match self.asset_io.load_path(&meta_path(self.path)).await {
+                Ok(meta) => Some(meta),
+                Err(AssetIoError::NotFound(_)) => None,
+                Err(err) => return Err(err.into()),
+            },
+        };
+        match meta {
+            Some(meta) => Ok(SourceMeta::from_bytes(&meta)?.loader_settings()?),
+            None => Ok(S::default()),
+        }
+    }
+
     pub fn has_labeled_asset(&self, label: &str) -> bool {
         self.labeled_assets.contains_key(&Some(label.to_string()))
     }
@@ -112,15 +166,18 @@ impl<'a> LoadContext<'a> {
         assert!(!label.is_empty());
         self.labeled_assets
             .insert(Some(label.to_string()), asset.into());
-        self.get_handle(AssetPath::new_ref(self.path(), Some(label)))
+        self.get_handle(self.asset_path(Some(label)))
     }
 
     pub fn get_handle<I: Into<HandleId>, T: Asset>(&self, id: I) -> Handle<T> {
         Handle::strong(id.into(), self.ref_change_channel.sender.clone())
     }
 
+    /// Reads another file of the asset's source. The asset is reloaded when that file changes.
     pub async fn read_asset_bytes<P: AsRef<Path>>(&self, path: P) -> Result<Vec<u8>, AssetIoError> {
-        self.asset_io.load_path(path.as_ref()).await
+        let bytes = self.asset_io.load_path(path.as_ref()).await?;
+        self.read_paths.lock().push(path.as_ref().to_owned());
+        Ok(bytes)
     }
 
     pub fn get_asset_metas(&self) -> Vec<AssetMeta> {
@@ -199,3 +256,45 @@ pub fn update_asset_storage_system<T: Asset + AssetDynamic>(
 ) {
     asset_server.update_asset_storage(&mut assets);
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::FileAssetIo;
+    use futures_lite::future::block_on;
+    use serde::{Deserialize, Serialize};
+
+    #[derive(Debug, Serialize, Deserialize, PartialEq)]
+    struct Settings {
+        is_srgb: bool,
+    }
+
+    impl Default for Settings {
+        fn default() -> Self {
+            Settings { is_srgb: true }
+        }
+    }
+
+    #[test]
+    fn settings_precedence() {
+        let ref_change_channel = RefChangeChannel::default();
+        let asset_io = FileAssetIo::new(&".");
+        let settings = |asset_path: &AssetPath, meta: Option<&[u8]>| {
+            let load_context = LoadContext::new(
+                asset_path,
+                Some(meta.map(|meta| meta.to_vec())),
+                &ref_change_channel,
+                &asset_io,
+                0,
+            );
+            block_on(load_context.settings::<Settings>()).unwrap()
+        };
+        let meta: &[u8] = b"(processor: None, loader_settings: Some((is_srgb: false)))";
+
+        let path = AssetPath::from("textures/normal.png");
+        assert_eq!(settings(&path, None), Settings { is_srgb: true });
+        assert_eq!(settings(&path, Some(meta)), Settings { is_srgb: false });
+        let path = path.with_settings(&Settings { is_srgb: true });
+        assert_eq!(settings(&path, Some(meta)), Settings { is_srgb: true });
+    }
+}
@@ -3,33 +3,79 @@ use bevy_utils::AHasher;
 use serde::{Deserialize, Serialize};
 use std::{
     borrow::Cow,
+    fmt,
     hash::{Hash, Hasher},
     path::{Path, PathBuf},
 };
 
+/// The path of an asset, with an optional label selecting a sub asset and an optional named source.
+///
+/// As a string, an asset path looks like `source://path/to/file.ext#label`. Paths without a
+/// source are loaded from the default `AssetIo`, while paths with one are loaded from the `AssetIo`
+/// mounted under that name with [AssetServer::mount](crate::AssetServer::mount).
+///
+/// Paths can also carry loader settings, see [AssetPath::with_settings]. The same file loaded
+/// with different settings has different ids.
 #[derive(Debug, Hash, Clone, Serialize, Deserialize)]
 pub struct AssetPath<'a> {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    source: Option<Cow<'a, str>>,
     path: Cow<'a, Path>,
     label: Option<Cow<'a, str>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    settings: Option<Cow<'a, str>>,
 }
 
 impl<'a> AssetPath<'a> {
     #[inline]
     pub fn new_ref(path: &'a Path, label: Option<&'a str>) -> AssetPath<'a> {
         AssetPath {
+            source: None,
             path: Cow::Borrowed(path),
             label: label.map(|val| Cow::Borrowed(val)),
+            settings: None,
         }
     }
 
     #[inline]
     pub fn new(path: PathBuf, label: Option<String>) -> AssetPath<'a> {
         AssetPath {
+            source: None,
             path: Cow::Owned(path),
             label: label.map(Cow::Owned),
+            settings: None,
         }
     }
 
+    /// Returns this path loaded from the given named source instead, or from the default source if
+    /// `source` is `None`.
+    #[inline]
+    pub fn with_source<S: Into<Cow<'a, str>>>(mut self, source: Option<S>) -> AssetPath<'a> {
+        self.source = source.map(Into::into);
+        self
+    }
+
+    /// Returns this path with settings for the [AssetLoader](crate::AssetLoader), which reads
+    /// them with [LoadContext::settings](crate::LoadContext::settings). They take precedence over
+    /// the `loader_settings` of the asset's `.meta` file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the settings can't be serialized to RON.
+    pub fn with_settings<S: Serialize>(self, settings: &S) -> AssetPath<'a> {
+        let settings =
+            ron::ser::to_string(settings).expect("Loader settings must be serializable to RON.");
+        self.with_serialized_settings(Some(settings))
+    }
+
+    pub(crate) fn with_serialized_settings<S: Into<Cow<'a, str>>>(
+        mut self,
+        settings: Option<S>,
+    ) -> AssetPath<'a> {
+        self.settings = settings.map(Into::into);
+        self
+    }
+
     #[inline]
     pub fn get_id(&self) -> AssetPathId {
         AssetPathId::from(self)
@@ -45,14 +91,33 @@ impl<'a> AssetPath<'a> {
         &self.path
     }
 
+    #[inline]
+    pub fn source(&self) -> Option<&str> {
+        self.source.as_ref().map(|source| source.as_ref())
+    }
+
+    /// The loader settings of this path, serialized to RON.
+    #[inline]
+    pub fn settings(&self) -> Option<&str> {
+        self.settings.as_ref().map(|settings| settings.as_ref())
+    }
+
     #[inline]
     pub fn to_owned(&self) -> AssetPath<'static> {
         AssetPath {
+            source: self
+                .source
+                .as_ref()
+                .map(|value| Cow::Owned(value.to_string())),
             path: Cow::Owned(self.path.to_path_buf()),
             label: self
                 .label
                 .as_ref()
                 .map(|value| Cow::Owned(value.to_string())),
+            settings: self
+                .settings
+                .as_ref()
+                .map(|value| Cow::Owned(value.to_string())),
         }
     }
 }
@@ -67,7 +132,7 @@ pub struct AssetPathId(SourcePathId, LabelId);
     Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize, Reflect,
 )]
 #[reflect_value(PartialEq, Hash, Serialize, Deserialize)]
-pub struct SourcePathId(u64);
+pub struct SourcePathId(pub(crate) u64);
 
 #[derive(
     Debug, Clone, Copy, Eq, PartialEq, Hash, Ord, PartialOrd, Serialize, Deserialize, Reflect,
@@ -83,6 +148,22 @@ impl<'a> From<&'a Path> for SourcePathId {
     }
 }
 
+impl SourcePathId {
+    /// Paths in the default source and without loader settings hash exactly like a bare [Path],
+    /// so their ids don't change when named sources or settings are in use.
+    pub fn new(source: Option<&str>, path: &Path, settings: Option<&str>) -> Self {
+        let mut hasher = get_hasher();
+        if let Some(source) = source {
+            source.hash(&mut hasher);
+        }
+        path.hash(&mut hasher);
+        if let Some(settings) = settings {
+            settings.hash(&mut hasher);
+        }
+        SourcePathId(hasher.finish())
+    }
+}
+
 impl From<AssetPathId> for SourcePathId {
     fn from(id: AssetPathId) -> Self {
         id.source_path_id()
@@ -124,17 +205,18 @@ where
 {
     fn from(value: T) -> Self {
         let asset_path: AssetPath = value.into();
-        AssetPathId(
-            SourcePathId::from(asset_path.path()),
-            LabelId::from(asset_path.label()),
-        )
+        AssetPathId::from(&asset_path)
     }
 }
 
 impl<'a, 'b> From<&'a AssetPath<'b>> for AssetPathId {
     fn from(asset_path: &'a AssetPath<'b>) -> Self {
         AssetPathId(
-            SourcePathId::from(asset_path.path()),
+            SourcePathId::new(
+                asset_path.source(),
+                asset_path.path(),
+                asset_path.settings(),
+            ),
             LabelId::from(asset_path.label()),
         )
     }
@@ -142,21 +224,44 @@ impl<'a, 'b> From<&'a AssetPath<'b>> for AssetPathId {
 
 impl<'a> From<&'a str> for AssetPath<'a> {
     fn from(asset_path: &'a str) -> Self {
+        let (source, asset_path) = match asset_path.find("://") {
+            Some(index) => (Some(&asset_path[..index]), &asset_path[index + 3..]),
+            None => (None, asset_path),
+        };
         let mut parts = asset_path.split('#');
         let path = Path::new(parts.next().expect("Path must be set."));
         let label = parts.next();
         AssetPath {
+            source: source.map(Cow::Borrowed),
             path: Cow::Borrowed(path),
             label: label.map(|label| Cow::Borrowed(label)),
+            settings: None,
         }
     }
 }
 
+/// Formats the path as `source://path/to/file.ext#label`, which parses back into the same path.
+/// Loader settings are left out.
+impl<'a> fmt::Display for AssetPath<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(source) = self.source() {
+            write!(f, "{}://", source)?;
+        }
+        write!(f, "{}", self.path.display())?;
+        if let Some(label) = self.label() {
+            write!(f, "#{}", label)?;
+        }
+        Ok(())
+    }
+}
+
 impl<'a> From<&'a Path> for AssetPath<'a> {
     fn from(path: &'a Path) -> Self {
         AssetPath {
+            source: None,
             path: Cow::Borrowed(path),
             label: None,
+            settings: None,
         }
     }
 }
@@ -164,8 +269,60 @@ impl<'a> From<&'a Path> for AssetPath<'a> {
 impl<'a> From<PathBuf> for AssetPath<'a> {
     fn from(path: PathBuf) -> Self {
         AssetPath {
+            source: None,
             path: Cow::Owned(path),
             label: None,
+            settings: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_source() {
+        let path = AssetPath::from("dlc1://models/ship.gltf#Mesh0");
+        assert_eq!(path.source(), Some("dlc1"));
+        assert_eq!(path.path(), Path::new("models/ship.gltf"));
+        assert_eq!(path.label(), Some("Mesh0"));
+
+        let path = AssetPath::from("models/ship.gltf");
+        assert_eq!(path.source(), None);
+        assert_eq!(path.label(), None);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for path in &["dlc1://models/ship.gltf#Mesh0", "models/ship.gltf"] {
+            assert_eq!(AssetPath::from(*path).to_string(), *path);
         }
     }
+
+    #[test]
+    fn source_is_part_of_id() {
+        let base = AssetPath::from("models/ship.gltf").get_id();
+        let dlc = AssetPath::from("dlc1://models/ship.gltf").get_id();
+        assert_ne!(base, dlc);
+        assert_eq!(
+            base.source_path_id(),
+            SourcePathId::from(Path::new("models/ship.gltf"))
+        );
+        assert_eq!(
+            dlc,
+            AssetPath::new(PathBuf::from("models/ship.gltf"), None)
+                .with_source(Some("dlc1"))
+                .get_id()
+        );
+    }
+
+    #[test]
+    fn settings_are_part_of_id() {
+        let path = AssetPath::from("textures/normal.png");
+        let linear = path.clone().with_settings(&false).get_id();
+        assert_ne!(path.get_id(), linear);
+        assert_eq!(path.clone().with_settings(&false).get_id(), linear);
+        assert_ne!(path.with_settings(&true).get_id(), linear);
+    }
 }
@@ -0,0 +1,477 @@
+use crate::{
+    path::get_hasher, AssetIo, AssetIoError, AssetPath, AssetServer, AssetServerError, SourceMeta,
+    SourcePathId,
+};
+use anyhow::Result;
+use bevy_utils::BoxedFuture;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+/// Selects whether assets are processed before they are loaded
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum AssetProcessingMode {
+    /// Load asset sources directly.
+    Disabled,
+    /// Process asset sources that have a meta file when they are loaded, reusing the cached
+    /// artifacts of assets whose source, meta file and processor haven't changed.
+    Process,
+    /// Load processed artifacts without reading meta files, as shipped builds do. Assets that
+    /// weren't processed are loaded from their sources.
+    ProcessedOnly,
+}
+
+impl Default for AssetProcessingMode {
+    fn default() -> Self {
+        AssetProcessingMode::Disabled
+    }
+}
+
+/// The artifact produced by an [AssetProcessor]
+pub struct ProcessedAsset {
+    pub bytes: Vec<u8>,
+    /// The extension of the artifact, which selects the [AssetLoader](crate::AssetLoader) it is
+    /// loaded with.
+    pub extension: String,
+}
+
+/// Transforms an asset source into an artifact that is faster to load, such as a compressed
+/// texture or a baked mesh
+pub trait AssetProcessor: Send + Sync + 'static {
+    type Settings: DeserializeOwned + Default + Send + Sync + 'static;
+
+    fn process<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        settings: &'a Self::Settings,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<ProcessedAsset, anyhow::Error>>;
+
+    /// Bump this when the processor's output changes, so cached artifacts are processed again.
+    fn version(&self) -> u32 {
+        0
+    }
+}
+
+pub(crate) trait ErasedAssetProcessor: Send + Sync + 'static {
+    fn process_with_meta<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        meta: &'a SourceMeta,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<ProcessedAsset, anyhow::Error>>;
+    fn processor_version(&self) -> u32;
+}
+
+impl<P: AssetProcessor> ErasedAssetProcessor for P {
+    fn process_with_meta<'a>(
+        &'a self,
+        bytes: &'a [u8],
+        meta: &'a SourceMeta,
+        path: &'a Path,
+    ) -> BoxedFuture<'a, Result<ProcessedAsset, anyhow::Error>> {
+        Box::pin(async move {
+            let settings = meta.processor_settings::<P::Settings>()?;
+            AssetProcessor::process(self, bytes, &settings, path).await
+        })
+    }
+
+    fn processor_version(&self) -> u32 {
+        self.version()
+    }
+}
+
+/// What the processed asset cache knows about an asset: the hash of the inputs its artifact was
+/// produced from, and the extension of that artifact.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProcessedAssetInfo {
+    input_hash: u64,
+    extension: String,
+    /// The meta file of the source, so loader settings are available without reading it.
+    #[serde(default)]
+    meta: Option<String>,
+}
+
+/// The bytes an asset is loaded from, as returned by [AssetServer::read_asset_source].
+pub(crate) struct AssetSourceBytes {
+    pub bytes: Vec<u8>,
+    /// The path whose extension selects the loader.
+    pub loader_path: PathBuf,
+    /// The meta file of the asset, if it was read. `Some(None)` means there is none.
+    pub meta: Option<Option<Vec<u8>>>,
+    /// Whether the source itself was read, so it exists and can be watched for changes.
+    pub read_source: bool,
+}
+
+/// The cache of processed artifacts used by an [AssetServer] in one of the processing modes.
+///
+/// Artifacts are stored under the hash of their inputs, next to one small record per asset that
+/// points at the current artifact. Both are read and written through an [AssetIo], so shipped
+/// builds can read them from a pack. Nothing is cached if that [AssetIo] is read-only.
+pub struct ProcessedAssets {
+    mode: AssetProcessingMode,
+    asset_io: Box<dyn AssetIo>,
+}
+
+impl ProcessedAssets {
+    pub fn new(mode: AssetProcessingMode, asset_io: Box<dyn AssetIo>) -> Self {
+        Self { mode, asset_io }
+    }
+
+    pub fn mode(&self) -> AssetProcessingMode {
+        self.mode
+    }
+
+    fn info_path(source_path_id: SourcePathId) -> PathBuf {
+        PathBuf::from(format!("{:016x}.ron", source_path_id.0))
+    }
+
+    fn artifact_path(info: &ProcessedAssetInfo) -> PathBuf {
+        PathBuf::from(format!("{:016x}.{}", info.input_hash, info.extension))
+    }
+
+    async fn read_info(&self, source_path_id: SourcePathId) -> Option<ProcessedAssetInfo> {
+        let bytes = self
+            .asset_io
+            .load_path(&Self::info_path(source_path_id))
+            .await
+            .ok()?;
+        ron::de::from_bytes(&bytes).ok()
+    }
+
+    async fn write(
+        &self,
+        source_path_id: SourcePathId,
+        info: &ProcessedAssetInfo,
+        artifact: &[u8],
+    ) -> Result<(), AssetIoError> {
+        let result = self
+            .asset_io
+            .save_path(&Self::artifact_path(info), artifact)
+            .await;
+        if let Err(AssetIoError::ReadOnly(_)) = result {
+            return Ok(());
+        }
+        result?;
+        let info = ron::ser::to_string(info).expect("ProcessedAssetInfo is serializable");
+        self.asset_io
+            .save_path(&Self::info_path(source_path_id), info.as_bytes())
+            .await
+    }
+}
+
+/// Appends `.meta` to the file name of `path`.
+pub(crate) fn meta_path(path: &Path) -> PathBuf {
+    let mut meta_path = path.as_os_str().to_owned();
+    meta_path.push(".meta");
+    PathBuf::from(meta_path)
+}
+
+impl AssetServer {
+    pub fn add_processor<P: AssetProcessor>(&self, processor: P) {
+        self.server
+            .processors
+            .write()
+            .insert(std::any::type_name::<P>().to_string(), Arc::new(processor));
+    }
+
+    /// Sets the cache assets are processed into, or stops processing assets if `None`.
+    pub fn set_processed_assets(&self, processed_assets: Option<ProcessedAssets>) {
+        *self.server.processed_assets.write() = processed_assets.map(Arc::new);
+    }
+
+    fn get_processor(&self, name: &str) -> Option<Arc<dyn ErasedAssetProcessor>> {
+        let processors = self.server.processors.read();
+        processors.get(name).cloned().or_else(|| {
+            processors
+                .iter()
+                .find(|(type_name, _)| type_name.rsplit("::").next() == Some(name))
+                .map(|(_, processor)| processor.clone())
+        })
+    }
+
+    /// Reads the bytes an asset is loaded from: its source, or its processed artifact if assets
+    /// are processed.
+    pub(crate) async fn read_asset_source(
+        &self,
+        asset_path: &AssetPath<'_>,
+        asset_io: &dyn AssetIo,
+    ) -> Result<AssetSourceBytes, AssetServerError> {
+        let path = asset_path.path();
+        let processed_assets = self.server.processed_assets.read().clone();
+        let processed_assets = match processed_assets {
+            Some(processed_assets) => processed_assets,
+            None => {
+                return Ok(AssetSourceBytes {
+                    bytes: asset_io.load_path(path).await?,
+                    loader_path: path.to_owned(),
+                    meta: None,
+                    read_source: true,
+                })
+            }
+        };
+
+        let source_path_id = SourcePathId::new(asset_path.source(), path, None);
+        let info = processed_assets.read_info(source_path_id).await;
+        if processed_assets.mode == AssetProcessingMode::ProcessedOnly {
+            return match info {
+                Some(info) => {
+                    let artifact_path = ProcessedAssets::artifact_path(&info);
+                    let bytes = processed_assets.asset_io.load_path(&artifact_path).await?;
+                    Ok(AssetSourceBytes {
+                        bytes,
+                        loader_path: artifact_path,
+                        meta: Some(info.meta.map(String::into_bytes)),
+                        read_source: false,
+                    })
+                }
+                // only assets with a meta file are processed
+                None => Ok(AssetSourceBytes {
+                    bytes: asset_io.load_path(path).await?,
+                    loader_path: path.to_owned(),
+                    meta: Some(None),
+                    read_source: true,
+                }),
+            };
+        }
+
+        let bytes = asset_io.load_path(path).await?;
+        let meta_bytes = match asset_io.load_path(&meta_path(path)).await {
+            Ok(meta) => meta,
+            Err(AssetIoError::NotFound(_)) => {
+                return Ok(AssetSourceBytes {
+                    bytes,
+                    loader_path: path.to_owned(),
+                    meta: Some(None),
+                    read_source: true,
+                })
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let meta = SourceMeta::from_bytes(&meta_bytes).map_err(|err| {
+            AssetServerError::AssetProcessorError(anyhow::anyhow!(
+                "invalid meta file for {}: {}",
+                path.display(),
+                err
+            ))
+        })?;
+        let processor = match &meta.processor {
+            Some(name) => Some(
+                self.get_processor(name)
+                    .ok_or_else(|| AssetServerError::MissingAssetProcessor(name.clone()))?,
+            ),
+            None => None,
+        };
+
+        let mut hasher = get_hasher();
+        bytes.hash(&mut hasher);
+        meta_bytes.hash(&mut hasher);
+        if let Some(processor) = &processor {
+            processor.processor_version().hash(&mut hasher);
+        }
+        let input_hash = hasher.finish();
+
+        if let Some(info) = info.filter(|info| info.input_hash == input_hash) {
+            let artifact_path = ProcessedAssets::artifact_path(&info);
+            if let Ok(bytes) = processed_assets.asset_io.load_path(&artifact_path).await {
+                return Ok(AssetSourceBytes {
+                    bytes,
+                    loader_path: artifact_path,
+                    meta: Some(Some(meta_bytes)),
+                    read_source: true,
+                });
+            }
+        }
+
+        let processed = match processor {
+            Some(processor) => processor
+                .process_with_meta(&bytes, &meta, path)
+                .await
+                .map_err(AssetServerError::AssetProcessorError)?,
+            None => ProcessedAsset {
+                extension: path
+                    .file_name()
+                    .and_then(|file_name| file_name.to_str())
+                    .and_then(|file_name| file_name.find('.').map(|i| &file_name[i + 1..]))
+                    .unwrap_or("")
+                    .to_string(),
+                bytes,
+            },
+        };
+        let info = ProcessedAssetInfo {
+            input_hash,
+            extension: processed.extension,
+            meta: Some(String::from_utf8_lossy(&meta_bytes).into_owned()),
+        };
+        processed_assets
+            .write(source_path_id, &info, &processed.bytes)
+            .await?;
+        Ok(AssetSourceBytes {
+            bytes: processed.bytes,
+            loader_path: ProcessedAssets::artifact_path(&info),
+            meta: Some(Some(meta_bytes)),
+            read_source: true,
+        })
+    }
+
+    /// Processes every asset in a folder of the given source, waiting until all of them are
+    /// written to the processed asset cache. This is useful to prepare the cache for a
+    /// [AssetProcessingMode::ProcessedOnly] build.
+    pub fn process_folder<'a, P: Into<AssetPath<'a>>>(
+        &self,
+        path: P,
+    ) -> Result<(), AssetServerError> {
+        let path: AssetPath = path.into();
+        let asset_io = self.get_asset_io(path.source())?;
+        let mut files = Vec::new();
+        let mut folders = vec![path.path().to_owned()];
+        while let Some(folder) = folders.pop() {
+            for child_path in asset_io.read_directory(&folder)? {
+                if asset_io.is_directory(&child_path) {
+                    folders.push(child_path);
+                } else if child_path.extension().map_or(true, |ext| ext != "meta") {
+                    files.push(child_path);
+                }
+            }
+        }
+
+        let results = self.server.task_pool.scope(|scope| {
+            for file in files.iter() {
+                let asset_path = AssetPath::new_ref(file, None).with_source(path.source());
+                let asset_io = &*asset_io;
+                scope.spawn(async move {
+                    self.read_asset_source(&asset_path, asset_io)
+                        .await
+                        .map(|_| ())
+                });
+            }
+        });
+        results.into_iter().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Deserialize, Default, PartialEq, Debug)]
+    struct CompressorSettings {
+        quality: f32,
+    }
+
+    #[test]
+    fn parse_meta_file() {
+        let meta = br#"(
+            processor: Some("TextureCompressor"),
+            processor_settings: Some((quality: 0.5)),
+        )"#;
+        let meta = SourceMeta::from_bytes(meta).unwrap();
+        assert_eq!(meta.processor.as_deref(), Some("TextureCompressor"));
+        assert_eq!(
+            meta.processor_settings::<CompressorSettings>().unwrap(),
+            CompressorSettings { quality: 0.5 }
+        );
+
+        let meta = SourceMeta::from_bytes(b"()").unwrap();
+        assert_eq!(meta.processor, None);
+        assert!(meta.assets.is_empty());
+        assert_eq!(
+            meta.processor_settings::<CompressorSettings>().unwrap(),
+            CompressorSettings::default()
+        );
+    }
+
+    #[test]
+    fn meta_path_appends_extension() {
+        assert_eq!(
+            meta_path(Path::new("textures/grass.png")),
+            PathBuf::from("textures/grass.png.meta")
+        );
+    }
+
+    struct UppercaseProcessor;
+
+    #[derive(Deserialize, Default)]
+    struct UppercaseSettings {
+        suffix: String,
+    }
+
+    impl AssetProcessor for UppercaseProcessor {
+        type Settings = UppercaseSettings;
+
+        fn process<'a>(
+            &'a self,
+            bytes: &'a [u8],
+            settings: &'a Self::Settings,
+            _path: &'a Path,
+        ) -> BoxedFuture<'a, Result<ProcessedAsset, anyhow::Error>> {
+            Box::pin(async move {
+                let mut bytes = bytes.to_ascii_uppercase();
+                bytes.extend(settings.suffix.as_bytes());
+                Ok(ProcessedAsset {
+                    bytes,
+                    extension: "upper".to_string(),
+                })
+            })
+        }
+    }
+
+    #[cfg(all(not(target_arch = "wasm32"), not(target_os = "android")))]
+    #[test]
+    fn process_and_cache() {
+        use crate::FileAssetIo;
+        use futures_lite::future::block_on;
+
+        let root =
+            std::env::temp_dir().join(format!("bevy_asset_processing_{}", std::process::id()));
+        let assets = root.join("assets");
+        let processed = root.join("processed");
+        std::fs::create_dir_all(&assets).unwrap();
+        std::fs::write(assets.join("note.txt"), "hello").unwrap();
+        std::fs::write(
+            assets.join("note.txt.meta"),
+            r#"(processor: Some("UppercaseProcessor"), processor_settings: Some((suffix: "!")))"#,
+        )
+        .unwrap();
+        std::fs::write(assets.join("plain.txt"), "as is").unwrap();
+
+        let asset_server = AssetServer::new(FileAssetIo::new(&assets), Default::default());
+        asset_server.add_processor(UppercaseProcessor);
+        let processed_assets = |mode| {
+            Some(ProcessedAssets::new(
+                mode,
+                Box::new(FileAssetIo::new(&processed)),
+            ))
+        };
+        asset_server.set_processed_assets(processed_assets(AssetProcessingMode::Process));
+        let asset_io = asset_server.get_asset_io(None).unwrap();
+        let read = |path: &str| {
+            block_on(asset_server.read_asset_source(&AssetPath::from(path), &*asset_io)).unwrap()
+        };
+
+        let source = read("note.txt");
+        assert_eq!(source.bytes, b"HELLO!");
+        assert_eq!(source.loader_path.extension().unwrap(), "upper");
+        let source = read("plain.txt");
+        assert_eq!(source.bytes, b"as is");
+        assert_eq!(source.loader_path, Path::new("plain.txt"));
+        assert_eq!(source.meta, Some(None));
+        // assets without a meta file aren't copied into the cache
+        assert_eq!(std::fs::read_dir(&processed).unwrap().count(), 2);
+
+        // shipped builds only need the sources of assets that weren't processed
+        std::fs::remove_file(assets.join("note.txt")).unwrap();
+        std::fs::remove_file(assets.join("note.txt.meta")).unwrap();
+        asset_server.set_processed_assets(processed_assets(AssetProcessingMode::ProcessedOnly));
+        let source = read("note.txt");
+        assert_eq!(source.bytes, b"HELLO!");
+        assert!(!source.read_source);
+        assert!(source.meta.unwrap().is_some());
+        assert_eq!(read("plain.txt").bytes, b"as is");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,471 @@
+use crate::{
+    path::get_hasher, Asset, AssetDynamic, AssetPath, AssetServer, AssetServerError, Assets,
+    Handle, HandleId,
+};
+use anyhow::Result;
+use bevy_ecs::system::Res;
+use bevy_log::warn;
+use bevy_reflect::TypeUuid;
+use bevy_utils::{Duration, Instant, Uuid};
+use std::{
+    hash::{Hash, Hasher},
+    path::{Path, PathBuf},
+    sync::{atomic::Ordering, Arc},
+};
+
+/// Writes assets to files that an [AssetLoader](crate::AssetLoader) can load again. Assets are
+/// saved with [AssetServer::save](crate::AssetServer::save).
+pub trait AssetSaver: Send + Sync + 'static {
+    type Asset: Asset;
+
+    /// Serializes `asset` for the file at `path`. This runs during the
+    /// [AssetStage::AssetEvents](crate::AssetStage::AssetEvents) stage, and the returned bytes are
+    /// written on the `IoTaskPool`.
+    fn save(&self, asset: &Self::Asset, path: &Path) -> Result<Vec<u8>, anyhow::Error>;
+    fn extensions(&self) -> &[&str];
+}
+
+pub(crate) trait ErasedAssetSaver: Send + Sync + 'static {
+    fn save(&self, asset: &dyn AssetDynamic, path: &Path) -> Result<Vec<u8>, anyhow::Error>;
+    fn extensions(&self) -> &[&str];
+    fn asset_type_uuid(&self) -> Uuid;
+}
+
+impl<S: AssetSaver> ErasedAssetSaver for S {
+    fn save(&self, asset: &dyn AssetDynamic, path: &Path) -> Result<Vec<u8>, anyhow::Error> {
+        let asset = asset
+            .downcast_ref::<S::Asset>()
+            .expect("Asset should have the type of the saver.");
+        AssetSaver::save(self, asset, path)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        AssetSaver::extensions(self)
+    }
+
+    fn asset_type_uuid(&self) -> Uuid {
+        S::Asset::TYPE_UUID
+    }
+}
+
+/// An asset waiting to be serialized by the next run of
+/// [save_queued_assets_system](crate::save_queued_assets_system) for its type.
+pub(crate) struct QueuedSave {
+    pub(crate) id: HandleId,
+    pub(crate) path: AssetPath<'static>,
+    pub(crate) saver: Arc<dyn ErasedAssetSaver>,
+}
+
+/// A file the [AssetServer] saved an asset over the source of, so the change it causes isn't
+/// mistaken for an edit and hot reloaded.
+pub(crate) struct SavedFile {
+    hash: u64,
+    /// When the file was written, or `None` while it is being written. Changes are ignored
+    /// entirely until then.
+    written: Option<Instant>,
+}
+
+/// How long after a save its change events are expected. Saved files are forgotten after that, so
+/// they don't pile up, and later changes reload the asset as usual.
+const SAVED_FILE_TIMEOUT: Duration = Duration::from_secs(2);
+
+fn hash_bytes(bytes: &[u8]) -> u64 {
+    let mut hasher = get_hasher();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+impl AssetServer {
+    pub fn add_saver<T>(&self, saver: T)
+    where
+        T: AssetSaver,
+    {
+        self.server.savers.write().push(Arc::new(saver));
+    }
+
+    fn get_path_asset_saver(
+        &self,
+        type_uuid: Uuid,
+        path: &Path,
+    ) -> Result<Arc<dyn ErasedAssetSaver>, AssetServerError> {
+        let file_name = path
+            .file_name()
+            .and_then(|file_name| file_name.to_str())
+            .map(|file_name| file_name.to_lowercase())
+            .unwrap_or_default();
+        let savers = self.server.savers.read();
+        let mut exts = Vec::new();
+        let mut ext = file_name.as_str();
+        while let Some(idx) = ext.find('.') {
+            ext = &ext[idx + 1..];
+            exts.push(ext.to_string());
+            // like loaders, savers added later take precedence
+            let saver = savers.iter().rev().find(|saver| {
+                saver.asset_type_uuid() == type_uuid && saver.extensions().contains(&ext)
+            });
+            if let Some(saver) = saver {
+                return Ok(saver.clone());
+            }
+        }
+        Err(AssetServerError::MissingAssetSaver { extensions: exts })
+    }
+
+    /// Saves the asset of `handle` to `path` with the [AssetSaver] added for its type and the
+    /// extension of `path`. The asset is serialized the next time the
+    /// [AssetStage::AssetEvents](crate::AssetStage::AssetEvents) stage runs, and written by the
+    /// [AssetIo](crate::AssetIo) of the path's source on the `IoTaskPool`. Errors from then on
+    /// are logged.
+    ///
+    /// Saving an asset over the file it was loaded from doesn't hot reload it.
+    pub fn save<'a, T: Asset, P: Into<AssetPath<'a>>>(
+        &self,
+        handle: &Handle<T>,
+        path: P,
+    ) -> Result<(), AssetServerError> {
+        let path: AssetPath = path.into();
+        self.get_asset_io(path.source())?;
+        let saver = self.get_path_asset_saver(T::TYPE_UUID, path.path())?;
+        self.server
+            .queued_saves
+            .write()
+            .entry(T::TYPE_UUID)
+            .or_default()
+            .push(QueuedSave {
+                id: handle.id,
+                path: path.to_owned(),
+                saver,
+            });
+        Ok(())
+    }
+
+    pub(crate) fn save_queued_assets<T: Asset>(&self, assets: &Assets<T>) {
+        let queued = match self.server.queued_saves.write().get_mut(&T::TYPE_UUID) {
+            Some(queued) if !queued.is_empty() => std::mem::take(queued),
+            _ => return,
+        };
+        for QueuedSave { id, path, saver } in queued {
+            let asset = match assets.get(id) {
+                Some(asset) => asset,
+                None => {
+                    warn!("failed to save {:?}: the asset doesn't exist", path);
+                    continue;
+                }
+            };
+            let bytes = match saver.save(asset, path.path()) {
+                Ok(bytes) => bytes,
+                Err(err) => {
+                    warn!("failed to save {:?}: {}", path, err);
+                    continue;
+                }
+            };
+
+            let key = (
+                path.source().map(ToString::to_string),
+                path.path().to_owned(),
+            );
+            let overwrites_source = match id {
+                HandleId::AssetPathId(id) => self
+                    .server
+                    .asset_sources
+                    .read()
+                    .get(&id.source_path_id())
+                    .map_or(false, |source_info| {
+                        source_info.source == key.0 && source_info.path == key.1
+                    }),
+                HandleId::Id(..) => false,
+            };
+            // without hot reloading, the file's change events are never seen
+            let watching_for_changes = self.server.watching_for_changes.load(Ordering::Relaxed);
+            if overwrites_source && watching_for_changes {
+                self.server.saved_files.write().insert(
+                    key.clone(),
+                    SavedFile {
+                        hash: hash_bytes(&bytes),
+                        written: None,
+                    },
+                );
+            }
+
+            let server = self.clone();
+            self.server
+                .task_pool
+                .spawn(async move {
+                    let result = match server.get_asset_io(path.source()) {
+                        Ok(asset_io) => asset_io
+                            .save_path(path.path(), &bytes)
+                            .await
+                            .map_err(AssetServerError::from),
+                        Err(err) => Err(err),
+                    };
+                    let mut saved_files = server.server.saved_files.write();
+                    match result {
+                        Ok(()) => {
+                            if let Some(saved_file) = saved_files.get_mut(&key) {
+                                saved_file.written = Some(Instant::now());
+                            }
+                        }
+                        Err(err) => {
+                            warn!("failed to save {:?}: {}", path, err);
+                            saved_files.remove(&key);
+                        }
+                    }
+                })
+                .detach();
+        }
+    }
+
+    /// Removes the files the [AssetServer] saved from `changed_paths`. Those are only reloaded if
+    /// their contents changed since, which is checked on the task pool.
+    pub(crate) fn filter_saved_paths(
+        &self,
+        changed_paths: Vec<(Option<String>, PathBuf)>,
+    ) -> Vec<(Option<String>, PathBuf)> {
+        let mut saved_files = self.server.saved_files.write();
+        saved_files.retain(|_, saved_file| {
+            saved_file
+                .written
+                .map_or(true, |written| written.elapsed() < SAVED_FILE_TIMEOUT)
+        });
+        if saved_files.is_empty() {
+            return changed_paths;
+        }
+        let (saved, changed): (Vec<_>, Vec<_>) = changed_paths
+            .into_iter()
+            .partition(|changed_path| saved_files.contains_key(changed_path));
+        for (source, path) in saved {
+            if saved_files[&(source.clone(), path.clone())]
+                .written
+                .is_none()
+            {
+                continue;
+            }
+            let server = self.clone();
+            self.server
+                .task_pool
+                .spawn(async move {
+                    let bytes = match server.get_asset_io(source.as_deref()) {
+                        Ok(asset_io) => asset_io.load_path(&path).await.ok(),
+                        Err(_) => None,
+                    };
+                    let key = (source, path);
+                    {
+                        let mut saved_files = server.server.saved_files.write();
+                        let unchanged = match (saved_files.get(&key), bytes) {
+                            (Some(saved_file), Some(bytes)) => {
+                                saved_file.written.is_none()
+                                    || saved_file.hash == hash_bytes(&bytes)
+                            }
+                            _ => false,
+                        };
+                        if unchanged {
+                            return;
+                        }
+                        saved_files.remove(&key);
+                    }
+                    for asset_path in server.get_assets_to_reload(&[key]) {
+                        let _ = server.load_untracked(asset_path, true);
+                    }
+                })
+                .detach();
+        }
+        changed
+    }
+}
+
+/// Serializes the assets of type `T` queued with [AssetServer::save].
+pub fn save_queued_assets_system<T: Asset>(asset_server: Res<AssetServer>, assets: Res<Assets<T>>) {
+    asset_server.save_queued_assets(&assets);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddAsset, AssetIo, AssetIoError, AssetPlugin, LoadContext, LoadedAsset};
+    use bevy_app::App;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+    use bevy_utils::{BoxedFuture, HashMap};
+    use parking_lot::RwLock;
+
+    #[derive(Default)]
+    struct MemoryAssetIo(RwLock<HashMap<PathBuf, Vec<u8>>>);
+
+    impl AssetIo for MemoryAssetIo {
+        fn load_path<'a>(
+            &'a self,
+            path: &'a Path,
+        ) -> BoxedFuture<'a, Result<Vec<u8>, AssetIoError>> {
+            Box::pin(async move {
+                self.0
+                    .read()
+                    .get(path)
+                    .cloned()
+                    .ok_or_else(|| AssetIoError::NotFound(path.to_owned()))
+            })
+        }
+
+        fn read_directory(
+            &self,
+            path: &Path,
+        ) -> Result<Box<dyn Iterator<Item = PathBuf>>, AssetIoError> {
+            Err(AssetIoError::NotFound(path.to_owned()))
+        }
+
+        fn is_directory(&self, _path: &Path) -> bool {
+            false
+        }
+
+        fn watch_path_for_changes(&self, _path: &Path) -> Result<(), AssetIoError> {
+            Ok(())
+        }
+
+        fn watch_for_changes(&self) -> Result<(), AssetIoError> {
+            Ok(())
+        }
+
+        fn save_path<'a>(
+            &'a self,
+            path: &'a Path,
+            bytes: &'a [u8],
+        ) -> BoxedFuture<'a, Result<(), AssetIoError>> {
+            Box::pin(async move {
+                self.0.write().insert(path.to_owned(), bytes.to_vec());
+                Ok(())
+            })
+        }
+    }
+
+    #[derive(TypeUuid)]
+    #[uuid = "b5f3c0a2-3c1e-4c47-9d8e-2a6f1e7d4b90"]
+    struct Text(String);
+
+    struct TextLoader;
+
+    impl crate::AssetLoader for TextLoader {
+        fn load<'a>(
+            &'a self,
+            bytes: &'a [u8],
+            load_context: &'a mut LoadContext,
+        ) -> BoxedFuture<'a, Result<(), anyhow::Error>> {
+            Box::pin(async move {
+                let text = String::from_utf8(bytes.to_vec())?;
+                load_context.set_default_asset(LoadedAsset::new(Text(text)));
+                Ok(())
+            })
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+    }
+
+    struct TextSaver;
+
+    impl AssetSaver for TextSaver {
+        type Asset = Text;
+
+        fn save(&self, text: &Text, _path: &Path) -> Result<Vec<u8>> {
+            Ok(text.0.clone().into_bytes())
+        }
+
+        fn extensions(&self) -> &[&str] {
+            &["txt"]
+        }
+    }
+
+    fn update_until(app: &mut App, done: impl Fn(&App) -> bool) {
+        for _ in 0..500 {
+            app.update();
+            if done(app) {
+                return;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        panic!("timed out");
+    }
+
+    fn saved_text(app: &App, path: &str) -> Option<String> {
+        let asset_server = app.world.get_resource::<AssetServer>().unwrap();
+        let asset_io = asset_server.get_asset_io(None).unwrap();
+        let files = &asset_io.downcast_ref::<MemoryAssetIo>().unwrap().0;
+        let text = files.read().get(Path::new(path)).cloned()?;
+        Some(String::from_utf8(text).unwrap())
+    }
+
+    #[test]
+    fn save_assets() {
+        let task_pool = TaskPool::new();
+        let asset_io = MemoryAssetIo::default();
+        asset_io
+            .0
+            .write()
+            .insert(PathBuf::from("notes.txt"), b"draft".to_vec());
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(task_pool.clone()))
+            .insert_resource(AssetServer::new(asset_io, task_pool))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Text>()
+            .add_asset_loader(TextLoader)
+            .add_asset_saver(TextSaver);
+        let mut app = std::mem::take(&mut app.app);
+
+        let asset_server = app.world.get_resource::<AssetServer>().unwrap().clone();
+        asset_server.watch_for_changes().unwrap();
+        let loaded: Handle<Text> = asset_server.load("notes.txt");
+        update_until(&mut app, |app| {
+            app.world
+                .get_resource::<Assets<Text>>()
+                .unwrap()
+                .contains(&loaded)
+        });
+        let created = app
+            .world
+            .get_resource_mut::<Assets<Text>>()
+            .unwrap()
+            .add(Text("created".to_string()));
+        app.world
+            .get_resource_mut::<Assets<Text>>()
+            .unwrap()
+            .get_mut(&loaded)
+            .unwrap()
+            .0 = "edited".to_string();
+
+        asset_server.save(&loaded, "notes.txt").unwrap();
+        asset_server.save(&created, "new/created.txt").unwrap();
+        assert!(matches!(
+            asset_server.save(&created, "created.png"),
+            Err(AssetServerError::MissingAssetSaver { .. })
+        ));
+        update_until(&mut app, |app| {
+            saved_text(app, "new/created.txt").is_some()
+                && asset_server.server.saved_files.read()[&(None, PathBuf::from("notes.txt"))]
+                    .written
+                    .is_some()
+        });
+        assert_eq!(saved_text(&app, "notes.txt").unwrap(), "edited");
+        assert_eq!(saved_text(&app, "new/created.txt").unwrap(), "created");
+
+        // the saved source isn't reloaded, other changes are
+        let changed_paths = vec![
+            (None, PathBuf::from("notes.txt")),
+            (None, PathBuf::from("new/created.txt")),
+        ];
+        assert_eq!(
+            asset_server.filter_saved_paths(changed_paths.clone()),
+            vec![(None, PathBuf::from("new/created.txt"))]
+        );
+
+        // saved files are forgotten once their change events are due
+        asset_server
+            .server
+            .saved_files
+            .write()
+            .get_mut(&(None, PathBuf::from("notes.txt")))
+            .unwrap()
+            .written = Instant::now().checked_sub(SAVED_FILE_TIMEOUT);
+        assert_eq!(
+            asset_server.filter_saved_paths(changed_paths.clone()),
+            changed_paths
+        );
+        assert!(asset_server.server.saved_files.read().is_empty());
+    }
+}
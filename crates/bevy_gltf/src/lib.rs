@@ -1,7 +1,9 @@
 use std::collections::HashMap;
 
 mod loader;
+mod saver;
 pub use loader::*;
+pub use saver::*;
 
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, Handle};
@@ -10,13 +12,14 @@ use bevy_reflect::TypeUuid;
 use bevy_render::mesh::Mesh;
 use bevy_scene::Scene;
 
-/// Adds support for GLTF file loading to Apps
+/// Adds support for GLTF file loading, and saving meshes as GLTF files, to Apps
 #[derive(Default)]
 pub struct GltfPlugin;
 
 impl Plugin for GltfPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.init_asset_loader::<GltfLoader>()
+            .init_asset_saver::<GltfMeshSaver>()
             .add_asset::<Gltf>()
             .add_asset::<GltfNode>()
             .add_asset::<GltfPrimitive>()
@@ -1,9 +1,7 @@
 use anyhow::Result;
-use bevy_asset::{
-    AssetIoError, AssetLoader, AssetPath, BoxedFuture, Handle, LoadContext, LoadedAsset,
-};
+use bevy_asset::{AssetIoError, AssetLoader, BoxedFuture, Handle, LoadContext, LoadedAsset};
 use bevy_core::Name;
-use bevy_ecs::world::World;
+use bevy_ecs::{entity::Entity, world::World};
 use bevy_math::Mat4;
 use bevy_pbr::prelude::{PbrBundle, StandardMaterial};
 use bevy_render::{
@@ -14,6 +12,7 @@ use bevy_render::{
     pipeline::PrimitiveTopology,
     prelude::{Color, Texture},
     render_graph::base,
+    skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
     texture::{AddressMode, FilterMode, ImageType, SamplerDescriptor, TextureError, TextureFormat},
 };
 use bevy_scene::Scene;
@@ -142,6 +141,30 @@ async fn load_gltf<'a, 'b>(
                 mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vertex_attribute);
             }
 
+            if let Some(vertex_attribute) = reader.read_joints(0).map(|v| {
+                VertexAttributeValues::Uint4(
+                    v.into_u16()
+                        .map(|joints| {
+                            [
+                                joints[0] as u32,
+                                joints[1] as u32,
+                                joints[2] as u32,
+                                joints[3] as u32,
+                            ]
+                        })
+                        .collect(),
+                )
+            }) {
+                mesh.set_attribute(Mesh::ATTRIBUTE_JOINT_INDEX, vertex_attribute);
+            }
+
+            if let Some(vertex_attribute) = reader
+                .read_weights(0)
+                .map(|v| VertexAttributeValues::Float4(v.into_f32().collect()))
+            {
+                mesh.set_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, vertex_attribute);
+            }
+
             if let Some(indices) = reader.read_indices() {
                 mesh.set_indices(Some(Indices::U32(indices.into_u32().collect())));
             };
@@ -165,6 +188,23 @@ async fn load_gltf<'a, 'b>(
         meshes.push(handle);
     }
 
+    for skin in gltf.skins() {
+        let reader = skin.reader(|buffer| Some(&buffer_data[buffer.index()]));
+        // the inverse bind matrices are identity matrices when they aren't given
+        let inverse_bindposes = reader
+            .read_inverse_bind_matrices()
+            .map(|matrices| {
+                matrices
+                    .map(|matrix| Mat4::from_cols_array_2d(&matrix))
+                    .collect()
+            })
+            .unwrap_or_else(|| vec![Mat4::IDENTITY; skin.joints().count()]);
+        load_context.set_labeled_asset(
+            &skin_label(&skin),
+            LoadedAsset::new(SkinnedMeshInverseBindposes(inverse_bindposes)),
+        );
+    }
+
     let mut nodes_intermediate = vec![];
     let mut named_nodes_intermediate = HashMap::new();
     for node in gltf.nodes() {
@@ -248,12 +288,21 @@ async fn load_gltf<'a, 'b>(
     for scene in gltf.scenes() {
         let mut err = None;
         let mut world = World::default();
+        let mut node_entities = HashMap::new();
+        let mut skinned_meshes = vec![];
         world
             .spawn()
             .insert_bundle((Transform::identity(), GlobalTransform::identity()))
             .with_children(|parent| {
                 for node in scene.nodes() {
-                    let result = load_node(&node, parent, load_context, &buffer_data);
+                    let result = load_node(
+                        &node,
+                        parent,
+                        load_context,
+                        &buffer_data,
+                        &mut node_entities,
+                        &mut skinned_meshes,
+                    );
                     if result.is_err() {
                         err = Some(result);
                         return;
@@ -263,6 +312,23 @@ async fn load_gltf<'a, 'b>(
         if let Some(Err(err)) = err {
             return Err(err);
         }
+
+        // the joints of a skin can be anywhere in the scene, so they are resolved once every node
+        // has been spawned
+        for (entity, skin) in skinned_meshes {
+            let joints = skin
+                .joints()
+                .map(|joint| node_entities.get(&joint.index()).copied())
+                .collect::<Option<Vec<Entity>>>();
+            if let Some(joints) = joints {
+                let skin_label = skin_label(&skin);
+                let inverse_bindposes_path = load_context.asset_path(Some(&skin_label));
+                world.entity_mut(entity).insert(SkinnedMesh {
+                    inverse_bindposes: load_context.get_handle(inverse_bindposes_path),
+                    joints,
+                });
+            }
+        }
         let scene_handle = load_context
             .set_labeled_asset(&scene_label(&scene), LoadedAsset::new(Scene::new(world)));
 
@@ -299,7 +365,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
     let base_color_texture = if let Some(info) = pbr.base_color_texture() {
         // TODO: handle info.tex_coord() (the *set* index for the right texcoords)
         let label = texture_label(&info.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.asset_path(Some(&label));
         Some(load_context.get_handle(path))
     } else {
         None
@@ -309,7 +375,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         // TODO: handle normal_texture.scale
         // TODO: handle normal_texture.tex_coord() (the *set* index for the right texcoords)
         let label = texture_label(&normal_texture.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.asset_path(Some(&label));
         Some(load_context.get_handle(path))
     } else {
         None
@@ -318,7 +384,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
     let metallic_roughness_texture = if let Some(info) = pbr.metallic_roughness_texture() {
         // TODO: handle info.tex_coord() (the *set* index for the right texcoords)
         let label = texture_label(&info.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.asset_path(Some(&label));
         Some(load_context.get_handle(path))
     } else {
         None
@@ -328,7 +394,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         // TODO: handle occlusion_texture.tex_coord() (the *set* index for the right texcoords)
         // TODO: handle occlusion_texture.strength() (a scalar multiplier for occlusion strength)
         let label = texture_label(&occlusion_texture.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.asset_path(Some(&label));
         Some(load_context.get_handle(path))
     } else {
         None
@@ -339,7 +405,7 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
         // TODO: handle occlusion_texture.tex_coord() (the *set* index for the right texcoords)
         // TODO: handle occlusion_texture.strength() (a scalar multiplier for occlusion strength)
         let label = texture_label(&info.texture());
-        let path = AssetPath::new_ref(load_context.path(), Some(&label));
+        let path = load_context.asset_path(Some(&label));
         Some(load_context.get_handle(path))
     } else {
         None
@@ -364,11 +430,13 @@ fn load_material(material: &Material, load_context: &mut LoadContext) -> Handle<
     )
 }
 
-fn load_node(
-    gltf_node: &gltf::Node,
+fn load_node<'a>(
+    gltf_node: &gltf::Node<'a>,
     world_builder: &mut WorldChildBuilder,
     load_context: &mut LoadContext,
     buffer_data: &[Vec<u8>],
+    node_entities: &mut HashMap<usize, Entity>,
+    skinned_meshes: &mut Vec<(Entity, gltf::Skin<'a>)>,
 ) -> Result<(), GltfError> {
     let transform = gltf_node.transform();
     let mut gltf_error = None;
@@ -377,6 +445,8 @@ fn load_node(
         GlobalTransform::identity(),
     ));
 
+    node_entities.insert(gltf_node.index(), node.id());
+
     if let Some(name) = gltf_node.name() {
         node.insert(Name::new(name.to_string()));
     }
@@ -445,22 +515,36 @@ fn load_node(
                 }
 
                 let primitive_label = primitive_label(&mesh, &primitive);
-                let mesh_asset_path =
-                    AssetPath::new_ref(load_context.path(), Some(&primitive_label));
-                let material_asset_path =
-                    AssetPath::new_ref(load_context.path(), Some(&material_label));
-
-                parent.spawn_bundle(PbrBundle {
-                    mesh: load_context.get_handle(mesh_asset_path),
-                    material: load_context.get_handle(material_asset_path),
-                    ..Default::default()
-                });
+                let mesh_asset_path = load_context.asset_path(Some(&primitive_label));
+                let material_asset_path = load_context.asset_path(Some(&material_label));
+
+                let entity = parent
+                    .spawn_bundle(PbrBundle {
+                        mesh: load_context.get_handle(mesh_asset_path),
+                        material: load_context.get_handle(material_asset_path),
+                        ..Default::default()
+                    })
+                    .id();
+
+                // only primitives with joints can be skinned by the vertex shader
+                if let Some(skin) = gltf_node.skin() {
+                    if primitive.get(&gltf::Semantic::Joints(0)).is_some() {
+                        skinned_meshes.push((entity, skin));
+                    }
+                }
             }
         }
 
         // append other nodes
         for child in gltf_node.children() {
-            if let Err(err) = load_node(&child, parent, load_context, buffer_data) {
+            if let Err(err) = load_node(
+                &child,
+                parent,
+                load_context,
+                buffer_data,
+                node_entities,
+                skinned_meshes,
+            ) {
                 gltf_error = Some(err);
                 return;
             }
@@ -497,6 +581,10 @@ fn node_label(node: &gltf::Node) -> String {
     format!("Node{}", node.index())
 }
 
+fn skin_label(skin: &gltf::Skin) -> String {
+    format!("Skin{}/InverseBindMatrices", skin.index())
+}
+
 fn scene_label(scene: &gltf::Scene) -> String {
     format!("Scene{}", scene.index())
 }
@@ -0,0 +1,321 @@
+use anyhow::Result;
+use bevy_asset::AssetSaver;
+use bevy_render::{
+    mesh::{Indices, Mesh, VertexAttributeValues},
+    pipeline::{PrimitiveTopology, VertexFormat},
+};
+use std::{convert::TryFrom, path::Path};
+use thiserror::Error;
+
+/// An error that occurs when saving a [Mesh] as a GLTF file
+#[derive(Error, Debug)]
+pub enum GltfSaveError {
+    #[error("the {0} attribute can't be saved to GLTF")]
+    UnsupportedAttribute(String),
+    #[error("the {name} attribute is {format:?}, but GLTF stores it as {expected:?}")]
+    AttributeFormat {
+        name: String,
+        format: VertexFormat,
+        expected: VertexFormat,
+    },
+    #[error("joint index {0} doesn't fit in the 16 bits GLTF allows")]
+    JointIndexOutOfRange(u32),
+}
+
+const FLOAT: u32 = 5126;
+const UNSIGNED_SHORT: u32 = 5123;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+/// The mesh attributes the [GltfLoader](crate::GltfLoader) reads, with their GLTF name, format
+/// and accessor type. Joint indices are converted to 16 bits, everything else is written as is.
+const ATTRIBUTES: [(&str, &str, VertexFormat, &str); 6] = [
+    (
+        Mesh::ATTRIBUTE_POSITION,
+        "POSITION",
+        VertexFormat::Float3,
+        "VEC3",
+    ),
+    (
+        Mesh::ATTRIBUTE_NORMAL,
+        "NORMAL",
+        VertexFormat::Float3,
+        "VEC3",
+    ),
+    (
+        Mesh::ATTRIBUTE_TANGENT,
+        "TANGENT",
+        VertexFormat::Float4,
+        "VEC4",
+    ),
+    (
+        Mesh::ATTRIBUTE_UV_0,
+        "TEXCOORD_0",
+        VertexFormat::Float2,
+        "VEC2",
+    ),
+    (
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+        "JOINTS_0",
+        VertexFormat::Uint4,
+        "VEC4",
+    ),
+    (
+        Mesh::ATTRIBUTE_JOINT_WEIGHT,
+        "WEIGHTS_0",
+        VertexFormat::Float4,
+        "VEC4",
+    ),
+];
+
+/// Saves meshes as GLTF files holding a single mesh with its data embedded. The
+/// [GltfLoader](crate::GltfLoader) loads the mesh again as `Mesh0/Primitive0` of the file.
+#[derive(Clone, Default)]
+pub struct GltfMeshSaver;
+
+impl AssetSaver for GltfMeshSaver {
+    type Asset = Mesh;
+
+    fn save(&self, mesh: &Mesh, _path: &Path) -> Result<Vec<u8>> {
+        for attribute in mesh.get_vertex_buffer_layout().attributes {
+            if !ATTRIBUTES.iter().any(|(name, ..)| *name == attribute.name) {
+                return Err(GltfSaveError::UnsupportedAttribute(attribute.name.to_string()).into());
+            }
+        }
+
+        let mut buffer = GltfBuffer::default();
+        let mut attributes = Vec::new();
+        for &(name, gltf_name, expected, accessor_type) in ATTRIBUTES.iter() {
+            let values = match mesh.attribute(name) {
+                Some(values) => values,
+                None => continue,
+            };
+            let format = VertexFormat::from(values);
+            if format != expected {
+                return Err(GltfSaveError::AttributeFormat {
+                    name: name.to_string(),
+                    format,
+                    expected,
+                }
+                .into());
+            }
+            let accessor = match values {
+                VertexAttributeValues::Uint4(joints) => {
+                    let mut bytes = Vec::with_capacity(joints.len() * 8);
+                    for &joint in joints.iter().flatten() {
+                        let joint = u16::try_from(joint)
+                            .map_err(|_| GltfSaveError::JointIndexOutOfRange(joint))?;
+                        bytes.extend_from_slice(&joint.to_le_bytes());
+                    }
+                    buffer.push_accessor(
+                        &bytes,
+                        joints.len(),
+                        UNSIGNED_SHORT,
+                        accessor_type,
+                        ARRAY_BUFFER,
+                        None,
+                    )
+                }
+                VertexAttributeValues::Float3(positions) if name == Mesh::ATTRIBUTE_POSITION => {
+                    // GLTF requires the bounds of positions
+                    let bounds = positions.iter().fold(
+                        ([f32::MAX; 3], [f32::MIN; 3]),
+                        |(mut min, mut max), position| {
+                            for i in 0..3 {
+                                min[i] = min[i].min(position[i]);
+                                max[i] = max[i].max(position[i]);
+                            }
+                            (min, max)
+                        },
+                    );
+                    buffer.push_accessor(
+                        values.get_bytes(),
+                        values.len(),
+                        FLOAT,
+                        accessor_type,
+                        ARRAY_BUFFER,
+                        Some(bounds),
+                    )
+                }
+                _ => buffer.push_accessor(
+                    values.get_bytes(),
+                    values.len(),
+                    FLOAT,
+                    accessor_type,
+                    ARRAY_BUFFER,
+                    None,
+                ),
+            };
+            attributes.push(format!("\"{}\":{}", gltf_name, accessor));
+        }
+
+        let indices = mesh.indices().map(|indices| {
+            let (count, component_type) = match indices {
+                Indices::U16(indices) => (indices.len(), UNSIGNED_SHORT),
+                Indices::U32(indices) => (indices.len(), UNSIGNED_INT),
+            };
+            let bytes = mesh.get_index_buffer_bytes().unwrap();
+            let accessor = buffer.push_accessor(
+                &bytes,
+                count,
+                component_type,
+                "SCALAR",
+                ELEMENT_ARRAY_BUFFER,
+                None,
+            );
+            format!(",\"indices\":{}", accessor)
+        });
+        let mode = match mesh.primitive_topology() {
+            PrimitiveTopology::PointList => 0,
+            PrimitiveTopology::LineList => 1,
+            PrimitiveTopology::LineStrip => 3,
+            PrimitiveTopology::TriangleList => 4,
+            PrimitiveTopology::TriangleStrip => 5,
+        };
+
+        let gltf = format!(
+            concat!(
+                "{{\"asset\":{{\"version\":\"2.0\"}},",
+                "\"meshes\":[{{\"primitives\":[{{\"attributes\":{{{}}}{},\"mode\":{}}}]}}],",
+                "\"buffers\":[{{\"byteLength\":{},\"uri\":\"data:application/octet-stream;base64,{}\"}}],",
+                "\"bufferViews\":[{}],\"accessors\":[{}]}}"
+            ),
+            attributes.join(","),
+            indices.unwrap_or_default(),
+            mode,
+            buffer.data.len(),
+            base64::encode(&buffer.data),
+            buffer.buffer_views.join(","),
+            buffer.accessors.join(","),
+        );
+        Ok(gltf.into_bytes())
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["gltf"]
+    }
+}
+
+/// The data of a GLTF file's only buffer, with a view and an accessor for each part of it
+#[derive(Default)]
+struct GltfBuffer {
+    data: Vec<u8>,
+    buffer_views: Vec<String>,
+    accessors: Vec<String>,
+}
+
+impl GltfBuffer {
+    /// Appends `bytes` and returns the index of the accessor that reads them.
+    fn push_accessor(
+        &mut self,
+        bytes: &[u8],
+        count: usize,
+        component_type: u32,
+        accessor_type: &str,
+        target: u32,
+        bounds: Option<([f32; 3], [f32; 3])>,
+    ) -> usize {
+        // accessors must be aligned to their component size
+        while self.data.len() % 4 != 0 {
+            self.data.push(0);
+        }
+        self.buffer_views.push(format!(
+            "{{\"buffer\":0,\"byteOffset\":{},\"byteLength\":{},\"target\":{}}}",
+            self.data.len(),
+            bytes.len(),
+            target
+        ));
+        self.data.extend_from_slice(bytes);
+        let bounds = bounds
+            .map(|(min, max)| format!(",\"min\":{:?},\"max\":{:?}", min, max))
+            .unwrap_or_default();
+        self.accessors.push(format!(
+            "{{\"bufferView\":{},\"componentType\":{},\"count\":{},\"type\":\"{}\"{}}}",
+            self.buffer_views.len() - 1,
+            component_type,
+            count,
+            accessor_type,
+            bounds
+        ));
+        self.accessors.len() - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_mesh() {
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        let positions = vec![[0.0, 0.0, 0.0], [1.0, 0.0, -2.0], [0.0, 3.0, 0.0]];
+        let uvs = vec![[0.0, 0.0], [1.0, 0.0], [0.0, 1.0]];
+        let joints: Vec<[u32; 4]> = vec![[0, 1, 0, 0], [2, 0, 0, 0], [0, 0, 0, 0]];
+        mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
+        mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, uvs.clone());
+        mesh.set_attribute(Mesh::ATTRIBUTE_JOINT_INDEX, joints.clone());
+        mesh.set_indices(Some(Indices::U16(vec![0, 1, 2])));
+
+        let bytes = GltfMeshSaver.save(&mesh, Path::new("mesh.gltf")).unwrap();
+        let gltf = gltf::Gltf::from_slice(&bytes).unwrap();
+        let buffer = match gltf.buffers().next().unwrap().source() {
+            gltf::buffer::Source::Uri(uri) => base64::decode(
+                uri.strip_prefix("data:application/octet-stream;base64,")
+                    .unwrap(),
+            )
+            .unwrap(),
+            gltf::buffer::Source::Bin => panic!("the buffer should be embedded"),
+        };
+        let primitive = gltf.meshes().next().unwrap().primitives().next().unwrap();
+        assert_eq!(primitive.mode(), gltf::mesh::Mode::Triangles);
+        assert_eq!(
+            primitive.bounding_box(),
+            gltf::mesh::BoundingBox {
+                min: [0.0, 0.0, -2.0],
+                max: [1.0, 3.0, 0.0],
+            }
+        );
+        let reader = primitive.reader(|_| Some(&buffer));
+        assert_eq!(
+            reader.read_positions().unwrap().collect::<Vec<_>>(),
+            positions
+        );
+        assert_eq!(
+            reader
+                .read_tex_coords(0)
+                .unwrap()
+                .into_f32()
+                .collect::<Vec<_>>(),
+            uvs
+        );
+        assert_eq!(
+            reader
+                .read_joints(0)
+                .unwrap()
+                .into_u16()
+                .collect::<Vec<_>>(),
+            joints
+                .iter()
+                .map(|joint| [
+                    joint[0] as u16,
+                    joint[1] as u16,
+                    joint[2] as u16,
+                    joint[3] as u16
+                ])
+                .collect::<Vec<_>>()
+        );
+        assert!(reader.read_normals().is_none());
+        assert_eq!(
+            reader
+                .read_indices()
+                .unwrap()
+                .into_u32()
+                .collect::<Vec<_>>(),
+            vec![0, 1, 2]
+        );
+
+        mesh.set_attribute(Mesh::ATTRIBUTE_COLOR, vec![[1.0; 4]; 3]);
+        assert!(GltfMeshSaver.save(&mesh, Path::new("mesh.gltf")).is_err());
+    }
+}
@@ -0,0 +1,484 @@
+use bevy_ecs::{
+    entity::Entity,
+    reflect::{ReflectComponent, ReflectResource},
+    world::World,
+};
+use bevy_reflect::{
+    DynamicList, DynamicMap, DynamicStruct, DynamicTuple, DynamicTupleStruct, GetPath, Reflect,
+    ReflectRef, TypeRegistration, TypeRegistry,
+};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum InspectorError {
+    #[error("entity {0:?} does not exist")]
+    NonExistentEntity(Entity),
+    #[error("the type `{type_name}` is not registered. consider registering the type using `app.register_type::<T>()`")]
+    UnregisteredType { type_name: String },
+    #[error("the type `{type_name}` is not a reflected component. consider adding `#[reflect(Component)]` to your type")]
+    UnregisteredComponent { type_name: String },
+    #[error("the type `{type_name}` is not a reflected resource. consider adding `#[reflect(Resource)]` to your type")]
+    UnregisteredResource { type_name: String },
+    #[error("entity {entity:?} does not have the component `{type_name}`")]
+    MissingComponent { entity: Entity, type_name: String },
+    #[error("the resource `{type_name}` does not exist")]
+    MissingResource { type_name: String },
+    #[error("invalid path `{path}`: {message}")]
+    InvalidPath { path: String, message: String },
+    #[error("cannot assign a value of type `{value_type}` to `{path}` of type `{target_type}`")]
+    TypeMismatch {
+        path: String,
+        value_type: String,
+        target_type: String,
+    },
+    #[error("invalid value: {0}")]
+    InvalidValue(String),
+    #[error("invalid command: {0}")]
+    InvalidCommand(String),
+}
+
+/// A reflected component of an inspected entity.
+#[derive(Debug)]
+pub struct InspectedComponent {
+    pub type_name: String,
+    pub value: ReflectNode,
+}
+
+/// A node in the tree of reflected values that make up a component.
+///
+/// Leaf values are owned copies, so a tree can be edited freely and written back with
+/// [ReflectNode::to_dynamic] or field by field with [set_component_path].
+#[derive(Debug)]
+pub struct ReflectNode {
+    /// The [GetPath] path of this value, relative to the component. Values nested in tuples and
+    /// maps cannot be addressed by path and have no path.
+    pub path: Option<String>,
+    pub type_name: String,
+    pub kind: ReflectNodeKind,
+}
+
+#[derive(Debug)]
+pub enum ReflectNodeKind {
+    Struct(Vec<(String, ReflectNode)>),
+    TupleStruct(Vec<ReflectNode>),
+    Tuple(Vec<ReflectNode>),
+    List(Vec<ReflectNode>),
+    Map(Vec<(Box<dyn Reflect>, ReflectNode)>),
+    Value(Box<dyn Reflect>),
+}
+
+impl ReflectNode {
+    pub fn new(value: &dyn Reflect) -> Self {
+        Self::from_reflect(value, Some(String::new()))
+    }
+
+    fn from_reflect(value: &dyn Reflect, path: Option<String>) -> Self {
+        let child_path = |suffix: String| path.as_ref().map(|path| format!("{}{}", path, suffix));
+        let field_path = |name: &str| {
+            path.as_ref().map(|path| {
+                if path.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}.{}", path, name)
+                }
+            })
+        };
+        let kind = match value.reflect_ref() {
+            ReflectRef::Struct(value) => ReflectNodeKind::Struct(
+                (0..value.field_len())
+                    .map(|index| {
+                        let name = value.name_at(index).unwrap();
+                        let field = value.field_at(index).unwrap();
+                        (
+                            name.to_string(),
+                            Self::from_reflect(field, field_path(name)),
+                        )
+                    })
+                    .collect(),
+            ),
+            ReflectRef::TupleStruct(value) => ReflectNodeKind::TupleStruct(
+                value
+                    .iter_fields()
+                    .enumerate()
+                    .map(|(index, field)| Self::from_reflect(field, field_path(&index.to_string())))
+                    .collect(),
+            ),
+            ReflectRef::Tuple(value) => ReflectNodeKind::Tuple(
+                value
+                    .iter_fields()
+                    .map(|field| Self::from_reflect(field, None))
+                    .collect(),
+            ),
+            ReflectRef::List(value) => ReflectNodeKind::List(
+                value
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| {
+                        Self::from_reflect(item, child_path(format!("[{}]", index)))
+                    })
+                    .collect(),
+            ),
+            ReflectRef::Map(value) => ReflectNodeKind::Map(
+                value
+                    .iter()
+                    .map(|(key, value)| (key.clone_value(), Self::from_reflect(value, None)))
+                    .collect(),
+            ),
+            ReflectRef::Value(value) => ReflectNodeKind::Value(value.clone_value()),
+        };
+
+        ReflectNode {
+            path,
+            type_name: value.type_name().to_string(),
+            kind,
+        }
+    }
+
+    /// Returns the node with the given [GetPath] path, if it exists.
+    pub fn get(&self, path: &str) -> Option<&ReflectNode> {
+        if self.path.as_deref() == Some(path) {
+            return Some(self);
+        }
+        self.children().find_map(|child| child.get(path))
+    }
+
+    /// Returns the node with the given [GetPath] path, if it exists.
+    pub fn get_mut(&mut self, path: &str) -> Option<&mut ReflectNode> {
+        if self.path.as_deref() == Some(path) {
+            return Some(self);
+        }
+        match &mut self.kind {
+            ReflectNodeKind::Struct(fields) => {
+                fields.iter_mut().find_map(|(_, field)| field.get_mut(path))
+            }
+            ReflectNodeKind::TupleStruct(fields)
+            | ReflectNodeKind::Tuple(fields)
+            | ReflectNodeKind::List(fields) => {
+                fields.iter_mut().find_map(|field| field.get_mut(path))
+            }
+            ReflectNodeKind::Map(entries) => entries
+                .iter_mut()
+                .find_map(|(_, value)| value.get_mut(path)),
+            ReflectNodeKind::Value(_) => None,
+        }
+    }
+
+    /// Iterates over the direct children of this node.
+    pub fn children(&self) -> Box<dyn Iterator<Item = &ReflectNode> + '_> {
+        match &self.kind {
+            ReflectNodeKind::Struct(fields) => Box::new(fields.iter().map(|(_, field)| field)),
+            ReflectNodeKind::TupleStruct(fields)
+            | ReflectNodeKind::Tuple(fields)
+            | ReflectNodeKind::List(fields) => Box::new(fields.iter()),
+            ReflectNodeKind::Map(entries) => Box::new(entries.iter().map(|(_, value)| value)),
+            ReflectNodeKind::Value(_) => Box::new(std::iter::empty()),
+        }
+    }
+
+    /// Rebuilds a reflected value from this (possibly edited) tree, suitable for
+    /// [Reflect::apply] or [ReflectComponent::apply_component].
+    pub fn to_dynamic(&self) -> Box<dyn Reflect> {
+        match &self.kind {
+            ReflectNodeKind::Struct(fields) => {
+                let mut value = DynamicStruct::default();
+                value.set_name(self.type_name.clone());
+                for (name, field) in fields {
+                    value.insert_boxed(name, field.to_dynamic());
+                }
+                Box::new(value)
+            }
+            ReflectNodeKind::TupleStruct(fields) => {
+                let mut value = DynamicTupleStruct::default();
+                value.set_name(self.type_name.clone());
+                for field in fields {
+                    value.insert_boxed(field.to_dynamic());
+                }
+                Box::new(value)
+            }
+            ReflectNodeKind::Tuple(fields) => {
+                let mut value = DynamicTuple::default();
+                for field in fields {
+                    value.insert_boxed(field.to_dynamic());
+                }
+                Box::new(value)
+            }
+            ReflectNodeKind::List(items) => {
+                let mut value = DynamicList::default();
+                value.set_name(self.type_name.clone());
+                for item in items {
+                    value.push_box(item.to_dynamic());
+                }
+                Box::new(value)
+            }
+            ReflectNodeKind::Map(entries) => {
+                let mut value = DynamicMap::default();
+                value.set_name(self.type_name.clone());
+                for (key, entry) in entries {
+                    value.insert_boxed(key.clone_value(), entry.to_dynamic());
+                }
+                Box::new(value)
+            }
+            ReflectNodeKind::Value(value) => value.clone_value(),
+        }
+    }
+}
+
+/// Looks up a registered component type by its full or short type name.
+pub fn component_registration<'r>(
+    type_registry: &'r TypeRegistry,
+    type_name: &str,
+) -> Result<(&'r TypeRegistration, &'r ReflectComponent), InspectorError> {
+    let registration = type_registry
+        .get_with_name(type_name)
+        .or_else(|| type_registry.get_with_short_name(type_name))
+        .ok_or_else(|| InspectorError::UnregisteredType {
+            type_name: type_name.to_string(),
+        })?;
+    let reflect_component = registration.data::<ReflectComponent>().ok_or_else(|| {
+        InspectorError::UnregisteredComponent {
+            type_name: registration.name().to_string(),
+        }
+    })?;
+    Ok((registration, reflect_component))
+}
+
+/// Looks up a registered resource type by its full or short type name.
+pub fn resource_registration<'r>(
+    type_registry: &'r TypeRegistry,
+    type_name: &str,
+) -> Result<(&'r TypeRegistration, &'r ReflectResource), InspectorError> {
+    let registration = type_registry
+        .get_with_name(type_name)
+        .or_else(|| type_registry.get_with_short_name(type_name))
+        .ok_or_else(|| InspectorError::UnregisteredType {
+            type_name: type_name.to_string(),
+        })?;
+    let reflect_resource = registration.data::<ReflectResource>().ok_or_else(|| {
+        InspectorError::UnregisteredResource {
+            type_name: registration.name().to_string(),
+        }
+    })?;
+    Ok((registration, reflect_resource))
+}
+
+/// Returns the registrations of every reflected component on the given entity.
+pub fn entity_components<'r>(
+    world: &World,
+    type_registry: &'r TypeRegistry,
+    entity: Entity,
+) -> Result<Vec<&'r TypeRegistration>, InspectorError> {
+    let entity_ref = world
+        .get_entity(entity)
+        .ok_or(InspectorError::NonExistentEntity(entity))?;
+    Ok(entity_ref
+        .archetype()
+        .components()
+        .filter_map(|component_id| {
+            world
+                .components()
+                .get_info(component_id)
+                .and_then(|info| info.type_id())
+                .and_then(|type_id| type_registry.get(type_id))
+                .filter(|registration| registration.data::<ReflectComponent>().is_some())
+        })
+        .collect())
+}
+
+/// Builds a [ReflectNode] tree for every reflected component on the given entity.
+pub fn inspect_entity(
+    world: &World,
+    type_registry: &TypeRegistry,
+    entity: Entity,
+) -> Result<Vec<InspectedComponent>, InspectorError> {
+    let mut components = Vec::new();
+    for registration in entity_components(world, type_registry, entity)? {
+        let reflect_component = registration.data::<ReflectComponent>().unwrap();
+        if let Some(component) = reflect_component.reflect_component(world, entity) {
+            components.push(InspectedComponent {
+                type_name: registration.name().to_string(),
+                value: ReflectNode::new(component),
+            });
+        }
+    }
+    Ok(components)
+}
+
+/// Returns the value at `path` within the named component of the given entity. An empty path
+/// returns the whole component.
+pub fn read_component_path<'w>(
+    world: &'w World,
+    type_registry: &TypeRegistry,
+    entity: Entity,
+    component: &str,
+    path: &str,
+) -> Result<&'w dyn Reflect, InspectorError> {
+    let (registration, reflect_component) = component_registration(type_registry, component)?;
+    world
+        .get_entity(entity)
+        .ok_or(InspectorError::NonExistentEntity(entity))?;
+    let component = reflect_component
+        .reflect_component(world, entity)
+        .ok_or_else(|| InspectorError::MissingComponent {
+            entity,
+            type_name: registration.name().to_string(),
+        })?;
+    component
+        .path(path)
+        .map_err(|err| InspectorError::InvalidPath {
+            path: path.to_string(),
+            message: err.to_string(),
+        })
+}
+
+/// Applies `value` to the value at `path` within the named component of the given entity. The
+/// value must have the same type as the field it is applied to.
+pub fn set_component_path(
+    world: &mut World,
+    type_registry: &TypeRegistry,
+    entity: Entity,
+    component: &str,
+    path: &str,
+    value: &dyn Reflect,
+) -> Result<(), InspectorError> {
+    let (registration, reflect_component) = component_registration(type_registry, component)?;
+    world
+        .get_entity(entity)
+        .ok_or(InspectorError::NonExistentEntity(entity))?;
+    let mut component = reflect_component
+        .reflect_component_mut(world, entity)
+        .ok_or_else(|| InspectorError::MissingComponent {
+            entity,
+            type_name: registration.name().to_string(),
+        })?;
+    let target = component
+        .path_mut(path)
+        .map_err(|err| InspectorError::InvalidPath {
+            path: path.to_string(),
+            message: err.to_string(),
+        })?;
+    // `Reflect::apply` panics on mismatched value types, so reject those up front
+    if target.type_name() != value.type_name() {
+        return Err(InspectorError::TypeMismatch {
+            path: path.to_string(),
+            value_type: value.type_name().to_string(),
+            target_type: target.type_name().to_string(),
+        });
+    }
+    target.apply(value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::reflect::ReflectComponent;
+    use bevy_reflect::Reflect;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Component)]
+    struct Health {
+        current: f32,
+        max: f32,
+        regen: Vec<f32>,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Component)]
+    struct Name(String);
+
+    fn setup() -> (World, TypeRegistry, Entity) {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Health>();
+        type_registry.register::<Name>();
+        let mut world = World::new();
+        let entity = world
+            .spawn()
+            .insert(Health {
+                current: 5.0,
+                max: 10.0,
+                regen: vec![0.5, 1.0],
+            })
+            .insert(Name("orc".to_string()))
+            .insert(42usize)
+            .id();
+        (world, type_registry, entity)
+    }
+
+    #[test]
+    fn inspect_reflected_components() {
+        let (world, type_registry, entity) = setup();
+        let mut components = inspect_entity(&world, &type_registry, entity).unwrap();
+        components.sort_by(|a, b| a.type_name.cmp(&b.type_name));
+        assert_eq!(components.len(), 2);
+
+        let health = &components[0].value;
+        assert!(components[0].type_name.ends_with("Health"));
+        let regen = health.get("regen[1]").unwrap();
+        match &regen.kind {
+            ReflectNodeKind::Value(value) => assert_eq!(value.downcast_ref::<f32>(), Some(&1.0)),
+            _ => panic!("expected a value node"),
+        }
+        assert!(health.get("missing").is_none());
+
+        let name = &components[1].value;
+        assert_eq!(
+            name.get("0").unwrap().type_name,
+            std::any::type_name::<String>()
+        );
+    }
+
+    #[test]
+    fn edit_and_apply_tree() {
+        let (mut world, type_registry, entity) = setup();
+        let mut components = inspect_entity(&world, &type_registry, entity).unwrap();
+        let health = components
+            .iter_mut()
+            .find(|component| component.type_name.ends_with("Health"))
+            .unwrap();
+        if let ReflectNodeKind::Value(value) = &mut health.value.get_mut("max").unwrap().kind {
+            *value = Box::new(20.0f32);
+        }
+        let patched = health.value.to_dynamic();
+        set_component_path(&mut world, &type_registry, entity, "Health", "", &*patched).unwrap();
+        assert_eq!(world.get::<Health>(entity).unwrap().max, 20.0);
+    }
+
+    #[test]
+    fn set_field_by_path() {
+        let (mut world, type_registry, entity) = setup();
+        set_component_path(
+            &mut world,
+            &type_registry,
+            entity,
+            "Health",
+            "regen[0]",
+            &2.0f32,
+        )
+        .unwrap();
+        assert_eq!(world.get::<Health>(entity).unwrap().regen[0], 2.0);
+
+        let value =
+            read_component_path(&world, &type_registry, entity, "Health", "regen[0]").unwrap();
+        assert_eq!(value.downcast_ref::<f32>(), Some(&2.0));
+
+        assert!(matches!(
+            set_component_path(&mut world, &type_registry, entity, "Health", "max", &1usize),
+            Err(InspectorError::TypeMismatch { .. })
+        ));
+        assert!(matches!(
+            set_component_path(
+                &mut world,
+                &type_registry,
+                entity,
+                "Health",
+                "nope",
+                &1.0f32
+            ),
+            Err(InspectorError::InvalidPath { .. })
+        ));
+        assert!(matches!(
+            read_component_path(&world, &type_registry, entity, "usize", ""),
+            Err(InspectorError::UnregisteredType { .. })
+        ));
+    }
+}
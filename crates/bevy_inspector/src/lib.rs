@@ -0,0 +1,84 @@
+mod entity_inspector;
+pub mod protocol;
+pub mod remote;
+mod server;
+pub mod transport;
+
+pub use entity_inspector::*;
+pub use server::*;
+
+pub mod prelude {
+    pub use crate::{
+        remote::RemoteSettings, InspectorPlugin, InspectorSettings, InspectorTransport,
+        RemotePlugin,
+    };
+}
+
+use bevy_app::prelude::*;
+use bevy_ecs::{schedule::ExclusiveSystemDescriptorCoercion, system::IntoExclusiveSystem};
+use bevy_log::error;
+use remote::{remote_server_system, RemoteServer, RemoteSettings};
+
+/// Lets external tools list entities, read reflected components and patch their fields while the
+/// app is running, using the line based [protocol](protocol::InspectorCommand). The transport is
+/// configured with the [InspectorSettings] resource, or replaced entirely by inserting an
+/// [InspectorServer] before adding the plugin.
+#[derive(Default)]
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.world().get_resource::<InspectorServer>().is_none() {
+            let settings = app
+                .world_mut()
+                .get_resource_or_insert_with(InspectorSettings::default)
+                .clone();
+            match settings.transport.start() {
+                Ok(transport) => {
+                    app.insert_resource(InspectorServer::new(transport));
+                }
+                Err(err) => {
+                    error!("failed to start the inspector: {}", err);
+                    return;
+                }
+            }
+        }
+
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            inspector_server_system.exclusive_system().at_start(),
+        );
+    }
+}
+
+/// Serves a JSON-RPC 2.0 API for querying and editing the world from external tools. See
+/// [RemoteServer] for the supported methods. The transport is configured with the
+/// [RemoteSettings] resource, or replaced entirely by inserting a [RemoteServer] with a custom
+/// [RemoteTransport](transport::RemoteTransport) before adding the plugin.
+#[derive(Default)]
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if app.world().get_resource::<RemoteServer>().is_none() {
+            let settings = app
+                .world_mut()
+                .get_resource_or_insert_with(RemoteSettings::default)
+                .clone();
+            match settings.transport.start() {
+                Ok(transport) => {
+                    app.insert_resource(RemoteServer::new(transport));
+                }
+                Err(err) => {
+                    error!("failed to start the remote server: {}", err);
+                    return;
+                }
+            }
+        }
+
+        app.add_system_to_stage(
+            CoreStage::PreUpdate,
+            remote_server_system.exclusive_system().at_start(),
+        );
+    }
+}
@@ -0,0 +1,301 @@
+use crate::{
+    entity_inspector::{
+        entity_components, inspect_entity, read_component_path, set_component_path, ReflectNode,
+        ReflectNodeKind,
+    },
+    InspectorError,
+};
+use bevy_ecs::{entity::Entity, world::World};
+use bevy_reflect::{
+    serde::{ReflectDeserializer, ReflectSerializer},
+    Reflect, ReflectDeserialize, ReflectRef, TypeRegistry,
+};
+use serde::de::DeserializeSeed;
+use std::fmt::Write;
+
+/// A command of the line based inspector protocol.
+///
+/// Entities are written the way they are debug printed (`<id>v<generation>`) and fields are
+/// addressed as `<Component>.<path>`, where the component is its full or short type name and the
+/// path is a [GetPath](bevy_reflect::GetPath) path:
+///
+/// * `entities`: lists every entity and the reflected components it has
+/// * `inspect <entity>`: prints every reflected field of the entity's components
+/// * `get <entity> <Component>[.<path>]`: prints a component or one of its fields as RON
+/// * `set <entity> <Component>[.<path>] <value>`: assigns a RON value to a component or field
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InspectorCommand {
+    ListEntities,
+    Inspect(Entity),
+    Get {
+        entity: Entity,
+        component: String,
+        path: String,
+    },
+    Set {
+        entity: Entity,
+        component: String,
+        path: String,
+        value: String,
+    },
+}
+
+impl InspectorCommand {
+    pub fn parse(line: &str) -> Result<Self, InspectorError> {
+        let line = line.trim();
+        let (command, arguments) = split_token(line);
+        match command {
+            "entities" => Ok(InspectorCommand::ListEntities),
+            "inspect" => Ok(InspectorCommand::Inspect(parse_entity(arguments)?)),
+            "get" => {
+                let (entity, field) = split_token(arguments);
+                let (component, path) = split_field(field)?;
+                Ok(InspectorCommand::Get {
+                    entity: parse_entity(entity)?,
+                    component,
+                    path,
+                })
+            }
+            "set" => {
+                let (entity, arguments) = split_token(arguments);
+                let (field, value) = split_token(arguments);
+                let (component, path) = split_field(field)?;
+                if value.is_empty() {
+                    return Err(InspectorError::InvalidCommand(
+                        "expected a value to set".to_string(),
+                    ));
+                }
+                Ok(InspectorCommand::Set {
+                    entity: parse_entity(entity)?,
+                    component,
+                    path,
+                    value: value.to_string(),
+                })
+            }
+            _ => Err(InspectorError::InvalidCommand(format!(
+                "unknown command `{}`",
+                command
+            ))),
+        }
+    }
+
+    /// Runs the command against `world`, returning the text of the response.
+    pub fn execute(
+        &self,
+        world: &mut World,
+        type_registry: &TypeRegistry,
+    ) -> Result<String, InspectorError> {
+        let mut output = String::new();
+        match self {
+            InspectorCommand::ListEntities => {
+                for archetype in world.archetypes().iter() {
+                    for entity in archetype.entities() {
+                        let components = entity_components(world, type_registry, *entity)?
+                            .iter()
+                            .map(|registration| registration.short_name())
+                            .collect::<Vec<_>>();
+                        writeln!(output, "{:?} {}", entity, components.join(" ")).unwrap();
+                    }
+                }
+            }
+            InspectorCommand::Inspect(entity) => {
+                for component in inspect_entity(world, type_registry, *entity)? {
+                    let registration = type_registry.get_with_name(&component.type_name).unwrap();
+                    write_node(
+                        &mut output,
+                        registration.short_name(),
+                        &component.value,
+                        type_registry,
+                    );
+                }
+            }
+            InspectorCommand::Get {
+                entity,
+                component,
+                path,
+            } => {
+                let value = read_component_path(world, type_registry, *entity, component, path)?;
+                writeln!(output, "{}", value_to_ron(value, type_registry)?).unwrap();
+            }
+            InspectorCommand::Set {
+                entity,
+                component,
+                path,
+                value,
+            } => {
+                let target_type =
+                    read_component_path(world, type_registry, *entity, component, path)?
+                        .type_name()
+                        .to_string();
+                let value = value_from_ron(value, &target_type, type_registry)?;
+                set_component_path(world, type_registry, *entity, component, path, &*value)?;
+                writeln!(output, "ok").unwrap();
+            }
+        }
+        Ok(output)
+    }
+}
+
+/// Formats the response to a command line, terminated by an empty line.
+pub fn respond(world: &mut World, type_registry: &TypeRegistry, line: &str) -> String {
+    let response = InspectorCommand::parse(line)
+        .and_then(|command| command.execute(world, type_registry))
+        .unwrap_or_else(|err| format!("error: {}\n", err));
+    format!("{}\n", response)
+}
+
+fn split_token(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    match input.find(char::is_whitespace) {
+        Some(index) => (&input[..index], input[index..].trim_start()),
+        None => (input, ""),
+    }
+}
+
+fn split_field(field: &str) -> Result<(String, String), InspectorError> {
+    if field.is_empty() {
+        return Err(InspectorError::InvalidCommand(
+            "expected a component".to_string(),
+        ));
+    }
+    Ok(match field.find(&['.', '['][..]) {
+        Some(index) if field[index..].starts_with('.') => {
+            (field[..index].to_string(), field[index + 1..].to_string())
+        }
+        Some(index) => (field[..index].to_string(), field[index..].to_string()),
+        None => (field.to_string(), String::new()),
+    })
+}
+
+fn parse_entity(input: &str) -> Result<Entity, InspectorError> {
+    let invalid = || InspectorError::InvalidCommand(format!("invalid entity `{}`", input));
+    let input = input.trim();
+    let separator = input.find('v').ok_or_else(invalid)?;
+    let id = input[..separator].parse::<u32>().map_err(|_| invalid())?;
+    let generation = input[separator + 1..]
+        .parse::<u32>()
+        .map_err(|_| invalid())?;
+    Ok(Entity::from_bits(
+        u64::from(generation) << 32 | u64::from(id),
+    ))
+}
+
+fn write_node(
+    output: &mut String,
+    component: &str,
+    node: &ReflectNode,
+    type_registry: &TypeRegistry,
+) {
+    match &node.kind {
+        ReflectNodeKind::Value(value) => {
+            let field = match node.path.as_deref() {
+                Some("") => component.to_string(),
+                Some(path) if path.starts_with('[') => format!("{}{}", component, path),
+                Some(path) => format!("{}.{}", component, path),
+                // values without a path can't be patched, so there is no point listing them
+                None => return,
+            };
+            let value = value_to_ron(&**value, type_registry)
+                .unwrap_or_else(|_| "<unserializable>".to_string());
+            writeln!(output, "{}: {} = {}", field, node.type_name, value).unwrap();
+        }
+        _ => {
+            for child in node.children() {
+                write_node(output, component, child, type_registry);
+            }
+        }
+    }
+}
+
+fn value_to_ron(
+    value: &dyn Reflect,
+    type_registry: &TypeRegistry,
+) -> Result<String, InspectorError> {
+    let result = match (value.reflect_ref(), value.serializable()) {
+        // plain values are printed as-is so they can be pasted straight back into `set`
+        (ReflectRef::Value(_), Some(serializable)) => ron::ser::to_string(&serializable.borrow()),
+        _ => ron::ser::to_string(&ReflectSerializer::new(value, type_registry)),
+    };
+    result.map_err(|err| InspectorError::InvalidValue(err.to_string()))
+}
+
+fn value_from_ron(
+    value: &str,
+    target_type: &str,
+    type_registry: &TypeRegistry,
+) -> Result<Box<dyn Reflect>, InspectorError> {
+    let mut deserializer = ron::de::Deserializer::from_str(value)
+        .map_err(|err| InspectorError::InvalidValue(err.to_string()))?;
+    let reflect_deserialize = type_registry
+        .get_with_name(target_type)
+        .and_then(|registration| registration.data::<ReflectDeserialize>());
+    let result = match reflect_deserialize {
+        Some(reflect_deserialize) => reflect_deserialize.deserialize(&mut deserializer),
+        None => ReflectDeserializer::new(type_registry).deserialize(&mut deserializer),
+    };
+    result.map_err(|err| InspectorError::InvalidValue(err.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_commands() {
+        assert_eq!(
+            InspectorCommand::parse("entities").unwrap(),
+            InspectorCommand::ListEntities
+        );
+        assert_eq!(
+            InspectorCommand::parse("get 3v1 Transform.translation.x").unwrap(),
+            InspectorCommand::Get {
+                entity: Entity::from_bits(1 << 32 | 3),
+                component: "Transform".to_string(),
+                path: "translation.x".to_string(),
+            }
+        );
+        assert_eq!(
+            InspectorCommand::parse("set 0v0 Inventory[2] (count: 3)").unwrap(),
+            InspectorCommand::Set {
+                entity: Entity::new(0),
+                component: "Inventory".to_string(),
+                path: "[2]".to_string(),
+                value: "(count: 3)".to_string(),
+            }
+        );
+        assert!(InspectorCommand::parse("get 3 Transform").is_err());
+        assert!(InspectorCommand::parse("set 3v0 Transform").is_err());
+        assert!(InspectorCommand::parse("launch").is_err());
+    }
+
+    #[test]
+    fn get_and_set_fields() {
+        use bevy_ecs::reflect::ReflectComponent;
+
+        #[derive(Reflect, Default)]
+        #[reflect(Component)]
+        struct Speed {
+            value: f32,
+        }
+
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Speed>();
+        type_registry.register::<f32>();
+        let mut world = World::new();
+        let entity = world.spawn().insert(Speed { value: 1.5 }).id();
+
+        let get = format!("get {:?} Speed.value", entity);
+        assert_eq!(respond(&mut world, &type_registry, &get), "1.5\n\n");
+        let set = format!("set {:?} Speed.value 4.0", entity);
+        assert_eq!(respond(&mut world, &type_registry, &set), "ok\n\n");
+        assert_eq!(world.get::<Speed>(entity).unwrap().value, 4.0);
+
+        let inspect = format!("inspect {:?}", entity);
+        assert_eq!(
+            respond(&mut world, &type_registry, &inspect),
+            "Speed.value: f32 = 4\n\n"
+        );
+        let set = format!("set {:?} Speed.value \"fast\"", entity);
+        assert!(respond(&mut world, &type_registry, &set).starts_with("error: "));
+    }
+}
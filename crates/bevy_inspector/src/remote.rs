@@ -0,0 +1,765 @@
+use crate::{
+    component_registration, entity_components, resource_registration,
+    transport::{ClientId, RemoteTransport},
+    InspectorError, InspectorTransport,
+};
+use bevy_ecs::{
+    entity::Entity,
+    world::{Mut, World},
+};
+use bevy_reflect::{
+    serde::{ReflectDeserializer, ReflectSerializer},
+    Reflect, TypeRegistry, TypeRegistryArc,
+};
+use bevy_utils::HashMap;
+use serde::{de::DeserializeOwned, de::DeserializeSeed, Deserialize, Serialize};
+use serde_json::Value;
+use std::net::SocketAddr;
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+/// The notification sent to subscribers when watched components change.
+pub const CHANGED_NOTIFICATION: &str = "world.changed";
+
+/// A JSON-RPC 2.0 request. Requests without an id are notifications and get no response.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub jsonrpc: String,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    pub jsonrpc: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+    pub id: Value,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    /// The request was well formed but could not be carried out on the world.
+    pub const WORLD_ERROR: i64 = -32000;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<InspectorError> for RpcError {
+    fn from(err: InspectorError) -> Self {
+        RpcError::new(RpcError::WORLD_ERROR, err.to_string())
+    }
+}
+
+/// Configures the [RemotePlugin](crate::RemotePlugin). Insert it before adding the plugin.
+#[derive(Debug, Clone)]
+pub struct RemoteSettings {
+    pub transport: InspectorTransport,
+}
+
+impl Default for RemoteSettings {
+    fn default() -> Self {
+        Self {
+            transport: InspectorTransport::Tcp(SocketAddr::from(([127, 0, 0, 1], 15702))),
+        }
+    }
+}
+
+struct Subscription {
+    client: ClientId,
+    entity: Entity,
+    components: Option<Vec<String>>,
+    last_values: HashMap<String, Value>,
+}
+
+/// Answers JSON-RPC requests received by its transport and notifies subscribers of component
+/// changes.
+///
+/// Entities are sent as the `u64` produced by [Entity::to_bits]. Components and resources are
+/// named by their full or short type name, and their values use the format of
+/// [ReflectSerializer] and [ReflectDeserializer]. Supported methods:
+///
+/// * `world.list_entities {components?}`: entities, optionally only those with all `components`
+/// * `world.get_components {entity, components?}`: reflected components of an entity
+/// * `world.insert_components {entity, components}`: inserts or updates serialized components
+/// * `world.remove_components {entity, components}`
+/// * `world.spawn {components?}`: spawns an entity, returning it
+/// * `world.despawn {entity}`
+/// * `world.get_resources {resources}`: reflected resources
+/// * `world.subscribe {entity, components?}`: sends a `world.changed` notification with the
+///   current values, then again whenever one of the components changes, is removed or the entity
+///   is despawned. Returns the subscription id.
+/// * `world.unsubscribe {subscription}`
+pub struct RemoteServer {
+    transport: Box<dyn RemoteTransport>,
+    subscriptions: HashMap<u64, Subscription>,
+    next_subscription: u64,
+}
+
+impl RemoteServer {
+    pub fn new(transport: Box<dyn RemoteTransport>) -> Self {
+        Self {
+            transport,
+            subscriptions: Default::default(),
+            next_subscription: 0,
+        }
+    }
+
+    fn handle_message(
+        &mut self,
+        world: &mut World,
+        type_registry: &TypeRegistry,
+        client: ClientId,
+        message: &str,
+    ) -> Option<Value> {
+        let message = match serde_json::from_str::<Value>(message) {
+            Ok(message) => message,
+            Err(err) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(RpcError::PARSE_ERROR, err.to_string()),
+                ))
+            }
+        };
+        match message {
+            Value::Array(batch) => {
+                let responses = batch
+                    .into_iter()
+                    .filter_map(|request| {
+                        self.handle_request(world, type_registry, client, request)
+                    })
+                    .collect::<Vec<_>>();
+                if responses.is_empty() {
+                    None
+                } else {
+                    Some(Value::Array(responses))
+                }
+            }
+            request => self.handle_request(world, type_registry, client, request),
+        }
+    }
+
+    fn handle_request(
+        &mut self,
+        world: &mut World,
+        type_registry: &TypeRegistry,
+        client: ClientId,
+        request: Value,
+    ) -> Option<Value> {
+        let request = match serde_json::from_value::<RpcRequest>(request) {
+            Ok(request) if request.jsonrpc == JSONRPC_VERSION => request,
+            Ok(request) => {
+                return Some(error_response(
+                    request.id.unwrap_or(Value::Null),
+                    RpcError::new(RpcError::INVALID_REQUEST, "unsupported jsonrpc version"),
+                ))
+            }
+            Err(err) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(RpcError::INVALID_REQUEST, err.to_string()),
+                ))
+            }
+        };
+        let result = self.call(
+            world,
+            type_registry,
+            client,
+            &request.method,
+            request.params,
+        );
+        let id = request.id?;
+        Some(match result {
+            Ok(result) => serde_json::to_value(RpcResponse {
+                jsonrpc: JSONRPC_VERSION.to_string(),
+                result: Some(result),
+                error: None,
+                id,
+            })
+            .unwrap(),
+            Err(err) => error_response(id, err),
+        })
+    }
+
+    fn call(
+        &mut self,
+        world: &mut World,
+        type_registry: &TypeRegistry,
+        client: ClientId,
+        method: &str,
+        params: Value,
+    ) -> Result<Value, RpcError> {
+        match method {
+            "world.list_entities" => {
+                let params: ListEntitiesParams = parse_params(params)?;
+                let required = params
+                    .components
+                    .iter()
+                    .map(|name| {
+                        component_registration(type_registry, name)
+                            .map(|(registration, _)| registration.name())
+                    })
+                    .collect::<Result<Vec<_>, _>>()?;
+                let mut entities = Vec::new();
+                for archetype in world.archetypes().iter() {
+                    for entity in archetype.entities() {
+                        let components = entity_components(world, type_registry, *entity)?
+                            .iter()
+                            .map(|registration| registration.name())
+                            .collect::<Vec<_>>();
+                        if required.iter().all(|name| components.contains(name)) {
+                            entities.push(serde_json::json!({
+                                "entity": entity.to_bits(),
+                                "components": components,
+                            }));
+                        }
+                    }
+                }
+                Ok(Value::Array(entities))
+            }
+            "world.get_components" => {
+                let params: EntityComponentsParams = parse_params(params)?;
+                let values = serialize_components(
+                    world,
+                    type_registry,
+                    params.entity(),
+                    &params.components,
+                )?;
+                Ok(Value::Object(values.into_iter().collect()))
+            }
+            "world.insert_components" => {
+                let params: InsertComponentsParams = parse_params(params)?;
+                insert_components(
+                    world,
+                    type_registry,
+                    Entity::from_bits(params.entity),
+                    params.components,
+                )?;
+                Ok(Value::Null)
+            }
+            "world.remove_components" => {
+                let params: EntityComponentsParams = parse_params(params)?;
+                let entity = params.entity();
+                world
+                    .get_entity(entity)
+                    .ok_or(InspectorError::NonExistentEntity(entity))?;
+                for name in params.components.unwrap_or_default() {
+                    let (_, reflect_component) = component_registration(type_registry, &name)?;
+                    reflect_component.remove_component(world, entity);
+                }
+                Ok(Value::Null)
+            }
+            "world.spawn" => {
+                let params: SpawnParams = parse_params(params)?;
+                let entity = world.spawn().id();
+                if let Err(err) = insert_components(world, type_registry, entity, params.components)
+                {
+                    world.despawn(entity);
+                    return Err(err);
+                }
+                Ok(Value::from(entity.to_bits()))
+            }
+            "world.despawn" => {
+                let params: EntityParams = parse_params(params)?;
+                let entity = Entity::from_bits(params.entity);
+                if !world.despawn(entity) {
+                    return Err(InspectorError::NonExistentEntity(entity).into());
+                }
+                Ok(Value::Null)
+            }
+            "world.get_resources" => {
+                let params: ResourcesParams = parse_params(params)?;
+                let mut values = serde_json::Map::new();
+                for name in params.resources {
+                    let (registration, reflect_resource) =
+                        resource_registration(type_registry, &name)?;
+                    let resource = reflect_resource.reflect_resource(world).ok_or_else(|| {
+                        InspectorError::MissingResource {
+                            type_name: registration.name().to_string(),
+                        }
+                    })?;
+                    values.insert(
+                        registration.name().to_string(),
+                        to_json(resource, type_registry)?,
+                    );
+                }
+                Ok(Value::Object(values))
+            }
+            "world.subscribe" => {
+                let params: EntityComponentsParams = parse_params(params)?;
+                let entity = params.entity();
+                // validate the request up front rather than in the first notification
+                serialize_components(world, type_registry, entity, &params.components)?;
+                let id = self.next_subscription;
+                self.next_subscription += 1;
+                self.subscriptions.insert(
+                    id,
+                    Subscription {
+                        client,
+                        entity,
+                        components: params.components,
+                        last_values: HashMap::default(),
+                    },
+                );
+                Ok(Value::from(id))
+            }
+            "world.unsubscribe" => {
+                let params: UnsubscribeParams = parse_params(params)?;
+                Ok(Value::Bool(
+                    self.subscriptions.remove(&params.subscription).is_some(),
+                ))
+            }
+            _ => Err(RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                format!("unknown method `{}`", method),
+            )),
+        }
+    }
+
+    /// Drops the subscriptions of a client that disconnected, so it isn't notified anymore
+    fn remove_client(&mut self, client: ClientId) {
+        self.subscriptions
+            .retain(|_, subscription| subscription.client != client);
+    }
+
+    fn notify_subscribers(&mut self, world: &World, type_registry: &TypeRegistry) {
+        let mut finished = Vec::new();
+        for (id, subscription) in self.subscriptions.iter_mut() {
+            let mut changes = serde_json::Map::new();
+            let current = match serialize_components(
+                world,
+                type_registry,
+                subscription.entity,
+                &subscription.components,
+            ) {
+                Ok(current) => current,
+                Err(_) => {
+                    finished.push(*id);
+                    self.transport.send(
+                        subscription.client,
+                        notification(*id, subscription.entity, changes, true),
+                    );
+                    continue;
+                }
+            };
+            for (name, value) in current.iter() {
+                if subscription.last_values.get(name) != Some(value) {
+                    changes.insert(name.clone(), value.clone());
+                }
+            }
+            for name in subscription.last_values.keys() {
+                if !current.contains_key(name) {
+                    changes.insert(name.clone(), Value::Null);
+                }
+            }
+            if !changes.is_empty() {
+                self.transport.send(
+                    subscription.client,
+                    notification(*id, subscription.entity, changes, false),
+                );
+            }
+            subscription.last_values = current;
+        }
+        for id in finished {
+            self.subscriptions.remove(&id);
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct EntityParams {
+    entity: u64,
+}
+
+#[derive(Deserialize)]
+struct EntityComponentsParams {
+    entity: u64,
+    #[serde(default)]
+    components: Option<Vec<String>>,
+}
+
+impl EntityComponentsParams {
+    fn entity(&self) -> Entity {
+        Entity::from_bits(self.entity)
+    }
+}
+
+#[derive(Deserialize)]
+struct ListEntitiesParams {
+    #[serde(default)]
+    components: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct InsertComponentsParams {
+    entity: u64,
+    components: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct SpawnParams {
+    #[serde(default)]
+    components: Vec<Value>,
+}
+
+#[derive(Deserialize)]
+struct ResourcesParams {
+    resources: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct UnsubscribeParams {
+    subscription: u64,
+}
+
+fn parse_params<T: DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+    // methods without required parameters may be called without `params`
+    let params = if params.is_null() {
+        Value::Object(Default::default())
+    } else {
+        params
+    };
+    serde_json::from_value(params)
+        .map_err(|err| RpcError::new(RpcError::INVALID_PARAMS, err.to_string()))
+}
+
+fn error_response(id: Value, error: RpcError) -> Value {
+    serde_json::to_value(RpcResponse {
+        jsonrpc: JSONRPC_VERSION.to_string(),
+        result: None,
+        error: Some(error),
+        id,
+    })
+    .unwrap()
+}
+
+fn notification(
+    subscription: u64,
+    entity: Entity,
+    components: serde_json::Map<String, Value>,
+    despawned: bool,
+) -> String {
+    let mut params = serde_json::json!({
+        "subscription": subscription,
+        "entity": entity.to_bits(),
+        "components": components,
+    });
+    if despawned {
+        params["despawned"] = Value::Bool(true);
+    }
+    serde_json::to_string(&RpcRequest {
+        jsonrpc: JSONRPC_VERSION.to_string(),
+        method: CHANGED_NOTIFICATION.to_string(),
+        params,
+        id: None,
+    })
+    .unwrap()
+}
+
+fn to_json(value: &dyn Reflect, type_registry: &TypeRegistry) -> Result<Value, RpcError> {
+    serde_json::to_value(ReflectSerializer::new(value, type_registry))
+        .map_err(|err| RpcError::new(RpcError::WORLD_ERROR, err.to_string()))
+}
+
+/// Serializes the named components of an entity, or all of its reflected components if no names
+/// are given.
+fn serialize_components(
+    world: &World,
+    type_registry: &TypeRegistry,
+    entity: Entity,
+    components: &Option<Vec<String>>,
+) -> Result<HashMap<String, Value>, RpcError> {
+    let registrations = match components {
+        Some(names) => {
+            world
+                .get_entity(entity)
+                .ok_or(InspectorError::NonExistentEntity(entity))?;
+            names
+                .iter()
+                .map(|name| component_registration(type_registry, name).map(|(r, _)| r))
+                .collect::<Result<Vec<_>, _>>()?
+        }
+        None => entity_components(world, type_registry, entity)?,
+    };
+    let mut values = HashMap::default();
+    for registration in registrations {
+        let reflect_component = component_registration(type_registry, registration.name())?.1;
+        // components the entity doesn't have are left out, so subscribers see them as removed
+        if let Some(component) = reflect_component.reflect_component(world, entity) {
+            values.insert(
+                registration.name().to_string(),
+                to_json(component, type_registry)?,
+            );
+        }
+    }
+    Ok(values)
+}
+
+fn insert_components(
+    world: &mut World,
+    type_registry: &TypeRegistry,
+    entity: Entity,
+    components: Vec<Value>,
+) -> Result<(), RpcError> {
+    world
+        .get_entity(entity)
+        .ok_or(InspectorError::NonExistentEntity(entity))?;
+    // deserialize everything before touching the world so a bad component inserts nothing
+    let components = components
+        .into_iter()
+        .map(|component| {
+            ReflectDeserializer::new(type_registry)
+                .deserialize(component)
+                .map_err(|err| RpcError::new(RpcError::INVALID_PARAMS, err.to_string()))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    for component in components.iter() {
+        component_registration(type_registry, component.type_name())?;
+    }
+    for component in components {
+        let (registration, reflect_component) =
+            component_registration(type_registry, component.type_name())?;
+        if world
+            .entity(entity)
+            .contains_type_id(registration.type_id())
+        {
+            reflect_component.apply_component(world, entity, &*component);
+        } else {
+            reflect_component.add_component(world, entity, &*component);
+        }
+    }
+    Ok(())
+}
+
+/// Answers every queued JSON-RPC request, then notifies subscribers of changed components.
+/// Subscriptions of disconnected clients are dropped.
+pub fn remote_server_system(world: &mut World) {
+    let type_registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+    let type_registry = type_registry.read();
+    world.resource_scope(|world, mut server: Mut<RemoteServer>| {
+        while let Some((client, message)) = server.transport.receive() {
+            if let Some(response) = server.handle_message(world, &type_registry, client, &message) {
+                server.transport.send(client, response.to_string());
+            }
+        }
+        for client in server.transport.disconnected() {
+            server.remove_client(client);
+        }
+        server.notify_subscribers(world, &type_registry);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::transport::{ChannelClient, ChannelTransport};
+    use bevy_ecs::reflect::{ReflectComponent, ReflectResource};
+    use serde_json::json;
+
+    #[derive(Reflect, Default, PartialEq, Debug)]
+    #[reflect(Component)]
+    struct Position {
+        x: f32,
+        y: f32,
+    }
+
+    #[derive(Reflect, Default)]
+    #[reflect(Resource)]
+    struct Score {
+        points: u32,
+    }
+
+    fn setup() -> (World, ChannelClient) {
+        let mut type_registry = TypeRegistry::default();
+        type_registry.register::<Position>();
+        type_registry.register::<Score>();
+        type_registry.register::<f32>();
+        type_registry.register::<u32>();
+        let type_registry_arc = TypeRegistryArc::default();
+        *type_registry_arc.write() = type_registry;
+
+        let (transport, client) = ChannelTransport::new();
+        let mut world = World::new();
+        world.insert_resource(type_registry_arc);
+        world.insert_resource(RemoteServer::new(Box::new(transport)));
+        world.insert_resource(Score { points: 7 });
+        (world, client)
+    }
+
+    fn call(world: &mut World, client: &ChannelClient, method: &str, params: Value) -> Value {
+        client.send(
+            json!({"jsonrpc": "2.0", "method": method, "params": params, "id": 1}).to_string(),
+        );
+        remote_server_system(world);
+        serde_json::from_str(&client.try_recv().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn spawn_get_and_remove_components() {
+        let (mut world, client) = setup();
+        let position = json!({
+            "type": std::any::type_name::<Position>(),
+            "struct": {
+                "x": {"type": "f32", "value": 1.0},
+                "y": {"type": "f32", "value": 2.0},
+            }
+        });
+        let response = call(
+            &mut world,
+            &client,
+            "world.spawn",
+            json!({ "components": [position] }),
+        );
+        let entity = Entity::from_bits(response["result"].as_u64().unwrap());
+        assert_eq!(
+            world.get::<Position>(entity),
+            Some(&Position { x: 1.0, y: 2.0 })
+        );
+
+        let response = call(
+            &mut world,
+            &client,
+            "world.get_components",
+            json!({"entity": entity.to_bits(), "components": ["Position"]}),
+        );
+        assert_eq!(
+            response["result"][std::any::type_name::<Position>()],
+            position
+        );
+
+        let response = call(
+            &mut world,
+            &client,
+            "world.list_entities",
+            json!({"components": ["Position"]}),
+        );
+        assert_eq!(response["result"].as_array().unwrap().len(), 1);
+
+        call(
+            &mut world,
+            &client,
+            "world.remove_components",
+            json!({"entity": entity.to_bits(), "components": ["Position"]}),
+        );
+        assert!(world.get::<Position>(entity).is_none());
+    }
+
+    #[test]
+    fn errors() {
+        let (mut world, client) = setup();
+        let response = call(&mut world, &client, "world.explode", Value::Null);
+        assert_eq!(response["error"]["code"], RpcError::METHOD_NOT_FOUND);
+        let response = call(
+            &mut world,
+            &client,
+            "world.despawn",
+            json!({"entity": 1234}),
+        );
+        assert_eq!(response["error"]["code"], RpcError::WORLD_ERROR);
+        let response = call(&mut world, &client, "world.despawn", json!({}));
+        assert_eq!(response["error"]["code"], RpcError::INVALID_PARAMS);
+
+        client.send("{not json");
+        remote_server_system(&mut world);
+        let response: Value = serde_json::from_str(&client.try_recv().unwrap()).unwrap();
+        assert_eq!(response["error"]["code"], RpcError::PARSE_ERROR);
+    }
+
+    #[test]
+    fn get_resources() {
+        let (mut world, client) = setup();
+        let response = call(
+            &mut world,
+            &client,
+            "world.get_resources",
+            json!({"resources": ["Score"]}),
+        );
+        assert_eq!(
+            response["result"][std::any::type_name::<Score>()]["struct"]["points"]["value"],
+            7
+        );
+    }
+
+    #[test]
+    fn subscriptions() {
+        let (mut world, client) = setup();
+        let entity = world.spawn().insert(Position { x: 0.0, y: 0.0 }).id();
+        let response = call(
+            &mut world,
+            &client,
+            "world.subscribe",
+            json!({"entity": entity.to_bits()}),
+        );
+        let subscription = response["result"].as_u64().unwrap();
+        // the first notification carries the current values
+        let notification: Value = serde_json::from_str(&client.try_recv().unwrap()).unwrap();
+        assert_eq!(notification["method"], CHANGED_NOTIFICATION);
+        assert_eq!(notification["params"]["subscription"], subscription);
+
+        remote_server_system(&mut world);
+        assert!(client.try_recv().is_none());
+
+        world.get_mut::<Position>(entity).unwrap().x = 5.0;
+        remote_server_system(&mut world);
+        let notification: Value = serde_json::from_str(&client.try_recv().unwrap()).unwrap();
+        assert_eq!(
+            notification["params"]["components"][std::any::type_name::<Position>()]["struct"]["x"]
+                ["value"],
+            5.0
+        );
+
+        world.despawn(entity);
+        remote_server_system(&mut world);
+        let notification: Value = serde_json::from_str(&client.try_recv().unwrap()).unwrap();
+        assert_eq!(notification["params"]["despawned"], true);
+        remote_server_system(&mut world);
+        assert!(client.try_recv().is_none());
+    }
+
+    #[test]
+    fn subscriptions_are_dropped_on_disconnect() {
+        let (mut world, client) = setup();
+        let entity = world.spawn().insert(Position { x: 0.0, y: 0.0 }).id();
+        call(
+            &mut world,
+            &client,
+            "world.subscribe",
+            json!({"entity": entity.to_bits()}),
+        );
+        assert_eq!(
+            world
+                .get_resource::<RemoteServer>()
+                .unwrap()
+                .subscriptions
+                .len(),
+            1
+        );
+
+        drop(client);
+        remote_server_system(&mut world);
+        assert!(world
+            .get_resource::<RemoteServer>()
+            .unwrap()
+            .subscriptions
+            .is_empty());
+    }
+}
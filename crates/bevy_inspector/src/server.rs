@@ -0,0 +1,63 @@
+use crate::{
+    protocol::respond,
+    transport::{RemoteTransport, StdioTransport, TcpTransport},
+};
+use bevy_ecs::world::{Mut, World};
+use bevy_reflect::TypeRegistryArc;
+use std::{io, net::SocketAddr};
+
+/// Selects the [RemoteTransport] used by the inspector and remote plugins.
+#[derive(Debug, Clone)]
+pub enum InspectorTransport {
+    /// Accept any number of clients on a TCP socket, one message per line.
+    Tcp(SocketAddr),
+    /// Read messages from stdin and write responses to stdout.
+    Stdin,
+}
+
+impl InspectorTransport {
+    pub fn start(&self) -> io::Result<Box<dyn RemoteTransport>> {
+        Ok(match self {
+            InspectorTransport::Tcp(address) => Box::new(TcpTransport::bind(*address)?),
+            InspectorTransport::Stdin => Box::new(StdioTransport::new()?),
+        })
+    }
+}
+
+/// Configures the [InspectorPlugin](crate::InspectorPlugin). Insert it before adding the plugin.
+#[derive(Debug, Clone)]
+pub struct InspectorSettings {
+    pub transport: InspectorTransport,
+}
+
+impl Default for InspectorSettings {
+    fn default() -> Self {
+        Self {
+            transport: InspectorTransport::Tcp(SocketAddr::from(([127, 0, 0, 1], 15701))),
+        }
+    }
+}
+
+/// Answers the line based [InspectorCommand](crate::protocol::InspectorCommand)s received by its
+/// transport.
+pub struct InspectorServer {
+    transport: Box<dyn RemoteTransport>,
+}
+
+impl InspectorServer {
+    pub fn new(transport: Box<dyn RemoteTransport>) -> Self {
+        Self { transport }
+    }
+}
+
+/// Runs every queued inspector command against the world.
+pub fn inspector_server_system(world: &mut World) {
+    let type_registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+    let type_registry = type_registry.read();
+    world.resource_scope(|world, mut server: Mut<InspectorServer>| {
+        while let Some((client, line)) = server.transport.receive() {
+            let response = respond(world, &type_registry, &line);
+            server.transport.send(client, response);
+        }
+    });
+}
@@ -0,0 +1,256 @@
+use bevy_log::{info, warn};
+use bevy_utils::HashMap;
+use crossbeam_channel::{Receiver, Sender, TryRecvError};
+use parking_lot::Mutex;
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::Arc,
+    thread,
+};
+
+/// Identifies a client connected to a [RemoteTransport].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ClientId(pub u64);
+
+/// Moves line based messages between the app and its remote clients.
+///
+/// Transports are polled from the main world once per frame, so implementations must never block
+/// in [RemoteTransport::receive].
+pub trait RemoteTransport: Send + Sync + 'static {
+    /// Returns the next message received from any client, if there is one.
+    fn receive(&mut self) -> Option<(ClientId, String)>;
+    /// Sends a message to a client. Messages to disconnected clients are dropped.
+    fn send(&mut self, client: ClientId, message: String);
+    /// Returns the clients that disconnected since the last call, so their state can be dropped.
+    fn disconnected(&mut self) -> Vec<ClientId> {
+        Vec::new()
+    }
+}
+
+/// Serves any number of clients on a TCP socket, one message per line.
+pub struct TcpTransport {
+    incoming: Receiver<(ClientId, String)>,
+    disconnected: Receiver<ClientId>,
+    clients: Arc<Mutex<HashMap<ClientId, Sender<String>>>>,
+    local_addr: SocketAddr,
+}
+
+impl TcpTransport {
+    pub fn bind(address: SocketAddr) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        let local_addr = listener.local_addr()?;
+        let (incoming_sender, incoming) = crossbeam_channel::unbounded();
+        let (disconnected_sender, disconnected) = crossbeam_channel::unbounded();
+        let clients = Arc::new(Mutex::new(HashMap::default()));
+        let listener_clients = clients.clone();
+        thread::Builder::new()
+            .name(format!("remote listener {}", local_addr))
+            .spawn(move || {
+                for (index, stream) in listener.incoming().enumerate() {
+                    let client = ClientId(index as u64);
+                    match stream.and_then(|stream| {
+                        Self::serve_client(
+                            client,
+                            stream,
+                            incoming_sender.clone(),
+                            disconnected_sender.clone(),
+                            listener_clients.clone(),
+                        )
+                    }) {
+                        Ok(()) => info!("remote client {:?} connected", client),
+                        Err(err) => warn!("failed to accept a remote client: {}", err),
+                    }
+                }
+            })?;
+        Ok(Self {
+            incoming,
+            disconnected,
+            clients,
+            local_addr,
+        })
+    }
+
+    /// The address the transport is listening on, which is useful when binding to port 0.
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    fn serve_client(
+        client: ClientId,
+        stream: TcpStream,
+        incoming: Sender<(ClientId, String)>,
+        disconnected: Sender<ClientId>,
+        clients: Arc<Mutex<HashMap<ClientId, Sender<String>>>>,
+    ) -> io::Result<()> {
+        let (outgoing_sender, outgoing) = crossbeam_channel::unbounded::<String>();
+        let mut writer = stream.try_clone()?;
+        thread::Builder::new()
+            .name(format!("remote client {} writer", client.0))
+            .spawn(move || {
+                for message in outgoing.iter() {
+                    if write_line(&mut writer, &message).is_err() {
+                        break;
+                    }
+                }
+            })?;
+        clients.lock().insert(client, outgoing_sender);
+        thread::Builder::new()
+            .name(format!("remote client {} reader", client.0))
+            .spawn(move || {
+                read_lines(BufReader::new(stream), client, &incoming);
+                // dropping the sender stops the writer thread
+                clients.lock().remove(&client);
+                info!("remote client {:?} disconnected", client);
+                let _ = disconnected.send(client);
+            })?;
+        Ok(())
+    }
+}
+
+impl RemoteTransport for TcpTransport {
+    fn receive(&mut self) -> Option<(ClientId, String)> {
+        self.incoming.try_recv().ok()
+    }
+
+    fn send(&mut self, client: ClientId, message: String) {
+        if let Some(sender) = self.clients.lock().get(&client) {
+            let _ = sender.send(message);
+        }
+    }
+
+    fn disconnected(&mut self) -> Vec<ClientId> {
+        self.disconnected.try_iter().collect()
+    }
+}
+
+/// Reads messages from stdin and writes responses to stdout, as a single client.
+pub struct StdioTransport {
+    incoming: Receiver<(ClientId, String)>,
+}
+
+impl StdioTransport {
+    pub const CLIENT: ClientId = ClientId(0);
+
+    pub fn new() -> io::Result<Self> {
+        let (incoming_sender, incoming) = crossbeam_channel::unbounded();
+        thread::Builder::new()
+            .name("remote stdin".to_string())
+            .spawn(move || {
+                let stdin = io::stdin();
+                read_lines(stdin.lock(), Self::CLIENT, &incoming_sender);
+            })?;
+        Ok(Self { incoming })
+    }
+}
+
+impl RemoteTransport for StdioTransport {
+    fn receive(&mut self) -> Option<(ClientId, String)> {
+        self.incoming.try_recv().ok()
+    }
+
+    fn send(&mut self, _client: ClientId, message: String) {
+        let _ = write_line(&mut io::stdout(), &message);
+    }
+}
+
+/// An in-memory transport with a single client, for tests and in-process tools.
+pub struct ChannelTransport {
+    incoming: Receiver<String>,
+    outgoing: Sender<String>,
+    connected: bool,
+    disconnected: bool,
+}
+
+/// The client end of a [ChannelTransport]. The client disconnects when all of its clones are
+/// dropped.
+#[derive(Clone)]
+pub struct ChannelClient {
+    outgoing: Sender<String>,
+    incoming: Receiver<String>,
+}
+
+impl ChannelTransport {
+    pub const CLIENT: ClientId = ClientId(0);
+
+    pub fn new() -> (ChannelTransport, ChannelClient) {
+        let (client_sender, transport_receiver) = crossbeam_channel::unbounded();
+        let (transport_sender, client_receiver) = crossbeam_channel::unbounded();
+        (
+            ChannelTransport {
+                incoming: transport_receiver,
+                outgoing: transport_sender,
+                connected: true,
+                disconnected: false,
+            },
+            ChannelClient {
+                outgoing: client_sender,
+                incoming: client_receiver,
+            },
+        )
+    }
+}
+
+impl RemoteTransport for ChannelTransport {
+    fn receive(&mut self) -> Option<(ClientId, String)> {
+        match self.incoming.try_recv() {
+            Ok(message) => Some((Self::CLIENT, message)),
+            Err(TryRecvError::Disconnected) => {
+                // the client is reported once, after all of its messages have been received
+                if self.connected {
+                    self.connected = false;
+                    self.disconnected = true;
+                }
+                None
+            }
+            Err(TryRecvError::Empty) => None,
+        }
+    }
+
+    fn send(&mut self, _client: ClientId, message: String) {
+        let _ = self.outgoing.send(message);
+    }
+
+    fn disconnected(&mut self) -> Vec<ClientId> {
+        if std::mem::take(&mut self.disconnected) {
+            vec![Self::CLIENT]
+        } else {
+            Vec::new()
+        }
+    }
+}
+
+impl ChannelClient {
+    pub fn send(&self, message: impl Into<String>) {
+        let _ = self.outgoing.send(message.into());
+    }
+
+    /// Returns the next message sent to this client, if there is one.
+    pub fn try_recv(&self) -> Option<String> {
+        self.incoming.try_recv().ok()
+    }
+}
+
+fn read_lines(reader: impl BufRead, client: ClientId, incoming: &Sender<(ClientId, String)>) {
+    for line in reader.lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        if incoming.send((client, line)).is_err() {
+            // the app has shut down
+            break;
+        }
+    }
+}
+
+fn write_line(writer: &mut impl Write, message: &str) -> io::Result<()> {
+    writer.write_all(message.as_bytes())?;
+    if !message.ends_with('\n') {
+        writer.write_all(b"\n")?;
+    }
+    writer.flush()
+}
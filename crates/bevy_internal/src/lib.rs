@@ -93,6 +93,12 @@ pub mod gltf {
     pub use bevy_gltf::*;
 }
 
+#[cfg(feature = "bevy_inspector")]
+pub mod inspector {
+    //! Runtime inspection and editing of entities over reflection.
+    pub use bevy_inspector::*;
+}
+
 #[cfg(feature = "bevy_pbr")]
 pub mod pbr {
     //! Physically based rendering.
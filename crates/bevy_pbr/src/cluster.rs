@@ -0,0 +1,321 @@
+use crate::{Light, SpotLight};
+use bevy_ecs::{
+    entity::Entity,
+    system::{Query, Res, ResMut},
+};
+use bevy_math::{Mat4, UVec3, Vec2, Vec3};
+use bevy_render::{
+    camera::{ActiveCameras, CameraProjection, PerspectiveProjection},
+    render_graph::base,
+};
+use bevy_transform::components::GlobalTransform;
+
+#[derive(Debug, Clone)]
+pub struct ClusterSettings {
+    /// The number of clusters the view of the 3d camera is divided into along each axis. Slices
+    /// along z get exponentially deeper away from the camera.
+    pub dimensions: UVec3,
+}
+
+impl Default for ClusterSettings {
+    fn default() -> Self {
+        ClusterSettings {
+            dimensions: UVec3::new(16, 9, 24),
+        }
+    }
+}
+
+/// The point and spot lights that can affect each cluster of the 3d camera's view, so the pbr
+/// shader only shades a fragment with the lights of its cluster. Clusters are volumes of the
+/// world, so every view finds the cluster of a fragment with the 3d camera's `view_proj`. Fragments
+/// outside of the 3d camera's view, which other cameras can see, are shaded with every light.
+#[derive(Debug, Default)]
+pub struct Clusters {
+    pub dimensions: UVec3,
+    /// The view projection of the camera the clusters divide the view of, or `None` if there is no
+    /// 3d camera and every light is in a single cluster
+    pub view_proj: Option<Mat4>,
+    /// The scale and bias that turn the log of a view depth into a z slice
+    pub z_params: Vec2,
+    /// The point and spot lights, in the order they are written to the light buffer
+    pub lights: Vec<Entity>,
+    /// The offset into `light_indices` and the number of lights of each cluster. Clusters are
+    /// ordered by x, then y, then z.
+    pub offsets_and_counts: Vec<[u32; 2]>,
+    /// Indices into `lights`
+    pub light_indices: Vec<u32>,
+}
+
+impl Clusters {
+    pub fn cluster_index(&self, cluster: UVec3) -> usize {
+        (cluster.x
+            + cluster.y * self.dimensions.x
+            + cluster.z * self.dimensions.x * self.dimensions.y) as usize
+    }
+
+    /// The lights that can affect a cluster
+    pub fn cluster_lights(&self, cluster: UVec3) -> &[u32] {
+        let [offset, count] = self.offsets_and_counts[self.cluster_index(cluster)];
+        &self.light_indices[offset as usize..(offset + count) as usize]
+    }
+
+    /// Puts every light in a single cluster
+    fn unclustered(&mut self, light_count: usize) {
+        self.dimensions = UVec3::ONE;
+        self.view_proj = None;
+        self.z_params = Vec2::ZERO;
+        self.offsets_and_counts.push([0, light_count as u32]);
+        self.light_indices.extend(0..light_count as u32);
+    }
+}
+
+/// The scale and bias that turn `ln(depth)` into the z slice of `depth`, for `slices` slices that
+/// get exponentially deeper from `near` to `far`
+pub fn cluster_z_params(near: f32, far: f32, slices: u32) -> Vec2 {
+    let scale = slices as f32 / (far / near).ln();
+    Vec2::new(scale, -near.ln() * scale)
+}
+
+fn z_slice(depth: f32, z_params: Vec2, slices: u32) -> u32 {
+    let slice = depth.max(f32::MIN_POSITIVE).ln() * z_params.x + z_params.y;
+    (slice.max(0.0) as u32).min(slices - 1)
+}
+
+/// The view space bounding boxes of the clusters of a perspective projection, as (min, max)
+pub fn cluster_aabbs(projection: &PerspectiveProjection, dimensions: UVec3) -> Vec<(Vec3, Vec3)> {
+    let tan_y = (projection.fov * 0.5).tan();
+    let tan_x = tan_y * projection.aspect_ratio;
+    let slice_depth = |z: u32| {
+        projection.near * (projection.far / projection.near).powf(z as f32 / dimensions.z as f32)
+    };
+    let tile_ndc = |i: u32, count: u32| i as f32 / count as f32 * 2.0 - 1.0;
+
+    let mut aabbs = Vec::with_capacity((dimensions.x * dimensions.y * dimensions.z) as usize);
+    for z in 0..dimensions.z {
+        let depths = [slice_depth(z), slice_depth(z + 1)];
+        for y in 0..dimensions.y {
+            let ys = [tile_ndc(y, dimensions.y), tile_ndc(y + 1, dimensions.y)];
+            for x in 0..dimensions.x {
+                let xs = [tile_ndc(x, dimensions.x), tile_ndc(x + 1, dimensions.x)];
+                let mut min = Vec3::splat(f32::MAX);
+                let mut max = Vec3::splat(f32::MIN);
+                for &depth in depths.iter() {
+                    for &ndc_x in xs.iter() {
+                        for &ndc_y in ys.iter() {
+                            let corner =
+                                Vec3::new(ndc_x * tan_x * depth, ndc_y * tan_y * depth, -depth);
+                            min = min.min(corner);
+                            max = max.max(corner);
+                        }
+                    }
+                }
+                aabbs.push((min, max));
+            }
+        }
+    }
+    aabbs
+}
+
+fn sphere_intersects_aabb(center: Vec3, radius: f32, (min, max): (Vec3, Vec3)) -> bool {
+    let closest = center.max(min).min(max);
+    closest.distance_squared(center) <= radius * radius
+}
+
+/// Assigns the point and spot lights to the clusters of the 3d camera's view that their range
+/// reaches. Other views use the same clusters, see [Clusters].
+pub fn assign_lights_to_clusters(
+    mut clusters: ResMut<Clusters>,
+    cluster_settings: Res<ClusterSettings>,
+    active_cameras: Res<ActiveCameras>,
+    camera_query: Query<(&GlobalTransform, &PerspectiveProjection)>,
+    point_lights: Query<(Entity, &Light, &GlobalTransform)>,
+    spot_lights: Query<(Entity, &SpotLight, &GlobalTransform)>,
+) {
+    let clusters = &mut *clusters;
+    clusters.lights.clear();
+    clusters.offsets_and_counts.clear();
+    clusters.light_indices.clear();
+
+    let mut light_spheres = Vec::new();
+    for (entity, light, global_transform) in point_lights.iter() {
+        clusters.lights.push(entity);
+        light_spheres.push((global_transform.translation, light.range));
+    }
+    for (entity, light, global_transform) in spot_lights.iter() {
+        clusters.lights.push(entity);
+        light_spheres.push((global_transform.translation, light.range));
+    }
+
+    let camera = active_cameras
+        .get(base::camera::CAMERA_3D)
+        .and_then(|camera| camera.entity)
+        .and_then(|entity| camera_query.get(entity).ok());
+    let dimensions = cluster_settings.dimensions.max(UVec3::ONE);
+    let (camera_transform, projection) = match camera {
+        Some(camera) => camera,
+        None => {
+            clusters.unclustered(light_spheres.len());
+            return;
+        }
+    };
+
+    clusters.dimensions = dimensions;
+    clusters.z_params = cluster_z_params(projection.near, projection.far, dimensions.z);
+    let view = camera_transform.compute_matrix().inverse();
+    let projection_matrix = projection.get_projection_matrix();
+    clusters.view_proj = Some(projection_matrix * view);
+    let aabbs = cluster_aabbs(projection, dimensions);
+    let mut cluster_lights = vec![Vec::new(); aabbs.len()];
+    for (index, (position, range)) in light_spheres.iter().enumerate() {
+        let center = view.transform_point3(*position);
+        if let Some((min, max)) = cluster_range(
+            center,
+            *range,
+            projection,
+            &projection_matrix,
+            dimensions,
+            clusters.z_params,
+        ) {
+            for z in min.z..=max.z {
+                for y in min.y..=max.y {
+                    for x in min.x..=max.x {
+                        let cluster = clusters.cluster_index(UVec3::new(x, y, z));
+                        if sphere_intersects_aabb(center, *range, aabbs[cluster]) {
+                            cluster_lights[cluster].push(index as u32);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    for lights in cluster_lights {
+        clusters
+            .offsets_and_counts
+            .push([clusters.light_indices.len() as u32, lights.len() as u32]);
+        clusters.light_indices.extend(lights);
+    }
+}
+
+/// The range of clusters covered by the screen space bounds of a light, or `None` if the light is
+/// out of view
+fn cluster_range(
+    center: Vec3,
+    radius: f32,
+    projection: &PerspectiveProjection,
+    projection_matrix: &Mat4,
+    dimensions: UVec3,
+    z_params: Vec2,
+) -> Option<(UVec3, UVec3)> {
+    let near_depth = -center.z - radius;
+    let far_depth = -center.z + radius;
+    if far_depth < projection.near || near_depth > projection.far {
+        return None;
+    }
+    let min_z = z_slice(near_depth, z_params, dimensions.z);
+    let max_z = z_slice(far_depth, z_params, dimensions.z);
+
+    // a light that reaches in front of the near plane can't be projected, so it's in every tile
+    let (mut ndc_min, mut ndc_max) = (Vec2::splat(-1.0), Vec2::splat(1.0));
+    if near_depth > projection.near {
+        ndc_min = Vec2::splat(f32::MAX);
+        ndc_max = Vec2::splat(f32::MIN);
+        for &x in [-radius, radius].iter() {
+            for &y in [-radius, radius].iter() {
+                for &z in [-radius, radius].iter() {
+                    let clip = *projection_matrix * (center + Vec3::new(x, y, z)).extend(1.0);
+                    let ndc = Vec2::new(clip.x, clip.y) / clip.w;
+                    ndc_min = ndc_min.min(ndc);
+                    ndc_max = ndc_max.max(ndc);
+                }
+            }
+        }
+        if ndc_max.x < -1.0 || ndc_max.y < -1.0 || ndc_min.x > 1.0 || ndc_min.y > 1.0 {
+            return None;
+        }
+    }
+
+    let tile = |ndc: f32, count: u32| {
+        let tile = ((ndc * 0.5 + 0.5) * count as f32).max(0.0) as u32;
+        tile.min(count - 1)
+    };
+    Some((
+        UVec3::new(
+            tile(ndc_min.x, dimensions.x),
+            tile(ndc_min.y, dimensions.y),
+            min_z,
+        ),
+        UVec3::new(
+            tile(ndc_max.x, dimensions.x),
+            tile(ndc_max.y, dimensions.y),
+            max_z,
+        ),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lights_only_reach_nearby_clusters() {
+        let projection = PerspectiveProjection::default();
+        let dimensions = UVec3::new(16, 9, 24);
+        let z_params = cluster_z_params(projection.near, projection.far, dimensions.z);
+        assert_eq!(z_slice(projection.near, z_params, dimensions.z), 0);
+        assert_eq!(z_slice(projection.far, z_params, dimensions.z), 23);
+        assert_eq!(z_slice(5000.0, z_params, dimensions.z), 23);
+
+        let aabbs = cluster_aabbs(&projection, dimensions);
+        assert_eq!(aabbs.len(), 16 * 9 * 24);
+
+        // a small light straight ahead is in the middle tiles only
+        let center = Vec3::new(0.0, 0.0, -10.0);
+        let (min, max) = cluster_range(
+            center,
+            0.5,
+            &projection,
+            &projection.get_projection_matrix(),
+            dimensions,
+            z_params,
+        )
+        .unwrap();
+        assert!(min.x >= 6 && max.x <= 9 && min.y >= 3 && max.y <= 5);
+        assert!(min.z > 0 && max.z < 23);
+        assert!(min.z == z_slice(9.5, z_params, 24) && max.z == z_slice(10.5, z_params, 24));
+
+        // lights behind the camera or off to the side aren't in any cluster
+        let projection_matrix = projection.get_projection_matrix();
+        assert!(cluster_range(
+            Vec3::new(0.0, 0.0, 10.0),
+            1.0,
+            &projection,
+            &projection_matrix,
+            dimensions,
+            z_params
+        )
+        .is_none());
+        assert!(cluster_range(
+            Vec3::new(100.0, 0.0, -10.0),
+            1.0,
+            &projection,
+            &projection_matrix,
+            dimensions,
+            z_params
+        )
+        .is_none());
+
+        // a light around the camera reaches every tile
+        let (min, max) = cluster_range(
+            Vec3::ZERO,
+            5.0,
+            &projection,
+            &projection_matrix,
+            dimensions,
+            z_params,
+        )
+        .unwrap();
+        assert_eq!((min.x, min.y, min.z), (0, 0, 0));
+        assert_eq!((max.x, max.y), (15, 8));
+    }
+}
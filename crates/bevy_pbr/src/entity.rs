@@ -1,4 +1,8 @@
-use crate::{light::Light, material::StandardMaterial, render_graph::PBR_PIPELINE_HANDLE};
+use crate::{
+    light::{DirectionalLight, Light, SpotLight},
+    material::StandardMaterial,
+    render_graph::PBR_PIPELINE_HANDLE,
+};
 use bevy_asset::Handle;
 use bevy_ecs::bundle::Bundle;
 use bevy_render::{
@@ -47,3 +51,19 @@ pub struct LightBundle {
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
+
+/// A component bundle for "directional light" entities
+#[derive(Debug, Bundle, Default)]
+pub struct DirectionalLightBundle {
+    pub directional_light: DirectionalLight,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
+
+/// A component bundle for "spot light" entities
+#[derive(Debug, Bundle, Default)]
+pub struct SpotLightBundle {
+    pub spot_light: SpotLight,
+    pub transform: Transform,
+    pub global_transform: GlobalTransform,
+}
@@ -0,0 +1,879 @@
+use bevy_app::EventReader;
+use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_ecs::{
+    system::{Local, Query, Res, ResMut},
+    world::{FromWorld, World},
+};
+use bevy_math::{Vec2, Vec3};
+use bevy_render::{
+    camera::ActiveCameras,
+    color::Color,
+    render_graph::base,
+    texture::{
+        AddressMode, Extent3d, FilterMode, SamplerDescriptor, Texture, TextureDimension,
+        TextureFormat,
+    },
+};
+use bevy_tasks::{AsyncComputeTaskPool, Task};
+use bevy_utils::tracing::warn;
+use futures_lite::future;
+use std::f32::consts::PI;
+use thiserror::Error;
+
+/// Lights the scene with its surroundings, such as a sky, when inserted as a resource or added to
+/// the 3d camera. The environment map of the camera takes precedence over the resource.
+///
+/// The texture is either equirectangular, twice as wide as it is tall, like the `.hdr` files loaded
+/// with the `hdr` feature, or a cube texture with six layers ordered +X, -X, +Y, -Y, +Z, -Z (see
+/// [Texture::reinterpret_stacked_2d_as_array]). It is prefiltered into [EnvironmentMapTextures] on
+/// the CPU in the background once it's loaded.
+#[derive(Debug, Clone)]
+pub struct EnvironmentMap {
+    pub texture: Handle<Texture>,
+    /// Scales the light of the environment
+    pub intensity: f32,
+}
+
+impl EnvironmentMap {
+    pub fn new(texture: Handle<Texture>) -> Self {
+        EnvironmentMap {
+            texture,
+            intensity: 1.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvironmentMapSettings {
+    /// The width of the sharpest specular level, rounded up to a power of two. Each rougher level
+    /// is half as large as the previous one.
+    pub specular_size: u32,
+    /// The number of specular levels, from perfectly smooth to fully rough
+    pub specular_levels: u32,
+    /// The number of samples of the environment taken for each texel of the rough specular levels
+    pub sample_count: u32,
+}
+
+impl Default for EnvironmentMapSettings {
+    fn default() -> Self {
+        EnvironmentMapSettings {
+            specular_size: 256,
+            specular_levels: 6,
+            sample_count: 64,
+        }
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum EnvironmentMapError {
+    #[error("environment maps can't be read from {0:?} textures")]
+    UnsupportedFormat(TextureFormat),
+    #[error("environment maps must be twice as wide as they are tall, or have 6 square layers, not {0:?}")]
+    UnsupportedSize(Extent3d),
+}
+
+/// The textures the pbr shader lights the scene with. Until an [EnvironmentMap] is prefiltered,
+/// the environment textures are black.
+#[derive(Debug)]
+pub struct EnvironmentMapTextures {
+    /// The irradiance of each direction, divided by π, as an equirectangular texture
+    pub diffuse: Handle<Texture>,
+    /// The radiance of each direction, blurred more at each level for rougher surfaces. The levels
+    /// are equirectangular textures stacked from the top, each padded with a column from the other
+    /// side on the left and right so that they wrap around.
+    pub specular: Handle<Texture>,
+    pub specular_levels: u32,
+    /// The scale and bias to `f0` of the specular reflection of the environment, by the cosine of
+    /// the view angle along x and perceptual roughness along y
+    pub brdf_lut: Handle<Texture>,
+}
+
+impl FromWorld for EnvironmentMapTextures {
+    fn from_world(world: &mut World) -> Self {
+        let mut textures = world.get_resource_mut::<Assets<Texture>>().unwrap();
+        let black = || {
+            Texture::new_fill(
+                Extent3d::new(1, 1, 1),
+                TextureDimension::D2,
+                &[0; 8],
+                TextureFormat::Rgba16Float,
+            )
+        };
+        EnvironmentMapTextures {
+            diffuse: textures.add(black()),
+            specular: textures.add(black()),
+            specular_levels: 1,
+            brdf_lut: textures.add(brdf_lut(64, 128)),
+        }
+    }
+}
+
+/// Radiance in an equirectangular layout, where `u` goes around the y axis and `v` goes from +Y to
+/// -Y
+#[derive(Debug, Clone)]
+struct Equirect {
+    width: u32,
+    height: u32,
+    data: Vec<Vec3>,
+}
+
+fn equirect_direction(uv: Vec2) -> Vec3 {
+    let phi = (uv.x - 0.5) * 2.0 * PI;
+    let theta = uv.y * PI;
+    Vec3::new(
+        theta.sin() * phi.cos(),
+        theta.cos(),
+        theta.sin() * phi.sin(),
+    )
+}
+
+fn equirect_uv(direction: Vec3) -> Vec2 {
+    Vec2::new(
+        direction.z.atan2(direction.x) / (2.0 * PI) + 0.5,
+        direction.y.clamp(-1.0, 1.0).acos() / PI,
+    )
+}
+
+/// The linear rgb colors of the texels of a texture
+fn texture_colors(texture: &Texture) -> Result<Vec<Vec3>, EnvironmentMapError> {
+    Ok(match texture.format {
+        TextureFormat::Rgba32Float => texture
+            .data
+            .chunks_exact(16)
+            .map(|pixel| {
+                let channel = |i: usize| {
+                    f32::from_ne_bytes([
+                        pixel[i * 4],
+                        pixel[i * 4 + 1],
+                        pixel[i * 4 + 2],
+                        pixel[i * 4 + 3],
+                    ])
+                };
+                Vec3::new(channel(0), channel(1), channel(2))
+            })
+            .collect(),
+        TextureFormat::Rgba16Float => texture
+            .data
+            .chunks_exact(8)
+            .map(|pixel| {
+                let channel =
+                    |i: usize| f16_to_f32(u16::from_ne_bytes([pixel[i * 2], pixel[i * 2 + 1]]));
+                Vec3::new(channel(0), channel(1), channel(2))
+            })
+            .collect(),
+        TextureFormat::Rgba8UnormSrgb => texture
+            .data
+            .chunks_exact(4)
+            .map(|pixel| {
+                let [r, g, b, _] = Color::rgb_u8(pixel[0], pixel[1], pixel[2]).as_linear_rgba_f32();
+                Vec3::new(r, g, b)
+            })
+            .collect(),
+        TextureFormat::Rgba8Unorm => texture
+            .data
+            .chunks_exact(4)
+            .map(|pixel| Vec3::new(pixel[0] as f32, pixel[1] as f32, pixel[2] as f32) / 255.0)
+            .collect(),
+        format => return Err(EnvironmentMapError::UnsupportedFormat(format)),
+    })
+}
+
+impl Equirect {
+    fn from_texture(texture: &Texture) -> Result<Self, EnvironmentMapError> {
+        let pixels = texture_colors(texture)?;
+        let size = texture.size;
+        if size.depth == 1 && size.width == size.height * 2 && size.height > 0 {
+            Ok(Equirect {
+                width: size.width,
+                height: size.height,
+                data: pixels,
+            })
+        } else if size.depth == 6 && size.width == size.height && size.width > 0 {
+            Ok(Equirect::from_cube(size.width, &pixels))
+        } else {
+            Err(EnvironmentMapError::UnsupportedSize(size))
+        }
+    }
+
+    /// Resamples the faces of a cube, in the order +X, -X, +Y, -Y, +Z, -Z
+    fn from_cube(face_size: u32, faces: &[Vec3]) -> Self {
+        let face_texel = |face: usize, uv: Vec2| {
+            let x = ((uv.x * face_size as f32) as u32).min(face_size - 1);
+            let y = ((uv.y * face_size as f32) as u32).min(face_size - 1);
+            faces[face * (face_size * face_size) as usize + (y * face_size + x) as usize]
+        };
+        let (width, height) = (face_size * 4, face_size * 2);
+        let data = texel_uvs(width, height)
+            .map(|uv| {
+                let d = equirect_direction(uv);
+                let a = d.abs();
+                // the face, and the coordinates on it going right and down
+                let (face, s, t, major) = if a.x >= a.y && a.x >= a.z {
+                    if d.x > 0.0 {
+                        (0, -d.z, -d.y, a.x)
+                    } else {
+                        (1, d.z, -d.y, a.x)
+                    }
+                } else if a.y >= a.z {
+                    if d.y > 0.0 {
+                        (2, d.x, d.z, a.y)
+                    } else {
+                        (3, d.x, -d.z, a.y)
+                    }
+                } else if d.z > 0.0 {
+                    (4, d.x, -d.y, a.z)
+                } else {
+                    (5, -d.x, -d.y, a.z)
+                };
+                face_texel(face, (Vec2::new(s, t) / major + Vec2::ONE) * 0.5)
+            })
+            .collect();
+        Equirect {
+            width,
+            height,
+            data,
+        }
+    }
+
+    fn texel(&self, x: u32, y: u32) -> Vec3 {
+        self.data[(y * self.width + x) as usize]
+    }
+
+    /// Averages each 2x2 block of texels
+    fn downsample(&self) -> Self {
+        let (width, height) = ((self.width / 2).max(1), (self.height / 2).max(1));
+        let mut data = Vec::with_capacity((width * height) as usize);
+        for y in 0..height {
+            for x in 0..width {
+                let (x0, y0) = ((x * 2).min(self.width - 1), (y * 2).min(self.height - 1));
+                let (x1, y1) = ((x0 + 1).min(self.width - 1), (y0 + 1).min(self.height - 1));
+                data.push(
+                    (self.texel(x0, y0)
+                        + self.texel(x1, y0)
+                        + self.texel(x0, y1)
+                        + self.texel(x1, y1))
+                        * 0.25,
+                );
+            }
+        }
+        Equirect {
+            width,
+            height,
+            data,
+        }
+    }
+
+    /// Bilinearly samples the radiance in a direction
+    fn sample(&self, direction: Vec3) -> Vec3 {
+        let uv = equirect_uv(direction);
+        let x = uv.x * self.width as f32 - 0.5;
+        let y = (uv.y * self.height as f32 - 0.5)
+            .max(0.0)
+            .min((self.height - 1) as f32);
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let x0 = (x0 as i32).rem_euclid(self.width as i32) as u32;
+        let x1 = (x0 + 1) % self.width;
+        let y0 = y0 as u32;
+        let y1 = (y0 + 1).min(self.height - 1);
+        let top = self.texel(x0, y0).lerp(self.texel(x1, y0), fx);
+        let bottom = self.texel(x0, y1).lerp(self.texel(x1, y1), fx);
+        top.lerp(bottom, fy)
+    }
+}
+
+/// The uvs of the texel centers of a texture, row by row
+fn texel_uvs(width: u32, height: u32) -> impl Iterator<Item = Vec2> {
+    (0..height).flat_map(move |y| {
+        (0..width).map(move |x| {
+            Vec2::new(
+                (x as f32 + 0.5) / width as f32,
+                (y as f32 + 0.5) / height as f32,
+            )
+        })
+    })
+}
+
+/// An environment and its downsampled copies, each half as large as the previous one
+struct EquirectPyramid {
+    levels: Vec<Equirect>,
+}
+
+impl EquirectPyramid {
+    fn new(base: Equirect) -> Self {
+        let mut levels = vec![base];
+        loop {
+            let last = levels.last().unwrap();
+            if last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        EquirectPyramid { levels }
+    }
+
+    /// Samples between the two levels around `lod`
+    fn sample(&self, direction: Vec3, lod: f32) -> Vec3 {
+        let lod = lod.max(0.0).min((self.levels.len() - 1) as f32);
+        let lower = lod.floor() as usize;
+        let upper = (lower + 1).min(self.levels.len() - 1);
+        self.levels[lower]
+            .sample(direction)
+            .lerp(self.levels[upper].sample(direction), lod - lower as f32)
+    }
+
+    /// The smallest level that is at least `width` texels wide
+    fn level_for_width(&self, width: u32) -> &Equirect {
+        self.levels
+            .iter()
+            .rev()
+            .find(|level| level.width >= width)
+            .unwrap_or(&self.levels[0])
+    }
+}
+
+/// The i-th of `count` points of the Hammersley set
+fn hammersley(i: u32, count: u32) -> Vec2 {
+    Vec2::new(
+        i as f32 / count as f32,
+        i.reverse_bits() as f32 * (1.0 / 4_294_967_296.0),
+    )
+}
+
+/// A half vector around +Z, distributed by the GGX distribution of roughness `alpha`
+fn importance_sample_ggx(xi: Vec2, alpha: f32) -> Vec3 {
+    let phi = 2.0 * PI * xi.x;
+    let cos_theta = ((1.0 - xi.y) / (1.0 + (alpha * alpha - 1.0) * xi.y)).sqrt();
+    let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+    Vec3::new(sin_theta * phi.cos(), sin_theta * phi.sin(), cos_theta)
+}
+
+fn d_ggx(alpha: f32, n_dot_h: f32) -> f32 {
+    let a2 = alpha * alpha;
+    let d = n_dot_h * n_dot_h * (a2 - 1.0) + 1.0;
+    a2 / (PI * d * d)
+}
+
+/// Two vectors perpendicular to `n` and to each other
+fn tangent_frame(n: Vec3) -> (Vec3, Vec3) {
+    let up = if n.z.abs() < 0.999 { Vec3::Z } else { Vec3::X };
+    let tangent = up.cross(n).normalize();
+    (tangent, n.cross(tangent))
+}
+
+/// The diffuse and specular lighting of an [EnvironmentMap]
+#[derive(Debug)]
+pub struct PrefilteredEnvironmentMap {
+    pub diffuse: Texture,
+    pub specular: Texture,
+    pub specular_levels: u32,
+}
+
+/// Convolves an environment with the diffuse and specular BRDFs, as described in
+/// [EnvironmentMapTextures]
+pub fn prefilter_environment_map(
+    texture: &Texture,
+    settings: &EnvironmentMapSettings,
+) -> Result<PrefilteredEnvironmentMap, EnvironmentMapError> {
+    let pyramid = EquirectPyramid::new(Equirect::from_texture(texture)?);
+    Ok(PrefilteredEnvironmentMap {
+        diffuse: diffuse_irradiance(&pyramid),
+        specular: specular_levels(&pyramid, settings),
+        specular_levels: specular_level_count(settings),
+    })
+}
+
+const DIFFUSE_WIDTH: u32 = 32;
+
+fn diffuse_irradiance(pyramid: &EquirectPyramid) -> Texture {
+    // irradiance changes slowly, so a small copy of the environment is enough to integrate
+    let source = pyramid.level_for_width(DIFFUSE_WIDTH * 2);
+    let texel_area = (2.0 * PI / source.width as f32) * (PI / source.height as f32);
+    let samples: Vec<(Vec3, Vec3)> = texel_uvs(source.width, source.height)
+        .zip(source.data.iter())
+        .map(|(uv, radiance)| {
+            // each texel's solid angle shrinks towards the poles
+            let solid_angle = texel_area * (uv.y * PI).sin();
+            (equirect_direction(uv), *radiance * solid_angle)
+        })
+        .collect();
+
+    let (width, height) = (DIFFUSE_WIDTH, DIFFUSE_WIDTH / 2);
+    let data: Vec<Vec3> = texel_uvs(width, height)
+        .map(|uv| {
+            let normal = equirect_direction(uv);
+            let irradiance = samples
+                .iter()
+                .fold(Vec3::ZERO, |sum, (direction, radiance)| {
+                    sum + *radiance * normal.dot(*direction).max(0.0)
+                });
+            irradiance / PI
+        })
+        .collect();
+
+    let mut texture = rgba16_texture(width, height, &data);
+    texture.sampler.address_mode_u = AddressMode::Repeat;
+    texture
+}
+
+fn specular_level_count(settings: &EnvironmentMapSettings) -> u32 {
+    let height = settings.specular_size.next_power_of_two().max(2) / 2;
+    settings
+        .specular_levels
+        .max(1)
+        .min(height.trailing_zeros() + 1)
+}
+
+fn specular_levels(pyramid: &EquirectPyramid, settings: &EnvironmentMapSettings) -> Texture {
+    let width = settings.specular_size.next_power_of_two().max(2);
+    let level_count = specular_level_count(settings);
+    let source = &pyramid.levels[0];
+    let source_texel_solid_angle = 4.0 * PI / (source.width * source.height) as f32;
+    let sample_count = settings.sample_count.max(1);
+
+    let atlas_width = width + 2;
+    let atlas_height = (0..level_count)
+        .map(|level| (width / 2) >> level)
+        .sum::<u32>();
+    let mut data = vec![Vec3::ZERO; (atlas_width * atlas_height) as usize];
+    let mut level_top = 0;
+    for level in 0..level_count {
+        let (level_width, level_height) = (width >> level, (width / 2) >> level);
+        let perceptual_roughness = if level_count > 1 {
+            level as f32 / (level_count - 1) as f32
+        } else {
+            0.0
+        };
+        let alpha = perceptual_roughness * perceptual_roughness;
+
+        for (index, uv) in texel_uvs(level_width, level_height).enumerate() {
+            let n = equirect_direction(uv);
+            let radiance = if level == 0 {
+                let lod = (source.width as f32 / level_width as f32).log2();
+                pyramid.sample(n, lod)
+            } else {
+                // filtered importance sampling: each sample reads a level of the pyramid whose
+                // texels cover about as much of the sphere as the sample does
+                let (tangent, bitangent) = tangent_frame(n);
+                let mut sum = Vec3::ZERO;
+                let mut weight = 0.0;
+                for i in 0..sample_count {
+                    let h = importance_sample_ggx(hammersley(i, sample_count), alpha);
+                    let h = tangent * h.x + bitangent * h.y + n * h.z;
+                    let n_dot_h = n.dot(h);
+                    let l = h * 2.0 * n_dot_h - n;
+                    let n_dot_l = n.dot(l);
+                    if n_dot_l > 0.0 {
+                        let pdf = d_ggx(alpha, n_dot_h) * 0.25;
+                        let sample_solid_angle = 1.0 / (sample_count as f32 * pdf + 1e-4);
+                        let lod =
+                            0.5 * (sample_solid_angle / source_texel_solid_angle).log2() + 1.0;
+                        sum += pyramid.sample(l, lod) * n_dot_l;
+                        weight += n_dot_l;
+                    }
+                }
+                sum / weight.max(1e-4)
+            };
+            let x = index as u32 % level_width;
+            let y = index as u32 / level_width;
+            data[((level_top + y) * atlas_width + x + 1) as usize] = radiance;
+        }
+
+        // wrap each row around, so filtering is seamless
+        for y in level_top..level_top + level_height {
+            let row = (y * atlas_width) as usize;
+            data[row] = data[row + level_width as usize];
+            data[row + level_width as usize + 1] = data[row + 1];
+        }
+        level_top += level_height;
+    }
+
+    rgba16_texture(atlas_width, atlas_height, &data)
+}
+
+/// Integrates the specular BRDF over the hemisphere, by the cosine of the view angle along x and
+/// perceptual roughness along y. Each texel holds the scale and bias to `f0` of Karis' split sum
+/// approximation.
+pub fn brdf_lut(size: u32, sample_count: u32) -> Texture {
+    let data: Vec<Vec3> = texel_uvs(size, size)
+        .map(|uv| {
+            let n_dot_v = uv.x;
+            let alpha = uv.y * uv.y;
+            let k = alpha / 2.0;
+            let g1 = |n_dot_x: f32| n_dot_x / (n_dot_x * (1.0 - k) + k);
+            let v = Vec3::new((1.0 - n_dot_v * n_dot_v).sqrt(), 0.0, n_dot_v);
+            let (mut scale, mut bias) = (0.0, 0.0);
+            for i in 0..sample_count {
+                let h = importance_sample_ggx(hammersley(i, sample_count), alpha);
+                let v_dot_h = v.dot(h);
+                let l = h * 2.0 * v_dot_h - v;
+                let n_dot_l = l.z;
+                if n_dot_l > 0.0 {
+                    let g_vis = g1(n_dot_v) * g1(n_dot_l) * v_dot_h.max(0.0) / (h.z * n_dot_v);
+                    let fresnel = (1.0 - v_dot_h).max(0.0).powi(5);
+                    scale += (1.0 - fresnel) * g_vis;
+                    bias += fresnel * g_vis;
+                }
+            }
+            Vec3::new(scale, bias, 0.0) / sample_count as f32
+        })
+        .collect();
+    rgba16_texture(size, size, &data)
+}
+
+fn rgba16_texture(width: u32, height: u32, data: &[Vec3]) -> Texture {
+    let mut bytes = Vec::with_capacity(data.len() * 8);
+    for color in data {
+        for &channel in [color.x, color.y, color.z, 1.0].iter() {
+            bytes.extend_from_slice(&f32_to_f16(channel).to_ne_bytes());
+        }
+    }
+    let mut texture = Texture::new(
+        Extent3d::new(width, height, 1),
+        TextureDimension::D2,
+        bytes,
+        TextureFormat::Rgba16Float,
+    );
+    texture.sampler = SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    };
+    texture
+}
+
+fn f32_to_f16(value: f32) -> u16 {
+    let bits = value.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exponent = ((bits >> 23) & 0xff) as i32 - 127 + 15;
+    let mantissa = bits & 0x007f_ffff;
+    if value.is_nan() {
+        sign | 0x7e00
+    } else if exponent >= 0x1f {
+        sign | 0x7c00
+    } else if exponent <= 0 {
+        if exponent < -10 {
+            sign
+        } else {
+            // subnormal
+            let mantissa = (mantissa | 0x0080_0000) >> (1 - exponent);
+            sign | ((mantissa + 0x1000) >> 13) as u16
+        }
+    } else {
+        // rounding can carry into the exponent, which is still correct
+        let half = ((exponent as u32) << 10) | (mantissa >> 13);
+        sign | (half + ((mantissa >> 12) & 1)) as u16
+    }
+}
+
+fn f16_to_f32(half: u16) -> f32 {
+    let sign = if half & 0x8000 != 0 { -1.0 } else { 1.0 };
+    let exponent = ((half >> 10) & 0x1f) as i32;
+    let mantissa = (half & 0x03ff) as f32;
+    match exponent {
+        0 => sign * mantissa * 2f32.powi(-24),
+        0x1f if mantissa == 0.0 => sign * f32::INFINITY,
+        0x1f => f32::NAN,
+        _ => sign * (1.0 + mantissa / 1024.0) * 2f32.powi(exponent - 15),
+    }
+}
+
+/// The [EnvironmentMap] of the 3d camera, or the [EnvironmentMap] resource when the camera
+/// doesn't have one
+pub(crate) fn active_environment_map<'a>(
+    environment_map: Option<&'a EnvironmentMap>,
+    active_cameras: &ActiveCameras,
+    camera_query: &'a Query<&EnvironmentMap>,
+) -> Option<&'a EnvironmentMap> {
+    active_cameras
+        .get(base::camera::CAMERA_3D)
+        .and_then(|camera| camera.entity)
+        .and_then(|entity| camera_query.get(entity).ok())
+        .or(environment_map)
+}
+
+/// Local state of [prefilter_environment_map_system]
+#[derive(Debug, Default)]
+pub struct PrefilterEnvironmentMapState {
+    /// The texture the [EnvironmentMapTextures] were, or are being, prefiltered from
+    prefiltered: Option<Handle<Texture>>,
+    task: Option<Task<Result<PrefilteredEnvironmentMap, EnvironmentMapError>>>,
+}
+
+/// Prefilters the active [EnvironmentMap] into the [EnvironmentMapTextures] when it, its texture
+/// or the [EnvironmentMapSettings] change. Prefiltering runs on the [AsyncComputeTaskPool], and the
+/// previous textures are kept until it finishes.
+#[allow(clippy::too_many_arguments)]
+pub fn prefilter_environment_map_system(
+    mut state: Local<PrefilterEnvironmentMapState>,
+    environment_map: Option<Res<EnvironmentMap>>,
+    active_cameras: Res<ActiveCameras>,
+    camera_query: Query<&EnvironmentMap>,
+    settings: Res<EnvironmentMapSettings>,
+    task_pool: Res<AsyncComputeTaskPool>,
+    mut environment_map_textures: ResMut<EnvironmentMapTextures>,
+    mut textures: ResMut<Assets<Texture>>,
+    mut texture_events: EventReader<AssetEvent<Texture>>,
+) {
+    let state = &mut *state;
+    let environment_map = if let Some(environment_map) =
+        active_environment_map(environment_map.as_deref(), &active_cameras, &camera_query)
+    {
+        environment_map
+    } else {
+        // the environment map was removed
+        state.task = None;
+        if state.prefiltered.take().is_some() {
+            let black = Extent3d::new(1, 1, 1);
+            for handle in [
+                &environment_map_textures.diffuse,
+                &environment_map_textures.specular,
+            ]
+            .iter()
+            {
+                let texture = textures.get_mut(*handle).unwrap();
+                *texture = rgba16_texture(black.width, black.height, &[Vec3::ZERO]);
+            }
+            environment_map_textures.specular_levels = 1;
+        }
+        return;
+    };
+
+    let texture_modified = texture_events.iter().any(|event| {
+        matches!(event, AssetEvent::Modified { handle } if *handle == environment_map.texture)
+    });
+    if state.prefiltered.as_ref() != Some(&environment_map.texture)
+        || texture_modified
+        || settings.is_changed()
+    {
+        // retried every frame until the texture is loaded
+        if let Some(texture) = textures.get(&environment_map.texture) {
+            let texture = texture.clone();
+            let settings = settings.clone();
+            // replacing the task of an outdated environment map cancels it
+            state.task = Some(
+                task_pool.spawn(async move { prefilter_environment_map(&texture, &settings) }),
+            );
+            state.prefiltered = Some(environment_map.texture.clone_weak());
+        }
+    }
+
+    let result = match state.task.as_mut() {
+        Some(task) => future::block_on(future::poll_once(task)),
+        None => None,
+    };
+    if let Some(result) = result {
+        state.task = None;
+        match result {
+            Ok(prefiltered) => {
+                *textures.get_mut(&environment_map_textures.diffuse).unwrap() = prefiltered.diffuse;
+                *textures
+                    .get_mut(&environment_map_textures.specular)
+                    .unwrap() = prefiltered.specular;
+                environment_map_textures.specular_levels = prefiltered.specular_levels;
+            }
+            Err(err) => warn!("{}", err),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin};
+    use bevy_ecs::system::IntoSystem;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+
+    fn equirect(width: u32, radiance: impl Fn(Vec3) -> Vec3) -> Equirect {
+        let height = width / 2;
+        Equirect {
+            width,
+            height,
+            data: texel_uvs(width, height)
+                .map(|uv| radiance(equirect_direction(uv)))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn equirect_mapping() {
+        for &direction in [
+            Vec3::X,
+            -Vec3::X,
+            Vec3::new(0.3, 0.5, -0.8).normalize(),
+            Vec3::new(-0.1, -0.9, 0.4).normalize(),
+        ]
+        .iter()
+        {
+            assert!(equirect_direction(equirect_uv(direction)).distance(direction) < 1e-5);
+        }
+
+        // a cube with a different color on each face
+        let colors = [
+            Vec3::X,
+            Vec3::Y,
+            Vec3::Z,
+            Vec3::ONE,
+            Vec3::new(0.5, 0.0, 0.0),
+            Vec3::new(0.0, 0.5, 0.0),
+        ];
+        let faces: Vec<Vec3> = colors.iter().flat_map(|color| vec![*color; 16]).collect();
+        let cube = Equirect::from_cube(4, &faces);
+        let axes = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        for (axis, color) in axes.iter().zip(colors.iter()) {
+            assert_eq!(cube.sample(*axis), *color);
+        }
+    }
+
+    #[test]
+    fn prefiltering_preserves_uniform_light() {
+        let radiance = Vec3::new(1.0, 0.5, 0.25);
+        let pyramid = EquirectPyramid::new(equirect(64, |_| radiance));
+        let settings = EnvironmentMapSettings {
+            specular_size: 32,
+            specular_levels: 4,
+            sample_count: 16,
+        };
+
+        // the irradiance of a uniform environment is π times its radiance
+        let diffuse = texture_colors(&diffuse_irradiance(&pyramid)).unwrap();
+        for texel in diffuse.iter() {
+            assert!(texel.distance(radiance) < 0.02, "{:?}", texel);
+        }
+        let specular = specular_levels(&pyramid, &settings);
+        assert_eq!(specular.size, Extent3d::new(34, 16 + 8 + 4 + 2, 1));
+        let texels = texture_colors(&specular).unwrap();
+        let mut top = 0;
+        for level in 0..4 {
+            // each level and its padding
+            let (width, height) = ((32 >> level) + 2, 16 >> level);
+            for y in top..top + height {
+                for texel in texels[y * 34..y * 34 + width].iter() {
+                    assert!(texel.distance(radiance) < 0.01, "{:?}", texel);
+                }
+            }
+            top += height;
+        }
+    }
+
+    #[test]
+    fn rough_levels_blur_the_environment() {
+        // a bright light straight up
+        let pyramid = EquirectPyramid::new(equirect(128, |direction| {
+            Vec3::splat(if direction.y > 0.95 { 100.0 } else { 0.0 })
+        }));
+        let at = |perceptual_roughness: f32, direction: Vec3| {
+            let alpha = perceptual_roughness * perceptual_roughness;
+            let (tangent, bitangent) = tangent_frame(direction);
+            let mut sum = Vec3::ZERO;
+            for i in 0..256 {
+                let h = importance_sample_ggx(hammersley(i, 256), alpha);
+                let h = tangent * h.x + bitangent * h.y + direction * h.z;
+                sum += pyramid.sample(h * 2.0 * direction.dot(h) - direction, 0.0);
+            }
+            sum.x / 256.0
+        };
+        let sideways = Vec3::new(1.0, 1.0, 0.0).normalize();
+        assert!(at(0.1, Vec3::Y) > at(1.0, Vec3::Y));
+        assert!(at(0.1, sideways) < at(1.0, sideways));
+    }
+
+    #[test]
+    fn brdf_lut_is_bounded() {
+        let lut = texture_colors(&brdf_lut(8, 64)).unwrap();
+        for texel in lut.iter() {
+            assert!(texel.x >= 0.0 && texel.y >= 0.0 && texel.x + texel.y <= 1.05);
+        }
+        // smooth surfaces seen head on reflect exactly f0
+        let smooth = lut[7];
+        assert!(
+            (smooth.x - 1.0).abs() < 0.1 && smooth.y < 0.05,
+            "{:?}",
+            smooth
+        );
+    }
+
+    #[test]
+    fn f16_round_trip() {
+        for &value in [0.0, 1.0, 0.5, 65504.0, 1e-5, 2.5, 1e6].iter() {
+            let round_trip = f16_to_f32(f32_to_f16(value));
+            if value > 65504.0 {
+                assert!(round_trip.is_infinite());
+            } else {
+                assert!(
+                    (round_trip - value).abs() <= value * 1e-3 + 1e-7,
+                    "{}",
+                    value
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn camera_environment_map_is_prefiltered_in_the_background() {
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .insert_resource(AsyncComputeTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Texture>()
+            .init_resource::<ActiveCameras>()
+            .insert_resource(EnvironmentMapSettings {
+                specular_size: 16,
+                specular_levels: 2,
+                sample_count: 4,
+            })
+            .init_resource::<EnvironmentMapTextures>()
+            .add_system(prefilter_environment_map_system.system());
+
+        let uniform = |radiance: f32| rgba16_texture(16, 8, &[Vec3::splat(radiance); 16 * 8]);
+        let mut textures = app
+            .world_mut()
+            .get_resource_mut::<Assets<Texture>>()
+            .unwrap();
+        let resource_texture = textures.add(uniform(1.0));
+        let camera_texture = textures.add(uniform(2.0));
+        app.insert_resource(EnvironmentMap::new(resource_texture));
+        let camera = app
+            .world_mut()
+            .spawn()
+            .insert(EnvironmentMap::new(camera_texture))
+            .id();
+        let mut active_cameras = app.world_mut().get_resource_mut::<ActiveCameras>().unwrap();
+        active_cameras.add(base::camera::CAMERA_3D);
+        active_cameras
+            .get_mut(base::camera::CAMERA_3D)
+            .unwrap()
+            .entity = Some(camera);
+
+        let prefiltered = |world: &World| {
+            world
+                .get_resource::<EnvironmentMapTextures>()
+                .unwrap()
+                .specular_levels
+                == 2
+        };
+        for _ in 0..1000 {
+            app.app.update();
+            if prefiltered(&app.app.world) {
+                break;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(
+            prefiltered(&app.app.world),
+            "the environment map wasn't prefiltered"
+        );
+
+        let world = &app.app.world;
+        // the camera's environment map is used instead of the resource
+        let environment_map_textures = world.get_resource::<EnvironmentMapTextures>().unwrap();
+        let textures = world.get_resource::<Assets<Texture>>().unwrap();
+        let diffuse =
+            texture_colors(textures.get(&environment_map_textures.diffuse).unwrap()).unwrap();
+        for texel in diffuse.iter() {
+            assert!(texel.distance(Vec3::splat(2.0)) < 0.1, "{:?}", texel);
+        }
+    }
+}
@@ -1,23 +1,35 @@
 pub mod render_graph;
 
+mod cluster;
 mod entity;
+mod environment_map;
 mod light;
 mod material;
+mod shadow;
 
+pub use cluster::*;
 pub use entity::*;
+pub use environment_map::*;
 pub use light::*;
 pub use material::*;
+pub use shadow::*;
 
 pub mod prelude {
-    pub use crate::{entity::*, light::Light, material::StandardMaterial};
+    pub use crate::{
+        entity::*,
+        environment_map::EnvironmentMap,
+        light::{DirectionalLight, Light, SpotLight},
+        material::StandardMaterial,
+        shadow::{NotShadowCaster, NotShadowReceiver},
+    };
 }
 
 use bevy_app::prelude::*;
 use bevy_asset::{AddAsset, Assets, Handle};
-use bevy_ecs::system::IntoSystem;
-use bevy_render::{prelude::Color, shader};
+use bevy_ecs::{schedule::ParallelSystemDescriptorCoercion, system::IntoSystem};
+use bevy_render::{instancing, prelude::Color, shader, RenderStage, RenderSystem};
 use material::StandardMaterial;
-use render_graph::add_pbr_graph;
+use render_graph::{add_pbr_graph, draw_shadows_system, ShadowCommands};
 
 /// NOTE: this isn't PBR yet. consider this name "aspirational" :)
 #[derive(Default)]
@@ -27,11 +39,53 @@ impl Plugin for PbrPlugin {
     fn build(&self, app: &mut AppBuilder) {
         app.add_asset::<StandardMaterial>()
             .register_type::<Light>()
+            .register_type::<DirectionalLight>()
+            .register_type::<SpotLight>()
+            .register_type::<NotShadowCaster>()
+            .register_type::<NotShadowReceiver>()
             .add_system_to_stage(
                 CoreStage::PostUpdate,
                 shader::asset_shader_defs_system::<StandardMaterial>.system(),
             )
-            .init_resource::<AmbientLight>();
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                material::material_texture_detection_system.system(),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                shadow::shadow_receiver_system.system(),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                shadow::shadow_views_system
+                    .system()
+                    .after(RenderSystem::VisibleEntities),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                cluster::assign_lights_to_clusters
+                    .system()
+                    .after(RenderSystem::VisibleEntities),
+            )
+            .add_system_to_stage(
+                CoreStage::PostUpdate,
+                environment_map::prefilter_environment_map_system.system(),
+            )
+            .add_system_to_stage(
+                RenderStage::RenderResource,
+                instancing::batch_instances_system::<StandardMaterial>
+                    .system()
+                    .label(RenderSystem::MeshInstances),
+            )
+            .add_system_to_stage(RenderStage::Draw, draw_shadows_system.system())
+            .init_resource::<AmbientLight>()
+            .init_resource::<ClusterSettings>()
+            .init_resource::<EnvironmentMapSettings>()
+            .init_resource::<EnvironmentMapTextures>()
+            .init_resource::<Clusters>()
+            .init_resource::<ShadowSettings>()
+            .init_resource::<ShadowViews>()
+            .init_resource::<ShadowCommands>();
         add_pbr_graph(app.world_mut());
 
         // add default StandardMaterial
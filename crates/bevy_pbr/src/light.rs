@@ -1,10 +1,9 @@
+use crate::MAX_CASCADES;
 use bevy_core::Byteable;
 use bevy_ecs::reflect::ReflectComponent;
+use bevy_math::Vec3;
 use bevy_reflect::Reflect;
-use bevy_render::{
-    camera::{CameraProjection, PerspectiveProjection},
-    color::Color,
-};
+use bevy_render::color::Color;
 use bevy_transform::components::GlobalTransform;
 use std::ops::Range;
 
@@ -17,6 +16,14 @@ pub struct Light {
     pub depth: Range<f32>,
     pub intensity: f32,
     pub range: f32,
+    /// Renders the light's shadow maps, one per cube face, and shades with them
+    pub shadows_enabled: bool,
+    /// Pushes the depth compared against the shadow map away from the light, which removes
+    /// "shadow acne"
+    pub shadow_depth_bias: f32,
+    /// Offsets the shaded position along the surface normal before it is compared against the
+    /// shadow map
+    pub shadow_normal_bias: f32,
 }
 
 impl Default for Light {
@@ -27,43 +34,184 @@ impl Default for Light {
             fov: f32::to_radians(60.0),
             intensity: 200.0,
             range: 20.0,
+            shadows_enabled: false,
+            shadow_depth_bias: 0.0005,
+            shadow_normal_bias: 0.05,
         }
     }
 }
 
+/// The number of [DirectionalLight]s that are passed to the shader
+pub const MAX_DIRECTIONAL_LIGHTS: usize = 4;
+
+/// A light that shines in one direction from infinitely far away, like the sun. The light travels
+/// along the forward direction (-Z) of the entity's [GlobalTransform].
+#[derive(Debug, Reflect)]
+#[reflect(Component)]
+pub struct DirectionalLight {
+    pub color: Color,
+    /// Color is premultiplied by illuminance before being passed to the shader
+    pub illuminance: f32,
+    /// Renders cascaded shadow maps that cover the view of the active 3d camera
+    pub shadows_enabled: bool,
+    pub shadow_depth_bias: f32,
+    pub shadow_normal_bias: f32,
+    /// The number of shadow cascades, at most [MAX_CASCADES](crate::MAX_CASCADES)
+    pub shadow_cascades: usize,
+    /// How far from the camera shadows are drawn. Surfaces further away are always lit.
+    pub shadow_max_distance: f32,
+}
+
+impl Default for DirectionalLight {
+    fn default() -> Self {
+        DirectionalLight {
+            color: Color::rgb(1.0, 1.0, 1.0),
+            illuminance: 10.0,
+            shadows_enabled: false,
+            shadow_depth_bias: 0.002,
+            shadow_normal_bias: 0.1,
+            shadow_cascades: 4,
+            shadow_max_distance: 100.0,
+        }
+    }
+}
+
+/// A light that shines from a point in a cone around the forward direction (-Z) of the entity's
+/// [GlobalTransform], like a flashlight
+#[derive(Debug, Reflect)]
+#[reflect(Component)]
+pub struct SpotLight {
+    pub color: Color,
+    pub intensity: f32,
+    pub range: f32,
+    /// The angle from the center of the cone at which the light starts to fade, in radians
+    pub inner_angle: f32,
+    /// The angle from the center of the cone past which there is no light, in radians
+    pub outer_angle: f32,
+}
+
+impl Default for SpotLight {
+    fn default() -> Self {
+        SpotLight {
+            color: Color::rgb(1.0, 1.0, 1.0),
+            intensity: 200.0,
+            range: 20.0,
+            inner_angle: 0.0,
+            outer_angle: std::f32::consts::FRAC_PI_4,
+        }
+    }
+}
+
+/// A point or spot light, as it is laid out in the shader's light storage buffer
 #[repr(C)]
 #[derive(Debug, Clone, Copy)]
 pub(crate) struct LightRaw {
-    pub proj: [[f32; 4]; 4],
     pub pos: [f32; 4],
     pub color: [f32; 4],
+    /// the direction a spot light points in
+    pub spot_direction: [f32; 4],
+    /// scale and offset of the angular attenuation of a spot light
+    pub spot_attenuation: [f32; 4],
+    /// depth bias, normal bias, index of the first shadow view or -1
+    pub shadow: [f32; 4],
 }
 
 unsafe impl Byteable for LightRaw {}
 
 impl LightRaw {
-    pub fn from(light: &Light, global_transform: &GlobalTransform) -> LightRaw {
-        let perspective = PerspectiveProjection {
-            fov: light.fov,
-            aspect_ratio: 1.0,
-            near: light.depth.start,
-            far: light.depth.end,
-        };
-
-        let proj = perspective.get_projection_matrix() * global_transform.compute_matrix();
+    pub fn from(
+        light: &Light,
+        global_transform: &GlobalTransform,
+        first_shadow_view: Option<usize>,
+    ) -> LightRaw {
         let (x, y, z) = global_transform.translation.into();
 
         // premultiply color by intensity
         // we don't use the alpha at all, so no reason to multiply only [0..3]
         let color: [f32; 4] = (light.color * light.intensity).into();
         LightRaw {
-            proj: proj.to_cols_array_2d(),
             pos: [x, y, z, 1.0 / (light.range * light.range)], // pos.w is the attenuation.
             color,
+            spot_direction: [0.0; 4],
+            // a point light is lit at every angle
+            spot_attenuation: [0.0, 1.0, 0.0, 0.0],
+            shadow: shadow_raw(
+                light.shadow_depth_bias,
+                light.shadow_normal_bias,
+                first_shadow_view,
+                6,
+            ),
+        }
+    }
+
+    pub fn from_spot(light: &SpotLight, global_transform: &GlobalTransform) -> LightRaw {
+        let (x, y, z) = global_transform.translation.into();
+        let (dx, dy, dz) = (global_transform.rotation * -Vec3::Z).normalize().into();
+        let color: [f32; 4] = (light.color * light.intensity).into();
+
+        // https://google.github.io/filament/Filament.html#lighting/directlighting/spotlights
+        let cos_outer = light.outer_angle.cos();
+        let scale = 1.0 / (light.inner_angle.cos() - cos_outer).max(1e-4);
+        LightRaw {
+            pos: [x, y, z, 1.0 / (light.range * light.range)],
+            color,
+            spot_direction: [dx, dy, dz, 0.0],
+            spot_attenuation: [scale, -cos_outer * scale, 0.0, 0.0],
+            shadow: shadow_raw(0.0, 0.0, None, 0),
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DirectionalLightRaw {
+    pub color: [f32; 4],
+    /// the direction towards the light
+    pub direction: [f32; 4],
+    /// depth bias, normal bias, index of the first shadow view or -1, cascade count
+    pub shadow: [f32; 4],
+}
+
+unsafe impl Byteable for DirectionalLightRaw {}
+
+impl DirectionalLightRaw {
+    pub fn from(
+        light: &DirectionalLight,
+        global_transform: &GlobalTransform,
+        first_shadow_view: Option<usize>,
+    ) -> DirectionalLightRaw {
+        let (x, y, z) = (global_transform.rotation * Vec3::Z).normalize().into();
+        let color: [f32; 4] = (light.color * light.illuminance).into();
+        DirectionalLightRaw {
+            color,
+            direction: [x, y, z, 0.0],
+            shadow: shadow_raw(
+                light.shadow_depth_bias,
+                light.shadow_normal_bias,
+                first_shadow_view,
+                light.shadow_cascades.min(MAX_CASCADES),
+            ),
         }
     }
 }
 
+fn shadow_raw(
+    depth_bias: f32,
+    normal_bias: f32,
+    first_shadow_view: Option<usize>,
+    view_count: usize,
+) -> [f32; 4] {
+    match first_shadow_view {
+        Some(first_shadow_view) => [
+            depth_bias,
+            normal_bias,
+            first_shadow_view as f32,
+            view_count as f32,
+        ],
+        None => [depth_bias, normal_bias, -1.0, 0.0],
+    }
+}
+
 // Ambient light color.
 #[derive(Debug)]
 pub struct AmbientLight {
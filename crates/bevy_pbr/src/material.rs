@@ -1,4 +1,6 @@
-use bevy_asset::{self, Handle};
+use bevy_app::EventReader;
+use bevy_asset::{self, AssetEvent, Assets, Handle, HandleId};
+use bevy_ecs::system::ResMut;
 use bevy_reflect::TypeUuid;
 use bevy_render::{color::Color, renderer::RenderResources, shader::ShaderDefs, texture::Texture};
 
@@ -88,3 +90,44 @@ impl From<Handle<Texture>> for StandardMaterial {
         }
     }
 }
+
+impl StandardMaterial {
+    pub fn textures(&self) -> impl Iterator<Item = &Handle<Texture>> {
+        self.base_color_texture
+            .iter()
+            .chain(self.metallic_roughness_texture.iter())
+            .chain(self.normal_map.iter())
+            .chain(self.occlusion_texture.iter())
+            .chain(self.emissive_texture.iter())
+    }
+}
+
+/// Marks the materials that use a modified texture as modified, so they are prepared again with
+/// the texture's new render resources when it is hot reloaded.
+pub(crate) fn material_texture_detection_system(
+    mut texture_events: EventReader<AssetEvent<Texture>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let modified_textures: Vec<&Handle<Texture>> = texture_events
+        .iter()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { handle } => Some(handle),
+            _ => None,
+        })
+        .collect();
+    if modified_textures.is_empty() {
+        return;
+    }
+    let modified_materials: Vec<HandleId> = materials
+        .iter()
+        .filter(|(_, material)| {
+            material
+                .textures()
+                .any(|texture| modified_textures.contains(&texture))
+        })
+        .map(|(id, _)| id)
+        .collect();
+    for id in modified_materials {
+        materials.get_mut(id);
+    }
+}
@@ -1,34 +1,48 @@
 use crate::{
-    light::{AmbientLight, Light, LightRaw},
-    render_graph::uniform,
+    environment_map::active_environment_map,
+    light::{
+        AmbientLight, DirectionalLight, DirectionalLightRaw, Light, LightRaw, SpotLight,
+        MAX_DIRECTIONAL_LIGHTS,
+    },
+    render_graph::{storage, texture, uniform},
+    Clusters, EnvironmentMap, EnvironmentMapTextures, ShadowSettings, ShadowViews,
+    MAX_SHADOW_VIEWS, SHADOW_ATLAS_TILES_PER_ROW,
 };
+use bevy_asset::Handle;
 use bevy_core::{AsBytes, Byteable};
 use bevy_ecs::{
+    entity::Entity,
     system::{BoxedSystem, IntoSystem, Local, Query, Res, ResMut},
     world::World,
 };
 use bevy_render::{
+    camera::ActiveCameras,
     render_graph::{CommandQueue, Node, ResourceSlots, SystemNode},
     renderer::{
         BufferId, BufferInfo, BufferMapMode, BufferUsage, RenderContext, RenderResourceBinding,
-        RenderResourceBindings, RenderResourceContext,
+        RenderResourceBindings, RenderResourceContext, RenderResourceId, SamplerId, TextureId,
+    },
+    texture::{
+        Extent3d, FilterMode, SamplerDescriptor, Texture, TextureDescriptor, TextureDimension,
+        TextureFormat, TextureUsage, SAMPLER_ASSET_INDEX, TEXTURE_ASSET_INDEX,
     },
 };
 use bevy_transform::prelude::*;
 
-/// A Render Graph [Node] that write light data from the ECS to GPU buffers
+/// A Render Graph [Node] that write light data from the ECS to GPU buffers. Point and spot lights
+/// are written to storage buffers that grow with the number of lights, along with the lights of
+/// each of the [Clusters].
 #[derive(Debug, Default)]
 pub struct LightsNode {
     command_queue: CommandQueue,
-    max_lights: usize,
 }
 
 impl LightsNode {
-    pub fn new(max_lights: usize) -> Self {
-        LightsNode {
-            max_lights,
-            command_queue: CommandQueue::default(),
-        }
+    #[deprecated(
+        note = "point and spot lights are written to storage buffers, which have no maximum. Use `LightsNode::default()` instead."
+    )]
+    pub fn new(_max_lights: usize) -> Self {
+        Self::default()
     }
 }
 
@@ -52,14 +66,32 @@ struct LightCount {
 
 unsafe impl Byteable for LightCount {}
 
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ClusterConfig {
+    /// w is 1 if the lights are clustered
+    pub dimensions: [u32; 4],
+    pub z_params: [f32; 4],
+    pub view_proj: [[f32; 4]; 4],
+}
+
+unsafe impl Byteable for ClusterConfig {}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct ShadowViewRaw {
+    pub view_proj: [[f32; 4]; 4],
+    pub atlas_rect: [f32; 4],
+}
+
+unsafe impl Byteable for ShadowViewRaw {}
+
 impl SystemNode for LightsNode {
     fn get_system(&self) -> BoxedSystem {
         let system = lights_node_system.system().config(|config| {
             config.0 = Some(LightsNodeSystemState {
                 command_queue: self.command_queue.clone(),
-                max_lights: self.max_lights,
-                light_buffer: None,
-                staging_buffer: None,
+                ..Default::default()
             })
         });
         Box::new(system)
@@ -71,39 +103,177 @@ impl SystemNode for LightsNode {
 pub struct LightsNodeSystemState {
     light_buffer: Option<BufferId>,
     staging_buffer: Option<BufferId>,
+    point_lights: StorageBuffer,
+    cluster_light_indices: StorageBuffer,
+    cluster_offsets_and_counts: StorageBuffer,
+    /// the shadow atlas and the tile size it was created with
+    shadow_atlas: Option<(TextureId, u32)>,
+    shadow_atlas_sampler: Option<SamplerId>,
     command_queue: CommandQueue,
-    max_lights: usize,
 }
 
+/// A storage buffer that is recreated, and bound again, when its contents outgrow it
+#[derive(Debug, Default)]
+struct StorageBuffer {
+    buffer: Option<BufferId>,
+    staging_buffer: Option<BufferId>,
+    size: usize,
+}
+
+impl StorageBuffer {
+    fn write(
+        &mut self,
+        name: &str,
+        bytes: &[u8],
+        render_resource_context: &dyn RenderResourceContext,
+        render_resource_bindings: &mut RenderResourceBindings,
+        command_queue: &mut CommandQueue,
+    ) {
+        // bindings can't be empty
+        let bytes = if bytes.is_empty() {
+            &[0u8; 16][..]
+        } else {
+            bytes
+        };
+        match self.staging_buffer {
+            Some(staging_buffer) if bytes.len() <= self.size => {
+                render_resource_context.map_buffer(staging_buffer, BufferMapMode::Write);
+            }
+            _ => {
+                if let Some(buffer) = self.buffer.take() {
+                    render_resource_context.remove_buffer(buffer);
+                }
+                if let Some(staging_buffer) = self.staging_buffer.take() {
+                    render_resource_context.remove_buffer(staging_buffer);
+                }
+                self.size = bytes.len().next_power_of_two();
+                let buffer = render_resource_context.create_buffer(BufferInfo {
+                    size: self.size,
+                    buffer_usage: BufferUsage::STORAGE | BufferUsage::COPY_DST,
+                    ..Default::default()
+                });
+                render_resource_bindings.set(
+                    name,
+                    RenderResourceBinding::Buffer {
+                        buffer,
+                        range: 0..self.size as u64,
+                        dynamic_index: None,
+                    },
+                );
+                self.buffer = Some(buffer);
+                self.staging_buffer = Some(render_resource_context.create_buffer(BufferInfo {
+                    size: self.size,
+                    buffer_usage: BufferUsage::COPY_SRC | BufferUsage::MAP_WRITE,
+                    mapped_at_creation: true,
+                }));
+            }
+        }
+
+        let staging_buffer = self.staging_buffer.unwrap();
+        render_resource_context.write_mapped_buffer(
+            staging_buffer,
+            0..bytes.len() as u64,
+            &mut |data, _renderer| data.copy_from_slice(bytes),
+        );
+        render_resource_context.unmap_buffer(staging_buffer);
+        command_queue.copy_buffer_to_buffer(
+            staging_buffer,
+            0,
+            self.buffer.unwrap(),
+            0,
+            bytes.len() as u64,
+        );
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
 pub fn lights_node_system(
     mut state: Local<LightsNodeSystemState>,
     render_resource_context: Res<Box<dyn RenderResourceContext>>,
     ambient_light_resource: Res<AmbientLight>,
+    clusters: Res<Clusters>,
+    shadow_settings: Res<ShadowSettings>,
+    shadow_views: Res<ShadowViews>,
+    environment_map: Option<Res<EnvironmentMap>>,
+    environment_map_textures: Res<EnvironmentMapTextures>,
+    active_cameras: Res<ActiveCameras>,
+    environment_map_query: Query<&EnvironmentMap>,
     // TODO: this write on RenderResourceBindings will prevent this system from running in parallel
     // with other systems that do the same
     mut render_resource_bindings: ResMut<RenderResourceBindings>,
     query: Query<(&Light, &GlobalTransform)>,
+    spot_query: Query<(&SpotLight, &GlobalTransform)>,
+    directional_query: Query<(Entity, &DirectionalLight, &GlobalTransform)>,
 ) {
-    let state = &mut state;
+    let state = &mut *state;
     let render_resource_context = &**render_resource_context;
+    let environment_map = active_environment_map(
+        environment_map.as_deref(),
+        &active_cameras,
+        &environment_map_query,
+    );
+    update_shadow_atlas(
+        state,
+        render_resource_context,
+        &shadow_settings,
+        &mut render_resource_bindings,
+    );
+    bind_environment_map(
+        render_resource_context,
+        &environment_map_textures,
+        &mut render_resource_bindings,
+    );
+
+    // point and spot lights, in the order the clusters refer to them
+    let mut lights = Vec::with_capacity(clusters.lights.len());
+    for entity in clusters.lights.iter() {
+        let light = if let Ok((light, global_transform)) = query.get(*entity) {
+            LightRaw::from(light, global_transform, shadow_views.first_view(*entity))
+        } else if let Ok((light, global_transform)) = spot_query.get(*entity) {
+            LightRaw::from_spot(light, global_transform)
+        } else {
+            // keep the indices of the other lights valid
+            LightRaw::from(&Light::default(), &GlobalTransform::default(), None)
+        };
+        lights.push(light);
+    }
+    state.point_lights.write(
+        storage::POINT_LIGHTS,
+        lights.as_bytes(),
+        render_resource_context,
+        &mut render_resource_bindings,
+        &mut state.command_queue,
+    );
+    state.cluster_light_indices.write(
+        storage::CLUSTER_LIGHT_INDICES,
+        clusters.light_indices.as_bytes(),
+        render_resource_context,
+        &mut render_resource_bindings,
+        &mut state.command_queue,
+    );
+    state.cluster_offsets_and_counts.write(
+        storage::CLUSTER_OFFSETS_AND_COUNTS,
+        clusters.offsets_and_counts.as_bytes(),
+        render_resource_context,
+        &mut render_resource_bindings,
+        &mut state.command_queue,
+    );
 
     // premultiply ambient brightness
     let ambient_light: [f32; 4] =
         (ambient_light_resource.color * ambient_light_resource.brightness).into();
     let ambient_light_size = std::mem::size_of::<[f32; 4]>();
-    let light_count = query.iter().count();
-    let size = std::mem::size_of::<LightRaw>();
+    let directional_light_count = directional_query.iter().count().min(MAX_DIRECTIONAL_LIGHTS);
+    let directional_size = std::mem::size_of::<DirectionalLightRaw>();
+    let shadow_view_size = std::mem::size_of::<ShadowViewRaw>();
     let light_count_size = ambient_light_size + std::mem::size_of::<LightCount>();
-    let light_array_size = size * light_count;
-    let light_array_max_size = size * state.max_lights;
-    let current_light_uniform_size = light_count_size + light_array_size;
-    let max_light_uniform_size = light_count_size + light_array_max_size;
+    let cluster_config_end = light_count_size + std::mem::size_of::<ClusterConfig>();
+    let environment_map_end = cluster_config_end + std::mem::size_of::<[f32; 4]>();
+    let directional_light_array_end =
+        environment_map_end + directional_size * MAX_DIRECTIONAL_LIGHTS;
+    let max_light_uniform_size = directional_light_array_end + shadow_view_size * MAX_SHADOW_VIEWS;
 
     if let Some(staging_buffer) = state.staging_buffer {
-        if light_count == 0 {
-            return;
-        }
-
         render_resource_context.map_buffer(staging_buffer, BufferMapMode::Write);
     } else {
         let buffer = render_resource_context.create_buffer(BufferInfo {
@@ -132,21 +302,61 @@ pub fn lights_node_system(
     let staging_buffer = state.staging_buffer.unwrap();
     render_resource_context.write_mapped_buffer(
         staging_buffer,
-        0..current_light_uniform_size as u64,
+        0..max_light_uniform_size as u64,
         &mut |data, _renderer| {
             // ambient light
             data[0..ambient_light_size].copy_from_slice(ambient_light.as_bytes());
 
             // light count
-            data[ambient_light_size..light_count_size]
-                .copy_from_slice([light_count as u32, 0, 0, 0].as_bytes());
+            data[ambient_light_size..light_count_size].copy_from_slice(
+                [lights.len() as u32, directional_light_count as u32, 0, 0].as_bytes(),
+            );
 
-            // light array
-            for ((light, global_transform), slot) in query
+            // clusters
+            let cluster_config = ClusterConfig {
+                dimensions: clusters
+                    .dimensions
+                    .extend(clusters.view_proj.is_some() as u32)
+                    .into(),
+                z_params: clusters.z_params.extend(0.0).extend(0.0).into(),
+                view_proj: clusters.view_proj.unwrap_or_default().to_cols_array_2d(),
+            };
+            data[light_count_size..cluster_config_end].copy_from_slice(cluster_config.as_bytes());
+
+            // environment map intensity and specular levels
+            let intensity =
+                environment_map.map_or(0.0, |environment_map| environment_map.intensity);
+            let specular_levels = environment_map_textures.specular_levels as f32;
+            data[cluster_config_end..environment_map_end]
+                .copy_from_slice([intensity, specular_levels, 0.0, 0.0].as_bytes());
+
+            // directional light array
+            for ((entity, light, global_transform), slot) in directional_query.iter().zip(
+                data[environment_map_end..directional_light_array_end]
+                    .chunks_exact_mut(directional_size),
+            ) {
+                let first_shadow_view = shadow_views.first_view(entity);
+                slot.copy_from_slice(
+                    DirectionalLightRaw::from(light, global_transform, first_shadow_view)
+                        .as_bytes(),
+                );
+            }
+
+            // shadow views
+            for (index, (shadow_view, slot)) in shadow_views
+                .views
                 .iter()
-                .zip(data[light_count_size..current_light_uniform_size].chunks_exact_mut(size))
+                .zip(
+                    data[directional_light_array_end..max_light_uniform_size]
+                        .chunks_exact_mut(shadow_view_size),
+                )
+                .enumerate()
             {
-                slot.copy_from_slice(LightRaw::from(&light, &global_transform).as_bytes());
+                let shadow_view = ShadowViewRaw {
+                    view_proj: shadow_view.view_proj.to_cols_array_2d(),
+                    atlas_rect: ShadowViews::atlas_rect(index).into(),
+                };
+                slot.copy_from_slice(shadow_view.as_bytes());
             }
         },
     );
@@ -160,3 +370,87 @@ pub fn lights_node_system(
         max_light_uniform_size as u64,
     );
 }
+
+/// Creates the shadow atlas, or recreates it when the tile size changes, and binds it
+fn update_shadow_atlas(
+    state: &mut LightsNodeSystemState,
+    render_resource_context: &dyn RenderResourceContext,
+    shadow_settings: &ShadowSettings,
+    render_resource_bindings: &mut RenderResourceBindings,
+) {
+    if let Some((_, tile_size)) = state.shadow_atlas {
+        if tile_size == shadow_settings.tile_size {
+            return;
+        }
+    }
+    if let Some((shadow_atlas, _)) = state.shadow_atlas.take() {
+        render_resource_context.remove_texture(shadow_atlas);
+    }
+
+    let size = shadow_settings.tile_size * SHADOW_ATLAS_TILES_PER_ROW;
+    let shadow_atlas = render_resource_context.create_texture(TextureDescriptor {
+        size: Extent3d::new(size, size, 1),
+        mip_level_count: 1,
+        sample_count: 1,
+        dimension: TextureDimension::D2,
+        format: TextureFormat::Depth32Float,
+        usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+    });
+    state.shadow_atlas = Some((shadow_atlas, shadow_settings.tile_size));
+    render_resource_bindings.set(
+        texture::SHADOW_ATLAS,
+        RenderResourceBinding::Texture(shadow_atlas),
+    );
+
+    // depth comparison and filtering are done in the shader
+    let sampler = *state.shadow_atlas_sampler.get_or_insert_with(|| {
+        render_resource_context.create_sampler(&SamplerDescriptor {
+            mag_filter: FilterMode::Nearest,
+            min_filter: FilterMode::Nearest,
+            mipmap_filter: FilterMode::Nearest,
+            ..Default::default()
+        })
+    });
+    render_resource_bindings.set(
+        texture::SHADOW_ATLAS_SAMPLER,
+        RenderResourceBinding::Sampler(sampler),
+    );
+}
+
+/// Binds the environment map textures once their render resources exist. They are created again
+/// when the environment map is prefiltered.
+fn bind_environment_map(
+    render_resource_context: &dyn RenderResourceContext,
+    environment_map_textures: &EnvironmentMapTextures,
+    render_resource_bindings: &mut RenderResourceBindings,
+) {
+    let textures: [(&Handle<Texture>, &str, &str); 3] = [
+        (
+            &environment_map_textures.diffuse,
+            texture::ENVIRONMENT_MAP_DIFFUSE,
+            texture::ENVIRONMENT_MAP_DIFFUSE_SAMPLER,
+        ),
+        (
+            &environment_map_textures.specular,
+            texture::ENVIRONMENT_MAP_SPECULAR,
+            texture::ENVIRONMENT_MAP_SPECULAR_SAMPLER,
+        ),
+        (
+            &environment_map_textures.brdf_lut,
+            texture::BRDF_LUT,
+            texture::BRDF_LUT_SAMPLER,
+        ),
+    ];
+    for (handle, texture_name, sampler_name) in textures.iter() {
+        if let Some(RenderResourceId::Texture(texture)) =
+            render_resource_context.get_asset_resource(*handle, TEXTURE_ASSET_INDEX)
+        {
+            render_resource_bindings.set(texture_name, RenderResourceBinding::Texture(texture));
+        }
+        if let Some(RenderResourceId::Sampler(sampler)) =
+            render_resource_context.get_asset_resource(*handle, SAMPLER_ASSET_INDEX)
+        {
+            render_resource_bindings.set(sampler_name, RenderResourceBinding::Sampler(sampler));
+        }
+    }
+}
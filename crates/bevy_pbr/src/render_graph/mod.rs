@@ -1,15 +1,21 @@
 mod lights_node;
 mod pbr_pipeline;
+mod shadow_pass_node;
+mod shadow_pipeline;
 
 use bevy_ecs::world::World;
 pub use lights_node::*;
 pub use pbr_pipeline::*;
+pub use shadow_pass_node::*;
+pub use shadow_pipeline::*;
 
 /// the names of pbr graph nodes
 pub mod node {
     pub const TRANSFORM: &str = "transform";
+    pub const SKINNED_MESH_JOINTS: &str = "skinned_mesh_joints";
     pub const STANDARD_MATERIAL: &str = "standard_material";
     pub const LIGHTS: &str = "lights";
+    pub const SHADOW_PASS: &str = "shadow_pass";
 }
 
 /// the names of pbr uniforms
@@ -17,12 +23,32 @@ pub mod uniform {
     pub const LIGHTS: &str = "Lights";
 }
 
+/// the names of pbr storage buffers
+pub mod storage {
+    pub const POINT_LIGHTS: &str = "PointLights";
+    pub const CLUSTER_LIGHT_INDICES: &str = "ClusterLightIndices";
+    pub const CLUSTER_OFFSETS_AND_COUNTS: &str = "ClusterOffsetsAndCounts";
+}
+
+/// the names of pbr textures
+pub mod texture {
+    pub const SHADOW_ATLAS: &str = "ShadowAtlas";
+    pub const SHADOW_ATLAS_SAMPLER: &str = "ShadowAtlas_sampler";
+    pub const ENVIRONMENT_MAP_DIFFUSE: &str = "EnvironmentMapDiffuse";
+    pub const ENVIRONMENT_MAP_DIFFUSE_SAMPLER: &str = "EnvironmentMapDiffuse_sampler";
+    pub const ENVIRONMENT_MAP_SPECULAR: &str = "EnvironmentMapSpecular";
+    pub const ENVIRONMENT_MAP_SPECULAR_SAMPLER: &str = "EnvironmentMapSpecular_sampler";
+    pub const BRDF_LUT: &str = "BrdfLut";
+    pub const BRDF_LUT_SAMPLER: &str = "BrdfLut_sampler";
+}
+
 use crate::prelude::StandardMaterial;
 use bevy_asset::Assets;
 use bevy_render::{
     pipeline::PipelineDescriptor,
     render_graph::{base, AssetRenderResourcesNode, RenderGraph, RenderResourcesNode},
     shader::Shader,
+    skinning::SkinnedMeshJoints,
 };
 use bevy_transform::prelude::GlobalTransform;
 
@@ -33,11 +59,16 @@ pub(crate) fn add_pbr_graph(world: &mut World) {
             node::TRANSFORM,
             RenderResourcesNode::<GlobalTransform>::new(true),
         );
+        graph.add_system_node(
+            node::SKINNED_MESH_JOINTS,
+            RenderResourcesNode::<SkinnedMeshJoints>::new(false),
+        );
         graph.add_system_node(
             node::STANDARD_MATERIAL,
             AssetRenderResourcesNode::<StandardMaterial>::new(true),
         );
-        graph.add_system_node(node::LIGHTS, LightsNode::new(10));
+        graph.add_system_node(node::LIGHTS, LightsNode::default());
+        graph.add_node(node::SHADOW_PASS, ShadowPassNode::default());
 
         // TODO: replace these with "autowire" groups
         graph
@@ -46,13 +77,31 @@ pub(crate) fn add_pbr_graph(world: &mut World) {
         graph
             .add_node_edge(node::TRANSFORM, base::node::MAIN_PASS)
             .unwrap();
+        graph
+            .add_node_edge(node::SKINNED_MESH_JOINTS, base::node::MAIN_PASS)
+            .unwrap();
         graph
             .add_node_edge(node::LIGHTS, base::node::MAIN_PASS)
             .unwrap();
+        graph
+            .add_node_edge(node::TRANSFORM, node::SHADOW_PASS)
+            .unwrap();
+        graph
+            .add_node_edge(node::SKINNED_MESH_JOINTS, node::SHADOW_PASS)
+            .unwrap();
+        graph
+            .add_node_edge(base::node::SHARED_BUFFERS, node::SHADOW_PASS)
+            .unwrap();
+        graph
+            .add_node_edge(node::SHADOW_PASS, base::node::MAIN_PASS)
+            .unwrap();
     }
-    let pipeline = build_pbr_pipeline(&mut world.get_resource_mut::<Assets<Shader>>().unwrap());
+    let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+    let pipeline = build_pbr_pipeline(&mut shaders);
+    let shadow_pipeline = build_shadow_pipeline(&mut shaders);
     let mut pipelines = world
         .get_resource_mut::<Assets<PipelineDescriptor>>()
         .unwrap();
     pipelines.set_untracked(PBR_PIPELINE_HANDLE, pipeline);
+    pipelines.set_untracked(SHADOW_PIPELINE_HANDLE, shadow_pipeline);
 }
@@ -34,12 +34,33 @@
 
 #version 450
 
-const int MAX_LIGHTS = 10;
+const int MAX_DIRECTIONAL_LIGHTS = 4;
+const int MAX_SHADOW_VIEWS = 16;
 
+// a point or spot light
 struct Light {
-    mat4 proj;
     vec4 pos;
     vec4 color;
+    // the direction a spot light points in
+    vec4 spot_direction;
+    // scale and offset of the angular attenuation of a spot light
+    vec4 spot_attenuation;
+    // depth bias, normal bias, first shadow view or -1
+    vec4 shadow;
+};
+
+struct DirectionalLight {
+    vec4 color;
+    // the direction towards the light
+    vec4 direction;
+    // depth bias, normal bias, first shadow view or -1, cascade count
+    vec4 shadow;
+};
+
+struct ShadowView {
+    mat4 view_proj;
+    // x, y, width and height in the shadow atlas
+    vec4 atlas_rect;
 };
 
 layout(location = 0) in vec3 v_WorldPosition;
@@ -61,9 +82,44 @@ layout(std140, set = 0, binding = 1) uniform CameraPosition {
 
 layout(std140, set = 1, binding = 0) uniform Lights {
     vec4 AmbientColor;
+    // x: point and spot lights, y: directional lights
     uvec4 NumLights;
-    Light SceneLights[MAX_LIGHTS];
+    // the number of clusters along x, y and z, and w is 1 if the lights are clustered
+    uvec4 ClusterDimensions;
+    // scale and bias that turn the log of a view depth into a z slice
+    vec4 ClusterZParams;
+    // the view projection of the camera whose view the clusters divide, which may not be the
+    // camera of this pass
+    mat4 ClusterViewProj;
+    // x: intensity, y: the number of specular levels
+    vec4 EnvironmentMapParams;
+    DirectionalLight SceneDirectionalLights[MAX_DIRECTIONAL_LIGHTS];
+    ShadowView ShadowViews[MAX_SHADOW_VIEWS];
+};
+
+layout(set = 1, binding = 1) uniform texture2D ShadowAtlas;
+layout(set = 1, binding = 2) uniform sampler ShadowAtlas_sampler;
+
+layout(std430, set = 1, binding = 3) readonly buffer PointLights {
+    Light SceneLights[];
+};
+// the lights of each cluster are a range of this list
+layout(std430, set = 1, binding = 4) readonly buffer ClusterLightIndices {
+    uint LightIndices[];
 };
+// the offset into LightIndices and the number of lights of each cluster
+layout(std430, set = 1, binding = 5) readonly buffer ClusterOffsetsAndCounts {
+    uvec2 OffsetsAndCounts[];
+};
+
+// the irradiance divided by π, in an equirectangular layout
+layout(set = 1, binding = 6) uniform texture2D EnvironmentMapDiffuse;
+layout(set = 1, binding = 7) uniform sampler EnvironmentMapDiffuse_sampler;
+// the prefiltered radiance for each roughness, in equirectangular levels stacked from the top
+layout(set = 1, binding = 8) uniform texture2D EnvironmentMapSpecular;
+layout(set = 1, binding = 9) uniform sampler EnvironmentMapSpecular_sampler;
+layout(set = 1, binding = 10) uniform texture2D BrdfLut;
+layout(set = 1, binding = 11) uniform sampler BrdfLut_sampler;
 
 layout(set = 3, binding = 0) uniform StandardMaterial_base_color {
     vec4 base_color;
@@ -276,6 +332,147 @@ vec3 reinhard_extended_luminance(vec3 color, float max_white_l) {
     return change_luminance(color, l_new);
 }
 
+// Finds the offset into LightIndices and the number of lights of the cluster that contains the
+// fragment. Returns false if the fragment isn't in any cluster, and has to be shaded with every
+// light.
+bool clusterOffsetAndCount(out uvec2 offsetAndCount) {
+    offsetAndCount = uvec2(0u);
+    if (ClusterDimensions.w == 0u) {
+        return false;
+    }
+    vec4 clip = ClusterViewProj * vec4(v_WorldPosition, 1.0);
+    if (clip.w <= 0.0) {
+        return false;
+    }
+    vec2 ndc = clip.xy / clip.w;
+    // w is the view depth of a perspective projection
+    float slice = log(clip.w) * ClusterZParams.x + ClusterZParams.y;
+    uvec3 dimensions = ClusterDimensions.xyz;
+    if (any(greaterThan(abs(ndc), vec2(1.0))) || slice < 0.0 || slice >= float(dimensions.z)) {
+        return false;
+    }
+    uvec2 tile = uvec2(clamp((ndc * 0.5 + 0.5) * vec2(dimensions.xy), vec2(0.0),
+                             vec2(dimensions.xy - 1u)));
+    uint cluster = tile.x + tile.y * dimensions.x + uint(slice) * dimensions.x * dimensions.y;
+    offsetAndCount = OffsetsAndCounts[cluster];
+    return true;
+}
+
+vec2 equirectUv(vec3 direction) {
+    return vec2(atan(direction.z, direction.x) * (0.5 / PI) + 0.5,
+                acos(clamp(direction.y, -1.0, 1.0)) / PI);
+}
+
+// Each level is half as large as the previous one, and has a column of padding on either side
+// that wraps around
+vec3 sampleSpecularLevel(vec2 uv, float level) {
+    vec2 atlas_size =
+        vec2(textureSize(sampler2D(EnvironmentMapSpecular, EnvironmentMapSpecular_sampler), 0));
+    float width = atlas_size.x - 2.0;
+    vec2 size = vec2(width, width * 0.5) * exp2(-level);
+    float top = width * (1.0 - exp2(-level));
+    vec2 texel = vec2(1.0 + uv.x * size.x, top + clamp(uv.y * size.y, 0.5, size.y - 0.5));
+    return textureLod(sampler2D(EnvironmentMapSpecular, EnvironmentMapSpecular_sampler),
+                      texel / atlas_size, 0.0)
+        .rgb;
+}
+
+vec3 environmentMapSpecular(vec3 R, float perceptual_roughness) {
+    vec2 uv = equirectUv(R);
+    float last_level = EnvironmentMapParams.y - 1.0;
+    float level = saturate(perceptual_roughness) * last_level;
+    float lower = floor(level);
+    float upper = min(lower + 1.0, last_level);
+    return mix(sampleSpecularLevel(uv, lower), sampleSpecularLevel(uv, upper), level - lower);
+}
+
+vec3 environmentMapLight(vec3 N, vec3 V, float NdotV, float perceptual_roughness,
+                         vec3 diffuseColor, vec3 F0) {
+    vec3 irradiance = textureLod(sampler2D(EnvironmentMapDiffuse, EnvironmentMapDiffuse_sampler),
+                                 equirectUv(N), 0.0)
+                          .rgb;
+    vec3 radiance = environmentMapSpecular(reflect(-V, N), perceptual_roughness);
+    // split sum approximation
+    // see https://cdn2.unrealengine.com/Resources/files/2013SiggraphPresentationsNotes-26915738.pdf
+    vec2 f_ab = texture(sampler2D(BrdfLut, BrdfLut_sampler), vec2(NdotV, perceptual_roughness)).rg;
+    return diffuseColor * irradiance + radiance * (F0 * f_ab.x + f_ab.y);
+}
+
+#    ifndef NOT_SHADOW_RECEIVER
+// The uv and depth of a world position in a shadow view, which are outside of [0, 1] if the
+// view doesn't contain it
+vec3 shadowViewPosition(int view, vec3 world_position) {
+    vec4 clip = ShadowViews[view].view_proj * vec4(world_position, 1.0);
+    vec3 ndc = clip.xyz / clip.w;
+    return vec3(ndc.xy * vec2(0.5, -0.5) + 0.5, ndc.z);
+}
+
+bool inShadowView(vec3 position) {
+    return all(greaterThanEqual(position, vec3(0.0))) && all(lessThanEqual(position, vec3(1.0)));
+}
+
+// 3x3 percentage closer filtering of the view's tile in the shadow atlas. Returns 1.0 when lit.
+float sampleShadow(int view, vec3 position, float depth_bias) {
+    vec4 rect = ShadowViews[view].atlas_rect;
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(ShadowAtlas, ShadowAtlas_sampler), 0));
+    vec2 uv = rect.xy + position.xy * rect.zw;
+    // keep the filter from reading the neighbouring tiles
+    vec2 uv_min = rect.xy + texel * 0.5;
+    vec2 uv_max = rect.xy + rect.zw - texel * 0.5;
+    float depth = position.z - depth_bias;
+    float lit = 0.0;
+    for (int x = -1; x <= 1; ++x) {
+        for (int y = -1; y <= 1; ++y) {
+            vec2 sample_uv = clamp(uv + vec2(x, y) * texel, uv_min, uv_max);
+            float occluder = texture(sampler2D(ShadowAtlas, ShadowAtlas_sampler), sample_uv).r;
+            lit += depth <= occluder ? 1.0 : 0.0;
+        }
+    }
+    return lit / 9.0;
+}
+
+float pointLightShadow(Light light, vec3 N) {
+    int first_view = int(light.shadow.z);
+    if (first_view < 0) {
+        return 1.0;
+    }
+    vec3 world_position = v_WorldPosition + N * light.shadow.y;
+    // the cube face whose axis is closest to the direction from the light
+    vec3 from_light = v_WorldPosition - light.pos.xyz;
+    vec3 a = abs(from_light);
+    int face;
+    if (a.x >= a.y && a.x >= a.z) {
+        face = from_light.x > 0.0 ? 0 : 1;
+    } else if (a.y >= a.z) {
+        face = from_light.y > 0.0 ? 2 : 3;
+    } else {
+        face = from_light.z > 0.0 ? 4 : 5;
+    }
+    vec3 position = shadowViewPosition(first_view + face, world_position);
+    if (!inShadowView(position)) {
+        return 1.0;
+    }
+    return sampleShadow(first_view + face, position, light.shadow.x);
+}
+
+float directionalLightShadow(DirectionalLight light, vec3 N) {
+    int first_view = int(light.shadow.z);
+    if (first_view < 0) {
+        return 1.0;
+    }
+    vec3 world_position = v_WorldPosition + N * light.shadow.y;
+    // cascades are ordered from the camera outwards, so the first one that contains the position
+    // has the most detail
+    for (int cascade = 0; cascade < int(light.shadow.w); ++cascade) {
+        vec3 position = shadowViewPosition(first_view + cascade, world_position);
+        if (inShadowView(position)) {
+            return sampleShadow(first_view + cascade, position, light.shadow.x);
+        }
+    }
+    return 1.0;
+}
+#    endif
+
 #endif
 
 void main() {
@@ -342,14 +539,21 @@ void main() {
 
     // accumulate color
     vec3 light_accum = vec3(0.0);
-    for (int i = 0; i < int(NumLights.x) && i < MAX_LIGHTS; ++i) {
-        Light light = SceneLights[i];
+    uvec2 cluster;
+    bool clustered = clusterOffsetAndCount(cluster);
+    uint lightCount = clustered ? cluster.y : NumLights.x;
+    for (uint i = 0u; i < lightCount; ++i) {
+        Light light = SceneLights[clustered ? LightIndices[cluster.x + i] : i];
 
         vec3 lightDir = light.pos.xyz - v_WorldPosition.xyz;
         vec3 L = normalize(lightDir);
 
         float rangeAttenuation =
             getDistanceAttenuation(lightDir, light.pos.w);
+        // point lights have a scale of 0 and an offset of 1
+        float spotAttenuation = saturate(dot(-L, light.spot_direction.xyz) *
+                                         light.spot_attenuation.x + light.spot_attenuation.y);
+        rangeAttenuation *= spotAttenuation * spotAttenuation;
 
         vec3 H = normalize(L + V);
         float NoL = saturate(dot(N, L));
@@ -370,8 +574,32 @@ void main() {
         // See https://google.github.io/filament/Filament.html#mjx-eqn-pointLightLuminanceEquation
         // TODO compensate for energy loss https://google.github.io/filament/Filament.html#materialsystem/improvingthebrdfs/energylossinspecularreflectance
         // light.color.rgb is premultiplied with light.intensity on the CPU
+        float shadow = 1.0;
+#    ifndef NOT_SHADOW_RECEIVER
+        shadow = pointLightShadow(light, N);
+#    endif
         light_accum +=
-            ((diffuse + specular) * light.color.rgb) * (rangeAttenuation * NoL);
+            ((diffuse + specular) * light.color.rgb) * (rangeAttenuation * NoL * shadow);
+    }
+
+    for (int i = 0; i < int(NumLights.y) && i < MAX_DIRECTIONAL_LIGHTS; ++i) {
+        DirectionalLight light = SceneDirectionalLights[i];
+        vec3 L = light.direction.xyz;
+
+        vec3 H = normalize(L + V);
+        float NoL = saturate(dot(N, L));
+        float NoH = saturate(dot(N, H));
+        float LoH = saturate(dot(L, H));
+
+        vec3 specular = specular(F0, roughness, H, NdotV, NoL, NoH, LoH);
+        vec3 diffuse = diffuseColor * Fd_Burley(roughness, NdotV, NoL, LoH);
+
+        // light.color.rgb is premultiplied with light.illuminance on the CPU
+        float shadow = 1.0;
+#    ifndef NOT_SHADOW_RECEIVER
+        shadow = directionalLightShadow(light, N);
+#    endif
+        light_accum += ((diffuse + specular) * light.color.rgb) * (NoL * shadow);
     }
 
     vec3 diffuse_ambient = EnvBRDFApprox(diffuseColor, 1.0, NdotV);
@@ -379,13 +607,17 @@ void main() {
 
     output_color.rgb = light_accum;
     output_color.rgb += (diffuse_ambient + specular_ambient) * AmbientColor.xyz * occlusion;
+    output_color.rgb += environmentMapLight(N, V, NdotV, perceptual_roughness, diffuseColor, F0) *
+                        (EnvironmentMapParams.x * occlusion);
     output_color.rgb += emissive.rgb * output_color.a;
 
-    // tone_mapping
+#ifdef TONEMAP_IN_SHADER
+    // tone_mapping, unless an HDR target is tonemapped after the pass
     output_color.rgb = reinhard_luminance(output_color.rgb);
     // Gamma correction.
     // Not needed with sRGB buffer
     // output_color.rgb = pow(output_color.rgb, vec3(1.0 / 2.2));
+#endif
 #endif
 
     o_Target = output_color;
@@ -0,0 +1,289 @@
+use crate::{
+    render_graph::{texture, SHADOW_PIPELINE_HANDLE},
+    NotShadowCaster, ShadowSettings, ShadowViews, StandardMaterial, SHADOW_ATLAS_TILES_PER_ROW,
+};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{
+    query::{With, Without},
+    system::{Local, Query, Res, ResMut},
+    world::World,
+};
+use bevy_render::{
+    draw::{Draw, DrawContext, RenderCommand, Visible},
+    instancing::{MeshInstances, INSTANCING_SHADER_DEF},
+    mesh::{Indices, Mesh},
+    pass::{
+        LoadOp, Operations, PassDescriptor, RenderPassDepthStencilAttachmentDescriptor,
+        TextureAttachment,
+    },
+    pipeline::{PipelineDescriptor, PipelineSpecialization, RenderPipelines, ShaderSpecialization},
+    render_graph::{Node, ResourceSlots},
+    renderer::{RenderContext, RenderResourceBindings},
+    skinning::{has_joint_attributes, SkinnedMeshJoints, SKINNED_SHADER_DEF},
+};
+use bevy_utils::HashSet;
+
+/// The render commands that draw the shadow casters into each shadow map, written by
+/// [draw_shadows_system] and consumed by [ShadowPassNode]
+#[derive(Debug, Default)]
+pub struct ShadowCommands {
+    pub views: Vec<Vec<RenderCommand>>,
+}
+
+/// The name of the [ShadowView](crate::ShadowView) uniform in the shadow pipeline
+pub const SHADOW_VIEW: &str = "ShadowView";
+
+/// Records the draws of every mesh with a [StandardMaterial] into each [ShadowViews] view
+#[allow(clippy::type_complexity)]
+pub fn draw_shadows_system(
+    mut draw_context: DrawContext,
+    shadow_views: Res<ShadowViews>,
+    meshes: Res<Assets<Mesh>>,
+    mut shadow_commands: ResMut<ShadowCommands>,
+    mut view_bindings: Local<Vec<RenderResourceBindings>>,
+    mut query: Query<
+        (
+            &Handle<Mesh>,
+            &mut RenderPipelines,
+            &Visible,
+            Option<&MeshInstances>,
+            Option<&SkinnedMeshJoints>,
+        ),
+        (With<Handle<StandardMaterial>>, Without<NotShadowCaster>),
+    >,
+) {
+    shadow_commands.views.clear();
+    if shadow_views.views.is_empty() {
+        return;
+    }
+
+    view_bindings.resize_with(shadow_views.views.len(), Default::default);
+    for (shadow_view, bindings) in shadow_views.views.iter().zip(view_bindings.iter_mut()) {
+        let view_proj = draw_context
+            .get_uniform_buffer(&shadow_view.view_proj)
+            .unwrap();
+        bindings.set(SHADOW_VIEW, view_proj);
+    }
+
+    let shadow_pipeline = SHADOW_PIPELINE_HANDLE.typed::<PipelineDescriptor>();
+    let mut draws = vec![Draw::default(); shadow_views.views.len()];
+    for (mesh_handle, mut render_pipelines, visible, mesh_instances, skinned_mesh_joints) in
+        query.iter_mut()
+    {
+        if !visible.is_visible {
+            continue;
+        }
+        let instance_count = match mesh_instances {
+            None | Some(MeshInstances::Single) => None,
+            Some(MeshInstances::Instanced(instances)) => {
+                if render_pipelines.bindings.instance_buffer.is_none() {
+                    continue;
+                }
+                Some(instances.len() as u32)
+            }
+            Some(MeshInstances::DrawnBy(_)) => continue,
+        };
+        let mesh = if let Some(mesh) = meshes.get(mesh_handle) {
+            mesh
+        } else {
+            continue;
+        };
+
+        let mut shader_specialization = ShaderSpecialization::default();
+        if instance_count.is_some() {
+            shader_specialization
+                .shader_defs
+                .insert(INSTANCING_SHADER_DEF.to_string());
+        }
+        if skinned_mesh_joints.is_some() && has_joint_attributes(mesh) {
+            shader_specialization
+                .shader_defs
+                .insert(SKINNED_SHADER_DEF.to_string());
+        }
+        let specialization = PipelineSpecialization {
+            shader_specialization,
+            primitive_topology: mesh.primitive_topology(),
+            vertex_buffer_layout: mesh.get_vertex_buffer_layout(),
+            dynamic_bindings: render_pipelines
+                .bindings
+                .iter_dynamic_bindings()
+                .map(|name| name.to_string())
+                .collect::<HashSet<String>>(),
+            ..Default::default()
+        };
+        let index_range = match mesh.indices() {
+            Some(Indices::U32(indices)) => Some(0..indices.len() as u32),
+            Some(Indices::U16(indices)) => Some(0..indices.len() as u32),
+            None => None,
+        };
+
+        for (draw, bindings) in draws.iter_mut().zip(view_bindings.iter_mut()) {
+            draw_context
+                .set_pipeline(draw, &shadow_pipeline, &specialization)
+                .unwrap();
+            draw_context
+                .set_bind_groups_from_bindings(
+                    draw,
+                    &mut [&mut render_pipelines.bindings, bindings],
+                )
+                .unwrap();
+            draw_context
+                .set_vertex_buffers_from_bindings(draw, &[&render_pipelines.bindings])
+                .unwrap();
+
+            let instances = 0..instance_count.unwrap_or(1);
+            if let Some(indices) = index_range.clone() {
+                draw.draw_indexed(indices, 0, instances);
+            } else {
+                draw.draw(0..mesh.count_vertices() as u32, instances)
+            }
+        }
+    }
+
+    shadow_commands.views = draws.into_iter().map(|draw| draw.render_commands).collect();
+}
+
+/// A Render Graph [Node] that renders the shadow maps into the tiles of the shadow atlas
+#[derive(Debug)]
+pub struct ShadowPassNode {
+    descriptor: PassDescriptor,
+    views: Vec<Vec<RenderCommand>>,
+}
+
+impl Default for ShadowPassNode {
+    fn default() -> Self {
+        ShadowPassNode {
+            descriptor: PassDescriptor {
+                color_attachments: Vec::new(),
+                depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+                    attachment: TextureAttachment::Name(texture::SHADOW_ATLAS.to_string()),
+                    depth_ops: Some(Operations {
+                        load: LoadOp::Clear(1.0),
+                        store: true,
+                    }),
+                    stencil_ops: None,
+                }),
+                sample_count: 1,
+            },
+            views: Vec::new(),
+        }
+    }
+}
+
+impl Node for ShadowPassNode {
+    fn prepare(&mut self, world: &mut World) {
+        let mut shadow_commands = world.get_resource_mut::<ShadowCommands>().unwrap();
+        self.views = std::mem::take(&mut shadow_commands.views);
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        _input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
+        // the atlas is created with the lights uniform
+        if render_resource_bindings
+            .get(texture::SHADOW_ATLAS)
+            .is_none()
+        {
+            return;
+        }
+
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+        let tile_size = world.get_resource::<ShadowSettings>().unwrap().tile_size as f32;
+        let views = &mut self.views;
+        render_context.begin_pass(
+            &self.descriptor,
+            render_resource_bindings,
+            &mut |render_pass| {
+                let mut current_pipeline = None;
+                // draws are skipped until the pipeline's bind groups and buffers are all set, like
+                // in a `PassNode`
+                let mut bind_groups_set = Vec::new();
+                let mut vertex_buffer_set = false;
+                let mut index_buffer_set = false;
+                for (index, commands) in views.drain(..).enumerate() {
+                    let column = index as u32 % SHADOW_ATLAS_TILES_PER_ROW;
+                    let row = index as u32 / SHADOW_ATLAS_TILES_PER_ROW;
+                    render_pass.set_viewport(
+                        column as f32 * tile_size,
+                        row as f32 * tile_size,
+                        tile_size,
+                        tile_size,
+                        0.0,
+                        1.0,
+                    );
+                    for render_command in commands {
+                        match render_command {
+                            RenderCommand::SetPipeline { pipeline } => {
+                                render_pass.set_pipeline(&pipeline);
+                                let layout =
+                                    pipelines.get(&pipeline).unwrap().get_layout().unwrap();
+                                bind_groups_set = vec![false; layout.bind_groups.len()];
+                                vertex_buffer_set = false;
+                                index_buffer_set = false;
+                                current_pipeline = Some(pipeline);
+                            }
+                            RenderCommand::SetBindGroup {
+                                index,
+                                bind_group,
+                                dynamic_uniform_indices,
+                            } => {
+                                let pipeline =
+                                    pipelines.get(current_pipeline.as_ref().unwrap()).unwrap();
+                                let layout = pipeline.get_layout().unwrap();
+                                let bind_group_descriptor = layout.get_bind_group(index).unwrap();
+                                render_pass.set_bind_group(
+                                    index,
+                                    bind_group_descriptor.id,
+                                    bind_group,
+                                    dynamic_uniform_indices.as_deref(),
+                                );
+                                bind_groups_set[index as usize] = true;
+                            }
+                            RenderCommand::SetVertexBuffer {
+                                buffer,
+                                offset,
+                                slot,
+                            } => {
+                                render_pass.set_vertex_buffer(slot, buffer, offset);
+                                vertex_buffer_set = true;
+                            }
+                            RenderCommand::SetIndexBuffer {
+                                buffer,
+                                offset,
+                                index_format,
+                            } => {
+                                render_pass.set_index_buffer(buffer, offset, index_format);
+                                index_buffer_set = true;
+                            }
+                            RenderCommand::DrawIndexed {
+                                base_vertex,
+                                indices,
+                                instances,
+                            } => {
+                                if vertex_buffer_set
+                                    && index_buffer_set
+                                    && bind_groups_set.iter().all(|set| *set)
+                                {
+                                    render_pass.draw_indexed(indices, base_vertex, instances);
+                                }
+                            }
+                            RenderCommand::Draw {
+                                vertices,
+                                instances,
+                            } => {
+                                if vertex_buffer_set && bind_groups_set.iter().all(|set| *set) {
+                                    render_pass.draw(vertices, instances);
+                                }
+                            }
+                        }
+                    }
+                }
+            },
+        );
+    }
+}
@@ -0,0 +1,44 @@
+#version 450
+
+layout(location = 0) in vec3 Vertex_Position;
+
+#ifdef INSTANCING
+layout(location = 1) in vec4 I_Model_0;
+layout(location = 2) in vec4 I_Model_1;
+layout(location = 3) in vec4 I_Model_2;
+layout(location = 4) in vec4 I_Model_3;
+#endif
+
+#ifdef SKINNED
+layout(location = 5) in uvec4 Vertex_JointIndex;
+layout(location = 6) in vec4 Vertex_JointWeight;
+#endif
+
+layout(set = 0, binding = 0) uniform ShadowView {
+    mat4 ShadowViewProj;
+};
+
+layout(set = 1, binding = 0) uniform Transform {
+    mat4 Model;
+};
+
+#ifdef SKINNED
+layout(set = 1, binding = 1) buffer JointMatrices {
+    mat4[] Joints;
+};
+#endif
+
+void main() {
+#ifdef INSTANCING
+    mat4 model = Model * mat4(I_Model_0, I_Model_1, I_Model_2, I_Model_3);
+#else
+    mat4 model = Model;
+#endif
+#ifdef SKINNED
+    model = model * (Vertex_JointWeight.x * Joints[Vertex_JointIndex.x] +
+                     Vertex_JointWeight.y * Joints[Vertex_JointIndex.y] +
+                     Vertex_JointWeight.z * Joints[Vertex_JointIndex.z] +
+                     Vertex_JointWeight.w * Joints[Vertex_JointIndex.w]);
+#endif
+    gl_Position = ShadowViewProj * model * vec4(Vertex_Position, 1.0);
+}
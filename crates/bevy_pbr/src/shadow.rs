@@ -0,0 +1,299 @@
+use crate::{DirectionalLight, Light};
+use bevy_ecs::{
+    entity::Entity,
+    query::With,
+    reflect::ReflectComponent,
+    system::{Query, Res, ResMut},
+};
+use bevy_math::{Mat4, Vec3, Vec4};
+use bevy_reflect::Reflect;
+use bevy_render::{
+    camera::{ActiveCameras, PerspectiveProjection},
+    pipeline::RenderPipelines,
+    render_graph::base,
+};
+use bevy_transform::components::GlobalTransform;
+
+/// The number of shadow maps that fit in the shadow atlas. A point light uses six and a directional
+/// light uses one per cascade. Lights that don't fit are drawn without shadows.
+pub const MAX_SHADOW_VIEWS: usize = 16;
+/// The number of shadow maps in each row of the shadow atlas
+pub const SHADOW_ATLAS_TILES_PER_ROW: u32 = 4;
+pub const MAX_CASCADES: usize = 4;
+
+/// The shader def set on entities that have [NotShadowReceiver]
+pub const NOT_SHADOW_RECEIVER_DEF: &str = "NOT_SHADOW_RECEIVER";
+
+/// Keeps an entity out of the shadow maps, so it doesn't cast shadows
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct NotShadowCaster;
+
+/// Keeps shadows from being drawn on an entity
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct NotShadowReceiver;
+
+#[derive(Debug, Clone)]
+pub struct ShadowSettings {
+    /// The width and height of each shadow map in the shadow atlas, in texels
+    pub tile_size: u32,
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        ShadowSettings { tile_size: 1024 }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ShadowView {
+    pub light: Entity,
+    pub view_proj: Mat4,
+}
+
+/// The shadow maps to render this frame. Each view is drawn to the tile of the shadow atlas at the
+/// same index.
+#[derive(Debug, Default)]
+pub struct ShadowViews {
+    pub views: Vec<ShadowView>,
+}
+
+impl ShadowViews {
+    pub fn first_view(&self, light: Entity) -> Option<usize> {
+        self.views.iter().position(|view| view.light == light)
+    }
+
+    /// The x, y, width and height of a tile, as fractions of the shadow atlas size
+    pub fn atlas_rect(index: usize) -> Vec4 {
+        let tile = 1.0 / SHADOW_ATLAS_TILES_PER_ROW as f32;
+        let column = index as u32 % SHADOW_ATLAS_TILES_PER_ROW;
+        let row = index as u32 / SHADOW_ATLAS_TILES_PER_ROW;
+        Vec4::new(column as f32 * tile, row as f32 * tile, tile, tile)
+    }
+}
+
+/// The view projections of the six cube faces around a point light, in the order +X, -X, +Y, -Y,
+/// +Z, -Z
+pub fn point_light_view_projections(position: Vec3, near: f32, far: f32) -> [Mat4; 6] {
+    let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, near, far);
+    let face = |direction: Vec3, up: Vec3| {
+        projection * Mat4::look_at_rh(position, position + direction, up)
+    };
+    [
+        face(Vec3::X, Vec3::Y),
+        face(-Vec3::X, Vec3::Y),
+        face(Vec3::Y, Vec3::Z),
+        face(-Vec3::Y, Vec3::Z),
+        face(Vec3::Z, Vec3::Y),
+        face(-Vec3::Z, Vec3::Y),
+    ]
+}
+
+/// Splits the camera's view between `near` and `max_distance` into `count` cascades, placing the
+/// splits halfway between a uniform and a logarithmic distribution.
+pub fn cascade_splits(near: f32, max_distance: f32, count: usize) -> Vec<f32> {
+    (0..=count)
+        .map(|i| {
+            let fraction = i as f32 / count as f32;
+            let logarithmic = near * (max_distance / near).powf(fraction);
+            let uniform = near + (max_distance - near) * fraction;
+            0.5 * (logarithmic + uniform)
+        })
+        .collect()
+}
+
+/// The orthographic view projections of the cascades of a directional light shining along
+/// `direction`. Each cascade covers a bounding sphere of its slice of the camera's view, snapped
+/// to shadow map texels so shadows don't shimmer as the camera moves. Casters up to `max_distance`
+/// behind a slice, towards the light, are included.
+pub fn cascade_view_projections(
+    direction: Vec3,
+    camera_transform: &GlobalTransform,
+    projection: &PerspectiveProjection,
+    cascades: usize,
+    max_distance: f32,
+    tile_size: u32,
+) -> Vec<Mat4> {
+    let direction = direction.normalize();
+    let up = if direction.dot(Vec3::Y).abs() > 0.99 {
+        Vec3::X
+    } else {
+        Vec3::Y
+    };
+    let light_view = Mat4::look_at_rh(Vec3::ZERO, direction, up);
+    let camera = camera_transform.compute_matrix();
+    let tan_y = (projection.fov * 0.5).tan();
+    let tan_x = tan_y * projection.aspect_ratio;
+    let max_distance = max_distance.min(projection.far);
+    let splits = cascade_splits(projection.near, max_distance, cascades);
+
+    splits
+        .windows(2)
+        .map(|split| {
+            let mut corners = Vec::with_capacity(8);
+            for &distance in split.iter() {
+                for &(x, y) in [(-1.0, -1.0), (1.0, -1.0), (-1.0, 1.0), (1.0, 1.0)].iter() {
+                    corners.push(camera.transform_point3(Vec3::new(
+                        x * tan_x * distance,
+                        y * tan_y * distance,
+                        -distance,
+                    )));
+                }
+            }
+            let center = corners.iter().fold(Vec3::ZERO, |sum, corner| sum + *corner) / 8.0;
+            let radius = corners
+                .iter()
+                .map(|corner| corner.distance(center))
+                .fold(0.0, f32::max);
+            let radius = (radius * 16.0).ceil() / 16.0;
+
+            let texel = 2.0 * radius / tile_size as f32;
+            let mut center = light_view.transform_point3(center);
+            center.x = (center.x / texel).floor() * texel;
+            center.y = (center.y / texel).floor() * texel;
+            Mat4::orthographic_rh(
+                center.x - radius,
+                center.x + radius,
+                center.y - radius,
+                center.y + radius,
+                -center.z - radius - max_distance,
+                -center.z + radius,
+            ) * light_view
+        })
+        .collect()
+}
+
+pub fn shadow_views_system(
+    mut shadow_views: ResMut<ShadowViews>,
+    shadow_settings: Res<ShadowSettings>,
+    active_cameras: Res<ActiveCameras>,
+    camera_query: Query<(&GlobalTransform, &PerspectiveProjection)>,
+    point_lights: Query<(Entity, &Light, &GlobalTransform)>,
+    directional_lights: Query<(Entity, &DirectionalLight, &GlobalTransform)>,
+) {
+    let shadow_views = &mut shadow_views.views;
+    shadow_views.clear();
+
+    for (entity, light, global_transform) in point_lights.iter() {
+        if !light.shadows_enabled || shadow_views.len() + 6 > MAX_SHADOW_VIEWS {
+            continue;
+        }
+        for view_proj in point_light_view_projections(
+            global_transform.translation,
+            light.depth.start,
+            light.range,
+        )
+        .iter()
+        {
+            shadow_views.push(ShadowView {
+                light: entity,
+                view_proj: *view_proj,
+            });
+        }
+    }
+
+    // cascades follow the 3d camera. without a perspective camera there is nothing to fit them to
+    let camera = active_cameras
+        .get(base::camera::CAMERA_3D)
+        .and_then(|camera| camera.entity)
+        .and_then(|entity| camera_query.get(entity).ok());
+    let (camera_transform, projection) = if let Some(camera) = camera {
+        camera
+    } else {
+        return;
+    };
+    for (entity, light, global_transform) in directional_lights.iter() {
+        let cascades = light.shadow_cascades.min(MAX_CASCADES);
+        if !light.shadows_enabled
+            || cascades == 0
+            || shadow_views.len() + cascades > MAX_SHADOW_VIEWS
+        {
+            continue;
+        }
+        for view_proj in cascade_view_projections(
+            global_transform.rotation * -Vec3::Z,
+            camera_transform,
+            projection,
+            cascades,
+            light.shadow_max_distance,
+            shadow_settings.tile_size,
+        ) {
+            shadow_views.push(ShadowView {
+                light: entity,
+                view_proj,
+            });
+        }
+    }
+}
+
+/// Sets the [NOT_SHADOW_RECEIVER_DEF] shader def on entities that have [NotShadowReceiver]
+pub fn shadow_receiver_system(mut query: Query<&mut RenderPipelines, With<NotShadowReceiver>>) {
+    for mut render_pipelines in query.iter_mut() {
+        for render_pipeline in render_pipelines.pipelines.iter_mut() {
+            render_pipeline
+                .specialization
+                .shader_specialization
+                .shader_defs
+                .insert(NOT_SHADOW_RECEIVER_DEF.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn project(view_proj: &Mat4, point: Vec3) -> Vec3 {
+        let clip = *view_proj * point.extend(1.0);
+        clip.truncate() / clip.w
+    }
+
+    fn in_view(ndc: Vec3) -> bool {
+        ndc.x.abs() <= 1.0 && ndc.y.abs() <= 1.0 && ndc.z >= 0.0 && ndc.z <= 1.0
+    }
+
+    #[test]
+    fn point_light_faces() {
+        let position = Vec3::new(1.0, 2.0, 3.0);
+        let faces = point_light_view_projections(position, 0.1, 20.0);
+        let directions = [Vec3::X, -Vec3::X, Vec3::Y, -Vec3::Y, Vec3::Z, -Vec3::Z];
+        for (face, direction) in faces.iter().zip(directions.iter()) {
+            let ndc = project(face, position + *direction * 5.0);
+            assert!(in_view(ndc));
+            assert!(ndc.x.abs() < 1e-4 && ndc.y.abs() < 1e-4);
+            assert!(!in_view(project(face, position - *direction * 5.0)));
+        }
+    }
+
+    #[test]
+    fn cascades_cover_the_view() {
+        let splits = cascade_splits(0.1, 100.0, 4);
+        assert_eq!(splits.len(), 5);
+        assert!((splits[0] - 0.1).abs() < 1e-5 && (splits[4] - 100.0).abs() < 1e-3);
+        assert!(splits.windows(2).all(|split| split[0] < split[1]));
+
+        let camera_transform =
+            GlobalTransform::from_xyz(0.0, 5.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y);
+        let projection = PerspectiveProjection::default();
+        let direction = Vec3::new(-1.0, -2.0, -0.5);
+        let cascades =
+            cascade_view_projections(direction, &camera_transform, &projection, 4, 100.0, 1024);
+        assert_eq!(cascades.len(), 4);
+
+        // every point in front of the camera, up to the shadow distance, is in some cascade
+        let camera = camera_transform.compute_matrix();
+        for &distance in [1.5, 3.0, 20.0, 90.0].iter() {
+            let point = camera.transform_point3(Vec3::new(0.2, -0.1, -1.0) * distance);
+            assert!(cascades
+                .iter()
+                .any(|cascade| in_view(project(cascade, point))));
+        }
+        // and casters between it and the light are too
+        let point = camera.transform_point3(Vec3::new(0.0, 0.0, -10.0));
+        let caster = point - direction.normalize() * 20.0;
+        assert!(cascades
+            .iter()
+            .any(|cascade| in_view(project(cascade, point)) && in_view(project(cascade, caster))));
+    }
+}
@@ -1,5 +1,7 @@
 use super::CameraProjection;
+use crate::texture::Texture;
 use bevy_app::prelude::EventReader;
+use bevy_asset::{AssetEvent, Assets, Handle};
 use bevy_ecs::{
     component::Component,
     entity::Entity,
@@ -19,11 +21,46 @@ pub struct Camera {
     pub projection_matrix: Mat4,
     pub name: Option<String>,
     #[reflect(ignore)]
-    pub window: WindowId,
+    pub target: RenderTarget,
     #[reflect(ignore)]
     pub depth_calculation: DepthCalculation,
 }
 
+/// What a [Camera] renders to
+#[derive(Debug, Clone, PartialEq)]
+pub enum RenderTarget {
+    Window(WindowId),
+    /// A texture created with [Texture::new_render_target]. It is drawn into by the pass that
+    /// `render_graph::base::add_texture_target_camera` adds for the camera, whose pipelines are
+    /// specialized for the format of the texture.
+    Texture(Handle<Texture>),
+}
+
+impl Default for RenderTarget {
+    fn default() -> Self {
+        RenderTarget::Window(WindowId::primary())
+    }
+}
+
+impl RenderTarget {
+    /// The logical size of the window, or the size of the texture
+    pub fn get_size(&self, windows: &Windows, textures: &Assets<Texture>) -> Option<Vec2> {
+        match self {
+            RenderTarget::Window(window_id) => {
+                let window = windows.get(*window_id)?;
+                Some(Vec2::new(window.width(), window.height()))
+            }
+            RenderTarget::Texture(handle) => {
+                let texture = textures.get(handle)?;
+                Some(Vec2::new(
+                    texture.size.width as f32,
+                    texture.size.height as f32,
+                ))
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, Reflect, Serialize, Deserialize)]
 #[reflect_value(Serialize, Deserialize)]
 pub enum DepthCalculation {
@@ -44,11 +81,11 @@ impl Camera {
     pub fn world_to_screen(
         &self,
         windows: &Windows,
+        textures: &Assets<Texture>,
         camera_transform: &GlobalTransform,
         world_position: Vec3,
     ) -> Option<Vec2> {
-        let window = windows.get(self.window)?;
-        let window_size = Vec2::new(window.width(), window.height());
+        let window_size = self.target.get_size(windows, textures)?;
         // Build a transform to convert from world to NDC using camera data
         let world_to_ndc: Mat4 =
             self.projection_matrix * camera_transform.compute_matrix().inverse();
@@ -66,7 +103,9 @@ impl Camera {
 pub fn camera_system<T: CameraProjection + Component>(
     mut window_resized_events: EventReader<WindowResized>,
     mut window_created_events: EventReader<WindowCreated>,
+    mut texture_events: EventReader<AssetEvent<Texture>>,
     windows: Res<Windows>,
+    textures: Res<Assets<Texture>>,
     mut queries: QuerySet<(
         Query<(Entity, &mut Camera, &mut T)>,
         Query<Entity, Added<Camera>>,
@@ -93,14 +132,28 @@ pub fn camera_system<T: CameraProjection + Component>(
         changed_window_ids.push(event.id);
     }
 
+    let mut changed_textures = Vec::new();
+    for event in texture_events.iter() {
+        match event {
+            AssetEvent::Created { handle } | AssetEvent::Modified { handle } => {
+                changed_textures.push(handle)
+            }
+            _ => {}
+        }
+    }
+
     let mut added_cameras = vec![];
     for entity in &mut queries.q1().iter() {
         added_cameras.push(entity);
     }
     for (entity, mut camera, mut camera_projection) in queries.q0_mut().iter_mut() {
-        if let Some(window) = windows.get(camera.window) {
-            if changed_window_ids.contains(&window.id()) || added_cameras.contains(&entity) {
-                camera_projection.update(window.width(), window.height());
+        let target_changed = match &camera.target {
+            RenderTarget::Window(window_id) => changed_window_ids.contains(window_id),
+            RenderTarget::Texture(handle) => changed_textures.contains(&handle),
+        };
+        if target_changed || added_cameras.contains(&entity) {
+            if let Some(size) = camera.target.get_size(&windows, &textures) {
+                camera_projection.update(size.x, size.y);
                 camera.projection_matrix = camera_projection.get_projection_matrix();
                 camera.depth_calculation = camera_projection.depth_calculation();
             }
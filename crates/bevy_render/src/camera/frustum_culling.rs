@@ -0,0 +1,210 @@
+use super::Camera;
+use crate::{
+    draw::OutsideFrustum,
+    instancing::MeshInstances,
+    mesh::Mesh,
+    primitives::{Aabb, BoundingSphere, Frustum},
+};
+use bevy_app::prelude::EventReader;
+use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_ecs::{
+    entity::Entity,
+    query::{ChangeTrackers, Changed, Or, With, Without},
+    reflect::ReflectComponent,
+    system::{Commands, Query, Res},
+};
+use bevy_math::Mat4;
+use bevy_reflect::Reflect;
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::HashSet;
+
+/// Entities with this component aren't culled by the bounds of their mesh, and don't get an
+/// [Aabb] or [BoundingSphere] computed from it. This is needed for meshes that are resized in
+/// their vertex shader, like the quads of sprites and UI nodes.
+///
+/// Other systems can still cull these entities with [OutsideFrustum]. Sprites, for example, are
+/// culled by the sprite frustum culling of `bevy_sprite`, which uses their size instead.
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct NoFrustumCulling;
+
+/// Whether an entity with these local space bounds and `model` matrix is outside of `frustum`
+pub(crate) fn is_outside_frustum(
+    frustum: &Frustum,
+    aabb: &Aabb,
+    bounding_sphere: &BoundingSphere,
+    model: &Mat4,
+) -> bool {
+    // the sphere test is cheaper, and rejects most entities that are far outside
+    !frustum.intersects_sphere(&bounding_sphere.transformed(model))
+        || !frustum.intersects_obb(aabb, model)
+}
+
+/// Entities that are drawn with instancing are never culled, since their bounds don't contain
+/// their instances
+pub(crate) fn is_instanced(mesh_instances: Option<&MeshInstances>) -> bool {
+    matches!(mesh_instances, Some(MeshInstances::Instanced(_)))
+}
+
+/// Adds an [Aabb] and a [BoundingSphere] to entities with a loaded [Mesh], and updates them when
+/// the mesh or its handle changes
+#[allow(clippy::type_complexity)]
+pub fn calculate_bounds_system(
+    mut commands: Commands,
+    meshes: Res<Assets<Mesh>>,
+    mut mesh_events: EventReader<AssetEvent<Mesh>>,
+    new_entities_query: Query<(Entity, &Handle<Mesh>), (Without<Aabb>, Without<NoFrustumCulling>)>,
+    mut bounds_query: Query<
+        (
+            Entity,
+            &Handle<Mesh>,
+            ChangeTrackers<Handle<Mesh>>,
+            &mut Aabb,
+            &mut BoundingSphere,
+        ),
+        Without<NoFrustumCulling>,
+    >,
+) {
+    let mut changed_meshes = HashSet::default();
+    for event in mesh_events.iter() {
+        if let AssetEvent::Modified { handle } = event {
+            changed_meshes.insert(handle.clone_weak());
+        }
+    }
+
+    for (entity, mesh_handle) in new_entities_query.iter() {
+        if let Some((aabb, bounding_sphere)) =
+            meshes.get(mesh_handle).and_then(Mesh::compute_bounds)
+        {
+            commands
+                .entity(entity)
+                .insert_bundle((aabb, bounding_sphere));
+        }
+    }
+
+    for (entity, mesh_handle, mesh_handle_tracker, mut aabb, mut bounding_sphere) in
+        bounds_query.iter_mut()
+    {
+        if !mesh_handle_tracker.is_changed() && !changed_meshes.contains(mesh_handle) {
+            continue;
+        }
+        match meshes.get(mesh_handle).and_then(Mesh::compute_bounds) {
+            Some((new_aabb, new_bounding_sphere)) => {
+                *aabb = new_aabb;
+                *bounding_sphere = new_bounding_sphere;
+            }
+            None => {
+                commands
+                    .entity(entity)
+                    .remove_bundle::<(Aabb, BoundingSphere)>();
+            }
+        }
+    }
+}
+
+/// Updates the [Frustum] of cameras from their projection matrix and [GlobalTransform]
+#[allow(clippy::type_complexity)]
+pub fn update_frusta_system(
+    mut query: Query<
+        (&Camera, &GlobalTransform, &mut Frustum),
+        Or<(Changed<Camera>, Changed<GlobalTransform>)>,
+    >,
+) {
+    for (camera, global_transform, mut frustum) in query.iter_mut() {
+        let view_projection =
+            camera.projection_matrix * global_transform.compute_matrix().inverse();
+        *frustum = Frustum::from_view_projection(&view_projection);
+    }
+}
+
+/// Marks entities with bounds that are outside of the [Frustum] of every camera with
+/// [OutsideFrustum], so they aren't drawn. Nothing is culled while there is a camera without a
+/// frustum.
+#[allow(clippy::type_complexity)]
+pub fn mesh_frustum_culling_system(
+    mut commands: Commands,
+    camera_query: Query<Option<&Frustum>, With<Camera>>,
+    query: Query<
+        (
+            Entity,
+            &Aabb,
+            &BoundingSphere,
+            &GlobalTransform,
+            Option<&MeshInstances>,
+            Option<&OutsideFrustum>,
+        ),
+        Without<NoFrustumCulling>,
+    >,
+) {
+    let frusta = camera_query
+        .iter()
+        .map(|frustum| frustum.copied())
+        .collect::<Option<Vec<Frustum>>>()
+        .unwrap_or_default();
+
+    for (entity, aabb, bounding_sphere, global_transform, mesh_instances, outside_frustum) in
+        query.iter()
+    {
+        let model = global_transform.compute_matrix();
+        let is_outside = !frusta.is_empty()
+            && !is_instanced(mesh_instances)
+            && frusta
+                .iter()
+                .all(|frustum| is_outside_frustum(frustum, aabb, bounding_sphere, &model));
+        if is_outside && outside_frustum.is_none() {
+            commands.entity(entity).insert(OutsideFrustum);
+        } else if !is_outside && outside_frustum.is_some() {
+            commands.entity(entity).remove::<OutsideFrustum>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{mesh::shape, pipeline::PrimitiveTopology};
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, AssetServer, FileAssetIo};
+    use bevy_ecs::system::IntoSystem;
+    use bevy_math::Vec3;
+
+    #[test]
+    fn bounds_follow_mesh_changes() {
+        let mut app = App::build();
+        app.insert_resource(AssetServer::new(FileAssetIo::new(&"."), Default::default()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_system(calculate_bounds_system.system());
+        let mut meshes = app.world_mut().get_resource_mut::<Assets<Mesh>>().unwrap();
+        let mesh = meshes.add(Mesh::from(shape::Cube { size: 2.0 }));
+        let entity = app.world_mut().spawn().insert(mesh.clone()).id();
+
+        app.app.update();
+        assert_eq!(
+            app.world().get::<Aabb>(entity),
+            Some(&Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0)))
+        );
+
+        let mut meshes = app.world_mut().get_resource_mut::<Assets<Mesh>>().unwrap();
+        *meshes.get_mut(&mesh).unwrap() = Mesh::from(shape::Cube { size: 4.0 });
+        // the Modified event is sent after the system has run in this frame
+        app.app.update();
+        app.app.update();
+        assert_eq!(
+            app.world().get::<Aabb>(entity),
+            Some(&Aabb::from_min_max(Vec3::splat(-2.0), Vec3::splat(2.0)))
+        );
+        assert_eq!(
+            app.world().get::<BoundingSphere>(entity).unwrap().radius,
+            Vec3::splat(2.0).length()
+        );
+
+        // a mesh without positions has no bounds
+        let mut meshes = app.world_mut().get_resource_mut::<Assets<Mesh>>().unwrap();
+        *meshes.get_mut(&mesh).unwrap() = Mesh::new(PrimitiveTopology::TriangleList);
+        app.app.update();
+        app.app.update();
+        assert!(app.world().get::<Aabb>(entity).is_none());
+        assert!(app.world().get::<BoundingSphere>(entity).is_none());
+    }
+}
@@ -1,5 +1,10 @@
-use super::{Camera, DepthCalculation};
-use crate::{draw::OutsideFrustum, prelude::Visible};
+use super::{is_instanced, is_outside_frustum, Camera, DepthCalculation, NoFrustumCulling};
+use crate::{
+    draw::OutsideFrustum,
+    instancing::MeshInstances,
+    prelude::Visible,
+    primitives::{Aabb, BoundingSphere, Frustum},
+};
 use bevy_core::FloatOrd;
 use bevy_ecs::{entity::Entity, query::Without, reflect::ReflectComponent, system::Query};
 use bevy_reflect::Reflect;
@@ -42,7 +47,7 @@ pub type Layer = u8;
 /// An entity with this component without any layers is invisible.
 ///
 /// Entities without this component belong to layer `0`.
-#[derive(Copy, Clone, Reflect, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Copy, Clone, Reflect, PartialEq, Eq, PartialOrd, Ord, Hash)]
 #[reflect(Component, PartialEq)]
 pub struct RenderLayers(LayerMask);
 
@@ -197,17 +202,28 @@ mod rendering_mask_tests {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn visible_entities_system(
     mut camera_query: Query<(
         &Camera,
         &GlobalTransform,
         &mut VisibleEntities,
         Option<&RenderLayers>,
+        Option<&Frustum>,
     )>,
-    visible_query: Query<(Entity, &Visible, Option<&RenderLayers>), Without<OutsideFrustum>>,
-    visible_transform_query: Query<&GlobalTransform, Without<OutsideFrustum>>,
+    visible_query: Query<(
+        Entity,
+        &Visible,
+        Option<&RenderLayers>,
+        Option<&OutsideFrustum>,
+    )>,
+    visible_transform_query: Query<&GlobalTransform>,
+    bounds_query: Query<
+        (&Aabb, &BoundingSphere, Option<&MeshInstances>),
+        Without<NoFrustumCulling>,
+    >,
 ) {
-    for (camera, camera_global_transform, mut visible_entities, maybe_camera_mask) in
+    for (camera, camera_global_transform, mut visible_entities, maybe_camera_mask, frustum) in
         camera_query.iter_mut()
     {
         visible_entities.value.clear();
@@ -216,7 +232,7 @@ pub fn visible_entities_system(
 
         let mut no_transform_order = 0.0;
         let mut transparent_entities = Vec::new();
-        for (entity, visible, maybe_entity_mask) in visible_query.iter() {
+        for (entity, visible, maybe_entity_mask, outside_frustum) in visible_query.iter() {
             if !visible.is_visible {
                 continue;
             }
@@ -226,7 +242,29 @@ pub fn visible_entities_system(
                 continue;
             }
 
-            let order = if let Ok(global_transform) = visible_transform_query.get(entity) {
+            let global_transform = visible_transform_query.get(entity).ok();
+
+            // entities with bounds are culled by the frustum of each camera, and other entities
+            // by their OutsideFrustum marker
+            let is_culled = match (bounds_query.get(entity), global_transform) {
+                (Ok((aabb, bounding_sphere, mesh_instances)), Some(global_transform)) => {
+                    match frustum {
+                        Some(frustum) if !is_instanced(mesh_instances) => is_outside_frustum(
+                            frustum,
+                            aabb,
+                            bounding_sphere,
+                            &global_transform.compute_matrix(),
+                        ),
+                        _ => false,
+                    }
+                }
+                _ => outside_frustum.is_some(),
+            };
+            if is_culled {
+                continue;
+            }
+
+            let order = if let Some(global_transform) = global_transform {
                 let position = global_transform.translation;
                 // smaller distances are sorted to lower indices by using the distance from the
                 // camera
@@ -258,3 +296,69 @@ pub fn visible_entities_system(
         // to prevent holding unneeded memory
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::camera::{CameraProjection, PerspectiveProjection};
+    use bevy_ecs::{
+        schedule::{Stage, SystemStage},
+        system::IntoSystem,
+        world::World,
+    };
+    use bevy_math::{Quat, Vec3};
+
+    #[test]
+    fn entities_are_culled_by_each_camera() {
+        let mut world = World::default();
+        let mut spawn_camera = |global_transform: GlobalTransform| {
+            let camera = Camera {
+                projection_matrix: PerspectiveProjection::default().get_projection_matrix(),
+                ..Default::default()
+            };
+            let frustum = Frustum::from_view_projection(
+                &(camera.projection_matrix * global_transform.compute_matrix().inverse()),
+            );
+            world
+                .spawn()
+                .insert_bundle((
+                    camera,
+                    global_transform,
+                    frustum,
+                    VisibleEntities::default(),
+                ))
+                .id()
+        };
+        // both cameras are at the origin, looking down -z and +z
+        let front_camera = spawn_camera(GlobalTransform::identity());
+        let back_camera = spawn_camera(GlobalTransform::from_rotation(Quat::from_rotation_y(
+            std::f32::consts::PI,
+        )));
+
+        let aabb = Aabb::from_min_max(Vec3::splat(-1.0), Vec3::splat(1.0));
+        let entity = world
+            .spawn()
+            .insert_bundle((
+                Visible::default(),
+                GlobalTransform::from_xyz(0.0, 0.0, -10.0),
+                aabb,
+                BoundingSphere::enclosing(&aabb, vec![Vec3::splat(-1.0), Vec3::splat(1.0)]),
+            ))
+            .id();
+
+        let mut stage = SystemStage::parallel();
+        stage.add_system(visible_entities_system.system());
+        stage.run(&mut world);
+
+        let visible_entities = |camera: Entity| {
+            world
+                .get::<VisibleEntities>(camera)
+                .unwrap()
+                .iter()
+                .map(|visible_entity| visible_entity.entity)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(visible_entities(front_camera), vec![entity]);
+        assert!(visible_entities(back_camera).is_empty());
+    }
+}
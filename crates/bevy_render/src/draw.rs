@@ -353,6 +353,9 @@ impl<'a> DrawContext<'a> {
             if let Some(main_vertex_buffer) = bindings.vertex_attribute_buffer {
                 draw.set_vertex_buffer(0, main_vertex_buffer, 0);
             }
+            if let Some(instance_buffer) = bindings.instance_buffer {
+                draw.set_vertex_buffer(1, instance_buffer, 0);
+            }
         }
         Ok(())
     }
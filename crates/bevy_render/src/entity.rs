@@ -3,8 +3,10 @@ use crate::{
         Camera, DepthCalculation, OrthographicProjection, PerspectiveProjection, ScalingMode,
         VisibleEntities,
     },
+    hdr::Tonemapping,
     pipeline::RenderPipelines,
     prelude::Visible,
+    primitives::Frustum,
     render_graph::base,
     Draw, Mesh,
 };
@@ -33,6 +35,8 @@ pub struct PerspectiveCameraBundle {
     pub camera: Camera,
     pub perspective_projection: PerspectiveProjection,
     pub visible_entities: VisibleEntities,
+    pub frustum: Frustum,
+    pub tonemapping: Tonemapping,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
@@ -50,6 +54,8 @@ impl PerspectiveCameraBundle {
             },
             perspective_projection: Default::default(),
             visible_entities: Default::default(),
+            frustum: Default::default(),
+            tonemapping: Default::default(),
             transform: Default::default(),
             global_transform: Default::default(),
         }
@@ -65,6 +71,8 @@ impl Default for PerspectiveCameraBundle {
             },
             perspective_projection: Default::default(),
             visible_entities: Default::default(),
+            frustum: Default::default(),
+            tonemapping: Default::default(),
             transform: Default::default(),
             global_transform: Default::default(),
         }
@@ -79,6 +87,8 @@ pub struct OrthographicCameraBundle {
     pub camera: Camera,
     pub orthographic_projection: OrthographicProjection,
     pub visible_entities: VisibleEntities,
+    pub frustum: Frustum,
+    pub tonemapping: Tonemapping,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
@@ -99,6 +109,8 @@ impl OrthographicCameraBundle {
                 ..Default::default()
             },
             visible_entities: Default::default(),
+            frustum: Default::default(),
+            tonemapping: Tonemapping::none(),
             transform: Transform::from_xyz(0.0, 0.0, far - 0.1),
             global_transform: Default::default(),
         }
@@ -116,6 +128,8 @@ impl OrthographicCameraBundle {
                 ..Default::default()
             },
             visible_entities: Default::default(),
+            frustum: Default::default(),
+            tonemapping: Default::default(),
             transform: Default::default(),
             global_transform: Default::default(),
         }
@@ -129,6 +143,8 @@ impl OrthographicCameraBundle {
             },
             orthographic_projection: Default::default(),
             visible_entities: Default::default(),
+            frustum: Default::default(),
+            tonemapping: Tonemapping::none(),
             transform: Default::default(),
             global_transform: Default::default(),
         }
@@ -0,0 +1,54 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler Source_sampler;
+layout(set = 0, binding = 2) uniform Bloom {
+    float Threshold;
+    float Intensity;
+};
+
+vec3 sampleSource(vec2 uv) {
+    return texture(sampler2D(Source, Source_sampler), uv).rgb;
+}
+
+#ifdef BLOOM_PREFILTER
+// keeps the part of the color above the threshold, with a soft knee below it
+vec3 prefilter(vec3 color) {
+    float brightness = max(color.r, max(color.g, color.b));
+    float knee = Threshold * 0.5;
+    float soft = clamp(brightness - Threshold + knee, 0.0, 2.0 * knee);
+    soft = soft * soft / (4.0 * knee + 1e-4);
+    return color * (max(soft, brightness - Threshold) / max(brightness, 1e-4));
+}
+#endif
+
+void main() {
+    // the 13 tap filter from "Next Generation Post Processing in Call of Duty: Advanced Warfare"
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(Source, Source_sampler), 0));
+    vec3 a = sampleSource(v_Uv + texel * vec2(-2.0, -2.0));
+    vec3 b = sampleSource(v_Uv + texel * vec2(0.0, -2.0));
+    vec3 c = sampleSource(v_Uv + texel * vec2(2.0, -2.0));
+    vec3 d = sampleSource(v_Uv + texel * vec2(-2.0, 0.0));
+    vec3 e = sampleSource(v_Uv);
+    vec3 f = sampleSource(v_Uv + texel * vec2(2.0, 0.0));
+    vec3 g = sampleSource(v_Uv + texel * vec2(-2.0, 2.0));
+    vec3 h = sampleSource(v_Uv + texel * vec2(0.0, 2.0));
+    vec3 i = sampleSource(v_Uv + texel * vec2(2.0, 2.0));
+    vec3 j = sampleSource(v_Uv + texel * vec2(-1.0, -1.0));
+    vec3 k = sampleSource(v_Uv + texel * vec2(1.0, -1.0));
+    vec3 l = sampleSource(v_Uv + texel * vec2(-1.0, 1.0));
+    vec3 m = sampleSource(v_Uv + texel * vec2(1.0, 1.0));
+
+    vec3 color = e * 0.125;
+    color += (a + c + g + i) * 0.03125;
+    color += (b + d + f + h) * 0.0625;
+    color += (j + k + l + m) * 0.125;
+#ifdef BLOOM_PREFILTER
+    color = prefilter(color);
+#endif
+    o_Target = vec4(color, 1.0);
+}
@@ -0,0 +1,230 @@
+use super::{
+    camera_component, linear_sampler, BloomSettings, BLOOM_DOWNSAMPLE_PIPELINE_HANDLE,
+    BLOOM_UPSAMPLE_PIPELINE_HANDLE, HDR_TEXTURE_FORMAT,
+};
+use crate::{
+    pass::{
+        LoadOp, Operations, PassDescriptor, RenderPassColorAttachmentDescriptor, TextureAttachment,
+    },
+    pipeline::{PipelineSpecialization, ShaderSpecialization},
+    prelude::Color,
+    render_graph::{write_uniform_buffer, FullscreenPass, Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{
+        BufferId, RenderContext, RenderResourceBinding, RenderResourceBindings, RenderResourceType,
+        SamplerId, TextureId,
+    },
+    texture::{Extent3d, TextureDescriptor, TextureDimension, TextureUsage},
+};
+use bevy_asset::HandleUntyped;
+use bevy_core::AsBytes;
+use bevy_ecs::world::World;
+use bevy_window::{WindowId, Windows};
+use std::borrow::Cow;
+
+/// The maximum number of times the bright parts of the image are downsampled and blurred
+pub const MAX_BLOOM_LEVELS: usize = 6;
+
+/// A Render Graph [Node] that adds bloom to an HDR texture, with the [BloomSettings] of its
+/// cameras. The bright parts of the texture are downsampled into a chain of textures of halving
+/// sizes, which are then blurred back up and added onto the texture. Nothing is drawn if none of
+/// its cameras have [BloomSettings].
+#[derive(Debug)]
+pub struct BloomNode {
+    window_id: WindowId,
+    cameras: Vec<String>,
+    prefilter_pass: FullscreenPass,
+    downsample_pass: FullscreenPass,
+    upsample_pass: FullscreenPass,
+    final_upsample_pass: FullscreenPass,
+    size: (u32, u32),
+    levels: Vec<TextureId>,
+    downsample_bindings: Vec<RenderResourceBindings>,
+    upsample_bindings: Vec<RenderResourceBindings>,
+    sampler: Option<SamplerId>,
+    uniform_buffer: Option<BufferId>,
+}
+
+impl BloomNode {
+    pub const IN_HDR_TEXTURE: &'static str = "hdr_texture";
+
+    pub fn new(window_id: WindowId) -> Self {
+        let pass = |pipeline: HandleUntyped, shader_def: Option<&str>| {
+            let mut shader_specialization = ShaderSpecialization::default();
+            shader_specialization
+                .shader_defs
+                .extend(shader_def.map(|shader_def| shader_def.to_string()));
+            FullscreenPass::new(
+                pipeline.typed(),
+                PipelineSpecialization {
+                    shader_specialization,
+                    ..Default::default()
+                },
+            )
+        };
+
+        BloomNode {
+            window_id,
+            cameras: Vec::new(),
+            prefilter_pass: pass(BLOOM_DOWNSAMPLE_PIPELINE_HANDLE, Some("BLOOM_PREFILTER")),
+            downsample_pass: pass(BLOOM_DOWNSAMPLE_PIPELINE_HANDLE, None),
+            upsample_pass: pass(BLOOM_UPSAMPLE_PIPELINE_HANDLE, None),
+            final_upsample_pass: pass(BLOOM_UPSAMPLE_PIPELINE_HANDLE, Some("BLOOM_FINAL")),
+            size: (0, 0),
+            levels: Vec::new(),
+            downsample_bindings: Vec::new(),
+            upsample_bindings: Vec::new(),
+            sampler: None,
+            uniform_buffer: None,
+        }
+    }
+
+    /// Adds a camera whose [BloomSettings] are used. The first camera with them is used.
+    pub fn add_camera(&mut self, camera_name: &str) {
+        self.cameras.push(camera_name.to_string());
+    }
+
+    /// Recreates the textures of the levels if the size of the window changed
+    fn update_levels(&mut self, render_context: &mut dyn RenderContext, width: u32, height: u32) {
+        if self.size == (width, height) {
+            return;
+        }
+        self.size = (width, height);
+        let render_resource_context = render_context.resources();
+        for texture in self.levels.drain(..) {
+            render_resource_context.remove_texture(texture);
+        }
+
+        let (mut width, mut height) = ((width / 2).max(1), (height / 2).max(1));
+        loop {
+            self.levels
+                .push(render_resource_context.create_texture(TextureDescriptor {
+                    size: Extent3d::new(width, height, 1),
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: HDR_TEXTURE_FORMAT,
+                    usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+                }));
+            width /= 2;
+            height /= 2;
+            if self.levels.len() == MAX_BLOOM_LEVELS || width.min(height) < 4 {
+                break;
+            }
+        }
+        self.downsample_bindings
+            .resize_with(self.levels.len(), Default::default);
+        self.upsample_bindings
+            .resize_with(self.levels.len(), Default::default);
+    }
+}
+
+fn pass_descriptor(target: TextureId, load: LoadOp<Color>) -> PassDescriptor {
+    PassDescriptor {
+        color_attachments: vec![RenderPassColorAttachmentDescriptor {
+            attachment: TextureAttachment::Id(target),
+            resolve_target: None,
+            ops: Operations { load, store: true },
+        }],
+        depth_stencil_attachment: None,
+        sample_count: 1,
+    }
+}
+
+impl Node for BloomNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        static INPUT: &[ResourceSlotInfo] = &[ResourceSlotInfo {
+            name: Cow::Borrowed(BloomNode::IN_HDR_TEXTURE),
+            resource_type: RenderResourceType::Texture,
+        }];
+        INPUT
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        self.prefilter_pass.prepare(world);
+        self.downsample_pass.prepare(world);
+        self.upsample_pass.prepare(world);
+        self.final_upsample_pass.prepare(world);
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        let settings = match camera_component::<BloomSettings>(world, &self.cameras) {
+            Some(settings) if settings.intensity > 0.0 => settings,
+            _ => return,
+        };
+        let windows = world.get_resource::<Windows>().unwrap();
+        let window = if let Some(window) = windows.get(self.window_id) {
+            window
+        } else {
+            return;
+        };
+        self.update_levels(
+            render_context,
+            window.physical_width(),
+            window.physical_height(),
+        );
+
+        let uniform = [settings.threshold, settings.intensity, 0.0, 0.0];
+        let uniform_buffer =
+            write_uniform_buffer(render_context, &mut self.uniform_buffer, uniform.as_bytes());
+        let sampler = *self
+            .sampler
+            .get_or_insert_with(|| render_context.resources().create_sampler(&linear_sampler()));
+        let uniform_binding = RenderResourceBinding::Buffer {
+            buffer: uniform_buffer,
+            range: 0..uniform.as_bytes().len() as u64,
+            dynamic_index: None,
+        };
+        let hdr_texture = input
+            .get(Self::IN_HDR_TEXTURE)
+            .unwrap()
+            .get_texture()
+            .unwrap();
+        let set_source = |bindings: &mut RenderResourceBindings, source: TextureId| {
+            bindings.set("Source", RenderResourceBinding::Texture(source));
+            bindings.set("Source_sampler", RenderResourceBinding::Sampler(sampler));
+            bindings.set("Bloom", uniform_binding.clone());
+        };
+
+        // each level is a blurred downsample of the one above it, starting from the bright parts
+        // of the hdr texture
+        for (index, &target) in self.levels.iter().enumerate() {
+            let (pass, source) = if index == 0 {
+                (&self.prefilter_pass, hdr_texture)
+            } else {
+                (&self.downsample_pass, self.levels[index - 1])
+            };
+            let bindings = &mut self.downsample_bindings[index];
+            set_source(bindings, source);
+            pass.draw(
+                world,
+                render_context,
+                &pass_descriptor(target, LoadOp::Clear(Color::BLACK)),
+                bindings,
+            );
+        }
+
+        // then each level is upsampled and added onto the one above it, and the first level onto
+        // the hdr texture
+        for (index, &source) in self.levels.iter().enumerate().rev() {
+            let (pass, target) = if index == 0 {
+                (&self.final_upsample_pass, hdr_texture)
+            } else {
+                (&self.upsample_pass, self.levels[index - 1])
+            };
+            let bindings = &mut self.upsample_bindings[index];
+            set_source(bindings, source);
+            pass.draw(
+                world,
+                render_context,
+                &pass_descriptor(target, LoadOp::Load),
+                bindings,
+            );
+        }
+    }
+}
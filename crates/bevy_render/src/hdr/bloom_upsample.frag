@@ -0,0 +1,35 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D Source;
+layout(set = 0, binding = 1) uniform sampler Source_sampler;
+layout(set = 0, binding = 2) uniform Bloom {
+    float Threshold;
+    float Intensity;
+};
+
+vec3 sampleSource(vec2 uv) {
+    return texture(sampler2D(Source, Source_sampler), uv).rgb;
+}
+
+void main() {
+    // a 3x3 tent filter, added onto the target by the pipeline's blend state
+    vec2 texel = 1.0 / vec2(textureSize(sampler2D(Source, Source_sampler), 0));
+    vec3 color = sampleSource(v_Uv) * 4.0;
+    color += (sampleSource(v_Uv + texel * vec2(0.0, -1.0)) +
+              sampleSource(v_Uv + texel * vec2(-1.0, 0.0)) +
+              sampleSource(v_Uv + texel * vec2(1.0, 0.0)) +
+              sampleSource(v_Uv + texel * vec2(0.0, 1.0))) * 2.0;
+    color += sampleSource(v_Uv + texel * vec2(-1.0, -1.0)) +
+             sampleSource(v_Uv + texel * vec2(1.0, -1.0)) +
+             sampleSource(v_Uv + texel * vec2(-1.0, 1.0)) +
+             sampleSource(v_Uv + texel * vec2(1.0, 1.0));
+    color /= 16.0;
+#ifdef BLOOM_FINAL
+    color *= Intensity;
+#endif
+    o_Target = vec4(color, 0.0);
+}
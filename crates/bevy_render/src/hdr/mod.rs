@@ -0,0 +1,132 @@
+mod bloom_node;
+mod pipeline;
+mod tonemapping_node;
+
+pub use bloom_node::*;
+pub use tonemapping_node::*;
+
+use crate::{
+    camera::ActiveCameras,
+    pipeline::PipelineDescriptor,
+    shader::Shader,
+    texture::{FilterMode, SamplerDescriptor, TextureFormat},
+};
+use bevy_asset::{Assets, HandleUntyped};
+use bevy_ecs::{component::Component, reflect::ReflectComponent, world::World};
+use bevy_reflect::{Reflect, ReflectDeserialize, TypeUuid};
+use serde::{Deserialize, Serialize};
+
+/// The format of the HDR target that the main pass draws into, when the base render graph is
+/// configured with `add_hdr_target`
+pub const HDR_TEXTURE_FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+
+pub const TONEMAPPING_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x3d9a6c2b7f1e4805);
+
+pub const BLOOM_DOWNSAMPLE_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x6e2f08d45b97c13a);
+
+pub const BLOOM_UPSAMPLE_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x1a7c5e93d06b28f4);
+
+/// How the [TonemappingNode] maps the HDR colors a camera rendered to the range of the window.
+/// Without this component on the camera, colors are copied as they are and clamped. Cameras that
+/// don't draw into an HDR target aren't tonemapped by a node, and the pbr shader tonemaps their
+/// colors itself.
+#[derive(Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct Tonemapping {
+    pub operator: TonemappingOperator,
+    /// Colors are scaled by `2^exposure` before they are tonemapped
+    pub exposure: f32,
+}
+
+impl Default for Tonemapping {
+    fn default() -> Self {
+        Tonemapping {
+            operator: TonemappingOperator::ReinhardLuminance,
+            exposure: 0.0,
+        }
+    }
+}
+
+impl Tonemapping {
+    /// Clamps colors to the range of the window without tonemapping them
+    pub fn none() -> Self {
+        Tonemapping {
+            operator: TonemappingOperator::None,
+            exposure: 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect_value(PartialEq, Serialize, Deserialize)]
+pub enum TonemappingOperator {
+    None,
+    /// Reinhard on each channel, which desaturates bright colors
+    Reinhard,
+    /// Reinhard on the luminance, which keeps the hue of bright colors
+    ReinhardLuminance,
+    /// A fit of the ACES filmic curve
+    Aces,
+}
+
+/// Adds a glow around the parts of the image a camera rendered that are brighter than
+/// `threshold`. Bloom is only drawn for cameras with this component.
+#[derive(Debug, Clone, Reflect)]
+#[reflect(Component)]
+pub struct BloomSettings {
+    /// The brightness above which colors bloom. Colors start to bloom a bit below it, with a soft
+    /// knee.
+    pub threshold: f32,
+    /// How much of the blurred bright colors is added to the image
+    pub intensity: f32,
+}
+
+impl Default for BloomSettings {
+    fn default() -> Self {
+        BloomSettings {
+            threshold: 1.0,
+            intensity: 0.3,
+        }
+    }
+}
+
+pub(crate) fn add_hdr_pipelines(
+    shaders: &mut Assets<Shader>,
+    pipelines: &mut Assets<PipelineDescriptor>,
+) {
+    pipelines.set_untracked(
+        TONEMAPPING_PIPELINE_HANDLE,
+        pipeline::build_tonemapping_pipeline(shaders),
+    );
+    pipelines.set_untracked(
+        BLOOM_DOWNSAMPLE_PIPELINE_HANDLE,
+        pipeline::build_bloom_downsample_pipeline(shaders),
+    );
+    pipelines.set_untracked(
+        BLOOM_UPSAMPLE_PIPELINE_HANDLE,
+        pipeline::build_bloom_upsample_pipeline(shaders),
+    );
+}
+
+/// The component `T` of the first of `cameras` that has one
+pub(crate) fn camera_component<'a, T: Component>(
+    world: &'a World,
+    cameras: &[String],
+) -> Option<&'a T> {
+    let active_cameras = world.get_resource::<ActiveCameras>().unwrap();
+    cameras
+        .iter()
+        .filter_map(|name| active_cameras.get(name)?.entity)
+        .find_map(|entity| world.get::<T>(entity))
+}
+
+pub(crate) fn linear_sampler() -> SamplerDescriptor {
+    SamplerDescriptor {
+        mag_filter: FilterMode::Linear,
+        min_filter: FilterMode::Linear,
+        ..Default::default()
+    }
+}
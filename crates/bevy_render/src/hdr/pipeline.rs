@@ -0,0 +1,63 @@
+use super::HDR_TEXTURE_FORMAT;
+use crate::{
+    pipeline::{
+        BlendFactor, BlendOperation, BlendState, ColorTargetState, ColorWrite, PipelineDescriptor,
+    },
+    render_graph::build_fullscreen_pipeline,
+    shader::{Shader, ShaderStage},
+    texture::TextureFormat,
+};
+use bevy_asset::Assets;
+
+fn replace_color_target(format: TextureFormat) -> ColorTargetState {
+    ColorTargetState {
+        format,
+        color_blend: BlendState::REPLACE,
+        alpha_blend: BlendState::REPLACE,
+        write_mask: ColorWrite::ALL,
+    }
+}
+
+pub(crate) fn build_tonemapping_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
+    build_fullscreen_pipeline(
+        "tonemapping",
+        shaders.add(Shader::from_glsl(
+            ShaderStage::Fragment,
+            include_str!("tonemapping.frag"),
+        )),
+        replace_color_target(TextureFormat::default()),
+    )
+}
+
+pub(crate) fn build_bloom_downsample_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
+    build_fullscreen_pipeline(
+        "bloom_downsample",
+        shaders.add(Shader::from_glsl(
+            ShaderStage::Fragment,
+            include_str!("bloom_downsample.frag"),
+        )),
+        replace_color_target(HDR_TEXTURE_FORMAT),
+    )
+}
+
+/// The upsample passes add onto what is already in their target
+pub(crate) fn build_bloom_upsample_pipeline(shaders: &mut Assets<Shader>) -> PipelineDescriptor {
+    let additive = BlendState {
+        src_factor: BlendFactor::One,
+        dst_factor: BlendFactor::One,
+        operation: BlendOperation::Add,
+    };
+    build_fullscreen_pipeline(
+        "bloom_upsample",
+        shaders.add(Shader::from_glsl(
+            ShaderStage::Fragment,
+            include_str!("bloom_upsample.frag"),
+        )),
+        ColorTargetState {
+            format: HDR_TEXTURE_FORMAT,
+            color_blend: additive.clone(),
+            alpha_blend: additive,
+            write_mask: ColorWrite::ALL,
+        },
+    )
+}
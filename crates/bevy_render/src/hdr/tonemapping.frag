@@ -0,0 +1,56 @@
+#version 450
+
+layout(location = 0) in vec2 v_Uv;
+
+layout(location = 0) out vec4 o_Target;
+
+layout(set = 0, binding = 0) uniform texture2D HdrTexture;
+layout(set = 0, binding = 1) uniform sampler HdrTexture_sampler;
+layout(set = 0, binding = 2) uniform Tonemapping {
+    uint Operator;
+    float Exposure;
+};
+
+// from https://64.github.io/tonemapping/
+// reinhard on RGB oversaturates colors
+vec3 reinhard(vec3 color) {
+    return color / (1.0 + color);
+}
+
+// luminance coefficients from Rec. 709.
+// https://en.wikipedia.org/wiki/Rec._709
+float luminance(vec3 v) {
+    return dot(v, vec3(0.2126, 0.7152, 0.0722));
+}
+
+vec3 change_luminance(vec3 c_in, float l_out) {
+    float l_in = luminance(c_in);
+    return c_in * (l_out / max(l_in, 1e-6));
+}
+
+vec3 reinhard_luminance(vec3 color) {
+    float l_old = luminance(color);
+    float l_new = l_old / (1.0f + l_old);
+    return change_luminance(color, l_new);
+}
+
+// Krzysztof Narkowicz's fit of the ACES filmic curve
+// https://knarkowicz.wordpress.com/2016/01/06/aces-filmic-tone-mapping-curve/
+vec3 aces(vec3 color) {
+    vec3 numerator = color * (2.51 * color + 0.03);
+    vec3 denominator = color * (2.43 * color + 0.59) + 0.14;
+    return clamp(numerator / denominator, 0.0, 1.0);
+}
+
+void main() {
+    vec4 color = texture(sampler2D(HdrTexture, HdrTexture_sampler), v_Uv);
+    color.rgb = max(color.rgb * exp2(Exposure), vec3(0.0));
+    if (Operator == 1) {
+        color.rgb = reinhard(color.rgb);
+    } else if (Operator == 2) {
+        color.rgb = reinhard_luminance(color.rgb);
+    } else if (Operator == 3) {
+        color.rgb = aces(color.rgb);
+    }
+    o_Target = vec4(color.rgb, 1.0);
+}
@@ -0,0 +1,159 @@
+use super::{camera_component, linear_sampler, Tonemapping, TONEMAPPING_PIPELINE_HANDLE};
+use crate::{
+    pass::{LoadOp, Operations, PassDescriptor, TextureAttachment},
+    pipeline::PipelineSpecialization,
+    prelude::{Color, Msaa},
+    render_graph::{write_uniform_buffer, FullscreenPass, Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{
+        BufferId, RenderContext, RenderResourceBinding, RenderResourceBindings, RenderResourceType,
+        SamplerId,
+    },
+};
+use bevy_core::{AsBytes, Byteable};
+use bevy_ecs::world::World;
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct TonemappingUniform {
+    operator: u32,
+    exposure: f32,
+    _padding: [u32; 2],
+}
+
+unsafe impl Byteable for TonemappingUniform {}
+
+/// A Render Graph [Node] that tonemaps an HDR texture that a camera drew into a color attachment,
+/// with the [Tonemapping] of the camera. With msaa, the color attachment is multisampled and
+/// resolved into the color resolve target.
+#[derive(Debug)]
+pub struct TonemappingNode {
+    inputs: Vec<ResourceSlotInfo>,
+    camera: String,
+    pass: FullscreenPass,
+    descriptor: PassDescriptor,
+    bindings: RenderResourceBindings,
+    sampler: Option<SamplerId>,
+    uniform_buffer: Option<BufferId>,
+}
+
+impl TonemappingNode {
+    pub const IN_HDR_TEXTURE: &'static str = "hdr_texture";
+    pub const IN_COLOR_ATTACHMENT: &'static str = "color_attachment";
+    pub const IN_COLOR_RESOLVE_TARGET: &'static str = "color_resolve_target";
+
+    /// Tonemaps with the [Tonemapping] of the camera named `camera_name`
+    pub fn new(msaa: &Msaa, camera_name: &str) -> Self {
+        let mut inputs = vec![
+            ResourceSlotInfo::new(Self::IN_HDR_TEXTURE, RenderResourceType::Texture),
+            ResourceSlotInfo::new(Self::IN_COLOR_ATTACHMENT, RenderResourceType::Texture),
+        ];
+        if msaa.samples > 1 {
+            inputs.push(ResourceSlotInfo::new(
+                Self::IN_COLOR_RESOLVE_TARGET,
+                RenderResourceType::Texture,
+            ));
+        }
+
+        TonemappingNode {
+            inputs,
+            camera: camera_name.to_string(),
+            pass: FullscreenPass::new(
+                TONEMAPPING_PIPELINE_HANDLE.typed(),
+                PipelineSpecialization {
+                    sample_count: msaa.samples,
+                    ..Default::default()
+                },
+            ),
+            descriptor: PassDescriptor {
+                color_attachments: vec![msaa.color_attachment_descriptor(
+                    TextureAttachment::Input(Self::IN_COLOR_ATTACHMENT.to_string()),
+                    TextureAttachment::Input(Self::IN_COLOR_RESOLVE_TARGET.to_string()),
+                    Operations {
+                        load: LoadOp::Clear(Color::BLACK),
+                        store: true,
+                    },
+                )],
+                depth_stencil_attachment: None,
+                sample_count: msaa.samples,
+            },
+            bindings: Default::default(),
+            sampler: None,
+            uniform_buffer: None,
+        }
+    }
+}
+
+impl Node for TonemappingNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        &self.inputs
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        self.pass.prepare(world);
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        // the inputs aren't set without a window, like when running headless
+        let (hdr_texture, color_attachment_texture) = match (
+            input.get(Self::IN_HDR_TEXTURE),
+            input.get(Self::IN_COLOR_ATTACHMENT),
+        ) {
+            (Some(hdr_texture), Some(color_attachment)) => (
+                hdr_texture.get_texture().unwrap(),
+                color_attachment.get_texture().unwrap(),
+            ),
+            _ => return,
+        };
+
+        let tonemapping =
+            camera_component::<Tonemapping>(world, std::slice::from_ref(&self.camera))
+                .cloned()
+                .unwrap_or_else(Tonemapping::none);
+        let uniform = TonemappingUniform {
+            operator: tonemapping.operator as u32,
+            exposure: tonemapping.exposure,
+            _padding: [0; 2],
+        };
+        let uniform_buffer =
+            write_uniform_buffer(render_context, &mut self.uniform_buffer, uniform.as_bytes());
+        let sampler = *self
+            .sampler
+            .get_or_insert_with(|| render_context.resources().create_sampler(&linear_sampler()));
+
+        self.bindings
+            .set("HdrTexture", RenderResourceBinding::Texture(hdr_texture));
+        self.bindings.set(
+            "HdrTexture_sampler",
+            RenderResourceBinding::Sampler(sampler),
+        );
+        self.bindings.set(
+            "Tonemapping",
+            RenderResourceBinding::Buffer {
+                buffer: uniform_buffer,
+                range: 0..uniform.as_bytes().len() as u64,
+                dynamic_index: None,
+            },
+        );
+
+        let color_attachment = &mut self.descriptor.color_attachments[0];
+        color_attachment.attachment = TextureAttachment::Id(color_attachment_texture);
+        if let Some(resolve_target) = color_attachment.resolve_target.as_mut() {
+            match input.get(Self::IN_COLOR_RESOLVE_TARGET) {
+                Some(resolve_target_texture) => {
+                    *resolve_target =
+                        TextureAttachment::Id(resolve_target_texture.get_texture().unwrap());
+                }
+                None => return,
+            }
+        }
+
+        self.pass
+            .draw(world, render_context, &self.descriptor, &mut self.bindings);
+    }
+}
@@ -0,0 +1,444 @@
+use crate::{
+    camera::RenderLayers,
+    draw::{OutsideFrustum, Visible},
+    mesh::Mesh,
+    pipeline::RenderPipelines,
+    render_graph::base::MainPass,
+    renderer::{BufferInfo, BufferUsage, RenderResourceContext},
+    skinning::SkinnedMesh,
+};
+use bevy_asset::{Asset, Handle, HandleId};
+use bevy_core::Bytes;
+use bevy_ecs::{
+    entity::Entity,
+    query::{Changed, With, Without},
+    reflect::ReflectComponent,
+    system::{Commands, Local, Query, Res},
+};
+use bevy_math::Mat4;
+use bevy_reflect::Reflect;
+use bevy_transform::components::{GlobalTransform, Transform};
+use bevy_utils::{AHasher, HashMap};
+use std::hash::{Hash, Hasher};
+
+/// The shader def that is set on the pipelines of instanced draws. Vertex shaders that support
+/// instancing read the model matrix of each instance from the `I_Model_0` to `I_Model_3`
+/// attributes when it is defined, and multiply it with their `Model` uniform.
+pub const INSTANCING_SHADER_DEF: &str = "INSTANCING";
+
+/// Draws the mesh of an entity once for every instance, in a single instanced draw.
+///
+/// The instances are relative to the entity's [GlobalTransform]. Entities with this component
+/// are never batched with other entities, and their pipelines need a vertex shader that supports
+/// the [INSTANCING_SHADER_DEF], like the one of the `StandardMaterial`.
+#[derive(Debug, Default, Clone, Reflect)]
+#[reflect(Component)]
+pub struct InstancedMesh {
+    pub instances: Vec<Transform>,
+}
+
+/// How the mesh of an entity is drawn. This is kept up to date by [instanced_mesh_system] and
+/// [batch_instances_system], and shouldn't be changed manually.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MeshInstances {
+    /// The mesh is drawn once for the entity
+    Single,
+    /// The mesh is drawn once for each of these model matrices, which are relative to the
+    /// entity's [GlobalTransform]
+    Instanced(Vec<Mat4>),
+    /// The mesh is drawn as an instance of another entity's draw
+    DrawnBy(Entity),
+}
+
+impl Default for MeshInstances {
+    fn default() -> Self {
+        MeshInstances::Single
+    }
+}
+
+/// Configures the automatic batching of entities that share a mesh and material into instanced
+/// draws
+#[derive(Debug, Clone)]
+pub struct BatchingSettings {
+    pub enabled: bool,
+    /// The smallest number of entities that are drawn as a batch
+    pub min_batch_size: usize,
+}
+
+impl Default for BatchingSettings {
+    fn default() -> Self {
+        BatchingSettings {
+            enabled: true,
+            min_batch_size: 2,
+        }
+    }
+}
+
+/// Entities with this component are never batched with other entities. Batched entities are
+/// drawn with the per-entity bindings of one of them, so entities with their own uniforms, other
+/// than their transform, should opt out of batching.
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct NotBatched;
+
+/// What entities need to have in common to be drawn in the same batch: their mesh, material,
+/// pipelines, [RenderLayers] and whether they are drawn in the [MainPass]
+type BatchKey = (HandleId, HandleId, u64, RenderLayers, bool);
+
+/// A hash of the pipelines of `render_pipelines` and their shader defs
+fn hash_pipelines(render_pipelines: &RenderPipelines) -> u64 {
+    let mut hasher = AHasher::default();
+    for render_pipeline in render_pipelines.pipelines.iter() {
+        render_pipeline.pipeline.id.hash(&mut hasher);
+        let mut shader_defs = render_pipeline
+            .specialization
+            .shader_specialization
+            .shader_defs
+            .iter()
+            .filter(|shader_def| *shader_def != INSTANCING_SHADER_DEF)
+            .collect::<Vec<_>>();
+        shader_defs.sort();
+        shader_defs.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+/// Updates the [MeshInstances] of entities with an [InstancedMesh]
+pub fn instanced_mesh_system(
+    mut commands: Commands,
+    mut query: Query<(Entity, &InstancedMesh, Option<&mut MeshInstances>), Changed<InstancedMesh>>,
+) {
+    for (entity, instanced_mesh, mesh_instances) in query.iter_mut() {
+        let instances = MeshInstances::Instanced(
+            instanced_mesh
+                .instances
+                .iter()
+                .map(|instance| instance.compute_matrix())
+                .collect(),
+        );
+        if let Some(mut mesh_instances) = mesh_instances {
+            *mesh_instances = instances;
+        } else {
+            commands.entity(entity).insert(instances);
+        }
+    }
+}
+
+/// Groups the visible entities that share a mesh, a material of type `M` and their pipelines into
+/// instanced draws. The entity of each batch that comes first draws all of them, and the others
+/// are skipped. Transparent entities and entities whose [GlobalTransform] can't be inverted, like
+/// ones with a scale of zero, are drawn on their own.
+#[allow(clippy::type_complexity)]
+pub fn batch_instances_system<M: Asset>(
+    mut commands: Commands,
+    settings: Res<BatchingSettings>,
+    mut batches: Local<HashMap<BatchKey, Vec<(Entity, Mat4)>>>,
+    mut assigned: Local<HashMap<Entity, MeshInstances>>,
+    new_entities_query: Query<
+        Entity,
+        (
+            With<Handle<Mesh>>,
+            With<Handle<M>>,
+            With<RenderPipelines>,
+            Without<MeshInstances>,
+        ),
+    >,
+    batchable_query: Query<
+        (
+            Entity,
+            &Handle<Mesh>,
+            &Handle<M>,
+            &RenderPipelines,
+            &Visible,
+            &GlobalTransform,
+            Option<&RenderLayers>,
+            Option<&MainPass>,
+        ),
+        (
+            With<MeshInstances>,
+            Without<OutsideFrustum>,
+            Without<NotBatched>,
+            Without<InstancedMesh>,
+            Without<SkinnedMesh>,
+        ),
+    >,
+    mut mesh_instances_query: Query<(Entity, &mut MeshInstances), Without<InstancedMesh>>,
+) {
+    for entity in new_entities_query.iter() {
+        commands.entity(entity).insert(MeshInstances::default());
+    }
+
+    if settings.enabled {
+        for (
+            entity,
+            mesh,
+            material,
+            render_pipelines,
+            visible,
+            global_transform,
+            render_layers,
+            main_pass,
+        ) in batchable_query.iter()
+        {
+            // transparent entities are sorted by their distance to the camera, which batching
+            // would ignore
+            if !visible.is_visible || visible.is_transparent {
+                continue;
+            }
+            // the instances of a batch are relative to its leader, which needs an inverse
+            let matrix = global_transform.compute_matrix();
+            let determinant = matrix.determinant();
+            if determinant == 0.0 || !determinant.is_finite() {
+                continue;
+            }
+            let key = (
+                mesh.id,
+                material.id,
+                hash_pipelines(render_pipelines),
+                render_layers.copied().unwrap_or_default(),
+                main_pass.is_some(),
+            );
+            batches.entry(key).or_default().push((entity, matrix));
+        }
+
+        for (_, mut batch) in batches.drain() {
+            if batch.len() < settings.min_batch_size {
+                continue;
+            }
+            batch.sort_by_key(|(entity, _)| *entity);
+            let (leader, leader_matrix) = batch[0];
+            let leader_inverse = leader_matrix.inverse();
+            assigned.insert(
+                leader,
+                MeshInstances::Instanced(
+                    batch
+                        .iter()
+                        .map(|(_, matrix)| leader_inverse * *matrix)
+                        .collect(),
+                ),
+            );
+            for (entity, _) in batch.iter().skip(1) {
+                assigned.insert(*entity, MeshInstances::DrawnBy(leader));
+            }
+        }
+    }
+
+    // mesh_instances_resource_system recreates the instance buffer of every changed
+    // MeshInstances, so unchanged values aren't written back
+    for (entity, mut mesh_instances) in mesh_instances_query.iter_mut() {
+        let instances = assigned.remove(&entity).unwrap_or_default();
+        if *mesh_instances != instances {
+            *mesh_instances = instances;
+        }
+    }
+    assigned.clear();
+}
+
+/// Writes the model matrices of changed [MeshInstances] into the instance buffers of their
+/// [RenderPipelines]
+pub fn mesh_instances_resource_system(
+    render_resource_context: Res<Box<dyn RenderResourceContext>>,
+    mut query: Query<(&MeshInstances, &mut RenderPipelines), Changed<MeshInstances>>,
+) {
+    let render_resource_context = &**render_resource_context;
+    for (mesh_instances, mut render_pipelines) in query.iter_mut() {
+        if let Some(buffer) = render_pipelines.bindings.instance_buffer.take() {
+            render_resource_context.remove_buffer(buffer);
+        }
+
+        let instances = match mesh_instances {
+            MeshInstances::Instanced(instances) if !instances.is_empty() => instances,
+            _ => continue,
+        };
+        let matrix_size = std::mem::size_of::<Mat4>();
+        let mut data = vec![0; instances.len() * matrix_size];
+        for (instance, bytes) in instances.iter().zip(data.chunks_exact_mut(matrix_size)) {
+            instance.write_bytes(bytes);
+        }
+        let buffer = render_resource_context.create_buffer_with_data(
+            BufferInfo {
+                buffer_usage: BufferUsage::VERTEX,
+                ..Default::default()
+            },
+            &data,
+        );
+        render_pipelines.bindings.instance_buffer = Some(buffer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        draw::Draw,
+        pipeline::{
+            draw_render_pipelines_system, PipelineCompiler, PipelineDescriptor, RenderPipeline,
+        },
+        render_graph::base::Msaa,
+        renderer::{
+            AssetRenderResourceBindings, HeadlessRenderResourceContext, RenderResourceBindings,
+            SharedBuffers,
+        },
+        shader::Shader,
+        texture::Texture,
+    };
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, AssetServer, FileAssetIo};
+    use bevy_ecs::{
+        schedule::{ParallelSystemDescriptorCoercion, Stage, SystemStage},
+        system::IntoSystem,
+        world::World,
+    };
+    use bevy_math::Vec3;
+
+    #[test]
+    fn entities_sharing_mesh_and_material_are_batched() {
+        let mut app = App::build();
+        app.insert_resource(AssetServer::new(FileAssetIo::new(&"."), Default::default()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>()
+            .add_asset::<Shader>()
+            .add_asset::<PipelineDescriptor>()
+            .insert_resource(BatchingSettings::default())
+            .insert_resource(Msaa::default())
+            .insert_resource(PipelineCompiler::default())
+            .insert_resource(AssetRenderResourceBindings::default())
+            .insert_resource(RenderResourceBindings::default())
+            .insert_resource(SharedBuffers::new(4096))
+            .insert_resource::<Box<dyn RenderResourceContext>>(Box::new(
+                HeadlessRenderResourceContext::default(),
+            ));
+        let world = app.world_mut();
+        let mut stage = SystemStage::parallel();
+        stage
+            .add_system(batch_instances_system::<Texture>.system().label("batch"))
+            .add_system(
+                mesh_instances_resource_system
+                    .system()
+                    .label("instances")
+                    .after("batch"),
+            )
+            .add_system(draw_render_pipelines_system.system().after("instances"));
+
+        // the meshes aren't loaded, so nothing is drawn or compiled, but pipelines are specialized
+        let mesh = Handle::<Mesh>::weak(HandleId::random::<Mesh>());
+        let other_mesh = Handle::<Mesh>::weak(HandleId::random::<Mesh>());
+        let material = Handle::<Texture>::weak(HandleId::random::<Texture>());
+        let pipeline = Handle::<PipelineDescriptor>::weak(HandleId::random::<PipelineDescriptor>());
+        let mut spawn = |mesh: &Handle<Mesh>, x: f32| {
+            world
+                .spawn()
+                .insert_bundle((
+                    mesh.clone(),
+                    material.clone(),
+                    Draw::default(),
+                    RenderPipelines::from_pipelines(vec![RenderPipeline::new(pipeline.clone())]),
+                    Visible::default(),
+                    GlobalTransform::from_xyz(x, 0.0, 0.0),
+                ))
+                .id()
+        };
+        let leader = spawn(&mesh, 1.0);
+        let follower = spawn(&mesh, 3.0);
+        let single = spawn(&other_mesh, 0.0);
+        let is_instanced = |world: &World, entity: Entity| {
+            world.get::<RenderPipelines>(entity).unwrap().pipelines[0]
+                .specialization
+                .shader_specialization
+                .shader_defs
+                .contains(INSTANCING_SHADER_DEF)
+        };
+
+        // the first run inserts the MeshInstances of new entities, and the second batches them
+        stage.run(world);
+        stage.run(world);
+
+        assert_eq!(
+            world.get::<MeshInstances>(leader),
+            Some(&MeshInstances::Instanced(vec![
+                Mat4::IDENTITY,
+                Mat4::from_translation([2.0, 0.0, 0.0].into())
+            ]))
+        );
+        assert_eq!(
+            world.get::<MeshInstances>(follower),
+            Some(&MeshInstances::DrawnBy(leader))
+        );
+        assert_eq!(
+            world.get::<MeshInstances>(single),
+            Some(&MeshInstances::Single)
+        );
+        assert!(is_instanced(world, leader));
+        assert!(!is_instanced(world, single));
+
+        world.entity_mut(follower).insert(NotBatched);
+        stage.run(world);
+        assert_eq!(
+            world.get::<MeshInstances>(leader),
+            Some(&MeshInstances::Single)
+        );
+        assert_eq!(
+            world.get::<MeshInstances>(follower),
+            Some(&MeshInstances::Single)
+        );
+        assert!(!is_instanced(world, leader));
+    }
+
+    #[test]
+    fn transparent_and_degenerate_entities_are_not_batched() {
+        let mut world = World::default();
+        world.insert_resource(BatchingSettings::default());
+        let mut stage = SystemStage::parallel();
+        stage.add_system(batch_instances_system::<Texture>.system());
+
+        let mesh = Handle::<Mesh>::weak(HandleId::random::<Mesh>());
+        let material = Handle::<Texture>::weak(HandleId::random::<Texture>());
+        let mut spawn = |global_transform: GlobalTransform, is_transparent: bool| {
+            world
+                .spawn()
+                .insert_bundle((
+                    mesh.clone(),
+                    material.clone(),
+                    RenderPipelines::default(),
+                    Visible {
+                        is_visible: true,
+                        is_transparent,
+                    },
+                    global_transform,
+                ))
+                .id()
+        };
+        let degenerate = spawn(GlobalTransform::from_scale(Vec3::ZERO), false);
+        let leader = spawn(GlobalTransform::from_xyz(1.0, 0.0, 0.0), false);
+        let follower = spawn(GlobalTransform::from_xyz(2.0, 0.0, 0.0), false);
+        let transparent = [
+            spawn(GlobalTransform::from_xyz(3.0, 0.0, 0.0), true),
+            spawn(GlobalTransform::from_xyz(4.0, 0.0, 0.0), true),
+        ];
+
+        stage.run(&mut world);
+        stage.run(&mut world);
+
+        assert_eq!(
+            world.get::<MeshInstances>(degenerate),
+            Some(&MeshInstances::Single)
+        );
+        assert_eq!(
+            world.get::<MeshInstances>(leader),
+            Some(&MeshInstances::Instanced(vec![
+                Mat4::IDENTITY,
+                Mat4::from_translation(Vec3::X)
+            ]))
+        );
+        assert_eq!(
+            world.get::<MeshInstances>(follower),
+            Some(&MeshInstances::DrawnBy(leader))
+        );
+        for entity in transparent.iter() {
+            assert_eq!(
+                world.get::<MeshInstances>(*entity),
+                Some(&MeshInstances::Single)
+            );
+        }
+    }
+}
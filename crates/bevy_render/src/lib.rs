@@ -3,12 +3,17 @@ pub mod color;
 pub mod colorspace;
 pub mod draw;
 pub mod entity;
+pub mod hdr;
+pub mod instancing;
 pub mod mesh;
 pub mod pass;
 pub mod pipeline;
+pub mod primitives;
+pub mod readback;
 pub mod render_graph;
 pub mod renderer;
 pub mod shader;
+pub mod skinning;
 pub mod texture;
 pub mod wireframe;
 
@@ -27,6 +32,7 @@ pub mod prelude {
         color::Color,
         draw::{Draw, Visible},
         entity::*,
+        hdr::{BloomSettings, Tonemapping, TonemappingOperator},
         mesh::{shape, Mesh},
         pass::ClearColor,
         pipeline::RenderPipelines,
@@ -38,30 +44,39 @@ pub mod prelude {
 use crate::prelude::*;
 use base::Msaa;
 use bevy_app::prelude::*;
-use bevy_asset::{AddAsset, AssetStage};
+use bevy_asset::{AddAsset, AssetStage, Assets};
 use bevy_ecs::schedule::{StageLabel, SystemLabel};
 use camera::{
-    ActiveCameras, Camera, DepthCalculation, OrthographicProjection, PerspectiveProjection,
-    RenderLayers, ScalingMode, VisibleEntities, WindowOrigin,
+    ActiveCameras, Camera, DepthCalculation, NoFrustumCulling, OrthographicProjection,
+    PerspectiveProjection, RenderLayers, ScalingMode, VisibleEntities, WindowOrigin,
 };
+use instancing::{BatchingSettings, InstancedMesh, NotBatched};
 use pipeline::{
     IndexFormat, PipelineCompiler, PipelineDescriptor, PipelineSpecialization, PrimitiveTopology,
     ShaderSpecialization, VertexBufferLayout,
 };
+use primitives::{Aabb, BoundingSphere};
+use readback::Readbacks;
+#[cfg(feature = "png")]
+use readback::ScreenshotManager;
 use render_graph::{
     base::{self, BaseRenderGraphConfig, MainPass},
-    RenderGraph,
+    PostProcessTextures, RenderGraph, FULLSCREEN_SHADER_HANDLE,
 };
 use renderer::{AssetRenderResourceBindings, RenderResourceBindings};
-use shader::ShaderLoader;
+use shader::{ShaderLoader, ShaderStage};
+use skinning::{SkinnedMesh, SkinnedMeshInverseBindposes};
 #[cfg(feature = "hdr")]
 use texture::HdrTextureLoader;
 #[cfg(feature = "png")]
-use texture::ImageTextureLoader;
+use texture::{ImageTextureLoader, ImageTextureSaver};
 
 #[derive(Debug, Hash, PartialEq, Eq, Clone, SystemLabel)]
 pub enum RenderSystem {
     VisibleEntities,
+    MeshInstances,
+    UpdateProjections,
+    UpdateFrusta,
 }
 
 /// The names of "render" App stages
@@ -97,7 +112,10 @@ impl Plugin for RenderPlugin {
     fn build(&self, app: &mut AppBuilder) {
         #[cfg(feature = "png")]
         {
-            app.init_asset_loader::<ImageTextureLoader>();
+            app.init_asset_loader::<ImageTextureLoader>()
+                .init_asset_saver::<ImageTextureSaver>()
+                .init_resource::<ScreenshotManager>()
+                .add_system_to_stage(CoreStage::PostUpdate, readback::screenshot_system.system());
         }
         #[cfg(feature = "hdr")]
         {
@@ -134,6 +152,7 @@ impl Plugin for RenderPlugin {
         .add_asset::<Texture>()
         .add_asset::<Shader>()
         .add_asset::<PipelineDescriptor>()
+        .add_asset::<SkinnedMeshInverseBindposes>()
         .register_type::<Camera>()
         .register_type::<DepthCalculation>()
         .register_type::<Draw>()
@@ -153,6 +172,15 @@ impl Plugin for RenderPlugin {
         .register_type::<ScalingMode>()
         .register_type::<VertexBufferLayout>()
         .register_type::<WindowOrigin>()
+        .register_type::<Tonemapping>()
+        .register_type::<TonemappingOperator>()
+        .register_type::<BloomSettings>()
+        .register_type::<InstancedMesh>()
+        .register_type::<NotBatched>()
+        .register_type::<Aabb>()
+        .register_type::<BoundingSphere>()
+        .register_type::<NoFrustumCulling>()
+        .register_type::<SkinnedMesh>()
         .init_resource::<ClearColor>()
         .init_resource::<RenderGraph>()
         .init_resource::<PipelineCompiler>()
@@ -160,6 +188,9 @@ impl Plugin for RenderPlugin {
         .init_resource::<RenderResourceBindings>()
         .init_resource::<AssetRenderResourceBindings>()
         .init_resource::<ActiveCameras>()
+        .init_resource::<PostProcessTextures>()
+        .init_resource::<Readbacks>()
+        .init_resource::<BatchingSettings>()
         .add_system_to_stage(CoreStage::PreUpdate, draw::clear_draw_system.system())
         .add_system_to_stage(
             CoreStage::PostUpdate,
@@ -169,20 +200,50 @@ impl Plugin for RenderPlugin {
             CoreStage::PostUpdate,
             camera::camera_system::<OrthographicProjection>
                 .system()
+                .label(RenderSystem::UpdateProjections)
                 .before(RenderSystem::VisibleEntities),
         )
         .add_system_to_stage(
             CoreStage::PostUpdate,
             camera::camera_system::<PerspectiveProjection>
                 .system()
+                .label(RenderSystem::UpdateProjections)
                 .before(RenderSystem::VisibleEntities),
         )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera::update_frusta_system
+                .system()
+                .label(RenderSystem::UpdateFrusta)
+                .after(RenderSystem::UpdateProjections)
+                .after(TransformSystem::TransformPropagate),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera::calculate_bounds_system.system(),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            camera::mesh_frustum_culling_system
+                .system()
+                .after(RenderSystem::UpdateFrusta),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            skinning::skinned_mesh_joints_system
+                .system()
+                .after(TransformSystem::TransformPropagate),
+        )
+        .add_system_to_stage(
+            CoreStage::PostUpdate,
+            skinning::skinned_mesh_shader_defs_system.system(),
+        )
         .add_system_to_stage(
             CoreStage::PostUpdate,
             camera::visible_entities_system
                 .system()
                 .label(RenderSystem::VisibleEntities)
-                .after(TransformSystem::TransformPropagate),
+                .after(RenderSystem::UpdateFrusta),
         )
         .add_system_to_stage(
             RenderStage::RenderResource,
@@ -196,6 +257,18 @@ impl Plugin for RenderPlugin {
             RenderStage::RenderResource,
             Texture::texture_resource_system.system(),
         )
+        .add_system_to_stage(
+            RenderStage::RenderResource,
+            instancing::instanced_mesh_system
+                .system()
+                .label(RenderSystem::MeshInstances),
+        )
+        .add_system_to_stage(
+            RenderStage::RenderResource,
+            instancing::mesh_instances_resource_system
+                .system()
+                .after(RenderSystem::MeshInstances),
+        )
         .add_system_to_stage(
             RenderStage::RenderGraphSystems,
             render_graph::render_graph_schedule_executor_system.exclusive_system(),
@@ -204,11 +277,32 @@ impl Plugin for RenderPlugin {
             RenderStage::Draw,
             pipeline::draw_render_pipelines_system.system(),
         )
+        .add_system_to_stage(
+            RenderStage::Draw,
+            readback::window_readback_system.system(),
+        )
         .add_system_to_stage(
             RenderStage::PostRender,
             shader::clear_shader_defs_system.system(),
         );
 
+        {
+            let world = app.world_mut().cell();
+            let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+            let mut pipelines = world
+                .get_resource_mut::<Assets<PipelineDescriptor>>()
+                .unwrap();
+            shaders.set_untracked(
+                FULLSCREEN_SHADER_HANDLE,
+                Shader::from_glsl(
+                    ShaderStage::Vertex,
+                    include_str!("render_graph/nodes/fullscreen.vert"),
+                ),
+            );
+            hdr::add_hdr_pipelines(&mut shaders, &mut pipelines);
+            readback::add_readback_pipelines(&mut shaders, &mut pipelines);
+        }
+
         if let Some(ref config) = self.base_render_graph_config {
             crate::base::add_base_graph(config, app.world_mut());
             let mut active_cameras = app.world_mut().get_resource_mut::<ActiveCameras>().unwrap();
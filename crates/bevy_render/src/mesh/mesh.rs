@@ -1,5 +1,6 @@
 use crate::{
     pipeline::{IndexFormat, PrimitiveTopology, RenderPipelines, VertexFormat},
+    primitives::{Aabb, BoundingSphere},
     renderer::{BufferInfo, BufferUsage, RenderResourceContext, RenderResourceId},
 };
 use bevy_app::prelude::EventReader;
@@ -244,6 +245,12 @@ impl Mesh {
     pub const ATTRIBUTE_POSITION: &'static str = "Vertex_Position";
     /// Texture coordinates for the vertex. Use in conjunction with [`Mesh::set_attribute`]
     pub const ATTRIBUTE_UV_0: &'static str = "Vertex_Uv";
+    /// The indices of the up to four joints that move the vertex of a
+    /// [`SkinnedMesh`](crate::skinning::SkinnedMesh). Stored as [`VertexAttributeValues::Uint4`]
+    pub const ATTRIBUTE_JOINT_INDEX: &'static str = "Vertex_JointIndex";
+    /// How much each joint in [`Mesh::ATTRIBUTE_JOINT_INDEX`] moves the vertex. The weights of a
+    /// vertex should add up to 1. Stored as [`VertexAttributeValues::Float4`]
+    pub const ATTRIBUTE_JOINT_WEIGHT: &'static str = "Vertex_JointWeight";
 
     /// Construct a new mesh. You need to provide a PrimitiveTopology so that the
     /// renderer knows how to treat the vertex data. Most of the time this will be
@@ -341,6 +348,18 @@ impl Mesh {
         vertex_count.unwrap_or(0)
     }
 
+    /// The [Aabb] and [BoundingSphere] of the vertex positions of the mesh, or [None] if it has no
+    /// positions
+    pub fn compute_bounds(&self) -> Option<(Aabb, BoundingSphere)> {
+        let positions = match self.attribute(Mesh::ATTRIBUTE_POSITION)? {
+            VertexAttributeValues::Float3(positions) => positions,
+            _ => return None,
+        };
+        let points = || positions.iter().map(|position| Vec3::from(*position));
+        let aabb = Aabb::enclosing(points())?;
+        Some((aabb, BoundingSphere::enclosing(&aabb, points())))
+    }
+
     pub fn get_vertex_buffer_data(&self) -> Vec<u8> {
         let mut vertex_size = 0;
         for attribute_values in self.attributes.values() {
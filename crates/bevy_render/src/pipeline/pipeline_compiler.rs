@@ -3,6 +3,7 @@ use crate::{
     pipeline::{BindType, InputStepMode, VertexBufferLayout},
     renderer::RenderResourceContext,
     shader::{Shader, ShaderError},
+    texture::TextureFormat,
 };
 use bevy_asset::{Assets, Handle};
 use bevy_reflect::{Reflect, ReflectDeserialize};
@@ -19,6 +20,11 @@ pub struct PipelineSpecialization {
     pub strip_index_format: Option<IndexFormat>,
     pub vertex_buffer_layout: VertexBufferLayout,
     pub sample_count: u32,
+    /// Overrides the format of every color target of the pipeline, for pipelines that draw into
+    /// an attachment that isn't in the default format, like the HDR target of the main pass. The
+    /// [PassNode](crate::render_graph::PassNode) that draws an entity sets it for its attachment.
+    #[reflect(ignore)]
+    pub color_target_format: Option<TextureFormat>,
 }
 
 impl Default for PipelineSpecialization {
@@ -30,6 +36,7 @@ impl Default for PipelineSpecialization {
             primitive_topology: Default::default(),
             dynamic_bindings: Default::default(),
             vertex_buffer_layout: Default::default(),
+            color_target_format: None,
         }
     }
 }
@@ -41,6 +48,18 @@ impl PipelineSpecialization {
     }
 }
 
+/// A shader def that is set for pipelines whose color targets can't store colors brighter than
+/// white, so that their shaders can tonemap the colors they output
+pub const TONEMAP_IN_SHADER_DEF: &str = "TONEMAP_IN_SHADER";
+
+/// Whether colors drawn into `format` can be brighter than white
+fn is_hdr_format(format: TextureFormat) -> bool {
+    matches!(
+        format,
+        TextureFormat::Rgba16Float | TextureFormat::Rgba32Float | TextureFormat::Rg11b10Float
+    )
+}
+
 #[derive(Clone, Eq, PartialEq, Debug, Default, Reflect, Serialize, Deserialize)]
 #[reflect(PartialEq, Serialize, Deserialize)]
 pub struct ShaderSpecialization {
@@ -126,6 +145,29 @@ impl PipelineCompiler {
             .map(|specialized_pipeline| specialized_pipeline.pipeline.clone_weak())
     }
 
+    /// The pipeline that `specialized_pipeline` was compiled from, and the specialization it was
+    /// compiled with
+    pub fn get_source_pipeline(
+        &self,
+        specialized_pipeline: &Handle<PipelineDescriptor>,
+    ) -> Option<(&Handle<PipelineDescriptor>, &PipelineSpecialization)> {
+        self.specialized_pipelines
+            .iter()
+            .find_map(|(source_pipeline, specialized_pipelines)| {
+                specialized_pipelines
+                    .iter()
+                    .find(|current_specialized_pipeline| {
+                        current_specialized_pipeline.pipeline == *specialized_pipeline
+                    })
+                    .map(|current_specialized_pipeline| {
+                        (
+                            source_pipeline,
+                            &current_specialized_pipeline.specialization,
+                        )
+                    })
+            })
+    }
+
     pub fn compile_pipeline(
         &mut self,
         render_resource_context: &dyn RenderResourceContext,
@@ -136,12 +178,28 @@ impl PipelineCompiler {
     ) -> Handle<PipelineDescriptor> {
         let source_descriptor = pipelines.get(source_pipeline).unwrap();
         let mut specialized_descriptor = source_descriptor.clone();
+        if let Some(format) = pipeline_specialization.color_target_format {
+            for color_target in specialized_descriptor.color_target_states.iter_mut() {
+                color_target.format = format;
+            }
+        }
+
+        let mut shader_specialization = pipeline_specialization.shader_specialization.clone();
+        if specialized_descriptor
+            .color_target_states
+            .iter()
+            .any(|color_target| !is_hdr_format(color_target.format))
+        {
+            shader_specialization
+                .shader_defs
+                .insert(TONEMAP_IN_SHADER_DEF.to_string());
+        }
         let specialized_vertex_shader = self
             .compile_shader(
                 render_resource_context,
                 shaders,
                 &specialized_descriptor.shader_stages.vertex,
-                &pipeline_specialization.shader_specialization,
+                &shader_specialization,
             )
             .unwrap();
         specialized_descriptor.shader_stages.vertex = specialized_vertex_shader.clone_weak();
@@ -156,7 +214,7 @@ impl PipelineCompiler {
                         render_resource_context,
                         shaders,
                         fragment,
-                        &pipeline_specialization.shader_specialization,
+                        &shader_specialization,
                     )
                     .unwrap();
                 specialized_fragment_shader = Some(shader.clone_weak());
@@ -210,13 +268,24 @@ impl PipelineCompiler {
             ..Default::default()
         };
 
-        for shader_vertex_attribute in pipeline_layout.vertex_buffer_descriptors.iter() {
-            let shader_vertex_attribute = shader_vertex_attribute
+        // the vertex buffer descriptor of the per-instance attributes, which are read from the
+        // instance buffer in their shader location order
+        let mut compiled_instance_buffer_descriptor = VertexBufferLayout {
+            step_mode: InputStepMode::Instance,
+            ..Default::default()
+        };
+
+        for shader_vertex_buffer_descriptor in pipeline_layout.vertex_buffer_descriptors.iter() {
+            let shader_vertex_attribute = shader_vertex_buffer_descriptor
                 .attributes
                 .get(0)
                 .expect("Reflected layout has no attributes.");
 
-            if let Some(target_vertex_attribute) = mesh_vertex_buffer_layout
+            if shader_vertex_buffer_descriptor.step_mode == InputStepMode::Instance {
+                compiled_instance_buffer_descriptor
+                    .attributes
+                    .push(shader_vertex_attribute.clone());
+            } else if let Some(target_vertex_attribute) = mesh_vertex_buffer_layout
                 .attributes
                 .iter()
                 .find(|x| x.name == shader_vertex_attribute.name)
@@ -236,11 +305,22 @@ impl PipelineCompiler {
             }
         }
 
-        // TODO: add other buffers (like instancing) here
+        compiled_instance_buffer_descriptor
+            .attributes
+            .sort_by_key(|attribute| attribute.shader_location);
+        for attribute in compiled_instance_buffer_descriptor.attributes.iter_mut() {
+            attribute.offset = compiled_instance_buffer_descriptor.stride;
+            compiled_instance_buffer_descriptor.stride += attribute.format.get_size();
+        }
+
+        // the mesh attributes are bound to slot 0, and the instance attributes to slot 1
         let mut vertex_buffer_descriptors = Vec::<VertexBufferLayout>::default();
         if !pipeline_layout.vertex_buffer_descriptors.is_empty() {
             vertex_buffer_descriptors.push(compiled_vertex_buffer_descriptor);
         }
+        if !compiled_instance_buffer_descriptor.attributes.is_empty() {
+            vertex_buffer_descriptors.push(compiled_instance_buffer_descriptor);
+        }
 
         pipeline_layout.vertex_buffer_descriptors = vertex_buffer_descriptors;
         specialized_descriptor.multisample.count = pipeline_specialization.sample_count;
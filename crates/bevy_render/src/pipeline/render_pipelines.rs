@@ -1,6 +1,7 @@
 use super::{PipelineDescriptor, PipelineSpecialization};
 use crate::{
     draw::{Draw, DrawContext, OutsideFrustum},
+    instancing::{MeshInstances, INSTANCING_SHADER_DEF},
     mesh::{Indices, Mesh},
     prelude::{Msaa, Visible},
     renderer::RenderResourceBindings,
@@ -82,37 +83,50 @@ impl Default for RenderPipelines {
     }
 }
 
+#[allow(clippy::type_complexity)]
 pub fn draw_render_pipelines_system(
     mut draw_context: DrawContext,
     mut render_resource_bindings: ResMut<RenderResourceBindings>,
     msaa: Res<Msaa>,
     meshes: Res<Assets<Mesh>>,
     mut query: Query<
-        (&mut Draw, &mut RenderPipelines, &Handle<Mesh>, &Visible),
+        (
+            &mut Draw,
+            &mut RenderPipelines,
+            &Handle<Mesh>,
+            &Visible,
+            Option<&MeshInstances>,
+        ),
         Without<OutsideFrustum>,
     >,
 ) {
-    for (mut draw, mut render_pipelines, mesh_handle, visible) in query.iter_mut() {
+    for (mut draw, mut render_pipelines, mesh_handle, visible, mesh_instances) in query.iter_mut() {
         if !visible.is_visible {
             continue;
         }
 
-        // don't render if the mesh isn't loaded yet
-        let mesh = if let Some(mesh) = meshes.get(mesh_handle) {
-            mesh
-        } else {
-            continue;
-        };
-
-        let index_range = match mesh.indices() {
-            Some(Indices::U32(indices)) => Some(0..indices.len() as u32),
-            Some(Indices::U16(indices)) => Some(0..indices.len() as u32),
-            None => None,
+        // instanced draws need their instance buffer, and batched entities are drawn by another
+        let instance_count = match mesh_instances {
+            None | Some(MeshInstances::Single) => None,
+            Some(MeshInstances::Instanced(instances)) => {
+                if render_pipelines.bindings.instance_buffer.is_none() {
+                    continue;
+                }
+                Some(instances.len() as u32)
+            }
+            Some(MeshInstances::DrawnBy(_)) => continue,
         };
 
         let render_pipelines = &mut *render_pipelines;
         for pipeline in render_pipelines.pipelines.iter_mut() {
             pipeline.specialization.sample_count = msaa.samples;
+            // a batch that broke up is drawn on its own again, without an instance buffer
+            let shader_defs = &mut pipeline.specialization.shader_specialization.shader_defs;
+            if instance_count.is_some() {
+                shader_defs.insert(INSTANCING_SHADER_DEF.to_string());
+            } else {
+                shader_defs.remove(INSTANCING_SHADER_DEF);
+            }
             if pipeline.dynamic_bindings_generation
                 != render_pipelines.bindings.dynamic_bindings_generation()
             {
@@ -139,6 +153,19 @@ pub fn draw_render_pipelines_system(
             }
         }
 
+        // don't render if the mesh isn't loaded yet
+        let mesh = if let Some(mesh) = meshes.get(mesh_handle) {
+            mesh
+        } else {
+            continue;
+        };
+
+        let index_range = match mesh.indices() {
+            Some(Indices::U32(indices)) => Some(0..indices.len() as u32),
+            Some(Indices::U16(indices)) => Some(0..indices.len() as u32),
+            None => None,
+        };
+
         for render_pipeline in render_pipelines.pipelines.iter_mut() {
             let render_resource_bindings = &mut [
                 &mut render_pipelines.bindings,
@@ -158,10 +185,11 @@ pub fn draw_render_pipelines_system(
                 .set_vertex_buffers_from_bindings(&mut draw, &[&render_pipelines.bindings])
                 .unwrap();
 
+            let instances = 0..instance_count.unwrap_or(1);
             if let Some(indices) = index_range.clone() {
-                draw.draw_indexed(indices, 0, 0..1);
+                draw.draw_indexed(indices, 0, instances);
             } else {
-                draw.draw(0..mesh.count_vertices() as u32, 0..1)
+                draw.draw(0..mesh.count_vertices() as u32, instances)
             }
         }
     }
@@ -0,0 +1,200 @@
+use bevy_ecs::reflect::ReflectComponent;
+use bevy_math::{Mat4, Vec3, Vec4};
+use bevy_reflect::Reflect;
+
+/// An axis-aligned bounding box, in the local space of an entity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct Aabb {
+    pub center: Vec3,
+    pub half_extents: Vec3,
+}
+
+impl Aabb {
+    pub fn from_min_max(minimum: Vec3, maximum: Vec3) -> Self {
+        Aabb {
+            center: 0.5 * (maximum + minimum),
+            half_extents: 0.5 * (maximum - minimum),
+        }
+    }
+
+    /// The smallest [Aabb] that contains all `points`, or [None] if there are no points
+    pub fn enclosing(points: impl IntoIterator<Item = Vec3>) -> Option<Self> {
+        let mut points = points.into_iter();
+        let first = points.next()?;
+        let (minimum, maximum) = points.fold((first, first), |(minimum, maximum), point| {
+            (minimum.min(point), maximum.max(point))
+        });
+        Some(Self::from_min_max(minimum, maximum))
+    }
+
+    pub fn min(&self) -> Vec3 {
+        self.center - self.half_extents
+    }
+
+    pub fn max(&self) -> Vec3 {
+        self.center + self.half_extents
+    }
+}
+
+/// A bounding sphere, in the local space of an entity
+#[derive(Debug, Default, Clone, Copy, PartialEq, Reflect)]
+#[reflect(Component)]
+pub struct BoundingSphere {
+    pub center: Vec3,
+    pub radius: f32,
+}
+
+impl BoundingSphere {
+    /// A sphere around the center of `aabb` that contains all `points`
+    pub fn enclosing(aabb: &Aabb, points: impl IntoIterator<Item = Vec3>) -> Self {
+        let radius_squared = points
+            .into_iter()
+            .map(|point| (point - aabb.center).length_squared())
+            .fold(0.0, f32::max);
+        BoundingSphere {
+            center: aabb.center,
+            radius: radius_squared.sqrt(),
+        }
+    }
+
+    /// This sphere in the space that `model` transforms into. The radius is scaled by the largest
+    /// scale of `model`, so the sphere still contains everything it did.
+    pub fn transformed(&self, model: &Mat4) -> Self {
+        let scale = model
+            .x_axis
+            .truncate()
+            .length()
+            .max(model.y_axis.truncate().length())
+            .max(model.z_axis.truncate().length());
+        BoundingSphere {
+            center: model.transform_point3(self.center),
+            radius: self.radius * scale,
+        }
+    }
+}
+
+/// A plane whose normal points into the half-space it bounds, in the form `normal · p + d = 0`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Plane {
+    pub normal_d: Vec4,
+}
+
+impl Plane {
+    /// A plane from its unnormalized coefficients
+    pub fn new(normal_d: Vec4) -> Self {
+        Plane {
+            normal_d: normal_d / normal_d.truncate().length(),
+        }
+    }
+
+    pub fn normal(&self) -> Vec3 {
+        self.normal_d.truncate()
+    }
+
+    pub fn d(&self) -> f32 {
+        self.normal_d.w
+    }
+
+    /// The distance of `point` from the plane, which is negative outside of its half-space
+    pub fn signed_distance(&self, point: Vec3) -> f32 {
+        self.normal().dot(point) + self.d()
+    }
+}
+
+/// The volume a camera can see, bounded by the left, right, bottom, top, near and far planes of
+/// its view frustum in world space. This is kept up to date with the camera's projection and
+/// [GlobalTransform](bevy_transform::components::GlobalTransform).
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct Frustum {
+    pub planes: [Plane; 6],
+}
+
+impl Frustum {
+    /// The frustum of a view projection matrix, with a depth range of `0..1` in clip space
+    pub fn from_view_projection(view_projection: &Mat4) -> Self {
+        let row0 = view_projection.row(0);
+        let row1 = view_projection.row(1);
+        let row2 = view_projection.row(2);
+        let row3 = view_projection.row(3);
+        Frustum {
+            planes: [
+                Plane::new(row3 + row0),
+                Plane::new(row3 - row0),
+                Plane::new(row3 + row1),
+                Plane::new(row3 - row1),
+                Plane::new(row2),
+                Plane::new(row3 - row2),
+            ],
+        }
+    }
+
+    /// Whether the world space `sphere` is at least partly inside the frustum
+    pub fn intersects_sphere(&self, sphere: &BoundingSphere) -> bool {
+        self.planes
+            .iter()
+            .all(|plane| plane.signed_distance(sphere.center) > -sphere.radius)
+    }
+
+    /// Whether `aabb`, transformed by `model` into world space, is at least partly inside the
+    /// frustum. Boxes near the corners of the frustum may intersect it even though they are
+    /// outside.
+    pub fn intersects_obb(&self, aabb: &Aabb, model: &Mat4) -> bool {
+        let center = model.transform_point3(aabb.center);
+        let axes = [
+            model.x_axis.truncate() * aabb.half_extents.x,
+            model.y_axis.truncate() * aabb.half_extents.y,
+            model.z_axis.truncate() * aabb.half_extents.z,
+        ];
+        self.planes.iter().all(|plane| {
+            let normal = plane.normal();
+            let radius = axes.iter().map(|axis| normal.dot(*axis).abs()).sum::<f32>();
+            plane.signed_distance(center) > -radius
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_math::Quat;
+
+    fn frustum() -> Frustum {
+        let projection = Mat4::perspective_rh(std::f32::consts::FRAC_PI_2, 1.0, 1.0, 100.0);
+        // looking down -z from the origin
+        Frustum::from_view_projection(&projection)
+    }
+
+    #[test]
+    fn frustum_culls_spheres() {
+        let frustum = frustum();
+        let sphere = |x, y, z| BoundingSphere {
+            center: Vec3::new(x, y, z),
+            radius: 1.0,
+        };
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -10.0)));
+        // partly inside the near plane
+        assert!(frustum.intersects_sphere(&sphere(0.0, 0.0, -0.5)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, 10.0)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, 0.0, -102.0)));
+        assert!(!frustum.intersects_sphere(&sphere(20.0, 0.0, -10.0)));
+        assert!(!frustum.intersects_sphere(&sphere(0.0, -20.0, -10.0)));
+    }
+
+    #[test]
+    fn frustum_culls_transformed_boxes() {
+        let frustum = frustum();
+        let aabb = Aabb {
+            center: Vec3::ZERO,
+            half_extents: Vec3::new(10.0, 0.5, 0.5),
+        };
+        // a long box behind the camera is only inside the frustum once it is rotated towards it
+        let behind = Mat4::from_translation(Vec3::new(0.0, 0.0, 5.0));
+        assert!(!frustum.intersects_obb(&aabb, &behind));
+        let rotated = Mat4::from_rotation_translation(
+            Quat::from_rotation_y(std::f32::consts::FRAC_PI_2),
+            Vec3::new(0.0, 0.0, 5.0),
+        );
+        assert!(frustum.intersects_obb(&aabb, &rotated));
+    }
+}
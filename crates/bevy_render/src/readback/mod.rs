@@ -0,0 +1,216 @@
+mod readback_node;
+#[cfg(feature = "png")]
+mod screenshot;
+
+pub use readback_node::*;
+#[cfg(feature = "png")]
+pub use screenshot::*;
+
+use crate::{
+    pipeline::{BlendState, ColorTargetState, ColorWrite, PipelineDescriptor},
+    render_graph::{build_fullscreen_pipeline, RenderGraph},
+    renderer::BufferId,
+    shader::{Shader, ShaderStage},
+    texture::{Extent3d, Texture, TextureFormat},
+};
+use bevy_asset::{Assets, Handle, HandleId, HandleUntyped};
+use bevy_ecs::system::{Res, ResMut};
+use bevy_reflect::TypeUuid;
+use bevy_utils::{tracing::error, HashSet};
+use bevy_window::WindowId;
+
+/// Draws the texture a window was captured into onto its swap chain
+pub const BLIT_PIPELINE_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(PipelineDescriptor::TYPE_UUID, 0x48c1e07a95d3b26f);
+
+/// What a readback copies from the gpu
+#[derive(Debug, Clone)]
+pub enum ReadbackSource {
+    /// A 2d texture asset. The texture has to be created with
+    /// [TextureUsage::COPY_SRC](crate::texture::TextureUsage::COPY_SRC), like the textures of
+    /// [Texture::new_render_target].
+    Texture(Handle<Texture>),
+    /// What has been drawn into the swap chain of a window by the end of the frame
+    Window(WindowId),
+    /// A buffer with [BufferUsage::COPY_SRC](crate::renderer::BufferUsage::COPY_SRC) that holds
+    /// the rows of `size` texels in `format`, without padding between them
+    Buffer {
+        buffer: BufferId,
+        size: Extent3d,
+        format: TextureFormat,
+    },
+}
+
+/// Copies textures, windows and buffers from the gpu back into [Texture] assets.
+///
+/// The copies are made by the [ReadbackNode]s of the render graph at the end of the frame they
+/// are requested in. Their buffers are mapped without blocking once the frame has been submitted,
+/// and the bytes are added as the [Texture] asset of the returned handle as soon as the gpu has
+/// finished the copy, which sends an [AssetEvent::Created](bevy_asset::AssetEvent::Created).
+///
+/// Windows are only read back by a [ReadbackNode] of that window. The readbacks of windows
+/// without one are dropped with an error.
+#[derive(Debug, Default)]
+pub struct Readbacks {
+    requests: Vec<(ReadbackSource, Handle<Texture>)>,
+    captured_windows: HashSet<WindowId>,
+}
+
+impl Readbacks {
+    /// Requests a readback of `source`. The returned handle is loaded once its bytes are on the
+    /// cpu.
+    pub fn read(&mut self, textures: &Assets<Texture>, source: ReadbackSource) -> Handle<Texture> {
+        let handle = textures.get_handle(HandleId::random::<Texture>());
+        self.requests.push((source, handle.clone()));
+        handle
+    }
+
+    /// Whether the swap chain of `window_id` is drawn into a capture texture this frame
+    pub fn is_window_captured(&self, window_id: WindowId) -> bool {
+        self.captured_windows.contains(&window_id)
+    }
+
+    /// Takes the requests of textures and buffers, and the captures of `window_id`
+    fn take_requests(&mut self, window_id: WindowId) -> Vec<(ReadbackSource, Handle<Texture>)> {
+        let (taken, requests) = self
+            .requests
+            .drain(..)
+            .partition(|(source, _)| match source {
+                ReadbackSource::Window(id) => *id == window_id,
+                _ => true,
+            });
+        self.requests = requests;
+        taken
+    }
+}
+
+/// The windows that have a [ReadbackNode] in `render_graph`
+fn readback_windows(render_graph: &RenderGraph) -> HashSet<WindowId> {
+    render_graph
+        .iter_nodes()
+        .filter_map(|node_state| node_state.node.downcast_ref::<ReadbackNode>())
+        .map(|node| node.window_id())
+        .collect()
+}
+
+/// Drops the readbacks of windows that no [ReadbackNode] captures, which would never be loaded
+pub fn window_readback_system(render_graph: Res<RenderGraph>, mut readbacks: ResMut<Readbacks>) {
+    if readbacks.requests.is_empty() {
+        return;
+    }
+    let readback_windows = readback_windows(&render_graph);
+    readbacks.requests.retain(|(source, _)| match source {
+        ReadbackSource::Window(window_id) if !readback_windows.contains(window_id) => {
+            error!(
+                "Window {} can't be read back, because the render graph has no ReadbackNode for it.",
+                window_id
+            );
+            false
+        }
+        _ => true,
+    });
+}
+
+pub(crate) fn add_readback_pipelines(
+    shaders: &mut Assets<Shader>,
+    pipelines: &mut Assets<PipelineDescriptor>,
+) {
+    pipelines.set_untracked(
+        BLIT_PIPELINE_HANDLE,
+        build_fullscreen_pipeline(
+            "blit",
+            shaders.add(Shader::from_glsl(
+                ShaderStage::Fragment,
+                include_str!("blit.frag"),
+            )),
+            ColorTargetState {
+                format: TextureFormat::default(),
+                color_blend: BlendState::REPLACE,
+                alpha_blend: BlendState::REPLACE,
+                write_mask: ColorWrite::ALL,
+            },
+        ),
+    );
+}
+
+/// Removes the padding a texture to buffer copy adds to the end of each row
+fn remove_row_padding(data: Vec<u8>, row_size: usize, padded_row_size: usize) -> Vec<u8> {
+    if row_size == padded_row_size {
+        return data;
+    }
+    data.chunks(padded_row_size)
+        .flat_map(|row| &row[..row_size])
+        .copied()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy_ecs::{
+        schedule::{Stage, SystemStage},
+        system::IntoSystem,
+        world::World,
+    };
+
+    #[test]
+    fn row_padding_is_removed() {
+        let data = vec![1, 2, 3, 0, 4, 5, 6, 0];
+        assert_eq!(remove_row_padding(data, 3, 4), vec![1, 2, 3, 4, 5, 6]);
+        let data = vec![1, 2, 3, 4];
+        assert_eq!(remove_row_padding(data.clone(), 2, 2), data);
+    }
+
+    #[test]
+    fn window_captures_are_taken_by_their_window() {
+        let mut readbacks = Readbacks::default();
+        let other_window = WindowId::new();
+        for source in [
+            ReadbackSource::Window(WindowId::primary()),
+            ReadbackSource::Window(other_window),
+            ReadbackSource::Texture(Handle::default()),
+        ]
+        .iter()
+        {
+            readbacks.requests.push((source.clone(), Handle::default()));
+        }
+
+        let taken = readbacks.take_requests(WindowId::primary());
+        assert_eq!(taken.len(), 2);
+        assert_eq!(readbacks.requests.len(), 1);
+        assert!(matches!(
+            readbacks.requests[0].0,
+            ReadbackSource::Window(id) if id == other_window
+        ));
+    }
+
+    #[test]
+    fn readbacks_of_windows_without_a_node_are_dropped() {
+        let mut world = World::default();
+        let mut render_graph = RenderGraph::default();
+        render_graph.add_node("readback", ReadbackNode::new(WindowId::primary()));
+        world.insert_resource(render_graph);
+        let mut readbacks = Readbacks::default();
+        let other_window = WindowId::new();
+        for source in [
+            ReadbackSource::Window(WindowId::primary()),
+            ReadbackSource::Window(other_window),
+            ReadbackSource::Texture(Handle::default()),
+        ]
+        .iter()
+        {
+            readbacks.requests.push((source.clone(), Handle::default()));
+        }
+        world.insert_resource(readbacks);
+
+        let mut stage = SystemStage::parallel();
+        stage.add_system(window_readback_system.system());
+        stage.run(&mut world);
+
+        let readbacks = world.get_resource::<Readbacks>().unwrap();
+        assert_eq!(readbacks.requests.len(), 2);
+        assert!(!readbacks.requests.iter().any(
+            |(source, _)| matches!(source, ReadbackSource::Window(id) if *id == other_window)
+        ));
+    }
+}
@@ -0,0 +1,312 @@
+use super::{remove_row_padding, ReadbackSource, Readbacks, BLIT_PIPELINE_HANDLE};
+use crate::{
+    pass::{
+        LoadOp, Operations, PassDescriptor, RenderPassColorAttachmentDescriptor, TextureAttachment,
+    },
+    render_graph::{FullscreenPass, Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{
+        BufferId, BufferInfo, BufferUsage, RenderContext, RenderResourceBinding,
+        RenderResourceBindings, RenderResourceContext, RenderResourceType, SamplerId, TextureId,
+    },
+    texture::{
+        Extent3d, SamplerDescriptor, Texture, TextureDimension, TextureFormat, TEXTURE_ASSET_INDEX,
+    },
+};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::world::World;
+use bevy_window::{WindowId, Windows};
+use parking_lot::Mutex;
+use std::{borrow::Cow, sync::Arc};
+
+/// Where the bytes of a readback are copied from on the gpu
+#[derive(Debug, Clone, Copy)]
+enum CopySource {
+    Texture(TextureId),
+    Buffer(BufferId),
+}
+
+/// A copy into a buffer that starts being mapped in the frame after it was made
+#[derive(Debug)]
+struct PendingCopy {
+    buffer: BufferId,
+    size: Extent3d,
+    format: TextureFormat,
+    padded_row_size: usize,
+    handle: Handle<Texture>,
+}
+
+/// A copy whose bytes have been read back, but not yet added as a texture asset
+#[derive(Debug)]
+struct FinishedCopy {
+    buffer: BufferId,
+    handle: Handle<Texture>,
+    texture: Texture,
+}
+
+/// A Render Graph [Node] that copies the [Readbacks] requested in a frame into buffers, and adds
+/// the bytes of earlier copies as [Texture] assets once the gpu has finished them.
+///
+/// The node takes the captures of its window, and the readbacks of textures and buffers unless
+/// another readback node took them first. Its inputs are the two outputs of the window's
+/// [WindowSwapChainNode](crate::render_graph::WindowSwapChainNode), and it should run after the
+/// passes that draw into the swap chain. While the window is captured, these passes draw into a
+/// capture texture instead, which this node copies and then draws onto the swap chain.
+#[derive(Debug)]
+pub struct ReadbackNode {
+    window_id: WindowId,
+    requests: Vec<(ReadbackSource, Handle<Texture>)>,
+    copies: Vec<PendingCopy>,
+    finished_copies: Arc<Mutex<Vec<FinishedCopy>>>,
+    blit_pass: FullscreenPass,
+    bindings: RenderResourceBindings,
+    sampler: Option<SamplerId>,
+}
+
+impl ReadbackNode {
+    pub const IN_WINDOW_TEXTURE: &'static str = "window_texture";
+    pub const IN_SWAP_CHAIN_TEXTURE: &'static str = "swap_chain_texture";
+
+    pub fn new(window_id: WindowId) -> Self {
+        ReadbackNode {
+            window_id,
+            requests: Vec::new(),
+            copies: Vec::new(),
+            finished_copies: Default::default(),
+            blit_pass: FullscreenPass::new(BLIT_PIPELINE_HANDLE.typed(), Default::default()),
+            bindings: Default::default(),
+            sampler: None,
+        }
+    }
+
+    /// The window whose captures this node takes
+    pub fn window_id(&self) -> WindowId {
+        self.window_id
+    }
+
+    /// Adds the copies that have been read back as texture assets, and starts reading the copies
+    /// made in the previous frame, which have been submitted to the gpu by now
+    fn finish_copies(&mut self, world: &mut World) {
+        let world = world.cell();
+        let render_resource_context = world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap();
+        let mut textures = world.get_resource_mut::<Assets<Texture>>().unwrap();
+        for finished_copy in self.finished_copies.lock().drain(..) {
+            render_resource_context.remove_buffer(finished_copy.buffer);
+            textures.set_untracked(&finished_copy.handle, finished_copy.texture);
+        }
+
+        for copy in self.copies.drain(..) {
+            let finished_copies = self.finished_copies.clone();
+            let row_size = copy.size.width as usize * copy.format.pixel_size();
+            render_resource_context.read_buffer_async(
+                copy.buffer,
+                0..(copy.padded_row_size * copy.size.height as usize) as u64,
+                Box::new(move |data| {
+                    let texture = Texture::new(
+                        copy.size,
+                        TextureDimension::D2,
+                        remove_row_padding(data.to_vec(), row_size, copy.padded_row_size),
+                        copy.format,
+                    );
+                    finished_copies.lock().push(FinishedCopy {
+                        buffer: copy.buffer,
+                        handle: copy.handle,
+                        texture,
+                    });
+                }),
+            );
+        }
+    }
+
+    /// The gpu resource, size and format of `source`, if it can be copied this frame
+    fn copy_source(
+        &self,
+        world: &World,
+        render_context: &dyn RenderContext,
+        source: &ReadbackSource,
+        capture_texture: Option<TextureId>,
+    ) -> Option<(CopySource, Extent3d, TextureFormat)> {
+        match source {
+            ReadbackSource::Texture(handle) => {
+                let texture = world
+                    .get_resource::<Assets<Texture>>()
+                    .unwrap()
+                    .get(handle)?;
+                let texture_id = render_context
+                    .resources()
+                    .get_asset_resource(handle, TEXTURE_ASSET_INDEX)?
+                    .get_texture()?;
+                Some((
+                    CopySource::Texture(texture_id),
+                    Extent3d::new(texture.size.width, texture.size.height, 1),
+                    texture.format,
+                ))
+            }
+            ReadbackSource::Window(window_id) => {
+                let window = world.get_resource::<Windows>().unwrap().get(*window_id)?;
+                Some((
+                    CopySource::Texture(capture_texture?),
+                    Extent3d::new(window.physical_width(), window.physical_height(), 1),
+                    TextureFormat::default(),
+                ))
+            }
+            ReadbackSource::Buffer {
+                buffer,
+                size,
+                format,
+            } => Some((CopySource::Buffer(*buffer), *size, *format)),
+        }
+    }
+
+    /// Draws the texture the window was captured into onto its swap chain
+    fn draw_capture(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        capture_texture: TextureId,
+        swap_chain_texture: TextureId,
+    ) {
+        let sampler = *self.sampler.get_or_insert_with(|| {
+            render_context
+                .resources()
+                .create_sampler(&SamplerDescriptor::default())
+        });
+        self.bindings
+            .set("Source", RenderResourceBinding::Texture(capture_texture));
+        self.bindings
+            .set("Source_sampler", RenderResourceBinding::Sampler(sampler));
+        let descriptor = PassDescriptor {
+            color_attachments: vec![RenderPassColorAttachmentDescriptor {
+                attachment: TextureAttachment::Id(swap_chain_texture),
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+            sample_count: 1,
+        };
+        self.blit_pass
+            .draw(world, render_context, &descriptor, &mut self.bindings);
+    }
+}
+
+impl Node for ReadbackNode {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        static INPUT: &[ResourceSlotInfo] = &[
+            ResourceSlotInfo {
+                name: Cow::Borrowed(ReadbackNode::IN_WINDOW_TEXTURE),
+                resource_type: RenderResourceType::Texture,
+            },
+            ResourceSlotInfo {
+                name: Cow::Borrowed(ReadbackNode::IN_SWAP_CHAIN_TEXTURE),
+                resource_type: RenderResourceType::Texture,
+            },
+        ];
+        INPUT
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        self.finish_copies(world);
+        self.blit_pass.prepare(world);
+
+        let mut readbacks = world.get_resource_mut::<Readbacks>().unwrap();
+        self.requests
+            .extend(readbacks.take_requests(self.window_id));
+        let window_id = self.window_id;
+        if self
+            .requests
+            .iter()
+            .any(|(source, _)| matches!(source, ReadbackSource::Window(id) if *id == window_id))
+        {
+            readbacks.captured_windows.insert(window_id);
+        } else {
+            readbacks.captured_windows.remove(&window_id);
+        }
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        _output: &mut ResourceSlots,
+    ) {
+        // the window is only drawn into a separate texture while it is captured
+        let capture = match (
+            input
+                .get(Self::IN_WINDOW_TEXTURE)
+                .and_then(|texture| texture.get_texture()),
+            input
+                .get(Self::IN_SWAP_CHAIN_TEXTURE)
+                .and_then(|texture| texture.get_texture()),
+        ) {
+            (Some(capture_texture), Some(swap_chain_texture))
+                if capture_texture != swap_chain_texture =>
+            {
+                Some((capture_texture, swap_chain_texture))
+            }
+            _ => None,
+        };
+
+        // requests whose source isn't available yet are kept for the next frame
+        for (source, handle) in std::mem::take(&mut self.requests) {
+            let (copy_source, size, format) = match self.copy_source(
+                world,
+                render_context,
+                &source,
+                capture.map(|(capture_texture, _)| capture_texture),
+            ) {
+                Some(copy_source) => copy_source,
+                None => {
+                    self.requests.push((source, handle));
+                    continue;
+                }
+            };
+
+            let row_size = size.width as usize * format.pixel_size();
+            let padded_row_size = match copy_source {
+                CopySource::Texture(_) => render_context
+                    .resources()
+                    .get_aligned_texture_size(row_size),
+                CopySource::Buffer(_) => row_size,
+            };
+            let buffer = render_context.resources().create_buffer(BufferInfo {
+                size: padded_row_size * size.height as usize,
+                buffer_usage: BufferUsage::MAP_READ | BufferUsage::COPY_DST,
+                ..Default::default()
+            });
+            match copy_source {
+                CopySource::Texture(texture) => render_context.copy_texture_to_buffer(
+                    texture,
+                    [0, 0, 0],
+                    0,
+                    buffer,
+                    0,
+                    padded_row_size as u32,
+                    size,
+                ),
+                CopySource::Buffer(source_buffer) => render_context.copy_buffer_to_buffer(
+                    source_buffer,
+                    0,
+                    buffer,
+                    0,
+                    (row_size * size.height as usize) as u64,
+                ),
+            }
+            self.copies.push(PendingCopy {
+                buffer,
+                size,
+                format,
+                padded_row_size,
+                handle,
+            });
+        }
+
+        if let Some((capture_texture, swap_chain_texture)) = capture {
+            self.draw_capture(world, render_context, capture_texture, swap_chain_texture);
+        }
+    }
+}
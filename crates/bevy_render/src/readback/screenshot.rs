@@ -0,0 +1,68 @@
+use super::{readback_windows, ReadbackSource, Readbacks};
+use crate::{
+    render_graph::RenderGraph,
+    texture::{ImageTextureSaver, Texture},
+};
+use bevy_asset::{AssetSaver, Assets, Handle};
+use bevy_ecs::system::{Res, ResMut};
+use bevy_utils::tracing::error;
+use bevy_window::WindowId;
+use std::path::{Path, PathBuf};
+
+/// Saves what is drawn to windows as PNG images
+#[derive(Debug, Default)]
+pub struct ScreenshotManager {
+    requests: Vec<(WindowId, PathBuf)>,
+    pending: Vec<(Handle<Texture>, PathBuf)>,
+}
+
+impl ScreenshotManager {
+    /// Saves the frame drawn to `window_id` at `path`. The image is captured at the end of this
+    /// frame and saved a few frames later, once it has been read back from the gpu. Only windows
+    /// with a [ReadbackNode](super::ReadbackNode) in the render graph can be captured.
+    pub fn take_screenshot(&mut self, window_id: WindowId, path: impl AsRef<Path>) {
+        self.requests.push((window_id, path.as_ref().to_path_buf()));
+    }
+}
+
+/// Requests the [Readbacks] of new screenshots, and saves the ones that have been read back
+pub fn screenshot_system(
+    mut screenshot_manager: ResMut<ScreenshotManager>,
+    mut readbacks: ResMut<Readbacks>,
+    textures: Res<Assets<Texture>>,
+    render_graph: Res<RenderGraph>,
+) {
+    let screenshot_manager = &mut *screenshot_manager;
+    if !screenshot_manager.requests.is_empty() {
+        let readback_windows = readback_windows(&render_graph);
+        screenshot_manager.requests.retain(|(window_id, path)| {
+            let captured = readback_windows.contains(window_id);
+            if !captured {
+                error!(
+                    "Failed to save screenshot to {:?}: window {} has no ReadbackNode.",
+                    path, window_id
+                );
+            }
+            captured
+        });
+    }
+    for (window_id, path) in screenshot_manager.requests.drain(..) {
+        let handle = readbacks.read(&textures, ReadbackSource::Window(window_id));
+        screenshot_manager.pending.push((handle, path));
+    }
+
+    screenshot_manager.pending.retain(|(handle, path)| {
+        let texture = if let Some(texture) = textures.get(handle) {
+            texture
+        } else {
+            return true;
+        };
+        let result = ImageTextureSaver
+            .save(texture, path)
+            .and_then(|bytes| Ok(std::fs::write(path, bytes)?));
+        if let Err(err) = result {
+            error!("Failed to save screenshot to {:?}: {}", path, err);
+        }
+        false
+    });
+}
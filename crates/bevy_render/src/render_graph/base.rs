@@ -1,18 +1,23 @@
 use super::{
-    CameraNode, PassNode, RenderGraph, SharedBuffersNode, TextureCopyNode, WindowSwapChainNode,
-    WindowTextureNode,
+    CameraNode, Edge, PassNode, PostProcessNode, RenderGraph, RenderGraphError, SharedBuffersNode,
+    TextureCopyNode, TextureTargetNode, WindowSwapChainNode, WindowTextureNode,
 };
 use crate::{
+    camera::ActiveCameras,
+    hdr::{BloomNode, TonemappingNode, HDR_TEXTURE_FORMAT},
     pass::{
         LoadOp, Operations, PassDescriptor, RenderPassColorAttachmentDescriptor,
         RenderPassDepthStencilAttachmentDescriptor, TextureAttachment,
     },
+    readback::ReadbackNode,
+    renderer::RenderResources,
     texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
     Color,
 };
-use bevy_ecs::{reflect::ReflectComponent, world::World};
+use bevy_ecs::{component::Component, reflect::ReflectComponent, world::World};
 use bevy_reflect::Reflect;
 use bevy_window::WindowId;
+use std::borrow::Cow;
 
 /// A component that indicates that an entity should be drawn in the "main pass"
 #[derive(Clone, Debug, Default, Reflect)]
@@ -59,6 +64,11 @@ pub struct BaseRenderGraphConfig {
     pub add_3d_camera: bool,
     pub add_main_depth_texture: bool,
     pub add_main_pass: bool,
+    /// Draws the main pass of the 3d camera into an HDR texture instead of the swap chain. The
+    /// texture gets bloom from the bloom node and is tonemapped into the swap chain by the
+    /// tonemapping node, with the [Tonemapping](crate::hdr::Tonemapping) of the 3d camera. The 2d
+    /// camera is then drawn over it by its own pass.
+    pub add_hdr_target: bool,
     pub connect_main_pass_to_swapchain: bool,
     pub connect_main_pass_to_main_depth_texture: bool,
 }
@@ -70,8 +80,14 @@ pub mod node {
     pub const TEXTURE_COPY: &str = "texture_copy";
     pub const MAIN_DEPTH_TEXTURE: &str = "main_pass_depth_texture";
     pub const MAIN_SAMPLED_COLOR_ATTACHMENT: &str = "main_pass_sampled_color_attachment";
+    pub const MAIN_HDR_TEXTURE: &str = "main_pass_hdr_texture";
+    pub const MAIN_SAMPLED_HDR_ATTACHMENT: &str = "main_pass_sampled_hdr_attachment";
     pub const MAIN_PASS: &str = "main_pass";
+    pub const MAIN_PASS_2D: &str = "main_pass_2d";
+    pub const BLOOM: &str = "bloom";
+    pub const TONEMAPPING: &str = "tonemapping";
     pub const SHARED_BUFFERS: &str = "shared_buffers";
+    pub const READBACK: &str = "readback";
 }
 
 pub mod camera {
@@ -86,6 +102,7 @@ impl Default for BaseRenderGraphConfig {
             add_3d_camera: true,
             add_main_pass: true,
             add_main_depth_texture: true,
+            add_hdr_target: true,
             connect_main_pass_to_swapchain: true,
             connect_main_pass_to_main_depth_texture: true,
         }
@@ -154,12 +171,16 @@ pub(crate) fn add_base_graph(config: &BaseRenderGraphConfig, world: &mut World)
         });
 
         main_pass_node.use_default_clear_color(0);
+        if config.add_hdr_target {
+            main_pass_node.set_color_target_format(HDR_TEXTURE_FORMAT);
+        }
 
         if config.add_3d_camera {
             main_pass_node.add_camera(camera::CAMERA_3D);
         }
 
-        if config.add_2d_camera {
+        // with an hdr target, the 2d camera is drawn after tonemapping instead
+        if config.add_2d_camera && !config.add_hdr_target {
             main_pass_node.add_camera(camera::CAMERA_2D);
         }
 
@@ -178,32 +199,79 @@ pub(crate) fn add_base_graph(config: &BaseRenderGraphConfig, world: &mut World)
                 .unwrap();
         }
 
-        if config.add_2d_camera {
+        if config.add_2d_camera && !config.add_hdr_target {
             graph
                 .add_node_edge(node::CAMERA_2D, node::MAIN_PASS)
                 .unwrap();
         }
     }
 
+    // with an hdr target, the main pass draws into it and the tonemapping node draws it into the
+    // swap chain, so the tonemapping node and the 2d pass take the place of the main pass below
+    let swap_chain_passes: &[&str] = if config.add_hdr_target {
+        add_hdr_nodes(config, &mut graph, &msaa);
+        if config.add_main_pass && config.add_2d_camera {
+            add_main_pass_2d(&mut graph, &msaa);
+            &[node::TONEMAPPING, node::MAIN_PASS_2D]
+        } else {
+            &[node::TONEMAPPING]
+        }
+    } else {
+        &[node::MAIN_PASS]
+    };
+
     graph.add_node(
         node::PRIMARY_SWAP_CHAIN,
         WindowSwapChainNode::new(WindowId::primary()),
     );
 
-    if config.connect_main_pass_to_swapchain {
+    // the readback node copies the swap chain once the passes that draw into it have run
+    graph.add_node(node::READBACK, ReadbackNode::new(WindowId::primary()));
+    graph
+        .add_slot_edge(
+            node::PRIMARY_SWAP_CHAIN,
+            WindowSwapChainNode::OUT_TEXTURE,
+            node::READBACK,
+            ReadbackNode::IN_WINDOW_TEXTURE,
+        )
+        .unwrap();
+    graph
+        .add_slot_edge(
+            node::PRIMARY_SWAP_CHAIN,
+            WindowSwapChainNode::OUT_SWAP_CHAIN_TEXTURE,
+            node::READBACK,
+            ReadbackNode::IN_SWAP_CHAIN_TEXTURE,
+        )
+        .unwrap();
+    if config.add_main_pass {
         graph
-            .add_slot_edge(
-                node::PRIMARY_SWAP_CHAIN,
-                WindowSwapChainNode::OUT_TEXTURE,
-                node::MAIN_PASS,
-                if msaa.samples > 1 {
-                    "color_resolve_target"
-                } else {
-                    "color_attachment"
-                },
-            )
+            .add_node_edge(node::MAIN_PASS, node::READBACK)
             .unwrap();
     }
+    if config.add_hdr_target {
+        for swap_chain_pass in swap_chain_passes.iter() {
+            graph
+                .add_node_edge(*swap_chain_pass, node::READBACK)
+                .unwrap();
+        }
+    }
+
+    if config.connect_main_pass_to_swapchain {
+        for swap_chain_pass in swap_chain_passes.iter() {
+            graph
+                .add_slot_edge(
+                    node::PRIMARY_SWAP_CHAIN,
+                    WindowSwapChainNode::OUT_TEXTURE,
+                    *swap_chain_pass,
+                    if msaa.samples > 1 {
+                        "color_resolve_target"
+                    } else {
+                        "color_attachment"
+                    },
+                )
+                .unwrap();
+        }
+    }
 
     if msaa.samples > 1 {
         graph.add_node(
@@ -225,24 +293,483 @@ pub(crate) fn add_base_graph(config: &BaseRenderGraphConfig, world: &mut World)
             ),
         );
 
+        for swap_chain_pass in swap_chain_passes.iter() {
+            graph
+                .add_slot_edge(
+                    node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+                    WindowSwapChainNode::OUT_TEXTURE,
+                    *swap_chain_pass,
+                    "color_attachment",
+                )
+                .unwrap();
+        }
+    }
+
+    if config.connect_main_pass_to_main_depth_texture {
+        for pass in [node::MAIN_PASS, node::MAIN_PASS_2D].iter() {
+            if graph.get_node_id(*pass).is_ok() {
+                graph
+                    .add_slot_edge(
+                        node::MAIN_DEPTH_TEXTURE,
+                        WindowTextureNode::OUT_TEXTURE,
+                        *pass,
+                        "depth",
+                    )
+                    .unwrap();
+            }
+        }
+    }
+}
+
+/// Adds the pass that draws the entities of the main pass seen by the 2d camera over the
+/// tonemapped main pass, keeping its colors and depth
+fn add_main_pass_2d(graph: &mut RenderGraph, msaa: &Msaa) {
+    let mut main_pass_2d_node = PassNode::<&MainPass>::new(PassDescriptor {
+        color_attachments: vec![msaa.color_attachment_descriptor(
+            TextureAttachment::Input("color_attachment".to_string()),
+            TextureAttachment::Input("color_resolve_target".to_string()),
+            Operations {
+                load: LoadOp::Load,
+                store: true,
+            },
+        )],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+            attachment: TextureAttachment::Input("depth".to_string()),
+            depth_ops: Some(Operations {
+                load: LoadOp::Load,
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+        sample_count: msaa.samples,
+    });
+    main_pass_2d_node.add_camera(camera::CAMERA_2D);
+    graph.add_node(node::MAIN_PASS_2D, main_pass_2d_node);
+
+    for node in [node::CAMERA_2D, node::TEXTURE_COPY, node::SHARED_BUFFERS].iter() {
+        graph.add_node_edge(*node, node::MAIN_PASS_2D).unwrap();
+    }
+    graph
+        .add_node_edge(node::TONEMAPPING, node::MAIN_PASS_2D)
+        .unwrap();
+}
+
+/// Adds the hdr texture the main pass draws into, and the bloom and tonemapping nodes that run
+/// after the main pass
+fn add_hdr_nodes(config: &BaseRenderGraphConfig, graph: &mut RenderGraph, msaa: &Msaa) {
+    let hdr_texture_descriptor = |sample_count, usage| TextureDescriptor {
+        size: Extent3d {
+            depth: 1,
+            width: 1,
+            height: 1,
+        },
+        mip_level_count: 1,
+        sample_count,
+        dimension: TextureDimension::D2,
+        format: HDR_TEXTURE_FORMAT,
+        usage,
+    };
+    graph.add_node(
+        node::MAIN_HDR_TEXTURE,
+        WindowTextureNode::new(
+            WindowId::primary(),
+            hdr_texture_descriptor(1, TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED),
+        ),
+    );
+
+    // only the 3d camera draws into the hdr texture
+    let mut bloom_node = BloomNode::new(WindowId::primary());
+    bloom_node.add_camera(camera::CAMERA_3D);
+    graph.add_node(node::BLOOM, bloom_node);
+    graph.add_node(
+        node::TONEMAPPING,
+        TonemappingNode::new(msaa, camera::CAMERA_3D),
+    );
+    graph
+        .add_slot_edge(
+            node::MAIN_HDR_TEXTURE,
+            WindowTextureNode::OUT_TEXTURE,
+            node::BLOOM,
+            BloomNode::IN_HDR_TEXTURE,
+        )
+        .unwrap();
+    graph
+        .add_slot_edge(
+            node::MAIN_HDR_TEXTURE,
+            WindowTextureNode::OUT_TEXTURE,
+            node::TONEMAPPING,
+            TonemappingNode::IN_HDR_TEXTURE,
+        )
+        .unwrap();
+    graph.add_node_edge(node::BLOOM, node::TONEMAPPING).unwrap();
+
+    if !config.add_main_pass {
+        return;
+    }
+
+    graph.add_node_edge(node::MAIN_PASS, node::BLOOM).unwrap();
+    graph
+        .add_node_edge(node::MAIN_PASS, node::TONEMAPPING)
+        .unwrap();
+    if msaa.samples > 1 {
+        graph.add_node(
+            node::MAIN_SAMPLED_HDR_ATTACHMENT,
+            WindowTextureNode::new(
+                WindowId::primary(),
+                hdr_texture_descriptor(msaa.samples, TextureUsage::OUTPUT_ATTACHMENT),
+            ),
+        );
         graph
             .add_slot_edge(
-                node::MAIN_SAMPLED_COLOR_ATTACHMENT,
-                WindowSwapChainNode::OUT_TEXTURE,
+                node::MAIN_SAMPLED_HDR_ATTACHMENT,
+                WindowTextureNode::OUT_TEXTURE,
                 node::MAIN_PASS,
                 "color_attachment",
             )
             .unwrap();
-    }
-
-    if config.connect_main_pass_to_main_depth_texture {
         graph
             .add_slot_edge(
-                node::MAIN_DEPTH_TEXTURE,
+                node::MAIN_HDR_TEXTURE,
                 WindowTextureNode::OUT_TEXTURE,
                 node::MAIN_PASS,
-                "depth",
+                "color_resolve_target",
             )
             .unwrap();
+    } else {
+        graph
+            .add_slot_edge(
+                node::MAIN_HDR_TEXTURE,
+                WindowTextureNode::OUT_TEXTURE,
+                node::MAIN_PASS,
+                "color_attachment",
+            )
+            .unwrap();
+    }
+}
+
+/// Adds a camera named `camera_name` that draws the entities of the main pass into its
+/// [RenderTarget::Texture](crate::camera::RenderTarget::Texture), in the format of the texture.
+/// It is drawn before the main pass, so the texture can be sampled by materials there, and read
+/// back with [Readbacks](crate::readback::Readbacks). The nodes are named after the camera:
+/// `{camera_name}_camera`, `{camera_name}_target` and `{camera_name}_pass`.
+pub fn add_texture_target_camera(
+    graph: &mut RenderGraph,
+    active_cameras: &mut ActiveCameras,
+    msaa: &Msaa,
+    camera_name: &str,
+) {
+    let camera_node = format!("{}_camera", camera_name);
+    let target_node = format!("{}_target", camera_name);
+    let pass_node = format!("{}_pass", camera_name);
+    active_cameras.add(camera_name);
+    graph.add_system_node(
+        camera_node.clone(),
+        CameraNode::new(camera_name.to_string()),
+    );
+    graph.add_node(
+        target_node.clone(),
+        TextureTargetNode::new(camera_name, msaa),
+    );
+
+    let mut texture_pass = PassNode::<&MainPass>::new(PassDescriptor {
+        color_attachments: vec![msaa.color_attachment_descriptor(
+            TextureAttachment::Input("color_attachment".to_string()),
+            TextureAttachment::Input("color_resolve_target".to_string()),
+            Operations {
+                load: LoadOp::Clear(Color::rgb(0.1, 0.1, 0.1)),
+                store: true,
+            },
+        )],
+        depth_stencil_attachment: Some(RenderPassDepthStencilAttachmentDescriptor {
+            attachment: TextureAttachment::Input("depth".to_string()),
+            depth_ops: Some(Operations {
+                load: LoadOp::Clear(1.0),
+                store: true,
+            }),
+            stencil_ops: None,
+        }),
+        sample_count: msaa.samples,
+    });
+    texture_pass.use_default_clear_color(0);
+    texture_pass.add_camera(camera_name);
+    graph.add_node(pass_node.clone(), texture_pass);
+
+    graph.add_node_edge(camera_node, pass_node.clone()).unwrap();
+    graph
+        .add_slot_edge(
+            target_node.clone(),
+            TextureTargetNode::OUT_COLOR_ATTACHMENT,
+            pass_node.clone(),
+            "color_attachment",
+        )
+        .unwrap();
+    graph
+        .add_slot_edge(
+            target_node.clone(),
+            TextureTargetNode::OUT_DEPTH,
+            pass_node.clone(),
+            "depth",
+        )
+        .unwrap();
+    if msaa.samples > 1 {
+        graph
+            .add_slot_edge(
+                target_node,
+                TextureTargetNode::OUT_COLOR_RESOLVE_TARGET,
+                pass_node.clone(),
+                "color_resolve_target",
+            )
+            .unwrap();
+    }
+
+    for node in [node::TEXTURE_COPY, node::SHARED_BUFFERS].iter() {
+        if graph.get_node_id(*node).is_ok() {
+            graph.add_node_edge(*node, pass_node.clone()).unwrap();
+        }
+    }
+    if graph.get_node_id(node::MAIN_PASS).is_ok() {
+        graph
+            .add_node_edge(pass_node.clone(), node::MAIN_PASS)
+            .unwrap();
+    }
+    // so the texture can be read back in the same frame
+    if graph.get_node_id(node::READBACK).is_ok() {
+        graph.add_node_edge(pass_node, node::READBACK).unwrap();
+    }
+}
+
+/// Adds `post_process_node` to the main pass, between its HDR texture and the tonemapping node.
+/// Nodes added this way run in the order they were added, each one reading what the one before
+/// it drew. This requires a base render graph configured with `add_hdr_target`.
+pub fn add_main_post_process_node<T: RenderResources + Component>(
+    graph: &mut RenderGraph,
+    name: impl Into<Cow<'static, str>>,
+    post_process_node: PostProcessNode<T>,
+) -> Result<(), RenderGraphError> {
+    let tonemapping = graph.get_node_state(node::TONEMAPPING)?;
+    let hdr_texture_index = tonemapping
+        .input_slots
+        .get_slot_index(TonemappingNode::IN_HDR_TEXTURE)?;
+    let (source_node, source_index) = tonemapping
+        .edges
+        .input_edges
+        .iter()
+        .find_map(|edge| match edge {
+            Edge::SlotEdge {
+                output_node,
+                output_index,
+                input_index,
+                ..
+            } if *input_index == hdr_texture_index => Some((*output_node, *output_index)),
+            _ => None,
+        })
+        .ok_or(RenderGraphError::UnconnectedNodeInputSlot {
+            node: tonemapping.id,
+            input_slot: hdr_texture_index,
+        })?;
+
+    graph.remove_slot_edge(
+        source_node,
+        source_index,
+        node::TONEMAPPING,
+        hdr_texture_index,
+    )?;
+    let post_process_node = graph.add_node(name, post_process_node);
+    graph.add_slot_edge(
+        source_node,
+        source_index,
+        post_process_node,
+        PostProcessNode::<T>::IN_TEXTURE,
+    )?;
+    graph.add_slot_edge(
+        post_process_node,
+        PostProcessNode::<T>::OUT_TEXTURE,
+        node::TONEMAPPING,
+        hdr_texture_index,
+    )?;
+
+    // bloom is drawn into the hdr texture after the main pass, so both have to run first
+    graph.add_node_edge(node::BLOOM, post_process_node)?;
+    if graph.get_node_id(node::MAIN_PASS).is_ok() {
+        graph.add_node_edge(node::MAIN_PASS, post_process_node)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input_nodes(graph: &RenderGraph, node: &'static str) -> Vec<String> {
+        graph
+            .iter_node_inputs(node)
+            .unwrap()
+            .map(|(_, input)| input.name.as_ref().unwrap().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn main_pass_is_tonemapped_into_the_swap_chain() {
+        let mut world = World::default();
+        world.insert_resource(RenderGraph::default());
+        world.insert_resource(Msaa { samples: 4 });
+        add_base_graph(&BaseRenderGraphConfig::default(), &mut world);
+
+        let graph = world.get_resource::<RenderGraph>().unwrap();
+        let main_pass_inputs = input_nodes(graph, node::MAIN_PASS);
+        assert!(main_pass_inputs.contains(&node::MAIN_HDR_TEXTURE.to_string()));
+        assert!(main_pass_inputs.contains(&node::MAIN_SAMPLED_HDR_ATTACHMENT.to_string()));
+        assert!(!main_pass_inputs.contains(&node::PRIMARY_SWAP_CHAIN.to_string()));
+        assert!(!main_pass_inputs.contains(&node::CAMERA_2D.to_string()));
+
+        let tonemapping_inputs = input_nodes(graph, node::TONEMAPPING);
+        for input in [
+            node::MAIN_PASS,
+            node::BLOOM,
+            node::MAIN_HDR_TEXTURE,
+            node::PRIMARY_SWAP_CHAIN,
+            node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+        ]
+        .iter()
+        {
+            assert!(tonemapping_inputs.contains(&input.to_string()));
+        }
+
+        // the 2d camera is drawn over the tonemapped main pass
+        let main_pass_2d_inputs = input_nodes(graph, node::MAIN_PASS_2D);
+        for input in [
+            node::CAMERA_2D,
+            node::TONEMAPPING,
+            node::PRIMARY_SWAP_CHAIN,
+            node::MAIN_SAMPLED_COLOR_ATTACHMENT,
+            node::MAIN_DEPTH_TEXTURE,
+        ]
+        .iter()
+        {
+            assert!(main_pass_2d_inputs.contains(&input.to_string()));
+        }
+    }
+
+    #[test]
+    fn main_pass_draws_into_the_swap_chain_without_hdr_target() {
+        let mut world = World::default();
+        world.insert_resource(RenderGraph::default());
+        world.insert_resource(Msaa::default());
+        add_base_graph(
+            &BaseRenderGraphConfig {
+                add_hdr_target: false,
+                ..Default::default()
+            },
+            &mut world,
+        );
+
+        let graph = world.get_resource::<RenderGraph>().unwrap();
+        assert!(graph.get_node_state(node::TONEMAPPING).is_err());
+        assert!(graph.get_node_state(node::MAIN_PASS_2D).is_err());
+        let main_pass_inputs = input_nodes(graph, node::MAIN_PASS);
+        assert!(main_pass_inputs.contains(&node::PRIMARY_SWAP_CHAIN.to_string()));
+        assert!(main_pass_inputs.contains(&node::CAMERA_2D.to_string()));
+    }
+
+    #[test]
+    fn post_process_nodes_are_chained_before_tonemapping() {
+        use crate::renderer::{RenderResource, RenderResourceIterator};
+        use bevy_asset::Handle;
+
+        struct Effect;
+
+        impl RenderResources for Effect {
+            fn render_resources_len(&self) -> usize {
+                0
+            }
+
+            fn get_render_resource(&self, _index: usize) -> Option<&dyn RenderResource> {
+                None
+            }
+
+            fn get_render_resource_name(&self, _index: usize) -> Option<&str> {
+                None
+            }
+
+            fn iter(&self) -> RenderResourceIterator {
+                RenderResourceIterator::new(self)
+            }
+        }
+
+        let mut world = World::default();
+        world.insert_resource(RenderGraph::default());
+        world.insert_resource(Msaa::default());
+        add_base_graph(&BaseRenderGraphConfig::default(), &mut world);
+
+        let mut graph = world.get_resource_mut::<RenderGraph>().unwrap();
+        for name in ["first_effect", "second_effect"].iter() {
+            add_main_post_process_node(
+                &mut graph,
+                *name,
+                PostProcessNode::<Effect>::new(
+                    WindowId::primary(),
+                    HDR_TEXTURE_FORMAT,
+                    Handle::default(),
+                ),
+            )
+            .unwrap();
+        }
+
+        let first_inputs = input_nodes(&graph, "first_effect");
+        for input in [node::MAIN_HDR_TEXTURE, node::MAIN_PASS, node::BLOOM].iter() {
+            assert!(first_inputs.contains(&input.to_string()));
+        }
+        assert!(input_nodes(&graph, "second_effect").contains(&"first_effect".to_string()));
+
+        let tonemapping_inputs = input_nodes(&graph, node::TONEMAPPING);
+        assert!(tonemapping_inputs.contains(&"second_effect".to_string()));
+        assert!(!tonemapping_inputs.contains(&"first_effect".to_string()));
+        assert!(!tonemapping_inputs.contains(&node::MAIN_HDR_TEXTURE.to_string()));
+    }
+
+    #[test]
+    fn texture_target_cameras_are_drawn_before_the_main_pass() {
+        let mut world = World::default();
+        world.insert_resource(RenderGraph::default());
+        world.insert_resource(Msaa { samples: 4 });
+        add_base_graph(&BaseRenderGraphConfig::default(), &mut world);
+
+        let mut active_cameras = ActiveCameras::default();
+        let mut graph = world.get_resource_mut::<RenderGraph>().unwrap();
+        add_texture_target_camera(
+            &mut graph,
+            &mut active_cameras,
+            &Msaa { samples: 4 },
+            "mirror",
+        );
+
+        assert!(active_cameras.get("mirror").is_some());
+        let pass_inputs = input_nodes(&graph, "mirror_pass");
+        for input in ["mirror_camera", "mirror_target", node::TEXTURE_COPY].iter() {
+            assert!(pass_inputs.contains(&input.to_string()));
+        }
+        assert!(input_nodes(&graph, node::MAIN_PASS).contains(&"mirror_pass".to_string()));
+        assert!(input_nodes(&graph, node::READBACK).contains(&"mirror_pass".to_string()));
+    }
+
+    #[test]
+    fn readback_runs_after_the_swap_chain_is_drawn() {
+        let mut world = World::default();
+        world.insert_resource(RenderGraph::default());
+        world.insert_resource(Msaa::default());
+        add_base_graph(&BaseRenderGraphConfig::default(), &mut world);
+
+        let graph = world.get_resource::<RenderGraph>().unwrap();
+        let readback_inputs = input_nodes(graph, node::READBACK);
+        for input in [
+            node::PRIMARY_SWAP_CHAIN,
+            node::MAIN_PASS,
+            node::TONEMAPPING,
+            node::MAIN_PASS_2D,
+        ]
+        .iter()
+        {
+            assert!(readback_inputs.contains(&input.to_string()));
+        }
     }
 }
@@ -166,6 +166,60 @@ impl RenderGraph {
         Ok(())
     }
 
+    pub fn remove_slot_edge(
+        &mut self,
+        output_node: impl Into<NodeLabel>,
+        output_slot: impl Into<SlotLabel>,
+        input_node: impl Into<NodeLabel>,
+        input_slot: impl Into<SlotLabel>,
+    ) -> Result<(), RenderGraphError> {
+        let output_node_id = self.get_node_id(output_node)?;
+        let input_node_id = self.get_node_id(input_node)?;
+
+        let output_index = self
+            .get_node_state(output_node_id)?
+            .output_slots
+            .get_slot_index(output_slot)?;
+        let input_index = self
+            .get_node_state(input_node_id)?
+            .input_slots
+            .get_slot_index(input_slot)?;
+
+        self.remove_edge(Edge::SlotEdge {
+            output_node: output_node_id,
+            output_index,
+            input_node: input_node_id,
+            input_index,
+        })
+    }
+
+    pub fn remove_node_edge(
+        &mut self,
+        output_node: impl Into<NodeLabel>,
+        input_node: impl Into<NodeLabel>,
+    ) -> Result<(), RenderGraphError> {
+        let output_node_id = self.get_node_id(output_node)?;
+        let input_node_id = self.get_node_id(input_node)?;
+
+        self.remove_edge(Edge::NodeEdge {
+            output_node: output_node_id,
+            input_node: input_node_id,
+        })
+    }
+
+    fn remove_edge(&mut self, edge: Edge) -> Result<(), RenderGraphError> {
+        if !self.has_edge(&edge) {
+            return Err(RenderGraphError::EdgeDoesNotExist(edge));
+        }
+
+        self.get_node_state_mut(edge.get_output_node())?
+            .edges
+            .remove_output_edge(&edge)?;
+        self.get_node_state_mut(edge.get_input_node())?
+            .edges
+            .remove_input_edge(&edge)
+    }
+
     pub fn validate_edge(&mut self, edge: &Edge) -> Result<(), RenderGraphError> {
         if self.has_edge(edge) {
             return Err(RenderGraphError::EdgeAlreadyExists(edge.clone()));
@@ -481,4 +535,37 @@ mod tests {
             "Adding to a duplicate edge should return an error"
         );
     }
+
+    #[test]
+    pub fn test_remove_edges() {
+        let mut graph = RenderGraph::default();
+
+        graph.add_node("A", TestNode::new(0, 1));
+        graph.add_node("B", TestNode::new(1, 0));
+
+        graph.add_slot_edge("A", 0, "B", 0).unwrap();
+        graph.add_node_edge("A", "B").unwrap();
+
+        graph.remove_slot_edge("A", 0, "B", 0).unwrap();
+        graph.remove_node_edge("A", "B").unwrap();
+        assert!(
+            graph.iter_node_inputs("B").unwrap().next().is_none(),
+            "B has no inputs"
+        );
+        assert!(
+            graph.iter_node_outputs("A").unwrap().next().is_none(),
+            "A has no outputs"
+        );
+
+        assert_eq!(
+            graph.remove_node_edge("A", "B"),
+            Err(RenderGraphError::EdgeDoesNotExist(Edge::NodeEdge {
+                output_node: graph.get_node_id("A").unwrap(),
+                input_node: graph.get_node_id("B").unwrap(),
+            })),
+            "Removing an edge that does not exist should return an error"
+        );
+
+        graph.add_slot_edge("A", 0, "B", 0).unwrap();
+    }
 }
@@ -73,6 +73,24 @@ impl Edges {
         Ok(())
     }
 
+    pub(crate) fn remove_input_edge(&mut self, edge: &Edge) -> Result<(), RenderGraphError> {
+        if let Some(index) = self.input_edges.iter().position(|e| e == edge) {
+            self.input_edges.swap_remove(index);
+            Ok(())
+        } else {
+            Err(RenderGraphError::EdgeDoesNotExist(edge.clone()))
+        }
+    }
+
+    pub(crate) fn remove_output_edge(&mut self, edge: &Edge) -> Result<(), RenderGraphError> {
+        if let Some(index) = self.output_edges.iter().position(|e| e == edge) {
+            self.output_edges.swap_remove(index);
+            Ok(())
+        } else {
+            Err(RenderGraphError::EdgeDoesNotExist(edge.clone()))
+        }
+    }
+
     pub fn has_input_edge(&self, edge: &Edge) -> bool {
         self.input_edges.contains(edge)
     }
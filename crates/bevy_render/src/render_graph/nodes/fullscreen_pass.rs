@@ -0,0 +1,177 @@
+use crate::{
+    pass::PassDescriptor,
+    pipeline::{
+        ColorTargetState, CullMode, PipelineCompiler, PipelineDescriptor, PipelineSpecialization,
+    },
+    renderer::{
+        BufferId, BufferInfo, BufferUsage, RenderContext, RenderResourceBindings,
+        RenderResourceContext,
+    },
+    shader::{Shader, ShaderStages},
+};
+use bevy_asset::{Assets, Handle, HandleUntyped};
+use bevy_ecs::world::World;
+use bevy_reflect::TypeUuid;
+
+/// The vertex shader of full screen passes. It draws a triangle that covers the whole attachment
+/// and passes the uv of each fragment to the fragment shader at location 0.
+pub const FULLSCREEN_SHADER_HANDLE: HandleUntyped =
+    HandleUntyped::weak_from_u64(Shader::TYPE_UUID, 0x5c3b1a9e2d4f7061);
+
+/// Builds a pipeline that runs `fragment` over a whole attachment with a single color target
+pub fn build_fullscreen_pipeline(
+    name: &str,
+    fragment: Handle<Shader>,
+    color_target_state: ColorTargetState,
+) -> PipelineDescriptor {
+    let mut pipeline = PipelineDescriptor::new(ShaderStages {
+        vertex: FULLSCREEN_SHADER_HANDLE.typed(),
+        fragment: Some(fragment),
+    });
+    pipeline.name = Some(name.into());
+    pipeline.primitive.cull_mode = CullMode::None;
+    pipeline.color_target_states = vec![color_target_state];
+    pipeline
+}
+
+/// Draws a pipeline over a whole attachment, for render graph nodes that post process textures.
+/// The vertex stage of the pipeline should be [FULLSCREEN_SHADER_HANDLE], see
+/// [build_fullscreen_pipeline].
+#[derive(Debug)]
+pub struct FullscreenPass {
+    pipeline: Handle<PipelineDescriptor>,
+    specialization: PipelineSpecialization,
+    specialized_pipeline: Option<Handle<PipelineDescriptor>>,
+}
+
+impl FullscreenPass {
+    pub fn new(
+        pipeline: Handle<PipelineDescriptor>,
+        specialization: PipelineSpecialization,
+    ) -> Self {
+        FullscreenPass {
+            pipeline,
+            specialization,
+            specialized_pipeline: None,
+        }
+    }
+
+    pub fn specialization(&self) -> &PipelineSpecialization {
+        &self.specialization
+    }
+
+    pub fn set_specialization(&mut self, specialization: PipelineSpecialization) {
+        self.specialization = specialization;
+    }
+
+    /// Compiles the pipeline for the current specialization, unless it has already been compiled
+    /// or its shaders haven't finished loading. Call this from
+    /// [Node::prepare](crate::render_graph::Node::prepare).
+    pub fn prepare(&mut self, world: &mut World) {
+        let world = world.cell();
+        {
+            let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+            let shaders = world.get_resource::<Assets<Shader>>().unwrap();
+            let loaded = pipelines.get(&self.pipeline).map_or(false, |pipeline| {
+                pipeline
+                    .shader_stages
+                    .iter()
+                    .all(|shader| shaders.get(shader).is_some())
+            });
+            if !loaded {
+                self.specialized_pipeline = None;
+                return;
+            }
+        }
+        let mut pipeline_compiler = world.get_resource_mut::<PipelineCompiler>().unwrap();
+        let render_resource_context = world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap();
+        self.specialized_pipeline = Some(
+            pipeline_compiler
+                .get_specialized_pipeline(&self.pipeline, &self.specialization)
+                .unwrap_or_else(|| {
+                    let mut pipelines = world
+                        .get_resource_mut::<Assets<PipelineDescriptor>>()
+                        .unwrap();
+                    let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+                    pipeline_compiler.compile_pipeline(
+                        &**render_resource_context,
+                        &mut pipelines,
+                        &mut shaders,
+                        &self.pipeline,
+                        &self.specialization,
+                    )
+                }),
+        );
+    }
+
+    /// Runs the pass with the textures, samplers and buffers of `bindings`. Nothing is drawn if
+    /// the pipeline hasn't been prepared or one of its bindings is missing, in which case this
+    /// returns `false`.
+    pub fn draw(
+        &self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        pass_descriptor: &PassDescriptor,
+        bindings: &mut RenderResourceBindings,
+    ) -> bool {
+        let pipeline_handle = if let Some(pipeline_handle) = &self.specialized_pipeline {
+            pipeline_handle
+        } else {
+            return false;
+        };
+        let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+        let layout = pipelines
+            .get(pipeline_handle)
+            .unwrap()
+            .get_layout()
+            .unwrap();
+        let mut bind_groups = Vec::with_capacity(layout.bind_groups.len());
+        for bind_group_descriptor in layout.bind_groups.iter() {
+            match bindings.update_bind_group(bind_group_descriptor, render_context.resources()) {
+                Some(bind_group) => bind_groups.push((
+                    bind_group_descriptor.index,
+                    bind_group_descriptor.id,
+                    bind_group.id,
+                )),
+                None => return false,
+            }
+        }
+
+        render_context.begin_pass(pass_descriptor, bindings, &mut |render_pass| {
+            render_pass.set_pipeline(pipeline_handle);
+            for (index, bind_group_descriptor_id, bind_group_id) in bind_groups.iter() {
+                render_pass.set_bind_group(*index, *bind_group_descriptor_id, *bind_group_id, None);
+            }
+            render_pass.draw(0..3, 0..1);
+        });
+        true
+    }
+}
+
+/// Writes `data` to a uniform buffer through a staging buffer. The uniform buffer is created on
+/// the first write.
+pub fn write_uniform_buffer(
+    render_context: &mut dyn RenderContext,
+    buffer: &mut Option<BufferId>,
+    data: &[u8],
+) -> BufferId {
+    let buffer = *buffer.get_or_insert_with(|| {
+        render_context.resources().create_buffer(BufferInfo {
+            size: data.len(),
+            buffer_usage: BufferUsage::UNIFORM | BufferUsage::COPY_DST,
+            ..Default::default()
+        })
+    });
+    let staging_buffer = render_context.resources().create_buffer_with_data(
+        BufferInfo {
+            buffer_usage: BufferUsage::COPY_SRC,
+            ..Default::default()
+        },
+        data,
+    );
+    render_context.copy_buffer_to_buffer(staging_buffer, 0, buffer, 0, data.len() as u64);
+    render_context.resources().remove_buffer(staging_buffer);
+    buffer
+}
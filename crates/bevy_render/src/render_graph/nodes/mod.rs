@@ -1,15 +1,21 @@
 mod camera_node;
+mod fullscreen_pass;
 mod pass_node;
+mod post_process_node;
 mod render_resources_node;
 mod shared_buffers_node;
 mod texture_copy_node;
+mod texture_target_node;
 mod window_swapchain_node;
 mod window_texture_node;
 
 pub use camera_node::*;
+pub use fullscreen_pass::*;
 pub use pass_node::*;
+pub use post_process_node::*;
 pub use render_resources_node::*;
 pub use shared_buffers_node::*;
 pub use texture_copy_node::*;
+pub use texture_target_node::*;
 pub use window_swapchain_node::*;
 pub use window_texture_node::*;
@@ -1,17 +1,20 @@
 use crate::{
-    camera::{ActiveCameras, VisibleEntities},
+    camera::{ActiveCameras, Camera, RenderTarget, VisibleEntities},
     draw::{Draw, RenderCommand},
     pass::{ClearColor, LoadOp, PassDescriptor, TextureAttachment},
-    pipeline::{IndexFormat, PipelineDescriptor},
+    pipeline::{IndexFormat, PipelineCompiler, PipelineDescriptor, PipelineSpecialization},
     prelude::Visible,
     render_graph::{Node, ResourceSlotInfo, ResourceSlots},
     renderer::{
         BindGroupId, BufferId, RenderContext, RenderResourceBindings, RenderResourceContext,
         RenderResourceType,
     },
+    shader::Shader,
+    texture::{Texture, TextureFormat},
 };
 use bevy_asset::{Assets, Handle};
 use bevy_ecs::{
+    entity::Entity,
     query::{QueryState, ReadOnlyFetch, WorldQuery},
     world::{Mut, World},
 };
@@ -22,6 +25,7 @@ pub struct PassNode<Q: WorldQuery> {
     descriptor: PassDescriptor,
     inputs: Vec<ResourceSlotInfo>,
     cameras: Vec<String>,
+    color_target_format: Option<TextureFormat>,
     color_attachment_input_indices: Vec<Option<usize>>,
     color_resolve_target_indices: Vec<Option<usize>>,
     depth_stencil_attachment_input_index: Option<usize>,
@@ -36,6 +40,7 @@ impl<Q: WorldQuery> fmt::Debug for PassNode<Q> {
             .field("descriptor", &self.descriptor)
             .field("inputs", &self.inputs)
             .field("cameras", &self.cameras)
+            .field("color_target_format", &self.color_target_format)
             .field(
                 "color_attachment_input_indices",
                 &self.color_attachment_input_indices,
@@ -98,6 +103,7 @@ impl<Q: WorldQuery> PassNode<Q> {
             descriptor,
             inputs,
             cameras: Vec::new(),
+            color_target_format: None,
             color_attachment_input_indices,
             color_resolve_target_indices,
             depth_stencil_attachment_input_index,
@@ -114,6 +120,84 @@ impl<Q: WorldQuery> PassNode<Q> {
     pub fn use_default_clear_color(&mut self, color_attachment_index: usize) {
         self.default_clear_color_inputs.push(color_attachment_index);
     }
+
+    /// Draws into color attachments of `format`, such as an HDR texture. The pipelines of the
+    /// entities drawn by the pass are specialized for it. Without a format, cameras that render to
+    /// a [RenderTarget::Texture] draw in the format of their texture, and the others in the default
+    /// format.
+    pub fn set_color_target_format(&mut self, format: TextureFormat) {
+        self.color_target_format = Some(format);
+    }
+}
+
+/// The format of the texture `camera` renders to, if it renders to a texture
+fn camera_target_format(world: &World, camera: Entity) -> Option<TextureFormat> {
+    match &world.get::<Camera>(camera)?.target {
+        RenderTarget::Texture(handle) => world
+            .get_resource::<Assets<Texture>>()?
+            .get(handle)
+            .map(|texture| texture.format),
+        RenderTarget::Window(_) => None,
+    }
+}
+
+/// Replaces the pipelines set by `commands` with ones specialized for color targets of `format`
+fn specialize_for_color_target_format(
+    world: &mut World,
+    commands: &mut [RenderCommand],
+    format: TextureFormat,
+) {
+    let world = world.cell();
+    let mut pipeline_compiler = world.get_resource_mut::<PipelineCompiler>().unwrap();
+    let mut pipelines = world
+        .get_resource_mut::<Assets<PipelineDescriptor>>()
+        .unwrap();
+    let mut shaders = world.get_resource_mut::<Assets<Shader>>().unwrap();
+    let render_resource_context = world
+        .get_resource::<Box<dyn RenderResourceContext>>()
+        .unwrap();
+    let mut specialized_pipelines = HashMap::default();
+    for command in commands.iter_mut() {
+        let pipeline = if let RenderCommand::SetPipeline { pipeline } = command {
+            pipeline
+        } else {
+            continue;
+        };
+        *pipeline = specialized_pipelines
+            .entry(pipeline.clone_weak())
+            .or_insert_with(|| {
+                let draws_into_format = pipelines.get(&*pipeline).map_or(true, |descriptor| {
+                    descriptor
+                        .color_target_states
+                        .iter()
+                        .all(|color_target| color_target.format == format)
+                });
+                // pipelines that weren't compiled by the pipeline compiler are kept as they are
+                let (source_pipeline, specialization) =
+                    match pipeline_compiler.get_source_pipeline(pipeline) {
+                        Some((source_pipeline, specialization)) if !draws_into_format => (
+                            source_pipeline.clone_weak(),
+                            PipelineSpecialization {
+                                color_target_format: Some(format),
+                                ..specialization.clone()
+                            },
+                        ),
+                        _ => return pipeline.clone_weak(),
+                    };
+                pipeline_compiler
+                    .get_specialized_pipeline(&source_pipeline, &specialization)
+                    .unwrap_or_else(|| {
+                        pipeline_compiler.compile_pipeline(
+                            &**render_resource_context,
+                            &mut pipelines,
+                            &mut shaders,
+                            &source_pipeline,
+                            &specialization,
+                        )
+                    })
+            })
+            .clone_weak();
+    }
 }
 
 impl<Q: WorldQuery + Send + Sync + 'static> Node for PassNode<Q>
@@ -126,77 +210,98 @@ where
 
     fn prepare(&mut self, world: &mut World) {
         let query_state = self.query_state.get_or_insert_with(|| world.query());
-        let cameras = &self.cameras;
-        let commands = &mut self.commands;
-        world.resource_scope(|world, mut active_cameras: Mut<ActiveCameras>| {
-            let mut pipeline_camera_commands = HashMap::default();
-            let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
-            let render_resource_context = &**world
-                .get_resource::<Box<dyn RenderResourceContext>>()
-                .unwrap();
+        let color_target_format = self.color_target_format;
 
-            for camera_name in cameras.iter() {
-                let active_camera = if let Some(active_camera) = active_cameras.get_mut(camera_name)
-                {
-                    active_camera
-                } else {
+        // the draw commands of the entities each camera sees, and the format it draws them into
+        let mut camera_commands = Vec::new();
+        let active_cameras = world.get_resource::<ActiveCameras>().unwrap();
+        for camera_name in self.cameras.iter() {
+            let camera = if let Some(camera) = active_cameras
+                .get(camera_name)
+                .and_then(|active_camera| active_camera.entity)
+            {
+                camera
+            } else {
+                continue;
+            };
+
+            let visible_entities = world.get::<VisibleEntities>(camera).unwrap();
+            let mut commands = Vec::new();
+            for visible_entity in visible_entities.iter() {
+                if query_state.get(world, visible_entity.entity).is_err() {
+                    // visible entity does not match the Pass query
                     continue;
-                };
+                }
 
-                let visible_entities = if let Some(entity) = active_camera.entity {
-                    world.get::<VisibleEntities>(entity).unwrap()
+                let draw = if let Some(draw) = world.get::<Draw>(visible_entity.entity) {
+                    draw
                 } else {
                     continue;
                 };
-                for visible_entity in visible_entities.iter() {
-                    if query_state.get(world, visible_entity.entity).is_err() {
-                        // visible entity does not match the Pass query
+
+                if let Some(visible) = world.get::<Visible>(visible_entity.entity) {
+                    if !visible.is_visible {
                         continue;
                     }
+                }
+                commands.extend(draw.render_commands.iter().cloned());
+            }
+            let format = color_target_format.or_else(|| camera_target_format(world, camera));
+            camera_commands.push((camera_name, format, commands));
+        }
 
-                    let draw = if let Some(draw) = world.get::<Draw>(visible_entity.entity) {
-                        draw
-                    } else {
-                        continue;
-                    };
+        for (_, format, commands) in camera_commands.iter_mut() {
+            if let Some(format) = *format {
+                specialize_for_color_target_format(world, commands, format);
+            }
+        }
 
-                    if let Some(visible) = world.get::<Visible>(visible_entity.entity) {
-                        if !visible.is_visible {
-                            continue;
-                        }
-                    }
-                    for render_command in draw.render_commands.iter() {
-                        commands.push(render_command.clone());
-                        // whenever a new pipeline is set, ensure the relevant camera bind groups are set
-                        if let RenderCommand::SetPipeline { pipeline } = render_command {
-                            let bind_groups = pipeline_camera_commands
-                                .entry(pipeline.clone_weak())
-                                .or_insert_with(|| {
-                                    let descriptor = pipelines.get(pipeline).unwrap();
-                                    let layout = descriptor.get_layout().unwrap();
-                                    let mut commands = Vec::new();
-                                    for bind_group_descriptor in layout.bind_groups.iter() {
-                                        if let Some(bind_group) =
-                                            active_camera.bindings.update_bind_group(
-                                                bind_group_descriptor,
-                                                render_resource_context,
-                                            )
-                                        {
-                                            commands.push(RenderCommand::SetBindGroup {
-                                                index: bind_group_descriptor.index,
-                                                bind_group: bind_group.id,
-                                                dynamic_uniform_indices: bind_group
-                                                    .dynamic_uniform_indices
-                                                    .clone(),
-                                            })
-                                        }
-                                    }
-                                    commands
-                                });
+        let commands = &mut self.commands;
+        world.resource_scope(|world, mut active_cameras: Mut<ActiveCameras>| {
+            let mut pipeline_camera_commands = HashMap::default();
+            let pipelines = world.get_resource::<Assets<PipelineDescriptor>>().unwrap();
+            let render_resource_context = &**world
+                .get_resource::<Box<dyn RenderResourceContext>>()
+                .unwrap();
 
-                            commands.extend(bind_groups.iter().cloned());
-                        }
-                    }
+            for (camera_name, _, camera_commands) in camera_commands {
+                let active_camera = active_cameras.get_mut(camera_name).unwrap();
+                for render_command in camera_commands {
+                    // whenever a new pipeline is set, ensure the relevant camera bind groups are set
+                    let bind_groups =
+                        if let RenderCommand::SetPipeline { pipeline } = &render_command {
+                            Some(
+                                pipeline_camera_commands
+                                    .entry(pipeline.clone_weak())
+                                    .or_insert_with(|| {
+                                        let descriptor = pipelines.get(pipeline).unwrap();
+                                        let layout = descriptor.get_layout().unwrap();
+                                        let mut commands = Vec::new();
+                                        for bind_group_descriptor in layout.bind_groups.iter() {
+                                            if let Some(bind_group) =
+                                                active_camera.bindings.update_bind_group(
+                                                    bind_group_descriptor,
+                                                    render_resource_context,
+                                                )
+                                            {
+                                                commands.push(RenderCommand::SetBindGroup {
+                                                    index: bind_group_descriptor.index,
+                                                    bind_group: bind_group.id,
+                                                    dynamic_uniform_indices: bind_group
+                                                        .dynamic_uniform_indices
+                                                        .clone(),
+                                                })
+                                            }
+                                        }
+                                        commands
+                                    })
+                                    .clone(),
+                            )
+                        } else {
+                            None
+                        };
+                    commands.push(render_command);
+                    commands.extend(bind_groups.into_iter().flatten());
                 }
             }
         });
@@ -209,6 +314,20 @@ where
         input: &ResourceSlots,
         _output: &mut ResourceSlots,
     ) {
+        // a pass whose attachments aren't available yet is skipped
+        let get_texture = |index: usize| input.get(index).and_then(|id| id.get_texture());
+        let attachments_available = self
+            .color_attachment_input_indices
+            .iter()
+            .chain(self.color_resolve_target_indices.iter())
+            .chain(std::iter::once(&self.depth_stencil_attachment_input_index))
+            .flatten()
+            .all(|index| get_texture(*index).is_some());
+        if !attachments_available {
+            self.commands.clear();
+            return;
+        }
+
         for (i, color_attachment) in self.descriptor.color_attachments.iter_mut().enumerate() {
             if self.default_clear_color_inputs.contains(&i) {
                 if let Some(default_clear_color) = world.get_resource::<ClearColor>() {
@@ -217,12 +336,11 @@ where
             }
             if let Some(input_index) = self.color_attachment_input_indices[i] {
                 color_attachment.attachment =
-                    TextureAttachment::Id(input.get(input_index).unwrap().get_texture().unwrap());
+                    TextureAttachment::Id(get_texture(input_index).unwrap());
             }
             if let Some(input_index) = self.color_resolve_target_indices[i] {
-                color_attachment.resolve_target = Some(TextureAttachment::Id(
-                    input.get(input_index).unwrap().get_texture().unwrap(),
-                ));
+                color_attachment.resolve_target =
+                    Some(TextureAttachment::Id(get_texture(input_index).unwrap()));
             }
         }
 
@@ -231,8 +349,7 @@ where
                 .depth_stencil_attachment
                 .as_mut()
                 .unwrap()
-                .attachment =
-                TextureAttachment::Id(input.get(input_index).unwrap().get_texture().unwrap());
+                .attachment = TextureAttachment::Id(get_texture(input_index).unwrap());
         }
 
         let render_resource_bindings = world.get_resource::<RenderResourceBindings>().unwrap();
@@ -0,0 +1,311 @@
+use crate::{
+    hdr::{camera_component, linear_sampler},
+    pass::{
+        LoadOp, Operations, PassDescriptor, RenderPassColorAttachmentDescriptor, TextureAttachment,
+    },
+    pipeline::{BlendState, ColorTargetState, ColorWrite, PipelineDescriptor},
+    prelude::Color,
+    render_graph::{
+        build_fullscreen_pipeline, write_uniform_buffer, FullscreenPass, Node, ResourceSlotInfo,
+        ResourceSlots,
+    },
+    renderer::{
+        BufferId, RenderContext, RenderResourceBinding, RenderResourceBindings,
+        RenderResourceContext, RenderResourceId, RenderResourceType, RenderResources, SamplerId,
+        TextureId,
+    },
+    shader::Shader,
+    texture::{
+        Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage,
+        SAMPLER_ASSET_INDEX, TEXTURE_ASSET_INDEX,
+    },
+};
+use bevy_asset::{Assets, Handle};
+use bevy_ecs::{component::Component, world::World};
+use bevy_utils::HashMap;
+use bevy_window::{WindowId, Windows};
+use std::{borrow::Cow, marker::PhantomData};
+
+/// The window sized textures that the [PostProcessNode]s of a window draw into. Each node draws
+/// into the texture it doesn't read from, so a chain of nodes alternates between the two.
+#[derive(Debug, Default)]
+pub struct PostProcessTextures {
+    textures: HashMap<(WindowId, TextureFormat), PostProcessTexturePair>,
+}
+
+#[derive(Debug)]
+struct PostProcessTexturePair {
+    size: Extent3d,
+    textures: [TextureId; 2],
+}
+
+impl PostProcessTextures {
+    pub fn get(&self, window_id: WindowId, format: TextureFormat) -> Option<[TextureId; 2]> {
+        self.textures
+            .get(&(window_id, format))
+            .map(|pair| pair.textures)
+    }
+
+    /// Creates the textures of `window_id` in `format`, or recreates them when the window has
+    /// been resized
+    fn prepare(
+        &mut self,
+        render_resource_context: &dyn RenderResourceContext,
+        windows: &Windows,
+        window_id: WindowId,
+        format: TextureFormat,
+    ) {
+        let window = if let Some(window) = windows.get(window_id) {
+            window
+        } else {
+            return;
+        };
+        let size = Extent3d::new(window.physical_width(), window.physical_height(), 1);
+        if let Some(pair) = self.textures.get(&(window_id, format)) {
+            if pair.size == size {
+                return;
+            }
+            for texture in pair.textures.iter() {
+                render_resource_context.remove_texture(*texture);
+            }
+            self.textures.remove(&(window_id, format));
+        }
+        if size.width == 0 || size.height == 0 {
+            return;
+        }
+
+        let descriptor = TextureDescriptor {
+            size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format,
+            usage: TextureUsage::OUTPUT_ATTACHMENT | TextureUsage::SAMPLED,
+        };
+        self.textures.insert(
+            (window_id, format),
+            PostProcessTexturePair {
+                size,
+                textures: [
+                    render_resource_context.create_texture(descriptor),
+                    render_resource_context.create_texture(descriptor),
+                ],
+            },
+        );
+    }
+}
+
+/// A Render Graph [Node] that runs a fragment shader over its input texture. The shader reads the
+/// input as `Source` and `Source_sampler`, and the `T` component of the node's cameras like
+/// [RenderResources] are read by materials. The result is drawn into one of the
+/// [PostProcessTextures] of the window, which is passed on through the output slot.
+///
+/// When none of the cameras have a `T` or the shader hasn't loaded yet, the input texture is
+/// passed on as it is.
+pub struct PostProcessNode<T: RenderResources + Component> {
+    window_id: WindowId,
+    format: TextureFormat,
+    fragment_shader: Handle<Shader>,
+    cameras: Vec<String>,
+    pass: Option<FullscreenPass>,
+    bindings: RenderResourceBindings,
+    sampler: Option<SamplerId>,
+    uniform_buffers: Vec<Option<BufferId>>,
+    marker: PhantomData<T>,
+}
+
+impl<T: RenderResources + Component> PostProcessNode<T> {
+    pub const IN_TEXTURE: &'static str = "texture";
+    pub const OUT_TEXTURE: &'static str = "texture";
+
+    /// A node for the textures of `window_id` in `format`. The fragment shader writes the color
+    /// at location 0 and gets the uv of the fragment at location 0.
+    pub fn new(
+        window_id: WindowId,
+        format: TextureFormat,
+        fragment_shader: Handle<Shader>,
+    ) -> Self {
+        PostProcessNode {
+            window_id,
+            format,
+            fragment_shader,
+            cameras: Vec::new(),
+            pass: None,
+            bindings: Default::default(),
+            sampler: None,
+            uniform_buffers: Vec::new(),
+            marker: PhantomData,
+        }
+    }
+
+    /// Adds a camera whose `T` is used. The first camera with one is used.
+    pub fn add_camera(&mut self, camera_name: &str) {
+        self.cameras.push(camera_name.to_string());
+    }
+
+    /// Binds the render resources of `settings`, returning `false` if one of its textures isn't
+    /// available yet
+    fn set_bindings(&mut self, settings: &T, render_context: &mut dyn RenderContext) -> bool {
+        self.uniform_buffers
+            .resize(settings.render_resources_len(), None);
+        for (index, render_resource) in settings.iter().enumerate() {
+            let name = settings.get_render_resource_name(index).unwrap();
+            match render_resource.resource_type() {
+                Some(RenderResourceType::Buffer) => {
+                    let size = render_resource.buffer_byte_len().unwrap();
+                    let mut data = vec![0; size];
+                    render_resource.write_buffer_bytes(&mut data);
+                    let buffer = write_uniform_buffer(
+                        render_context,
+                        &mut self.uniform_buffers[index],
+                        &data,
+                    );
+                    self.bindings.set(
+                        name,
+                        RenderResourceBinding::Buffer {
+                            buffer,
+                            range: 0..size as u64,
+                            dynamic_index: None,
+                        },
+                    );
+                }
+                Some(RenderResourceType::Texture) => {
+                    let resources = render_context.resources();
+                    let texture = render_resource.texture().and_then(|handle| {
+                        Some((
+                            resources.get_asset_resource(handle, TEXTURE_ASSET_INDEX)?,
+                            resources.get_asset_resource(handle, SAMPLER_ASSET_INDEX)?,
+                        ))
+                    });
+                    if let Some((
+                        RenderResourceId::Texture(texture),
+                        RenderResourceId::Sampler(sampler),
+                    )) = texture
+                    {
+                        self.bindings
+                            .set(name, RenderResourceBinding::Texture(texture));
+                        self.bindings.set(
+                            &format!("{}_sampler", name),
+                            RenderResourceBinding::Sampler(sampler),
+                        );
+                    } else {
+                        return false;
+                    }
+                }
+                _ => {}
+            }
+        }
+        true
+    }
+}
+
+impl<T: RenderResources + Component> Node for PostProcessNode<T> {
+    fn input(&self) -> &[ResourceSlotInfo] {
+        static INPUT: &[ResourceSlotInfo] = &[ResourceSlotInfo {
+            name: Cow::Borrowed("texture"),
+            resource_type: RenderResourceType::Texture,
+        }];
+        INPUT
+    }
+
+    fn output(&self) -> &[ResourceSlotInfo] {
+        static OUTPUT: &[ResourceSlotInfo] = &[ResourceSlotInfo {
+            name: Cow::Borrowed("texture"),
+            resource_type: RenderResourceType::Texture,
+        }];
+        OUTPUT
+    }
+
+    fn prepare(&mut self, world: &mut World) {
+        if self.pass.is_none() {
+            let mut pipelines = world
+                .get_resource_mut::<Assets<PipelineDescriptor>>()
+                .unwrap();
+            let pipeline = pipelines.add(build_fullscreen_pipeline(
+                "post_process",
+                self.fragment_shader.clone(),
+                ColorTargetState {
+                    format: self.format,
+                    color_blend: BlendState::REPLACE,
+                    alpha_blend: BlendState::REPLACE,
+                    write_mask: ColorWrite::ALL,
+                },
+            ));
+            self.pass = Some(FullscreenPass::new(pipeline, Default::default()));
+        }
+        self.pass.as_mut().unwrap().prepare(world);
+
+        let world = world.cell();
+        let render_resource_context = world
+            .get_resource::<Box<dyn RenderResourceContext>>()
+            .unwrap();
+        let windows = world.get_resource::<Windows>().unwrap();
+        world
+            .get_resource_mut::<PostProcessTextures>()
+            .unwrap()
+            .prepare(
+                &**render_resource_context,
+                &windows,
+                self.window_id,
+                self.format,
+            );
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        let source = if let Some(source) = input.get(Self::IN_TEXTURE) {
+            source
+        } else {
+            return;
+        };
+        output.set(Self::OUT_TEXTURE, source.clone());
+
+        let settings = if let Some(settings) = camera_component::<T>(world, &self.cameras) {
+            settings
+        } else {
+            return;
+        };
+        let source = source.get_texture().unwrap();
+        let target = match world
+            .get_resource::<PostProcessTextures>()
+            .unwrap()
+            .get(self.window_id, self.format)
+        {
+            Some([first, second]) if first == source => second,
+            Some([first, _]) => first,
+            None => return,
+        };
+        if !self.set_bindings(settings, render_context) {
+            return;
+        }
+        let sampler = *self
+            .sampler
+            .get_or_insert_with(|| render_context.resources().create_sampler(&linear_sampler()));
+        self.bindings
+            .set("Source", RenderResourceBinding::Texture(source));
+        self.bindings
+            .set("Source_sampler", RenderResourceBinding::Sampler(sampler));
+
+        let descriptor = PassDescriptor {
+            color_attachments: vec![RenderPassColorAttachmentDescriptor {
+                attachment: TextureAttachment::Id(target),
+                resolve_target: None,
+                ops: Operations {
+                    load: LoadOp::Clear(Color::BLACK),
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+            sample_count: 1,
+        };
+        let pass = self.pass.as_ref().unwrap();
+        if pass.draw(world, render_context, &descriptor, &mut self.bindings) {
+            output.set(Self::OUT_TEXTURE, RenderResourceId::Texture(target));
+        }
+    }
+}
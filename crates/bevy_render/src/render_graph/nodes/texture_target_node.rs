@@ -0,0 +1,151 @@
+use crate::{
+    camera::{ActiveCameras, Camera, RenderTarget},
+    prelude::Msaa,
+    render_graph::{Node, ResourceSlotInfo, ResourceSlots},
+    renderer::{RenderContext, RenderResourceId, RenderResourceType, TextureId},
+    texture::{
+        Extent3d, Texture, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage,
+        TEXTURE_ASSET_INDEX,
+    },
+};
+use bevy_asset::Assets;
+use bevy_ecs::world::World;
+
+/// A Render Graph [Node] that outputs the attachments of a pass that draws a camera into its
+/// [RenderTarget::Texture]: the texture itself, and a depth texture of the same size. With msaa,
+/// the color attachment is a multisampled texture that is resolved into the target texture.
+///
+/// The outputs aren't set while the camera doesn't have a texture target, or the texture hasn't
+/// been created on the gpu yet, which skips the pass.
+#[derive(Debug)]
+pub struct TextureTargetNode {
+    camera_name: String,
+    sample_count: u32,
+    outputs: Vec<ResourceSlotInfo>,
+    descriptor: Option<TextureDescriptor>,
+    depth_texture: Option<TextureId>,
+    sampled_color_attachment: Option<TextureId>,
+}
+
+impl TextureTargetNode {
+    pub const OUT_COLOR_ATTACHMENT: &'static str = "color_attachment";
+    pub const OUT_COLOR_RESOLVE_TARGET: &'static str = "color_resolve_target";
+    pub const OUT_DEPTH: &'static str = "depth";
+
+    pub fn new(camera_name: &str, msaa: &Msaa) -> Self {
+        let mut outputs = vec![
+            ResourceSlotInfo::new(Self::OUT_COLOR_ATTACHMENT, RenderResourceType::Texture),
+            ResourceSlotInfo::new(Self::OUT_DEPTH, RenderResourceType::Texture),
+        ];
+        if msaa.samples > 1 {
+            outputs.push(ResourceSlotInfo::new(
+                Self::OUT_COLOR_RESOLVE_TARGET,
+                RenderResourceType::Texture,
+            ));
+        }
+
+        TextureTargetNode {
+            camera_name: camera_name.to_string(),
+            sample_count: msaa.samples,
+            outputs,
+            descriptor: None,
+            depth_texture: None,
+            sampled_color_attachment: None,
+        }
+    }
+
+    fn remove_attachments(&mut self, render_context: &mut dyn RenderContext) {
+        let render_resource_context = render_context.resources_mut();
+        if let Some(texture) = self.depth_texture.take() {
+            render_resource_context.remove_texture(texture);
+        }
+        if let Some(texture) = self.sampled_color_attachment.take() {
+            render_resource_context.remove_texture(texture);
+        }
+        self.descriptor = None;
+    }
+}
+
+impl Node for TextureTargetNode {
+    fn output(&self) -> &[ResourceSlotInfo] {
+        &self.outputs
+    }
+
+    fn update(
+        &mut self,
+        world: &World,
+        render_context: &mut dyn RenderContext,
+        _input: &ResourceSlots,
+        output: &mut ResourceSlots,
+    ) {
+        for slot in output.iter_mut() {
+            slot.resource = None;
+        }
+
+        let camera = world
+            .get_resource::<ActiveCameras>()
+            .unwrap()
+            .get(&self.camera_name)
+            .and_then(|active_camera| active_camera.entity)
+            .and_then(|entity| world.get::<Camera>(entity));
+        let textures = world.get_resource::<Assets<Texture>>().unwrap();
+        let target = camera.and_then(|camera| match &camera.target {
+            RenderTarget::Texture(handle) => Some((
+                textures.get(handle)?,
+                render_context
+                    .resources()
+                    .get_asset_resource(handle, TEXTURE_ASSET_INDEX)?,
+            )),
+            RenderTarget::Window(_) => None,
+        });
+        let (texture, texture_id) = match target {
+            Some((texture, RenderResourceId::Texture(texture_id))) => (texture, texture_id),
+            _ => {
+                self.remove_attachments(render_context);
+                return;
+            }
+        };
+
+        let descriptor = TextureDescriptor {
+            size: Extent3d::new(texture.size.width, texture.size.height, 1),
+            mip_level_count: 1,
+            sample_count: self.sample_count,
+            dimension: TextureDimension::D2,
+            format: texture.format,
+            usage: TextureUsage::OUTPUT_ATTACHMENT,
+        };
+        if self.descriptor != Some(descriptor) {
+            self.remove_attachments(render_context);
+            let render_resource_context = render_context.resources_mut();
+            self.depth_texture = Some(render_resource_context.create_texture(TextureDescriptor {
+                format: TextureFormat::Depth32Float,
+                ..descriptor
+            }));
+            if self.sample_count > 1 {
+                self.sampled_color_attachment =
+                    Some(render_resource_context.create_texture(descriptor));
+            }
+            self.descriptor = Some(descriptor);
+        }
+
+        output.set(
+            Self::OUT_DEPTH,
+            RenderResourceId::Texture(self.depth_texture.unwrap()),
+        );
+        if let Some(sampled_color_attachment) = self.sampled_color_attachment {
+            output.set(
+                Self::OUT_COLOR_ATTACHMENT,
+                RenderResourceId::Texture(sampled_color_attachment),
+            );
+            output.set(
+                Self::OUT_COLOR_RESOLVE_TARGET,
+                RenderResourceId::Texture(texture_id),
+            );
+        } else {
+            output.set(
+                Self::OUT_COLOR_ATTACHMENT,
+                RenderResourceId::Texture(texture_id),
+            );
+        }
+    }
+}
@@ -1,24 +1,36 @@
 use crate::{
+    readback::Readbacks,
     render_graph::{Node, ResourceSlotInfo, ResourceSlots},
-    renderer::{RenderContext, RenderResourceId, RenderResourceType},
+    renderer::{RenderContext, RenderResourceId, RenderResourceType, TextureId},
+    texture::{Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsage},
 };
 use bevy_app::{Events, ManualEventReader};
 use bevy_ecs::world::World;
 use bevy_window::{WindowCreated, WindowId, WindowResized, Windows};
 use std::borrow::Cow;
 
+/// A Render Graph [Node] that outputs the next texture of a window's swap chain.
+///
+/// While the window is captured by a [ReadbackNode](crate::readback::ReadbackNode),
+/// [OUT_TEXTURE](Self::OUT_TEXTURE), which passes draw into, is a capture texture that can be
+/// copied, and only [OUT_SWAP_CHAIN_TEXTURE](Self::OUT_SWAP_CHAIN_TEXTURE) is the swap chain
+/// texture.
+/// Without the window, like when running headless, the outputs aren't set.
 pub struct WindowSwapChainNode {
     window_id: WindowId,
+    capture_texture: Option<(TextureId, Extent3d)>,
     window_created_event_reader: ManualEventReader<WindowCreated>,
     window_resized_event_reader: ManualEventReader<WindowResized>,
 }
 
 impl WindowSwapChainNode {
     pub const OUT_TEXTURE: &'static str = "texture";
+    pub const OUT_SWAP_CHAIN_TEXTURE: &'static str = "swap_chain_texture";
 
     pub fn new(window_id: WindowId) -> Self {
         WindowSwapChainNode {
             window_id,
+            capture_texture: None,
             window_created_event_reader: Default::default(),
             window_resized_event_reader: Default::default(),
         }
@@ -27,10 +39,16 @@ impl WindowSwapChainNode {
 
 impl Node for WindowSwapChainNode {
     fn output(&self) -> &[ResourceSlotInfo] {
-        static OUTPUT: &[ResourceSlotInfo] = &[ResourceSlotInfo {
-            name: Cow::Borrowed(WindowSwapChainNode::OUT_TEXTURE),
-            resource_type: RenderResourceType::Texture,
-        }];
+        static OUTPUT: &[ResourceSlotInfo] = &[
+            ResourceSlotInfo {
+                name: Cow::Borrowed(WindowSwapChainNode::OUT_TEXTURE),
+                resource_type: RenderResourceType::Texture,
+            },
+            ResourceSlotInfo {
+                name: Cow::Borrowed(WindowSwapChainNode::OUT_SWAP_CHAIN_TEXTURE),
+                resource_type: RenderResourceType::Texture,
+            },
+        ];
         OUTPUT
     }
 
@@ -42,13 +60,19 @@ impl Node for WindowSwapChainNode {
         output: &mut ResourceSlots,
     ) {
         const WINDOW_TEXTURE: usize = 0;
+        const SWAP_CHAIN_TEXTURE: usize = 1;
         let window_created_events = world.get_resource::<Events<WindowCreated>>().unwrap();
         let window_resized_events = world.get_resource::<Events<WindowResized>>().unwrap();
         let windows = world.get_resource::<Windows>().unwrap();
 
-        let window = windows
-            .get(self.window_id)
-            .expect("Window swapchain node refers to a non-existent window.");
+        let window = if let Some(window) = windows.get(self.window_id) {
+            window
+        } else {
+            for slot in output.iter_mut() {
+                slot.resource = None;
+            }
+            return;
+        };
 
         let render_resource_context = render_context.resources_mut();
 
@@ -67,8 +91,40 @@ impl Node for WindowSwapChainNode {
 
         let swap_chain_texture = render_resource_context.next_swap_chain_texture(&window);
         output.set(
-            WINDOW_TEXTURE,
+            SWAP_CHAIN_TEXTURE,
             RenderResourceId::Texture(swap_chain_texture),
         );
+
+        let captured = world
+            .get_resource::<Readbacks>()
+            .map_or(false, |readbacks| {
+                readbacks.is_window_captured(self.window_id)
+            });
+        let size = Extent3d::new(window.physical_width(), window.physical_height(), 1);
+        if let Some((texture, texture_size)) = self.capture_texture {
+            if !captured || texture_size != size {
+                render_resource_context.remove_texture(texture);
+                self.capture_texture = None;
+            }
+        }
+        let window_texture = if captured {
+            let (texture, _) = *self.capture_texture.get_or_insert_with(|| {
+                let texture = render_resource_context.create_texture(TextureDescriptor {
+                    size,
+                    mip_level_count: 1,
+                    sample_count: 1,
+                    dimension: TextureDimension::D2,
+                    format: TextureFormat::default(),
+                    usage: TextureUsage::OUTPUT_ATTACHMENT
+                        | TextureUsage::COPY_SRC
+                        | TextureUsage::SAMPLED,
+                });
+                (texture, size)
+            });
+            texture
+        } else {
+            swap_chain_texture
+        };
+        output.set(WINDOW_TEXTURE, RenderResourceId::Texture(window_texture));
     }
 }
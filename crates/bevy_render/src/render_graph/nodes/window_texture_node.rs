@@ -49,9 +49,12 @@ impl Node for WindowTextureNode {
         let window_resized_events = world.get_resource::<Events<WindowResized>>().unwrap();
         let windows = world.get_resource::<Windows>().unwrap();
 
-        let window = windows
-            .get(self.window_id)
-            .expect("Window texture node refers to a non-existent window.");
+        // without the window, like when running headless, the texture is never created
+        let window = if let Some(window) = windows.get(self.window_id) {
+            window
+        } else {
+            return;
+        };
 
         if self
             .window_created_event_reader
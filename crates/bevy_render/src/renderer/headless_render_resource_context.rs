@@ -2,7 +2,8 @@ use super::RenderResourceContext;
 use crate::{
     pipeline::{BindGroupDescriptorId, PipelineDescriptor},
     renderer::{
-        BindGroup, BufferId, BufferInfo, BufferMapMode, RenderResourceId, SamplerId, TextureId,
+        BindGroup, BufferId, BufferInfo, BufferMapMode, BufferReadCallback, RenderResourceId,
+        SamplerId, TextureId,
     },
     shader::{Shader, ShaderError},
     texture::{SamplerDescriptor, TextureDescriptor},
@@ -83,6 +84,12 @@ impl RenderResourceContext for HeadlessRenderResourceContext {
 
     fn unmap_buffer(&self, _id: BufferId) {}
 
+    fn read_buffer_async(&self, _id: BufferId, range: Range<u64>, read: BufferReadCallback) {
+        read(&vec![0; (range.end - range.start) as usize]);
+    }
+
+    fn poll_buffer_reads(&self) {}
+
     fn create_buffer_with_data(&self, buffer_info: BufferInfo, _data: &[u8]) -> BufferId {
         let buffer = BufferId::new();
         self.add_buffer_info(buffer, buffer_info);
@@ -72,6 +72,8 @@ pub struct RenderResourceBindings {
     /// but undefined by the mesh.
     pub vertex_fallback_buffer: Option<BufferId>,
     pub index_buffer: Option<(BufferId, IndexFormat)>,
+    /// A Buffer that contains the per-instance attributes of an instanced draw
+    pub instance_buffer: Option<BufferId>,
     assets: HashSet<(HandleUntyped, TypeId)>,
     bind_groups: HashMap<BindGroupId, BindGroup>,
     bind_group_descriptors: HashMap<BindGroupDescriptorId, Option<BindGroupId>>,
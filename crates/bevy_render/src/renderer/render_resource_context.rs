@@ -11,6 +11,9 @@ use bevy_window::Window;
 use downcast_rs::{impl_downcast, Downcast};
 use std::ops::Range;
 
+/// Receives the bytes of a buffer read with [RenderResourceContext::read_buffer_async]
+pub type BufferReadCallback = Box<dyn FnOnce(&[u8]) + Send>;
+
 pub trait RenderResourceContext: Downcast + Send + Sync + 'static {
     fn create_swap_chain(&self, window: &Window);
     fn next_swap_chain_texture(&self, window: &Window) -> TextureId;
@@ -34,6 +37,14 @@ pub trait RenderResourceContext: Downcast + Send + Sync + 'static {
     );
     fn map_buffer(&self, id: BufferId, mode: BufferMapMode);
     fn unmap_buffer(&self, id: BufferId);
+    /// Maps `range` of a buffer that was created with [BufferUsage::MAP_READ] without waiting for
+    /// the gpu. Once the commands that write to the buffer have finished, `read` is called with its
+    /// bytes by [RenderResourceContext::poll_buffer_reads] and the buffer is unmapped again.
+    ///
+    /// [BufferUsage::MAP_READ]: crate::renderer::BufferUsage::MAP_READ
+    fn read_buffer_async(&self, id: BufferId, range: Range<u64>, read: BufferReadCallback);
+    /// Calls the `read` callbacks of the buffer reads that have finished on the gpu
+    fn poll_buffer_reads(&self);
     fn create_buffer_with_data(&self, buffer_info: BufferInfo, data: &[u8]) -> BufferId;
     fn create_shader_module(&self, shader_handle: &Handle<Shader>, shaders: &Assets<Shader>);
     fn create_shader_module_from_source(&self, shader_handle: &Handle<Shader>, shader: &Shader);
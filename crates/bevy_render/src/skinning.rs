@@ -0,0 +1,356 @@
+use crate::{
+    camera::NoFrustumCulling,
+    draw::OutsideFrustum,
+    mesh::Mesh,
+    pipeline::RenderPipelines,
+    renderer::{
+        RenderResource, RenderResourceHints, RenderResourceIterator, RenderResourceType,
+        RenderResources,
+    },
+    texture::Texture,
+};
+use bevy_asset::{Assets, Handle};
+use bevy_core::Bytes;
+use bevy_ecs::{
+    entity::{Entity, EntityMap, MapEntities, MapEntitiesError},
+    query::With,
+    reflect::{ReflectComponent, ReflectMapEntities},
+    system::{Commands, Local, Query, Res},
+};
+use bevy_math::Mat4;
+use bevy_reflect::{Reflect, TypeUuid};
+use bevy_transform::components::GlobalTransform;
+use bevy_utils::{tracing::warn, HashSet};
+
+/// The shader def that is set on the pipelines of skinned meshes. Vertex shaders that support
+/// skinning read the [Mesh::ATTRIBUTE_JOINT_INDEX](crate::mesh::Mesh::ATTRIBUTE_JOINT_INDEX) and
+/// [Mesh::ATTRIBUTE_JOINT_WEIGHT](crate::mesh::Mesh::ATTRIBUTE_JOINT_WEIGHT) attributes and the
+/// `JointMatrices` storage buffer when it is defined.
+pub const SKINNED_SHADER_DEF: &str = "SKINNED";
+
+/// The inverse bind matrices of a skin, which transform a vertex from the space of the mesh into
+/// the space of each joint in the bind pose
+#[derive(Debug, Default, Clone, TypeUuid)]
+#[uuid = "b9f155a9-54ec-4026-988f-e0a03e99a76f"]
+pub struct SkinnedMeshInverseBindposes(pub Vec<Mat4>);
+
+/// Deforms the mesh of an entity with the [GlobalTransform]s of its joints.
+///
+/// Each vertex of the mesh is moved by up to four joints, which are indices into `joints` and
+/// `inverse_bindposes`, so both need the same length. The mesh needs both joint attributes, and
+/// its pipelines need a vertex shader that supports the [SKINNED_SHADER_DEF], like the one of the
+/// `StandardMaterial`. Meshes without the joint attributes are drawn in their bind pose.
+/// Skinned meshes are never frustum culled, since their vertices can move outside of their
+/// bounds.
+#[derive(Debug, Default, Clone, Reflect)]
+#[reflect(Component, MapEntities)]
+pub struct SkinnedMesh {
+    pub inverse_bindposes: Handle<SkinnedMeshInverseBindposes>,
+    pub joints: Vec<Entity>,
+}
+
+impl MapEntities for SkinnedMesh {
+    fn map_entities(&mut self, entity_map: &EntityMap) -> Result<(), MapEntitiesError> {
+        for joint in self.joints.iter_mut() {
+            *joint = entity_map.get(*joint)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// The matrices that move the vertices of a [SkinnedMesh] from its bind pose into the current
+/// pose of its joints, relative to the entity's [GlobalTransform]. This is kept up to date by
+/// [skinned_mesh_joints_system].
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct SkinnedMeshJoints {
+    pub matrices: Vec<Mat4>,
+}
+
+impl RenderResource for SkinnedMeshJoints {
+    fn resource_type(&self) -> Option<RenderResourceType> {
+        Some(RenderResourceType::Buffer)
+    }
+
+    fn write_buffer_bytes(&self, buffer: &mut [u8]) {
+        let matrix_size = std::mem::size_of::<Mat4>();
+        for (matrix, bytes) in self
+            .matrices
+            .iter()
+            .zip(buffer.chunks_exact_mut(matrix_size))
+        {
+            matrix.write_bytes(bytes);
+        }
+    }
+
+    fn buffer_byte_len(&self) -> Option<usize> {
+        Some(self.matrices.len() * std::mem::size_of::<Mat4>())
+    }
+
+    fn texture(&self) -> Option<&Handle<Texture>> {
+        None
+    }
+}
+
+impl RenderResources for SkinnedMeshJoints {
+    fn render_resources_len(&self) -> usize {
+        1
+    }
+
+    fn get_render_resource(&self, index: usize) -> Option<&dyn RenderResource> {
+        if index == 0 {
+            Some(self)
+        } else {
+            None
+        }
+    }
+
+    fn get_render_resource_name(&self, index: usize) -> Option<&str> {
+        if index == 0 {
+            Some("JointMatrices")
+        } else {
+            None
+        }
+    }
+
+    fn get_render_resource_hints(&self, index: usize) -> Option<RenderResourceHints> {
+        if index == 0 {
+            Some(RenderResourceHints::BUFFER)
+        } else {
+            None
+        }
+    }
+
+    fn iter(&self) -> RenderResourceIterator {
+        RenderResourceIterator::new(self)
+    }
+}
+
+/// Whether `mesh` has the joint attributes that the [SKINNED_SHADER_DEF] reads
+pub fn has_joint_attributes(mesh: &Mesh) -> bool {
+    mesh.attribute(Mesh::ATTRIBUTE_JOINT_INDEX).is_some()
+        && mesh.attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT).is_some()
+}
+
+/// Computes the [SkinnedMeshJoints] of every [SkinnedMesh] from the [GlobalTransform]s of its
+/// joints. Skins whose inverse bind matrices or joints are missing are left as they are, and so
+/// are skins with a different number of joints and inverse bind matrices, with a warning.
+pub fn skinned_mesh_joints_system(
+    mut commands: Commands,
+    mut mismatched_skins: Local<HashSet<Entity>>,
+    inverse_bindposes: Res<Assets<SkinnedMeshInverseBindposes>>,
+    mut query: Query<(
+        Entity,
+        &SkinnedMesh,
+        &GlobalTransform,
+        Option<&mut SkinnedMeshJoints>,
+    )>,
+    joint_query: Query<&GlobalTransform>,
+) {
+    for (entity, skinned_mesh, global_transform, joints) in query.iter_mut() {
+        let inverse_bindposes = match inverse_bindposes.get(&skinned_mesh.inverse_bindposes) {
+            Some(inverse_bindposes) => inverse_bindposes,
+            None => continue,
+        };
+        if skinned_mesh.joints.len() != inverse_bindposes.0.len() {
+            if mismatched_skins.insert(entity) {
+                warn!(
+                    "The skin of {:?} has {} joints, but {} inverse bind matrices. It isn't \
+                     animated until they match.",
+                    entity,
+                    skinned_mesh.joints.len(),
+                    inverse_bindposes.0.len()
+                );
+            }
+            continue;
+        }
+        mismatched_skins.remove(&entity);
+
+        let mesh_inverse = global_transform.compute_matrix().inverse();
+        let matrices = skinned_mesh
+            .joints
+            .iter()
+            .zip(inverse_bindposes.0.iter())
+            .map(|(joint, inverse_bindpose)| {
+                let joint_transform = joint_query.get(*joint).ok()?;
+                Some(mesh_inverse * joint_transform.compute_matrix() * *inverse_bindpose)
+            })
+            .collect::<Option<Vec<Mat4>>>();
+        let new_joints = match matrices {
+            Some(matrices) if !matrices.is_empty() => SkinnedMeshJoints { matrices },
+            _ => continue,
+        };
+
+        if let Some(mut joints) = joints {
+            // assigning marks the joints as changed, which makes their RenderResourcesNode upload
+            // the joint buffer again
+            if *joints != new_joints {
+                *joints = new_joints;
+            }
+        } else {
+            commands
+                .entity(entity)
+                .insert_bundle((new_joints, NoFrustumCulling))
+                .remove::<OutsideFrustum>();
+        }
+    }
+}
+
+/// Sets the [SKINNED_SHADER_DEF] shader def on entities that have [SkinnedMeshJoints], if their
+/// mesh has the joint attributes the shader def reads
+pub fn skinned_mesh_shader_defs_system(
+    meshes: Res<Assets<Mesh>>,
+    mut query: Query<(&Handle<Mesh>, &mut RenderPipelines), With<SkinnedMeshJoints>>,
+) {
+    for (mesh, mut render_pipelines) in query.iter_mut() {
+        if !meshes.get(mesh).map_or(false, has_joint_attributes) {
+            continue;
+        }
+        for render_pipeline in render_pipelines.pipelines.iter_mut() {
+            render_pipeline
+                .specialization
+                .shader_specialization
+                .shader_defs
+                .insert(SKINNED_SHADER_DEF.to_string());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mesh::shape;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin, AssetServer, FileAssetIo};
+    use bevy_ecs::{
+        schedule::{Stage, SystemStage},
+        system::IntoSystem,
+    };
+    use bevy_math::Vec3;
+
+    #[test]
+    fn joint_matrices_follow_joints() {
+        let mut app = App::build();
+        app.insert_resource(AssetServer::new(FileAssetIo::new(&"."), Default::default()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<SkinnedMeshInverseBindposes>();
+        let world = app.world_mut();
+        // the joint is at (0, 1, 0) in the bind pose
+        let handle = world
+            .get_resource_mut::<Assets<SkinnedMeshInverseBindposes>>()
+            .unwrap()
+            .add(SkinnedMeshInverseBindposes(vec![Mat4::from_translation(
+                -Vec3::Y,
+            )]));
+        let mut stage = SystemStage::parallel();
+        stage.add_system(skinned_mesh_joints_system.system());
+
+        let joint = world
+            .spawn()
+            .insert(GlobalTransform::from_xyz(0.0, 3.0, 0.0))
+            .id();
+        let mesh = world
+            .spawn()
+            .insert_bundle((
+                SkinnedMesh {
+                    inverse_bindposes: handle,
+                    joints: vec![joint],
+                },
+                GlobalTransform::from_xyz(2.0, 0.0, 0.0),
+            ))
+            .id();
+        stage.run(world);
+
+        // the joint moved up by 2 from its bind pose, and left by 2 relative to the mesh
+        assert_eq!(
+            world.get::<SkinnedMeshJoints>(mesh),
+            Some(&SkinnedMeshJoints {
+                matrices: vec![Mat4::from_translation(Vec3::new(-2.0, 2.0, 0.0))]
+            })
+        );
+        assert!(world.get::<NoFrustumCulling>(mesh).is_some());
+
+        world.despawn(joint);
+        stage.run(world);
+        assert!(world.get::<SkinnedMeshJoints>(mesh).is_some());
+    }
+
+    #[test]
+    fn mismatched_skins_are_skipped() {
+        let mut app = App::build();
+        app.insert_resource(AssetServer::new(FileAssetIo::new(&"."), Default::default()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<SkinnedMeshInverseBindposes>();
+        let world = app.world_mut();
+        let handle = world
+            .get_resource_mut::<Assets<SkinnedMeshInverseBindposes>>()
+            .unwrap()
+            .add(SkinnedMeshInverseBindposes(vec![Mat4::IDENTITY]));
+        let mut stage = SystemStage::parallel();
+        stage.add_system(skinned_mesh_joints_system.system());
+
+        let joints = (0..2)
+            .map(|_| world.spawn().insert(GlobalTransform::identity()).id())
+            .collect::<Vec<_>>();
+        let mesh = world
+            .spawn()
+            .insert_bundle((
+                SkinnedMesh {
+                    inverse_bindposes: handle,
+                    joints,
+                },
+                GlobalTransform::identity(),
+            ))
+            .id();
+        stage.run(world);
+        assert!(world.get::<SkinnedMeshJoints>(mesh).is_none());
+
+        world.get_mut::<SkinnedMesh>(mesh).unwrap().joints.pop();
+        stage.run(world);
+        assert!(world.get::<SkinnedMeshJoints>(mesh).is_some());
+    }
+
+    #[test]
+    fn skinned_shader_def_needs_joint_attributes() {
+        let mut app = App::build();
+        app.insert_resource(AssetServer::new(FileAssetIo::new(&"."), Default::default()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Mesh>();
+        let world = app.world_mut();
+        let mut meshes = world.get_resource_mut::<Assets<Mesh>>().unwrap();
+        let mut skinned_mesh = Mesh::from(shape::Cube::default());
+        let unskinned_mesh = meshes.add(skinned_mesh.clone());
+        skinned_mesh.set_attribute(Mesh::ATTRIBUTE_JOINT_INDEX, vec![[0u32; 4]; 24]);
+        skinned_mesh.set_attribute(
+            Mesh::ATTRIBUTE_JOINT_WEIGHT,
+            vec![[1.0f32, 0.0, 0.0, 0.0]; 24],
+        );
+        let skinned_mesh = meshes.add(skinned_mesh);
+        let mut stage = SystemStage::parallel();
+        stage.add_system(skinned_mesh_shader_defs_system.system());
+
+        let mut spawn = |mesh: Handle<Mesh>| {
+            world
+                .spawn()
+                .insert_bundle((
+                    mesh,
+                    RenderPipelines::from_handles(&[Handle::default()]),
+                    SkinnedMeshJoints::default(),
+                ))
+                .id()
+        };
+        let skinned = spawn(skinned_mesh);
+        let unskinned = spawn(unskinned_mesh);
+        stage.run(world);
+
+        let has_skinned_def = |entity: Entity| {
+            world.get::<RenderPipelines>(entity).unwrap().pipelines[0]
+                .specialization
+                .shader_specialization
+                .shader_defs
+                .contains(SKINNED_SHADER_DEF)
+        };
+        assert!(has_skinned_def(skinned));
+        assert!(!has_skinned_def(unskinned));
+    }
+}
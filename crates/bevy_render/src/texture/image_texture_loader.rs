@@ -1,13 +1,31 @@
 use super::texture::{ImageType, Texture, TextureError};
+use crate::texture::TextureFormat;
 use anyhow::Result;
 use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
 use bevy_utils::BoxedFuture;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 /// Loader for images that can be read by the `image` crate.
 #[derive(Clone, Default)]
 pub struct ImageTextureLoader;
 
+/// Settings of the [ImageTextureLoader], set per load with
+/// [AssetServer::load_with_settings](bevy_asset::AssetServer::load_with_settings) or in the
+/// image's `.meta` file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ImageTextureSettings {
+    /// Whether 8 bit color images are stored in sRGB. Disable this for images that hold data
+    /// rather than color, such as normal maps.
+    pub is_srgb: bool,
+}
+
+impl Default for ImageTextureSettings {
+    fn default() -> Self {
+        ImageTextureSettings { is_srgb: true }
+    }
+}
+
 const FILE_EXTENSIONS: &[&str] = &["png", "dds", "tga", "jpg", "jpeg", "bmp"];
 
 impl AssetLoader for ImageTextureLoader {
@@ -20,13 +38,21 @@ impl AssetLoader for ImageTextureLoader {
             // use the file extension for the image type
             let ext = load_context.path().extension().unwrap().to_str().unwrap();
 
-            let dyn_img =
+            let settings = load_context.settings::<ImageTextureSettings>().await?;
+            let mut dyn_img =
                 Texture::from_buffer(bytes, ImageType::Extension(ext)).map_err(|err| {
                     FileTextureError {
                         error: err,
                         path: format!("{}", load_context.path().display()),
                     }
                 })?;
+            if !settings.is_srgb {
+                dyn_img.format = match dyn_img.format {
+                    TextureFormat::Rgba8UnormSrgb => TextureFormat::Rgba8Unorm,
+                    TextureFormat::Bgra8UnormSrgb => TextureFormat::Bgra8Unorm,
+                    format => format,
+                };
+            }
 
             load_context.set_default_asset(LoadedAsset::new(dyn_img));
             Ok(())
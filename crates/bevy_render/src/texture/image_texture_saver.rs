@@ -0,0 +1,34 @@
+use super::{
+    image_texture_conversion::texture_to_image,
+    texture::{Texture, TextureError},
+};
+use anyhow::Result;
+use bevy_asset::AssetSaver;
+use std::path::Path;
+
+/// Saves textures as PNG images that the [ImageTextureLoader](super::ImageTextureLoader) can
+/// load again.
+#[derive(Clone, Default)]
+pub struct ImageTextureSaver;
+
+impl AssetSaver for ImageTextureSaver {
+    type Asset = Texture;
+
+    fn save(&self, texture: &Texture, _path: &Path) -> Result<Vec<u8>> {
+        let mut image =
+            texture_to_image(texture).ok_or(TextureError::UnsupportedFormat(texture.format))?;
+        // png has no bgra color type, which is the format of windows and their screenshots
+        if let image::DynamicImage::ImageBgra8(_) = image {
+            image = image::DynamicImage::ImageRgba8(image.into_rgba8());
+        }
+        let mut bytes = Vec::new();
+        image
+            .write_to(&mut bytes, image::ImageOutputFormat::Png)
+            .map_err(TextureError::from)?;
+        Ok(bytes)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["png"]
+    }
+}
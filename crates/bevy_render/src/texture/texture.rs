@@ -1,6 +1,6 @@
 use super::{
     image_texture_conversion::image_to_texture, Extent3d, SamplerDescriptor, TextureDescriptor,
-    TextureDimension, TextureFormat,
+    TextureDimension, TextureFormat, TextureUsage,
 };
 use crate::renderer::{
     RenderResource, RenderResourceContext, RenderResourceId, RenderResourceType,
@@ -23,6 +23,8 @@ pub struct Texture {
     pub format: TextureFormat,
     pub dimension: TextureDimension,
     pub sampler: SamplerDescriptor,
+    /// How the texture can be used on the gpu
+    pub usage: TextureUsage,
 }
 
 impl Default for Texture {
@@ -37,6 +39,7 @@ impl Default for Texture {
             format: TextureFormat::Rgba8UnormSrgb,
             dimension: TextureDimension::D2,
             sampler: Default::default(),
+            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
         }
     }
 }
@@ -91,6 +94,22 @@ impl Texture {
         value
     }
 
+    /// Creates a texture that a camera can render to with
+    /// [RenderTarget::Texture](crate::camera::RenderTarget::Texture), and that can be sampled
+    /// and copied from like other textures
+    pub fn new_render_target(size: Extent3d, format: TextureFormat) -> Self {
+        let mut value = Texture {
+            format,
+            usage: TextureUsage::SAMPLED
+                | TextureUsage::COPY_DST
+                | TextureUsage::COPY_SRC
+                | TextureUsage::OUTPUT_ATTACHMENT,
+            ..Default::default()
+        };
+        value.resize(size);
+        value
+    }
+
     pub fn aspect_2d(&self) -> f32 {
         self.size.height as f32 / self.size.width as f32
     }
@@ -289,6 +308,8 @@ pub enum TextureError {
     InvalidImageExtension(String),
     #[error("failed to load an image")]
     ImageError(#[from] image::ImageError),
+    #[error("texture format can't be converted to an image: {0:?}")]
+    UnsupportedFormat(TextureFormat),
 }
 
 /// Type of a raw image buffer
@@ -19,7 +19,7 @@ impl From<&Texture> for TextureDescriptor {
             sample_count: 1,
             dimension: texture.dimension,
             format: texture.format,
-            usage: TextureUsage::SAMPLED | TextureUsage::COPY_DST,
+            usage: texture.usage,
         }
     }
 }
@@ -1,22 +1,68 @@
-use crate::{serde::SceneSerializer, Scene, SceneSpawnError};
+use crate::{serde::SceneSerializer, DynamicSceneBuilder, Scene, ScenePrefab, SceneSpawnError};
 use anyhow::Result;
 use bevy_ecs::{
-    entity::EntityMap,
-    reflect::{ReflectComponent, ReflectMapEntities},
+    entity::{EntityMap, MapEntitiesError},
+    reflect::{ReflectComponent, ReflectMapEntities, ReflectResource},
     world::World,
 };
-use bevy_reflect::{Reflect, TypeRegistryArc, TypeUuid};
+use bevy_reflect::{Reflect, TypeRegistry, TypeRegistryArc, TypeUuid};
 use serde::Serialize;
 
 #[derive(Default, TypeUuid)]
 #[uuid = "749479b1-fb8c-4ff8-a775-623aa76014f5"]
 pub struct DynamicScene {
     pub entities: Vec<Entity>,
+    /// Resources inserted into the world the scene is written to, see
+    /// [DynamicSceneBuilder::with_resource]
+    pub resources: Vec<Box<dyn Reflect>>,
+}
+
+impl Clone for DynamicScene {
+    fn clone(&self) -> Self {
+        DynamicScene {
+            entities: self.entities.clone(),
+            resources: self
+                .resources
+                .iter()
+                .map(|resource| resource.clone_value())
+                .collect(),
+        }
+    }
 }
 
 pub struct Entity {
     pub entity: u32,
     pub components: Vec<Box<dyn Reflect>>,
+    /// Another scene spawned as children of this entity
+    pub prefab: Option<ScenePrefab>,
+}
+
+impl Entity {
+    /// Whether both entities have equal components. Components that can't be compared count as
+    /// different.
+    pub(crate) fn reflect_eq(&self, other: &Entity) -> bool {
+        self.components.len() == other.components.len()
+            && self.components.iter().all(|component| {
+                other.components.iter().any(|other| {
+                    component.type_name() == other.type_name()
+                        && component.reflect_partial_eq(&**other) == Some(true)
+                })
+            })
+    }
+}
+
+impl Clone for Entity {
+    fn clone(&self) -> Self {
+        Entity {
+            entity: self.entity,
+            components: self
+                .components
+                .iter()
+                .map(|component| component.clone_value())
+                .collect(),
+            prefab: self.prefab.clone(),
+        }
+    }
 }
 
 impl DynamicScene {
@@ -24,38 +70,10 @@ impl DynamicScene {
         Self::from_world(&scene.world, type_registry)
     }
 
+    /// Extracts every entity of `world`, with every component that has a [ReflectComponent]. Use
+    /// a [DynamicSceneBuilder] to extract part of a world.
     pub fn from_world(world: &World, type_registry: &TypeRegistryArc) -> Self {
-        let mut scene = DynamicScene::default();
-        let type_registry = type_registry.read();
-        for archetype in world.archetypes().iter() {
-            let entities_offset = scene.entities.len();
-            for entity in archetype.entities() {
-                scene.entities.push(Entity {
-                    entity: entity.id(),
-                    components: Vec::new(),
-                });
-            }
-
-            for component_id in archetype.components() {
-                let reflect_component = world
-                    .components()
-                    .get_info(component_id)
-                    .and_then(|info| type_registry.get(info.type_id().unwrap()))
-                    .and_then(|registration| registration.data::<ReflectComponent>());
-                if let Some(reflect_component) = reflect_component {
-                    for (i, entity) in archetype.entities().iter().enumerate() {
-                        if let Some(component) = reflect_component.reflect_component(world, *entity)
-                        {
-                            scene.entities[entities_offset + i]
-                                .components
-                                .push(component.clone_value());
-                        }
-                    }
-                }
-            }
-        }
-
-        scene
+        DynamicSceneBuilder::new(world, type_registry).build()
     }
 
     pub fn write_to_world(
@@ -65,50 +83,110 @@ impl DynamicScene {
     ) -> Result<(), SceneSpawnError> {
         let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
         let type_registry = registry.read();
+        let mut written = Vec::new();
         for scene_entity in self.entities.iter() {
             let entity = *entity_map
                 .entry(bevy_ecs::entity::Entity::new(scene_entity.entity))
                 .or_insert_with(|| world.spawn().id());
-            for component in scene_entity.components.iter() {
-                let registration = type_registry
-                    .get_with_name(component.type_name())
-                    .ok_or_else(|| SceneSpawnError::UnregisteredType {
-                        type_name: component.type_name().to_string(),
-                    })?;
-                let reflect_component =
-                    registration.data::<ReflectComponent>().ok_or_else(|| {
-                        SceneSpawnError::UnregisteredComponent {
-                            type_name: component.type_name().to_string(),
-                        }
-                    })?;
-                if world
-                    .entity(entity)
-                    .contains_type_id(registration.type_id())
-                {
-                    reflect_component.apply_component(world, entity, &**component);
-                } else {
-                    reflect_component.add_component(world, entity, &**component);
-                }
-            }
+            write_components(world, &type_registry, entity, &scene_entity.components)?;
+            written.extend(
+                scene_entity
+                    .components
+                    .iter()
+                    .map(|component| (entity, component.type_name())),
+            );
         }
 
-        for registration in type_registry.iter() {
-            if let Some(map_entities_reflect) = registration.data::<ReflectMapEntities>() {
-                map_entities_reflect
-                    .map_entities(world, &entity_map)
-                    .unwrap();
-            }
+        for resource in self.resources.iter() {
+            let registration = type_registry
+                .get_with_name(resource.type_name())
+                .ok_or_else(|| SceneSpawnError::UnregisteredType {
+                    type_name: resource.type_name().to_string(),
+                })?;
+            let reflect_resource = registration.data::<ReflectResource>().ok_or_else(|| {
+                SceneSpawnError::UnregisteredResource {
+                    type_name: resource.type_name().to_string(),
+                }
+            })?;
+            reflect_resource.insert_resource(world, &**resource);
         }
 
+        map_written_entities(world, &type_registry, entity_map, &written)?;
         Ok(())
     }
 
-    // TODO: move to AssetSaver when it is implemented
+    /// Writes the entities that changed since the `previous` version of this scene to `world`, and
+    /// despawns the ones that were removed. Only the fields that changed are written, so the rest
+    /// of the state in `world` is preserved, and entities that were already despawned stay so.
+    pub fn patch_world(
+        &self,
+        previous: &DynamicScene,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+    ) -> Result<(), SceneSpawnError> {
+        previous.diff(self).apply_to_world(world, entity_map)
+    }
+
     pub fn serialize_ron(&self, registry: &TypeRegistryArc) -> Result<String, ron::Error> {
         serialize_ron(SceneSerializer::new(self, registry))
     }
 }
 
+/// Applies `components` to `entity`, inserting the ones it doesn't have yet.
+pub(crate) fn write_components(
+    world: &mut World,
+    type_registry: &TypeRegistry,
+    entity: bevy_ecs::entity::Entity,
+    components: &[Box<dyn Reflect>],
+) -> Result<(), SceneSpawnError> {
+    for component in components.iter() {
+        let registration = type_registry
+            .get_with_name(component.type_name())
+            .ok_or_else(|| SceneSpawnError::UnregisteredType {
+                type_name: component.type_name().to_string(),
+            })?;
+        let reflect_component = registration.data::<ReflectComponent>().ok_or_else(|| {
+            SceneSpawnError::UnregisteredComponent {
+                type_name: component.type_name().to_string(),
+            }
+        })?;
+        if world
+            .entity(entity)
+            .contains_type_id(registration.type_id())
+        {
+            reflect_component.apply_component(world, entity, &**component);
+        } else {
+            reflect_component.add_component(world, entity, &**component);
+        }
+    }
+    Ok(())
+}
+
+/// Maps the entities referenced by components that were just written from a scene, given by the
+/// entity they were written to and their type name. The other components of those entities are
+/// left alone, as they already reference entities of the world that may not be part of the scene,
+/// such as the children a prefab instance adds to the entity that references it.
+pub(crate) fn map_written_entities(
+    world: &mut World,
+    type_registry: &TypeRegistry,
+    entity_map: &EntityMap,
+    written: &[(bevy_ecs::entity::Entity, &str)],
+) -> Result<(), MapEntitiesError> {
+    for registration in type_registry.iter() {
+        if let Some(map_entities_reflect) = registration.data::<ReflectMapEntities>() {
+            let entities: Vec<_> = written
+                .iter()
+                .filter(|(_, type_name)| *type_name == registration.name())
+                .map(|(entity, _)| *entity)
+                .collect();
+            if !entities.is_empty() {
+                map_entities_reflect.map_specific_entities(world, entity_map, &entities)?;
+            }
+        }
+    }
+    Ok(())
+}
+
 pub fn serialize_ron<S>(serialize: S) -> Result<String, ron::Error>
 where
     S: Serialize,
@@ -0,0 +1,221 @@
+use crate::{DynamicScene, Entity};
+use bevy_ecs::{
+    component::Component,
+    reflect::{ReflectComponent, ReflectResource},
+    world::World,
+};
+use bevy_reflect::{Reflect, TypeRegistryArc};
+use bevy_utils::HashSet;
+use std::any::{type_name, TypeId};
+
+/// Marks the entities that belong in a save game, for use with [DynamicSceneBuilder::require].
+#[derive(Debug, Default, Clone, Copy, Reflect)]
+#[reflect(Component)]
+pub struct Persist;
+
+/// Builds a [DynamicScene] from part of a [World], such as the entities and resources that make
+/// up a save game.
+///
+/// ```ignore
+/// let scene = DynamicSceneBuilder::new(world, &type_registry)
+///     .require::<Persist>()
+///     .deny::<GlobalTransform>()
+///     .with_resource::<Score>()
+///     .build();
+/// ```
+pub struct DynamicSceneBuilder<'a> {
+    world: &'a World,
+    type_registry: &'a TypeRegistryArc,
+    required: Vec<TypeId>,
+    denied: HashSet<TypeId>,
+    resources: Vec<TypeId>,
+}
+
+impl<'a> DynamicSceneBuilder<'a> {
+    /// Starts with every entity and every component with a [ReflectComponent], and no resources.
+    pub fn new(world: &'a World, type_registry: &'a TypeRegistryArc) -> Self {
+        DynamicSceneBuilder {
+            world,
+            type_registry,
+            required: Vec::new(),
+            denied: HashSet::default(),
+            resources: Vec::new(),
+        }
+    }
+
+    /// Only extracts the entities that have a `T` component, such as [Persist].
+    pub fn require<T: Component>(mut self) -> Self {
+        self.required.push(TypeId::of::<T>());
+        self
+    }
+
+    /// Leaves `T` components out of the extracted entities.
+    pub fn deny<T: Component>(mut self) -> Self {
+        self.denied.insert(TypeId::of::<T>());
+        self
+    }
+
+    /// Extracts the `T` resource, if the world has it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `T` isn't registered with `#[reflect(Resource)]`.
+    pub fn with_resource<T: Component>(mut self) -> Self {
+        let type_registry = self.type_registry.read();
+        if type_registry
+            .get_type_data::<ReflectResource>(TypeId::of::<T>())
+            .is_none()
+        {
+            panic!(
+                "`{}` should be registered with `#[reflect(Resource)]` to be extracted",
+                type_name::<T>()
+            );
+        }
+        drop(type_registry);
+        self.resources.push(TypeId::of::<T>());
+        self
+    }
+
+    pub fn build(self) -> DynamicScene {
+        let world = self.world;
+        let type_registry = self.type_registry.read();
+        let mut scene = DynamicScene::default();
+        // a component without an id was never added to the world, so no entity has it
+        let required = self
+            .required
+            .iter()
+            .map(|type_id| world.components().get_id(*type_id))
+            .collect::<Option<Vec<_>>>();
+
+        for archetype in world.archetypes().iter() {
+            let has_required = required.as_ref().map_or(false, |required| {
+                required
+                    .iter()
+                    .all(|component_id| archetype.contains(*component_id))
+            });
+            if !has_required {
+                continue;
+            }
+            let entities_offset = scene.entities.len();
+            for entity in archetype.entities() {
+                scene.entities.push(Entity {
+                    entity: entity.id(),
+                    components: Vec::new(),
+                    prefab: None,
+                });
+            }
+
+            for component_id in archetype.components() {
+                let reflect_component = world
+                    .components()
+                    .get_info(component_id)
+                    .and_then(|info| info.type_id())
+                    .filter(|type_id| !self.denied.contains(type_id))
+                    .and_then(|type_id| type_registry.get(type_id))
+                    .and_then(|registration| registration.data::<ReflectComponent>());
+                if let Some(reflect_component) = reflect_component {
+                    for (i, entity) in archetype.entities().iter().enumerate() {
+                        if let Some(component) = reflect_component.reflect_component(world, *entity)
+                        {
+                            scene.entities[entities_offset + i]
+                                .components
+                                .push(component.clone_value());
+                        }
+                    }
+                }
+            }
+        }
+
+        for type_id in self.resources.iter() {
+            let resource = type_registry
+                .get_type_data::<ReflectResource>(*type_id)
+                .and_then(|reflect_resource| reflect_resource.reflect_resource(world));
+            if let Some(resource) = resource {
+                scene.resources.push(resource.clone_value());
+            }
+        }
+
+        scene
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::SceneDeserializer;
+    use bevy_ecs::entity::EntityMap;
+    use serde::de::DeserializeSeed;
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Health {
+        value: u32,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Cached {
+        value: u32,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Resource)]
+    struct Score {
+        value: u32,
+    }
+
+    #[test]
+    fn extract_save_game() {
+        let registry = TypeRegistryArc::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Persist>();
+            registry.register::<Health>();
+            registry.register::<Cached>();
+            registry.register::<Score>();
+            registry.register::<u32>();
+        }
+        let mut world = World::default();
+        world
+            .spawn()
+            .insert_bundle((Persist, Health { value: 10 }, Cached { value: 1 }));
+        world.spawn().insert_bundle((Health { value: 20 },));
+        world.insert_resource(Score { value: 42 });
+
+        let scene = DynamicSceneBuilder::new(&world, &registry)
+            .require::<Persist>()
+            .deny::<Cached>()
+            .with_resource::<Score>()
+            .build();
+        assert_eq!(scene.entities.len(), 1);
+        assert_eq!(scene.entities[0].components.len(), 2);
+        assert_eq!(scene.resources.len(), 1);
+
+        let ron = scene.serialize_ron(&registry).unwrap();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let scene = SceneDeserializer {
+            type_registry: &registry.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut loaded = World::default();
+        loaded.insert_resource(registry.clone());
+        scene
+            .write_to_world(&mut loaded, &mut EntityMap::default())
+            .unwrap();
+        assert_eq!(
+            loaded.query::<&Health>().iter(&loaded).collect::<Vec<_>>(),
+            vec![&Health { value: 10 }]
+        );
+        assert_eq!(loaded.query::<&Persist>().iter(&loaded).count(), 1);
+        assert_eq!(loaded.query::<&Cached>().iter(&loaded).count(), 0);
+        assert_eq!(loaded.get_resource::<Score>(), Some(&Score { value: 42 }));
+
+        // nothing has a component the world never saw
+        let scene = DynamicSceneBuilder::new(&world, &registry)
+            .require::<Score>()
+            .build();
+        assert!(scene.entities.is_empty());
+    }
+}
@@ -1,19 +1,28 @@
 mod command;
 mod dynamic_scene;
+mod dynamic_scene_builder;
+mod prefab;
 mod scene;
 mod scene_loader;
+mod scene_patch;
+mod scene_saver;
 mod scene_spawner;
 pub mod serde;
 
 pub use command::*;
 pub use dynamic_scene::*;
+pub use dynamic_scene_builder::*;
+pub use prefab::*;
 pub use scene::*;
 pub use scene_loader::*;
+pub use scene_patch::*;
+pub use scene_saver::*;
 pub use scene_spawner::*;
 
 pub mod prelude {
     pub use crate::{
-        DynamicScene, Scene, SceneSpawner, SpawnSceneAsChildCommands, SpawnSceneCommands,
+        DynamicScene, DynamicSceneBuilder, Persist, Scene, SceneSpawner, SpawnSceneAsChildCommands,
+        SpawnSceneCommands,
     };
 }
 
@@ -29,7 +38,10 @@ impl Plugin for ScenePlugin {
         app.add_asset::<DynamicScene>()
             .add_asset::<Scene>()
             .init_asset_loader::<SceneLoader>()
+            .init_asset_saver::<SceneSaver>()
             .init_resource::<SceneSpawner>()
+            .add_event::<SceneInstanceReady>()
+            .register_type::<Persist>()
             .add_system_to_stage(
                 CoreStage::PreUpdate,
                 scene_spawner_system.exclusive_system().at_end(),
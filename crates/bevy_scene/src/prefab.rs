@@ -0,0 +1,176 @@
+use crate::Entity;
+use bevy_asset::{AssetPath, AssetServer, HandleUntyped};
+use bevy_reflect::{DynamicStruct, Reflect, ReflectRef, Struct};
+
+/// Another scene spawned as part of a [DynamicScene](crate::DynamicScene), such as a door placed
+/// in a level. It is referenced by an entity of the scene, which becomes the parent of the root
+/// entities of the prefab's instance. Prefabs can reference prefabs themselves.
+///
+/// The prefab's instance is spawned again when the prefab asset changes, and its overrides are
+/// applied on top.
+#[derive(Clone)]
+pub struct ScenePrefab {
+    /// The path the prefab is loaded from, which is what scene files store
+    pub path: AssetPath<'static>,
+    /// The handle of the prefab, which is either a [DynamicScene](crate::DynamicScene) or a
+    /// [Scene](crate::Scene), such as a scene of a GLTF file. It is untyped, as scenes can't hold
+    /// typed handles to scenes.
+    pub handle: HandleUntyped,
+    /// Components applied over the entities of the prefab in this instance, by their id in the
+    /// prefab. Structs only need the fields that differ from the prefab, see
+    /// [ScenePrefab::with_override].
+    pub overrides: Vec<Entity>,
+}
+
+impl ScenePrefab {
+    pub fn new<'a, P: Into<AssetPath<'a>>>(asset_server: &AssetServer, path: P) -> Self {
+        let path = path.into().to_owned();
+        ScenePrefab {
+            handle: asset_server.load_untyped(path.clone()),
+            path,
+            overrides: Vec::new(),
+        }
+    }
+
+    /// Overrides a component of an entity of the prefab. Only the difference between the
+    /// `prefab_component` and the `component` is stored, so fields that aren't overridden still
+    /// follow changes to the prefab.
+    pub fn with_override(
+        mut self,
+        entity: u32,
+        prefab_component: &dyn Reflect,
+        component: &dyn Reflect,
+    ) -> Self {
+        let diff = match reflect_diff(prefab_component, component) {
+            Some(diff) => diff,
+            None => return self,
+        };
+        let index = match self
+            .overrides
+            .iter()
+            .position(|overrides| overrides.entity == entity)
+        {
+            Some(index) => index,
+            None => {
+                self.overrides.push(Entity {
+                    entity,
+                    components: Vec::new(),
+                    prefab: None,
+                });
+                self.overrides.len() - 1
+            }
+        };
+        let components = &mut self.overrides[index].components;
+        components.retain(|existing| existing.type_name() != diff.type_name());
+        components.push(diff);
+        self
+    }
+
+    /// Whether both reference the same prefab with equal overrides.
+    pub(crate) fn reflect_eq(&self, other: &ScenePrefab) -> bool {
+        self.handle.id == other.handle.id
+            && self.overrides.len() == other.overrides.len()
+            && self.overrides.iter().all(|overrides| {
+                other
+                    .overrides
+                    .iter()
+                    .any(|other| overrides.entity == other.entity && overrides.reflect_eq(other))
+            })
+    }
+}
+
+/// Returns what differs in `value` from `base`, which gives `value` back when it is applied to
+/// `base`. Only the struct fields that differ are kept, other values are cloned whole. Returns
+/// `None` if the values are equal. Values that can't be compared count as different.
+pub fn reflect_diff(base: &dyn Reflect, value: &dyn Reflect) -> Option<Box<dyn Reflect>> {
+    if base.type_name() == value.type_name() && value.reflect_partial_eq(base) == Some(true) {
+        return None;
+    }
+    match (base.reflect_ref(), value.reflect_ref()) {
+        (ReflectRef::Struct(base), ReflectRef::Struct(value))
+            if base.type_name() == value.type_name() =>
+        {
+            let mut diff = DynamicStruct::default();
+            diff.set_name(value.type_name().to_string());
+            for (i, field) in value.iter_fields().enumerate() {
+                let name = value.name_at(i).unwrap();
+                let field_diff = match base.field(name) {
+                    Some(base_field) => reflect_diff(base_field, field),
+                    None => Some(field.clone_value()),
+                };
+                if let Some(field_diff) = field_diff {
+                    diff.insert_boxed(name, field_diff);
+                }
+            }
+            if diff.field_len() == 0 {
+                None
+            } else {
+                Some(Box::new(diff))
+            }
+        }
+        _ => Some(value.clone_value()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Reflect, Default, Clone, PartialEq, Debug)]
+    struct Door {
+        width: u32,
+        height: u32,
+        lock: Lock,
+    }
+
+    #[derive(Reflect, Default, Clone, PartialEq, Debug)]
+    struct Lock {
+        code: u32,
+        locked: bool,
+    }
+
+    #[test]
+    fn diff_only_keeps_changed_fields() {
+        let base = Door {
+            width: 100,
+            height: 200,
+            lock: Lock {
+                code: 1234,
+                locked: true,
+            },
+        };
+        let mut value = base.clone();
+        value.height = 300;
+        value.lock.locked = false;
+
+        assert!(reflect_diff(&base, &base.clone()).is_none());
+        let diff_value = reflect_diff(&base, &value).unwrap();
+        let diff = match diff_value.reflect_ref() {
+            ReflectRef::Struct(diff) => diff,
+            _ => panic!("diff of a struct should be a struct"),
+        };
+        assert_eq!(diff.field_len(), 2);
+        assert!(diff.field("width").is_none());
+        match diff.field("lock").unwrap().reflect_ref() {
+            ReflectRef::Struct(lock) => {
+                assert_eq!(lock.field_len(), 1);
+                assert!(lock.field("locked").is_some());
+            }
+            _ => panic!("diff of a struct should be a struct"),
+        }
+
+        // applying the diff to a changed prefab keeps the changes that weren't overridden
+        let mut prefab = base;
+        prefab.width = 150;
+        prefab.apply(&*diff_value);
+        assert_eq!(prefab.width, 150);
+        assert_eq!(prefab.height, 300);
+        assert_eq!(
+            prefab.lock,
+            Lock {
+                code: 1234,
+                locked: false
+            }
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::serde::SceneDeserializer;
+use crate::{serde::SceneDeserializer, DynamicScene};
 use anyhow::Result;
 use bevy_asset::{AssetLoader, LoadContext, LoadedAsset};
 use bevy_ecs::world::{FromWorld, World};
@@ -31,8 +31,20 @@ impl AssetLoader for SceneLoader {
             let scene_deserializer = SceneDeserializer {
                 type_registry: &*self.type_registry.read(),
             };
-            let scene = scene_deserializer.deserialize(&mut deserializer)?;
-            load_context.set_default_asset(LoadedAsset::new(scene));
+            let mut scene = scene_deserializer.deserialize(&mut deserializer)?;
+            // prefabs are dependencies, which the scene keeps loaded
+            let mut dependencies = Vec::new();
+            for prefab in scene
+                .entities
+                .iter_mut()
+                .filter_map(|entity| entity.prefab.as_mut())
+            {
+                prefab.handle = load_context
+                    .get_handle::<_, DynamicScene>(prefab.path.clone())
+                    .clone_untyped();
+                dependencies.push(prefab.path.clone());
+            }
+            load_context.set_default_asset(LoadedAsset::new(scene).with_dependencies(dependencies));
             Ok(())
         })
     }
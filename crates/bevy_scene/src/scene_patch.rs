@@ -0,0 +1,434 @@
+use crate::{
+    dynamic_scene::map_written_entities, reflect_diff, serde::ScenePatchSerializer, serialize_ron,
+    write_components, DynamicScene, Entity, SceneSpawnError,
+};
+use bevy_ecs::{entity::EntityMap, reflect::ReflectComponent, world::World};
+use bevy_reflect::{Reflect, TypeRegistryArc};
+
+/// The changes that turn one [DynamicScene] into another, as returned by [DynamicScene::diff].
+/// Patches can be applied to a [DynamicScene] or to a [World] the scene was written to, and can be
+/// serialized, which makes them suitable for undo history or for sending edits over the network.
+#[derive(Default, Clone)]
+pub struct ScenePatch {
+    /// Entities that are new, with all of their components
+    pub added: Vec<Entity>,
+    /// Ids of the entities that were removed
+    pub removed: Vec<u32>,
+    /// Changes to the components of entities that are in both scenes
+    pub changed: Vec<EntityPatch>,
+}
+
+/// The changes to the components of one entity.
+pub struct EntityPatch {
+    pub entity: u32,
+    /// Components that were added, whole, and the fields that changed in existing components. See
+    /// [reflect_diff].
+    pub components: Vec<Box<dyn Reflect>>,
+    /// The type names of the components that were removed
+    pub removed_components: Vec<String>,
+}
+
+impl Clone for EntityPatch {
+    fn clone(&self) -> Self {
+        EntityPatch {
+            entity: self.entity,
+            components: self
+                .components
+                .iter()
+                .map(|component| component.clone_value())
+                .collect(),
+            removed_components: self.removed_components.clone(),
+        }
+    }
+}
+
+impl EntityPatch {
+    fn new(from: &Entity, to: &Entity) -> Self {
+        let components = to
+            .components
+            .iter()
+            .filter_map(|component| {
+                match from
+                    .components
+                    .iter()
+                    .find(|from_component| from_component.type_name() == component.type_name())
+                {
+                    Some(from_component) => reflect_diff(&**from_component, &**component),
+                    None => Some(component.clone_value()),
+                }
+            })
+            .collect();
+        let removed_components = from
+            .components
+            .iter()
+            .filter(|from_component| {
+                to.components
+                    .iter()
+                    .all(|component| component.type_name() != from_component.type_name())
+            })
+            .map(|from_component| from_component.type_name().to_string())
+            .collect();
+        EntityPatch {
+            entity: to.entity,
+            components,
+            removed_components,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.components.is_empty() && self.removed_components.is_empty()
+    }
+
+    /// Applies the changes to the components of `entity`.
+    pub fn apply_to_entity(&self, entity: &mut Entity) {
+        entity.components.retain(|component| {
+            !self
+                .removed_components
+                .iter()
+                .any(|removed| removed == component.type_name())
+        });
+        for component in self.components.iter() {
+            match entity
+                .components
+                .iter_mut()
+                .find(|existing| existing.type_name() == component.type_name())
+            {
+                Some(existing) => existing.apply(&**component),
+                None => entity.components.push(component.clone_value()),
+            }
+        }
+    }
+}
+
+impl DynamicScene {
+    /// Returns the changes that turn this scene into `other`. Entities are matched by id, and
+    /// only the fields that differ are kept for components in both scenes. Entities whose prefab
+    /// changed are removed and added again. Resources aren't compared.
+    pub fn diff(&self, other: &DynamicScene) -> ScenePatch {
+        let mut patch = ScenePatch::default();
+        for entity in other.entities.iter() {
+            let from = match self
+                .entities
+                .iter()
+                .find(|from| from.entity == entity.entity)
+            {
+                Some(from) => from,
+                None => {
+                    patch.added.push(entity.clone());
+                    continue;
+                }
+            };
+            let same_prefab = match (&from.prefab, &entity.prefab) {
+                (None, None) => true,
+                (Some(from_prefab), Some(prefab)) => from_prefab.reflect_eq(prefab),
+                _ => false,
+            };
+            if !same_prefab {
+                patch.removed.push(entity.entity);
+                patch.added.push(entity.clone());
+                continue;
+            }
+            let entity_patch = EntityPatch::new(from, entity);
+            if !entity_patch.is_empty() {
+                patch.changed.push(entity_patch);
+            }
+        }
+        for from in self.entities.iter() {
+            if other
+                .entities
+                .iter()
+                .all(|entity| entity.entity != from.entity)
+            {
+                patch.removed.push(from.entity);
+            }
+        }
+        patch
+    }
+}
+
+impl ScenePatch {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Applies the patch to `scene`. Changes to entities the scene doesn't have are ignored.
+    pub fn apply_to_scene(&self, scene: &mut DynamicScene) {
+        scene
+            .entities
+            .retain(|entity| !self.removed.contains(&entity.entity));
+        scene.entities.extend(self.added.iter().cloned());
+        for entity_patch in self.changed.iter() {
+            if let Some(entity) = scene
+                .entities
+                .iter_mut()
+                .find(|entity| entity.entity == entity_patch.entity)
+            {
+                entity_patch.apply_to_entity(entity);
+            }
+        }
+    }
+
+    /// Applies the patch to `world`, in which `entity_map` maps the scene's entities. Changes to
+    /// entities that were already despawned, or that aren't in `entity_map`, are ignored. Prefabs
+    /// of added entities aren't spawned, which is left to the
+    /// [SceneSpawner](crate::SceneSpawner).
+    pub fn apply_to_world(
+        &self,
+        world: &mut World,
+        entity_map: &mut EntityMap,
+    ) -> Result<(), SceneSpawnError> {
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let type_registry = registry.read();
+        for removed in self.removed.iter() {
+            let scene_entity = bevy_ecs::entity::Entity::new(*removed);
+            if let Ok(entity) = entity_map.get(scene_entity) {
+                world.despawn(entity);
+                entity_map.remove(scene_entity);
+            }
+        }
+
+        let mut written = Vec::new();
+        for scene_entity in self.added.iter() {
+            let entity = *entity_map
+                .entry(bevy_ecs::entity::Entity::new(scene_entity.entity))
+                .or_insert_with(|| world.spawn().id());
+            write_components(world, &type_registry, entity, &scene_entity.components)?;
+            written.extend(
+                scene_entity
+                    .components
+                    .iter()
+                    .map(|component| (entity, component.type_name())),
+            );
+        }
+
+        for entity_patch in self.changed.iter() {
+            let entity = match entity_map.get(bevy_ecs::entity::Entity::new(entity_patch.entity)) {
+                Ok(entity) if world.get_entity(entity).is_some() => entity,
+                _ => continue,
+            };
+            for type_name in entity_patch.removed_components.iter() {
+                if let Some(reflect_component) = type_registry
+                    .get_with_name(type_name)
+                    .and_then(|registration| registration.data::<ReflectComponent>())
+                {
+                    reflect_component.remove_component(world, entity);
+                }
+            }
+            write_components(world, &type_registry, entity, &entity_patch.components)?;
+            written.extend(
+                entity_patch
+                    .components
+                    .iter()
+                    .map(|component| (entity, component.type_name())),
+            );
+        }
+
+        map_written_entities(world, &type_registry, entity_map, &written)?;
+        Ok(())
+    }
+
+    pub fn serialize_ron(&self, registry: &TypeRegistryArc) -> Result<String, ron::Error> {
+        serialize_ron(ScenePatchSerializer::new(self, registry))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serde::ScenePatchDeserializer;
+    use bevy_transform::prelude::{Children, Parent};
+    use serde::de::DeserializeSeed;
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Door {
+        width: u32,
+        open: bool,
+    }
+
+    #[derive(Reflect, Default, Debug, PartialEq)]
+    #[reflect(Component)]
+    struct Locked {
+        code: u32,
+    }
+
+    fn door(entity: u32, width: u32, open: bool) -> Entity {
+        Entity {
+            entity,
+            components: vec![Box::new(Door { width, open })],
+            prefab: None,
+        }
+    }
+
+    fn registry() -> TypeRegistryArc {
+        let registry = TypeRegistryArc::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Door>();
+            registry.register::<Locked>();
+            registry.register::<u32>();
+            registry.register::<bool>();
+            registry.register::<Parent>();
+            registry.register::<Children>();
+        }
+        registry
+    }
+
+    #[test]
+    fn diff_and_patch() {
+        let registry = registry();
+        let before = DynamicScene {
+            entities: vec![door(0, 100, false), door(1, 100, false), {
+                let mut locked = door(2, 80, false);
+                locked.components.push(Box::new(Locked { code: 1234 }));
+                locked
+            }],
+            resources: Vec::new(),
+        };
+        let after = DynamicScene {
+            entities: vec![door(0, 100, true), door(2, 80, false), door(3, 120, false)],
+            resources: Vec::new(),
+        };
+
+        let patch = before.diff(&after);
+        assert_eq!(patch.added.len(), 1);
+        assert_eq!(patch.removed, vec![1]);
+        assert_eq!(patch.changed.len(), 2);
+        assert!(before.diff(&before).is_empty());
+
+        // only the field that changed is kept
+        let opened = &patch.changed[0];
+        assert_eq!(opened.entity, 0);
+        match opened.components[0].reflect_ref() {
+            bevy_reflect::ReflectRef::Struct(door) => {
+                assert_eq!(door.field_len(), 1);
+                assert!(door.field("open").is_some());
+            }
+            _ => panic!("diff of a struct should be a struct"),
+        }
+        assert_eq!(
+            patch.changed[1].removed_components,
+            vec![std::any::type_name::<Locked>().to_string()]
+        );
+
+        // the patch survives serialization
+        let ron = patch.serialize_ron(&registry).unwrap();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let patch = ScenePatchDeserializer {
+            type_registry: &registry.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        let mut patched = before.clone();
+        patch.apply_to_scene(&mut patched);
+        assert_eq!(patched.entities.len(), after.entities.len());
+        for entity in after.entities.iter() {
+            let patched_entity = patched
+                .entities
+                .iter()
+                .find(|patched_entity| patched_entity.entity == entity.entity)
+                .unwrap();
+            assert!(patched_entity.reflect_eq(entity));
+        }
+
+        let mut world = World::default();
+        world.insert_resource(registry);
+        let mut entity_map = EntityMap::default();
+        before.write_to_world(&mut world, &mut entity_map).unwrap();
+        let opened = entity_map.get(bevy_ecs::entity::Entity::new(0)).unwrap();
+        world.get_mut::<Door>(opened).unwrap().width = 90;
+        patch.apply_to_world(&mut world, &mut entity_map).unwrap();
+
+        // changes made in the world to fields the patch doesn't touch are kept
+        assert_eq!(
+            world.get::<Door>(opened),
+            Some(&Door {
+                width: 90,
+                open: true
+            })
+        );
+        let unlocked = entity_map.get(bevy_ecs::entity::Entity::new(2)).unwrap();
+        assert!(world.get::<Locked>(unlocked).is_none());
+        assert!(entity_map.get(bevy_ecs::entity::Entity::new(1)).is_err());
+        let added = entity_map.get(bevy_ecs::entity::Entity::new(3)).unwrap();
+        assert_eq!(world.get::<Door>(added).unwrap().width, 120);
+        assert_eq!(world.query::<&Door>().iter(&world).count(), 3);
+    }
+
+    #[test]
+    fn skip_entities_missing_from_the_world() {
+        let mut world = World::default();
+        world.insert_resource(registry());
+        let scene = DynamicScene {
+            entities: vec![door(0, 100, false), door(1, 100, false)],
+            resources: Vec::new(),
+        };
+        let mut entity_map = EntityMap::default();
+        scene.write_to_world(&mut world, &mut entity_map).unwrap();
+        let despawned = entity_map.get(bevy_ecs::entity::Entity::new(1)).unwrap();
+        world.despawn(despawned);
+
+        let patch = ScenePatch {
+            added: Vec::new(),
+            removed: vec![7],
+            changed: [1, 5]
+                .iter()
+                .map(|&entity| {
+                    EntityPatch::new(&door(entity, 100, false), &door(entity, 100, true))
+                })
+                .collect(),
+        };
+        patch.apply_to_world(&mut world, &mut entity_map).unwrap();
+        // neither the despawned entity nor the unknown one are spawned
+        assert!(world.get_entity(despawned).is_none());
+        assert!(entity_map.get(bevy_ecs::entity::Entity::new(5)).is_err());
+        assert_eq!(world.query::<&Door>().iter(&world).count(), 1);
+        assert!(world.query::<&Door>().iter(&world).all(|door| !door.open));
+    }
+
+    #[test]
+    fn map_patched_entities() {
+        let mut world = World::default();
+        world.insert_resource(registry());
+        let scene = DynamicScene {
+            entities: vec![door(0, 100, false)],
+            resources: Vec::new(),
+        };
+        let mut entity_map = EntityMap::default();
+        scene.write_to_world(&mut world, &mut entity_map).unwrap();
+        let frame = entity_map.get(bevy_ecs::entity::Entity::new(0)).unwrap();
+        // a child that isn't part of the scene
+        let handle = world.spawn().insert(Parent(frame)).id();
+        world.entity_mut(frame).insert(Children::with(&[handle]));
+
+        let mut hinged = door(1, 80, false);
+        hinged
+            .components
+            .push(Box::new(Parent(bevy_ecs::entity::Entity::new(0))));
+        let patch = ScenePatch {
+            added: vec![hinged],
+            removed: Vec::new(),
+            changed: vec![EntityPatch::new(&door(0, 100, false), &door(0, 100, true))],
+        };
+        patch.apply_to_world(&mut world, &mut entity_map).unwrap();
+        let hinged = entity_map.get(bevy_ecs::entity::Entity::new(1)).unwrap();
+        assert_eq!(world.get::<Parent>(hinged), Some(&Parent(frame)));
+        assert_eq!(world.get::<Children>(frame).unwrap().len(), 1);
+        assert!(world.get::<Door>(frame).unwrap().open);
+
+        // entities that aren't part of the scene can't be referenced
+        let mut orphan = door(2, 80, false);
+        orphan
+            .components
+            .push(Box::new(Parent(bevy_ecs::entity::Entity::new(9))));
+        let patch = ScenePatch {
+            added: vec![orphan],
+            ..Default::default()
+        };
+        assert!(matches!(
+            patch.apply_to_world(&mut world, &mut entity_map),
+            Err(SceneSpawnError::MapEntities(_))
+        ));
+    }
+}
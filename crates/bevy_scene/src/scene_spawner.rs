@@ -1,58 +1,143 @@
-use crate::{DynamicScene, Scene};
+use crate::{
+    dynamic_scene::{map_written_entities, write_components},
+    DynamicScene, Scene, ScenePrefab,
+};
 use bevy_app::{Events, ManualEventReader};
-use bevy_asset::{AssetEvent, Assets, Handle};
+use bevy_asset::{AssetEvent, AssetServer, Assets, Handle, HandleId, LoadState};
 use bevy_ecs::{
-    entity::{Entity, EntityMap},
+    entity::{Entity, EntityMap, MapEntitiesError},
     reflect::{ReflectComponent, ReflectMapEntities},
     world::{Mut, World},
 };
 use bevy_reflect::TypeRegistryArc;
-use bevy_transform::prelude::Parent;
+use bevy_transform::{hierarchy::despawn_with_children_recursive, prelude::Parent};
 use bevy_utils::{tracing::error, HashMap};
 use thiserror::Error;
 use uuid::Uuid;
 
-#[derive(Debug)]
 struct InstanceInfo {
     entity_map: EntityMap,
+    /// The instances of the prefabs referenced by the entities of this instance
+    prefab_instances: Vec<InstanceId>,
+    /// Set for the instances of prefabs
+    prefab: Option<PrefabInstance>,
+}
+
+impl InstanceInfo {
+    fn new(entity_map: EntityMap) -> Self {
+        InstanceInfo {
+            entity_map,
+            prefab_instances: Vec::new(),
+            prefab: None,
+        }
+    }
+}
+
+/// Where the instance of a [ScenePrefab] is spawned
+#[derive(Clone)]
+struct PrefabInstance {
+    /// The entity that references the prefab, and its id in the scene it belongs to
+    parent: Entity,
+    scene_entity: u32,
+    prefab: ScenePrefab,
+    /// The scenes that reference this prefab, directly or through other prefabs
+    ancestors: Vec<HandleId>,
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
 pub struct InstanceId(Uuid);
 
+/// Sent by the [scene_spawner_system] when an instance queued with [SceneSpawner::spawn],
+/// [SceneSpawner::spawn_as_child] or [SceneSpawner::spawn_dynamic] has been spawned. The
+/// instance's prefabs may still be loading.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SceneInstanceReady {
+    pub instance_id: InstanceId,
+}
+
 impl InstanceId {
     fn new() -> Self {
         InstanceId(Uuid::new_v4())
     }
 }
 
+/// What happens to the spawned instances of a scene when the scene asset is modified, for example
+/// when it is hot reloaded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SceneUpdateMode {
+    /// Write modified [DynamicScene]s over their instances. Modified [Scene]s are only applied to
+    /// the instances of prefabs.
+    Overwrite,
+    /// Only apply the entities that changed in modified [Scene]s and [DynamicScene]s to their
+    /// instances, so the state of the other entities is preserved. This keeps a copy of every
+    /// spawned scene to compare against.
+    Patch,
+}
+
+impl Default for SceneUpdateMode {
+    fn default() -> Self {
+        SceneUpdateMode::Overwrite
+    }
+}
+
 #[derive(Default)]
 pub struct SceneSpawner {
     spawned_scenes: HashMap<Handle<Scene>, Vec<InstanceId>>,
     spawned_dynamic_scenes: HashMap<Handle<DynamicScene>, Vec<InstanceId>>,
     spawned_instances: HashMap<InstanceId, InstanceInfo>,
+    update_mode: SceneUpdateMode,
+    /// The version of each spawned scene its instances were last updated to, in
+    /// [SceneUpdateMode::Patch]
+    scene_snapshots: HashMap<HandleId, DynamicScene>,
     scene_asset_event_reader: ManualEventReader<AssetEvent<DynamicScene>>,
-    dynamic_scenes_to_spawn: Vec<Handle<DynamicScene>>,
+    real_scene_asset_event_reader: ManualEventReader<AssetEvent<Scene>>,
+    dynamic_scenes_to_spawn: Vec<(Handle<DynamicScene>, InstanceId)>,
     scenes_to_spawn: Vec<(Handle<Scene>, InstanceId)>,
     scenes_to_despawn: Vec<Handle<DynamicScene>>,
+    /// Instances to despawn, and whether to despawn their children too
+    instances_to_despawn: Vec<(InstanceId, bool)>,
+    /// Queued instances that were spawned since the last [SceneInstanceReady] events were sent
+    instances_ready: Vec<InstanceId>,
     scenes_with_parent: Vec<(InstanceId, Entity)>,
+    /// The instances of prefabs that weren't loaded yet when they were spawned
+    prefabs_to_spawn: Vec<(InstanceId, PrefabInstance)>,
 }
 
 #[derive(Error, Debug)]
 pub enum SceneSpawnError {
     #[error("scene contains the unregistered component `{type_name}`. consider adding `#[reflect(Component)]` to your type")]
     UnregisteredComponent { type_name: String },
+    #[error("scene contains the unregistered resource `{type_name}`. consider adding `#[reflect(Resource)]` to your type")]
+    UnregisteredResource { type_name: String },
     #[error("scene contains the unregistered type `{type_name}`. consider registering the type using `app.register_type::<T>()`")]
     UnregisteredType { type_name: String },
     #[error("scene does not exist")]
     NonExistentScene { handle: Handle<DynamicScene> },
     #[error("scene does not exist")]
     NonExistentRealScene { handle: Handle<Scene> },
+    #[error("scene references an entity that isn't part of it")]
+    MapEntities(#[from] MapEntitiesError),
 }
 
 impl SceneSpawner {
-    pub fn spawn_dynamic(&mut self, scene_handle: Handle<DynamicScene>) {
-        self.dynamic_scenes_to_spawn.push(scene_handle);
+    pub fn update_mode(&self) -> SceneUpdateMode {
+        self.update_mode
+    }
+
+    /// Sets what happens to spawned instances when their scene is modified. Switch to
+    /// [SceneUpdateMode::Patch] before spawning the scenes that should be patched.
+    pub fn set_update_mode(&mut self, update_mode: SceneUpdateMode) {
+        self.update_mode = update_mode;
+        if update_mode != SceneUpdateMode::Patch {
+            self.scene_snapshots.clear();
+        }
+    }
+
+    pub fn spawn_dynamic(&mut self, scene_handle: Handle<DynamicScene>) -> InstanceId {
+        let instance_id = InstanceId::new();
+        self.dynamic_scenes_to_spawn
+            .push((scene_handle, instance_id));
+        instance_id
     }
 
     pub fn spawn(&mut self, scene_handle: Handle<Scene>) -> InstanceId {
@@ -72,12 +157,90 @@ impl SceneSpawner {
         self.scenes_to_despawn.push(scene_handle);
     }
 
+    /// Despawns the entities of an instance of a [Scene] or [DynamicScene]. Their children that
+    /// aren't part of the instance are kept, see [SceneSpawner::despawn_instance_recursive]. The
+    /// instances of prefabs are despawned along with the instance that references them.
+    pub fn despawn_instance(&mut self, instance_id: InstanceId) {
+        self.instances_to_despawn.push((instance_id, false));
+    }
+
+    /// Despawns the entities of an instance of a [Scene] or [DynamicScene], along with their
+    /// children.
+    pub fn despawn_instance_recursive(&mut self, instance_id: InstanceId) {
+        self.instances_to_despawn.push((instance_id, true));
+    }
+
+    pub fn despawn_instance_sync(&mut self, world: &mut World, instance_id: InstanceId) {
+        self.despawn_instance_internal(world, instance_id, false);
+    }
+
+    pub fn despawn_instance_recursive_sync(&mut self, world: &mut World, instance_id: InstanceId) {
+        self.despawn_instance_internal(world, instance_id, true);
+    }
+
+    /// Despawns an instance that isn't a prefab instance, or cancels its spawn if it's still
+    /// queued.
+    fn despawn_instance_internal(
+        &mut self,
+        world: &mut World,
+        instance_id: InstanceId,
+        recursive: bool,
+    ) {
+        self.dynamic_scenes_to_spawn
+            .retain(|(_, id)| *id != instance_id);
+        self.scenes_to_spawn.retain(|(_, id)| *id != instance_id);
+        self.scenes_with_parent.retain(|(id, _)| *id != instance_id);
+        self.instances_ready.retain(|id| *id != instance_id);
+        if self.is_prefab_instance(instance_id) {
+            return;
+        }
+
+        self.discard_prefab_instances(world, instance_id);
+        let instance_info = match self.spawned_instances.remove(&instance_id) {
+            Some(instance_info) => instance_info,
+            None => return,
+        };
+        for entity in instance_info.entity_map.values() {
+            if world.get_entity(entity).is_none() {
+                continue;
+            }
+            if recursive {
+                despawn_with_children_recursive(world, entity);
+            } else {
+                world.despawn(entity);
+            }
+        }
+
+        let mut emptied = None;
+        for (handle, instance_ids) in self.spawned_scenes.iter_mut() {
+            instance_ids.retain(|id| *id != instance_id);
+            if instance_ids.is_empty() {
+                emptied = Some(handle.clone_weak());
+            }
+        }
+        if let Some(handle) = emptied {
+            self.spawned_scenes.remove(&handle);
+            self.scene_snapshots.remove(&handle.id);
+        }
+        let mut emptied = None;
+        for (handle, instance_ids) in self.spawned_dynamic_scenes.iter_mut() {
+            instance_ids.retain(|id| *id != instance_id);
+            if instance_ids.is_empty() {
+                emptied = Some(handle.clone_weak());
+            }
+        }
+        if let Some(handle) = emptied {
+            self.spawned_dynamic_scenes.remove(&handle);
+            self.scene_snapshots.remove(&handle.id);
+        }
+    }
+
     pub fn despawn_sync(
         &mut self,
         world: &mut World,
         scene_handle: Handle<DynamicScene>,
     ) -> Result<(), SceneSpawnError> {
-        if let Some(instance_ids) = self.spawned_dynamic_scenes.get(&scene_handle) {
+        if let Some(instance_ids) = self.spawned_dynamic_scenes.remove(&scene_handle) {
             for instance_id in instance_ids {
                 if let Some(instance) = self.spawned_instances.get(&instance_id) {
                     for entity in instance.entity_map.values() {
@@ -85,9 +248,10 @@ impl SceneSpawner {
                                                        // it exists.
                     }
                 }
+                self.discard_prefab_instances(world, instance_id);
             }
 
-            self.spawned_dynamic_scenes.remove(&scene_handle);
+            self.scene_snapshots.remove(&scene_handle.id);
         }
         Ok(())
     }
@@ -96,20 +260,293 @@ impl SceneSpawner {
         &mut self,
         world: &mut World,
         scene_handle: &Handle<DynamicScene>,
-    ) -> Result<(), SceneSpawnError> {
+    ) -> Result<InstanceId, SceneSpawnError> {
+        self.spawn_dynamic_sync_internal(world, scene_handle, InstanceId::new())
+    }
+
+    fn spawn_dynamic_sync_internal(
+        &mut self,
+        world: &mut World,
+        scene_handle: &Handle<DynamicScene>,
+        instance_id: InstanceId,
+    ) -> Result<InstanceId, SceneSpawnError> {
         let mut entity_map = EntityMap::default();
         Self::spawn_dynamic_internal(world, scene_handle, &mut entity_map)?;
-        let instance_id = InstanceId::new();
         self.spawned_instances
-            .insert(instance_id, InstanceInfo { entity_map });
+            .insert(instance_id, InstanceInfo::new(entity_map));
         let spawned = self
             .spawned_dynamic_scenes
             .entry(scene_handle.clone())
             .or_insert_with(Vec::new);
         spawned.push(instance_id);
+        self.spawn_prefabs(world, instance_id, scene_handle)?;
+        self.take_snapshot(world, scene_handle.id);
+        Ok(instance_id)
+    }
+
+    /// Spawns the prefabs referenced by the entities of an instance of `scene_handle`. When the
+    /// instance is updated, the prefab instances whose reference didn't change are kept and the
+    /// others are spawned again.
+    fn spawn_prefabs(
+        &mut self,
+        world: &mut World,
+        instance_id: InstanceId,
+        scene_handle: &Handle<DynamicScene>,
+    ) -> Result<(), SceneSpawnError> {
+        let scenes = world.get_resource::<Assets<DynamicScene>>().unwrap();
+        let (scene, instance_info) = match (
+            scenes.get(scene_handle),
+            self.spawned_instances.get_mut(&instance_id),
+        ) {
+            (Some(scene), Some(instance_info)) => (scene, instance_info),
+            _ => return Ok(()),
+        };
+        let mut ancestors = instance_info
+            .prefab
+            .as_ref()
+            .map_or_else(Vec::new, |prefab| prefab.ancestors.clone());
+        ancestors.push(scene_handle.id);
+        let mut prefabs: Vec<PrefabInstance> = scene
+            .entities
+            .iter()
+            .filter_map(|scene_entity| {
+                let prefab = scene_entity.prefab.as_ref()?;
+                let parent = instance_info
+                    .entity_map
+                    .get(Entity::new(scene_entity.entity))
+                    .ok()?;
+                Some(PrefabInstance {
+                    parent,
+                    scene_entity: scene_entity.entity,
+                    prefab: prefab.clone(),
+                    ancestors: ancestors.clone(),
+                })
+            })
+            .collect();
+
+        let mut kept = Vec::new();
+        for prefab_instance_id in std::mem::take(&mut instance_info.prefab_instances) {
+            let existing = self
+                .spawned_instances
+                .get(&prefab_instance_id)
+                .and_then(|instance_info| instance_info.prefab.as_ref())
+                .or_else(|| {
+                    self.prefabs_to_spawn
+                        .iter()
+                        .find(|(id, _)| *id == prefab_instance_id)
+                        .map(|(_, prefab)| prefab)
+                });
+            let unchanged = existing.and_then(|existing| {
+                prefabs.iter().position(|prefab| {
+                    prefab.scene_entity == existing.scene_entity
+                        && prefab.parent == existing.parent
+                        && prefab.prefab.reflect_eq(&existing.prefab)
+                })
+            });
+            match unchanged {
+                Some(index) => {
+                    prefabs.remove(index);
+                    kept.push(prefab_instance_id);
+                }
+                None => self.discard_instance(world, prefab_instance_id),
+            }
+        }
+        if let Some(instance_info) = self.spawned_instances.get_mut(&instance_id) {
+            instance_info.prefab_instances = kept;
+        }
+
+        for prefab in prefabs {
+            if prefab.ancestors.contains(&prefab.prefab.handle.id) {
+                error!("scene prefab {} references itself", prefab.prefab.path);
+                continue;
+            }
+            let prefab_instance_id = InstanceId::new();
+            if let Some(instance_info) = self.spawned_instances.get_mut(&instance_id) {
+                instance_info.prefab_instances.push(prefab_instance_id);
+            }
+            self.spawn_prefab_instance(world, prefab_instance_id, prefab)?;
+        }
+        Ok(())
+    }
+
+    /// Spawns the instance of a prefab with its overrides, or queues it if the prefab isn't
+    /// loaded yet. Prefabs can be [DynamicScene]s or [Scene]s.
+    fn spawn_prefab_instance(
+        &mut self,
+        world: &mut World,
+        instance_id: InstanceId,
+        prefab: PrefabInstance,
+    ) -> Result<(), SceneSpawnError> {
+        let handle_id = prefab.prefab.handle.id;
+        let is_scene = world
+            .get_resource::<Assets<Scene>>()
+            .map_or(false, |scenes| scenes.contains(handle_id));
+        let mut entity_map = EntityMap::default();
+        let spawned = if is_scene {
+            let scene = Self::get_snapshot(world, handle_id).unwrap();
+            scene.write_to_world(world, &mut entity_map)
+        } else {
+            Self::spawn_dynamic_internal(world, &Handle::weak(handle_id), &mut entity_map)
+        };
+        match spawned {
+            Ok(()) => {}
+            Err(SceneSpawnError::NonExistentScene { .. }) => {
+                let load_state = world
+                    .get_resource::<AssetServer>()
+                    .map_or(LoadState::NotLoaded, |asset_server| {
+                        asset_server.get_load_state(handle_id)
+                    });
+                match load_state {
+                    LoadState::Loaded => {
+                        error!("scene prefab {} is not a scene", prefab.prefab.path)
+                    }
+                    LoadState::Failed => {
+                        error!("scene prefab {} failed to load", prefab.prefab.path)
+                    }
+                    _ => self.prefabs_to_spawn.push((instance_id, prefab)),
+                }
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        }
+
+        let registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
+        let type_registry = registry.read();
+        let mut written = Vec::new();
+        for overrides in prefab.prefab.overrides.iter() {
+            if let Ok(entity) = entity_map.get(Entity::new(overrides.entity)) {
+                write_components(world, &type_registry, entity, &overrides.components)?;
+                written.extend(
+                    overrides
+                        .components
+                        .iter()
+                        .map(|component| (entity, component.type_name())),
+                );
+            }
+        }
+        map_written_entities(world, &type_registry, &entity_map, &written)?;
+        for entity in entity_map.values() {
+            if let Some(mut entity_mut) = world.get_entity_mut(entity) {
+                if !entity_mut.contains::<Parent>() {
+                    entity_mut.insert(Parent(prefab.parent));
+                }
+            }
+        }
+
+        self.spawned_instances.insert(
+            instance_id,
+            InstanceInfo {
+                entity_map,
+                prefab_instances: Vec::new(),
+                prefab: Some(prefab),
+            },
+        );
+        if is_scene {
+            self.spawned_scenes
+                .entry(Handle::weak(handle_id))
+                .or_insert_with(Vec::new)
+                .push(instance_id);
+            Ok(())
+        } else {
+            let scene_handle = Handle::weak(handle_id);
+            self.spawned_dynamic_scenes
+                .entry(scene_handle.clone())
+                .or_insert_with(Vec::new)
+                .push(instance_id);
+            self.spawn_prefabs(world, instance_id, &scene_handle)
+        }
+    }
+
+    /// Despawns the instance of a prefab along with its children, including the prefab
+    /// instances nested in it, and forgets about them.
+    fn discard_instance(&mut self, world: &mut World, instance_id: InstanceId) {
+        self.prefabs_to_spawn.retain(|(id, _)| *id != instance_id);
+        let instance_info = match self.spawned_instances.remove(&instance_id) {
+            Some(instance_info) => instance_info,
+            None => return,
+        };
+        if let Some(prefab) = &instance_info.prefab {
+            let handle_id = prefab.prefab.handle.id;
+            if let Some(instance_ids) = self
+                .spawned_dynamic_scenes
+                .get_mut(&Handle::weak(handle_id))
+            {
+                instance_ids.retain(|id| *id != instance_id);
+                if instance_ids.is_empty() {
+                    self.spawned_dynamic_scenes.remove(&Handle::weak(handle_id));
+                }
+            }
+            if let Some(instance_ids) = self.spawned_scenes.get_mut(&Handle::weak(handle_id)) {
+                instance_ids.retain(|id| *id != instance_id);
+                if instance_ids.is_empty() {
+                    self.spawned_scenes.remove(&Handle::weak(handle_id));
+                }
+            }
+        }
+        for entity in instance_info.entity_map.values() {
+            if world.get_entity(entity).is_some() {
+                despawn_with_children_recursive(world, entity);
+            }
+        }
+        for prefab_instance_id in instance_info.prefab_instances {
+            self.discard_instance(world, prefab_instance_id);
+        }
+    }
+
+    fn discard_prefab_instances(&mut self, world: &mut World, instance_id: InstanceId) {
+        let prefab_instances = match self.spawned_instances.get_mut(&instance_id) {
+            Some(instance_info) => std::mem::take(&mut instance_info.prefab_instances),
+            None => return,
+        };
+        for prefab_instance_id in prefab_instances {
+            self.discard_instance(world, prefab_instance_id);
+        }
+    }
+
+    /// Spawns the instance of a prefab again, after the prefab changed.
+    fn respawn_prefab_instance(
+        &mut self,
+        world: &mut World,
+        instance_id: InstanceId,
+    ) -> Result<(), SceneSpawnError> {
+        let prefab = match self
+            .spawned_instances
+            .get(&instance_id)
+            .and_then(|instance_info| instance_info.prefab.clone())
+        {
+            Some(prefab) => prefab,
+            None => return Ok(()),
+        };
+        self.discard_instance(world, instance_id);
+        self.spawn_prefab_instance(world, instance_id, prefab)
+    }
+
+    /// Spawns the prefab instances of a modified [Scene] again. Its other instances are only
+    /// updated in [SceneUpdateMode::Patch].
+    fn respawn_scene_prefab_instances(
+        &mut self,
+        world: &mut World,
+        handle_id: HandleId,
+    ) -> Result<(), SceneSpawnError> {
+        let instance_ids = self
+            .spawned_scenes
+            .get(&Handle::weak(handle_id))
+            .cloned()
+            .unwrap_or_default();
+        for instance_id in instance_ids {
+            if self.is_prefab_instance(instance_id) {
+                self.respawn_prefab_instance(world, instance_id)?;
+            }
+        }
         Ok(())
     }
 
+    fn is_prefab_instance(&self, instance_id: InstanceId) -> bool {
+        self.spawned_instances
+            .get(&instance_id)
+            .map_or(false, |instance_info| instance_info.prefab.is_some())
+    }
+
     fn spawn_dynamic_internal(
         world: &mut World,
         scene_handle: &Handle<DynamicScene>,
@@ -140,9 +577,19 @@ impl SceneSpawner {
         scene_handle: Handle<Scene>,
         instance_id: InstanceId,
     ) -> Result<InstanceId, SceneSpawnError> {
-        let mut instance_info = InstanceInfo {
-            entity_map: EntityMap::default(),
-        };
+        let scene_handle_id = scene_handle.id;
+        let instance_id = self.spawn_scene_instance(world, scene_handle, instance_id)?;
+        self.take_snapshot(world, scene_handle_id);
+        Ok(instance_id)
+    }
+
+    fn spawn_scene_instance(
+        &mut self,
+        world: &mut World,
+        scene_handle: Handle<Scene>,
+        instance_id: InstanceId,
+    ) -> Result<InstanceId, SceneSpawnError> {
+        let mut instance_info = InstanceInfo::new(EntityMap::default());
         let type_registry = world.get_resource::<TypeRegistryArc>().unwrap().clone();
         let type_registry = type_registry.read();
         world.resource_scope(|world, scenes: Mut<Assets<Scene>>| {
@@ -189,9 +636,7 @@ impl SceneSpawner {
             }
             for registration in type_registry.iter() {
                 if let Some(map_entities_reflect) = registration.data::<ReflectMapEntities>() {
-                    map_entities_reflect
-                        .map_entities(world, &instance_info.entity_map)
-                        .unwrap();
+                    map_entities_reflect.map_entities(world, &instance_info.entity_map)?;
                 }
             }
             self.spawned_instances.insert(instance_id, instance_info);
@@ -210,21 +655,89 @@ impl SceneSpawner {
         scene_handles: &[Handle<DynamicScene>],
     ) -> Result<(), SceneSpawnError> {
         for scene_handle in scene_handles {
-            if let Some(spawned_instances) = self.spawned_dynamic_scenes.get(scene_handle) {
-                for instance_id in spawned_instances.iter() {
-                    if let Some(instance_info) = self.spawned_instances.get_mut(instance_id) {
-                        Self::spawn_dynamic_internal(
-                            world,
-                            scene_handle,
-                            &mut instance_info.entity_map,
-                        )?;
-                    }
+            let instance_ids = self
+                .spawned_dynamic_scenes
+                .get(scene_handle)
+                .cloned()
+                .unwrap_or_default();
+            for instance_id in instance_ids {
+                if self.is_prefab_instance(instance_id) {
+                    self.respawn_prefab_instance(world, instance_id)?;
+                } else if let Some(instance_info) = self.spawned_instances.get_mut(&instance_id) {
+                    Self::spawn_dynamic_internal(
+                        world,
+                        scene_handle,
+                        &mut instance_info.entity_map,
+                    )?;
+                    self.spawn_prefabs(world, instance_id, scene_handle)?;
                 }
             }
         }
         Ok(())
     }
 
+    /// Returns the current version of a [Scene] or [DynamicScene] as a [DynamicScene].
+    fn get_snapshot(world: &World, handle_id: HandleId) -> Option<DynamicScene> {
+        let scene = world
+            .get_resource::<Assets<Scene>>()
+            .and_then(|scenes| scenes.get(handle_id));
+        if let Some(scene) = scene {
+            let type_registry = world.get_resource::<TypeRegistryArc>().unwrap();
+            return Some(DynamicScene::from_scene(scene, type_registry));
+        }
+        world
+            .get_resource::<Assets<DynamicScene>>()
+            .and_then(|scenes| scenes.get(handle_id))
+            .cloned()
+    }
+
+    fn take_snapshot(&mut self, world: &World, handle_id: HandleId) {
+        if self.update_mode != SceneUpdateMode::Patch
+            || self.scene_snapshots.contains_key(&handle_id)
+        {
+            return;
+        }
+        if let Some(snapshot) = Self::get_snapshot(world, handle_id) {
+            self.scene_snapshots.insert(handle_id, snapshot);
+        }
+    }
+
+    /// Applies the changes to a modified scene to its instances, see [SceneUpdateMode::Patch].
+    pub fn patch_spawned_scenes(
+        &mut self,
+        world: &mut World,
+        handle_id: HandleId,
+    ) -> Result<(), SceneSpawnError> {
+        let instance_ids = self
+            .spawned_scenes
+            .get(&Handle::weak(handle_id))
+            .or_else(|| self.spawned_dynamic_scenes.get(&Handle::weak(handle_id)))
+            .cloned()
+            .unwrap_or_default();
+        let scene = match Self::get_snapshot(world, handle_id) {
+            Some(scene) => scene,
+            None => return Ok(()),
+        };
+        // instances spawned before patching was enabled are patched from an empty scene
+        let previous = self.scene_snapshots.remove(&handle_id).unwrap_or_default();
+        let is_dynamic = self
+            .spawned_dynamic_scenes
+            .contains_key(&Handle::weak(handle_id));
+        for instance_id in instance_ids {
+            // prefab instances are always spawned again, with their overrides
+            if self.is_prefab_instance(instance_id) {
+                self.respawn_prefab_instance(world, instance_id)?;
+            } else if let Some(instance_info) = self.spawned_instances.get_mut(&instance_id) {
+                scene.patch_world(&previous, world, &mut instance_info.entity_map)?;
+                if is_dynamic {
+                    self.spawn_prefabs(world, instance_id, &Handle::weak(handle_id))?;
+                }
+            }
+        }
+        self.scene_snapshots.insert(handle_id, scene);
+        Ok(())
+    }
+
     pub fn despawn_queued_scenes(&mut self, world: &mut World) -> Result<(), SceneSpawnError> {
         let scenes_to_despawn = std::mem::take(&mut self.scenes_to_despawn);
 
@@ -234,24 +747,38 @@ impl SceneSpawner {
         Ok(())
     }
 
+    pub fn despawn_queued_instances(&mut self, world: &mut World) {
+        let instances_to_despawn = std::mem::take(&mut self.instances_to_despawn);
+
+        for (instance_id, recursive) in instances_to_despawn {
+            self.despawn_instance_internal(world, instance_id, recursive);
+        }
+    }
+
     pub fn spawn_queued_scenes(&mut self, world: &mut World) -> Result<(), SceneSpawnError> {
         let scenes_to_spawn = std::mem::take(&mut self.dynamic_scenes_to_spawn);
 
-        for scene_handle in scenes_to_spawn {
-            match self.spawn_dynamic_sync(world, &scene_handle) {
-                Ok(_) => {}
-                Err(SceneSpawnError::NonExistentScene { .. }) => {
-                    self.dynamic_scenes_to_spawn.push(scene_handle)
-                }
+        for (scene_handle, instance_id) in scenes_to_spawn {
+            match self.spawn_dynamic_sync_internal(world, &scene_handle, instance_id) {
+                Ok(_) => self.instances_ready.push(instance_id),
+                Err(SceneSpawnError::NonExistentScene { .. }) => self
+                    .dynamic_scenes_to_spawn
+                    .push((scene_handle, instance_id)),
                 Err(err) => return Err(err),
             }
         }
 
+        let prefabs_to_spawn = std::mem::take(&mut self.prefabs_to_spawn);
+
+        for (instance_id, prefab) in prefabs_to_spawn {
+            self.spawn_prefab_instance(world, instance_id, prefab)?;
+        }
+
         let scenes_to_spawn = std::mem::take(&mut self.scenes_to_spawn);
 
         for (scene_handle, instance_id) in scenes_to_spawn {
             match self.spawn_sync_internal(world, scene_handle, instance_id) {
-                Ok(_) => {}
+                Ok(_) => self.instances_ready.push(instance_id),
                 Err(SceneSpawnError::NonExistentRealScene { handle }) => {
                     self.scenes_to_spawn.push((handle, instance_id))
                 }
@@ -314,13 +841,474 @@ pub fn scene_spawner_system(world: &mut World) {
             }
         }
 
+        let real_scene_asset_events = world.get_resource::<Events<AssetEvent<Scene>>>().unwrap();
+        let mut patched_scenes = Vec::new();
+        let mut respawned_scenes = Vec::new();
+        for event in scene_spawner
+            .real_scene_asset_event_reader
+            .iter(&real_scene_asset_events)
+        {
+            if let AssetEvent::Modified { handle } = event {
+                if !scene_spawner.spawned_scenes.contains_key(handle) {
+                    continue;
+                }
+                if scene_spawner.update_mode == SceneUpdateMode::Patch {
+                    patched_scenes.push(handle.id);
+                } else {
+                    respawned_scenes.push(handle.id);
+                }
+            }
+        }
+        if scene_spawner.update_mode == SceneUpdateMode::Patch {
+            patched_scenes.extend(updated_spawned_scenes.drain(..).map(|handle| handle.id));
+        }
+
         scene_spawner.despawn_queued_scenes(world).unwrap();
+        scene_spawner.despawn_queued_instances(world);
         scene_spawner
             .spawn_queued_scenes(world)
             .unwrap_or_else(|err| panic!("{}", err));
         scene_spawner
             .update_spawned_scenes(world, &updated_spawned_scenes)
             .unwrap();
+        for handle_id in patched_scenes {
+            scene_spawner
+                .patch_spawned_scenes(world, handle_id)
+                .unwrap();
+        }
+        for handle_id in respawned_scenes {
+            scene_spawner
+                .respawn_scene_prefab_instances(world, handle_id)
+                .unwrap();
+        }
         scene_spawner.set_scene_instance_parent_sync(world);
+
+        let instances_ready = std::mem::take(&mut scene_spawner.instances_ready);
+        if let Some(mut events) = world.get_resource_mut::<Events<SceneInstanceReady>>() {
+            for instance_id in instances_ready {
+                events.send(SceneInstanceReady { instance_id });
+            }
+        }
     });
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ScenePlugin;
+    use bevy_app::{App, AppBuilder};
+    use bevy_asset::{AssetPlugin, HandleUntyped};
+    use bevy_reflect::Reflect;
+    use bevy_tasks::{IoTaskPool, TaskPool};
+    use bevy_transform::prelude::Children;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Component)]
+    struct Door;
+
+    #[derive(Reflect, Default)]
+    #[reflect(Component)]
+    struct Lamp {
+        on: bool,
+    }
+
+    fn app() -> AppBuilder {
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin)
+            .add_plugin(ScenePlugin)
+            .register_type::<Door>()
+            .register_type::<Lamp>()
+            .register_type::<bool>()
+            .register_type::<Parent>()
+            .register_type::<Children>();
+        app
+    }
+
+    fn entity(
+        entity: u32,
+        components: Vec<Box<dyn Reflect>>,
+        prefab: Option<HandleUntyped>,
+    ) -> crate::Entity {
+        crate::Entity {
+            entity,
+            components,
+            prefab: prefab.map(|handle| ScenePrefab {
+                path: "prefab.scn".into(),
+                handle,
+                overrides: Vec::new(),
+            }),
+        }
+    }
+
+    fn lamps(world: &mut World) -> Vec<(Entity, bool)> {
+        world
+            .query::<(Entity, &Lamp)>()
+            .iter(world)
+            .map(|(entity, lamp)| (entity, lamp.on))
+            .collect()
+    }
+
+    #[test]
+    fn despawn_instance() {
+        let mut app = app();
+
+        let mut scene_world = World::default();
+        scene_world.spawn().insert(Door);
+        scene_world.spawn().insert(Door);
+        let scene = app
+            .world_mut()
+            .get_resource_mut::<Assets<Scene>>()
+            .unwrap()
+            .add(Scene::new(scene_world));
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        let first = spawner.spawn(scene.clone());
+        let second = spawner.spawn(scene.clone());
+        let cancelled = spawner.spawn(scene);
+        spawner.despawn_instance(cancelled);
+        app.app.update();
+
+        let world = app.world_mut();
+        let events = world.get_resource::<Events<SceneInstanceReady>>().unwrap();
+        let ready: Vec<InstanceId> = ManualEventReader::default()
+            .iter(&events)
+            .map(|event| event.instance_id)
+            .collect();
+        assert_eq!(ready, vec![first, second]);
+        assert_eq!(world.query::<&Door>().iter(world).count(), 4);
+
+        // attach a child that isn't part of the scene to each instance
+        let mut children = Vec::new();
+        for instance_id in [first, second].iter() {
+            let entity = world
+                .get_resource::<SceneSpawner>()
+                .unwrap()
+                .iter_instance_entities(*instance_id)
+                .unwrap()
+                .next()
+                .unwrap();
+            let child = world.spawn().insert(Parent(entity)).id();
+            world.entity_mut(entity).insert(Children::with(&[child]));
+            children.push(child);
+        }
+
+        world.resource_scope(|world, mut spawner: Mut<SceneSpawner>| {
+            spawner.despawn_instance_sync(world, first);
+            spawner.despawn_instance_recursive_sync(world, second);
+            assert!(!spawner.instance_is_ready(first));
+            assert!(spawner.spawned_scenes.is_empty());
+        });
+        assert_eq!(world.query::<&Door>().iter(world).count(), 0);
+        assert!(world.get_entity(children[0]).is_some());
+        assert!(world.get_entity(children[1]).is_none());
+    }
+
+    #[test]
+    fn despawn_unknown_instance() {
+        let mut app = app();
+        let mut scene_world = World::default();
+        scene_world.spawn().insert(Door);
+        let scene = app
+            .world_mut()
+            .get_resource_mut::<Assets<Scene>>()
+            .unwrap()
+            .add(Scene::new(scene_world));
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        let kept = spawner.spawn(scene.clone());
+        let despawned = spawner.spawn(scene);
+        app.app.update();
+
+        let world = &mut app.app.world;
+        world.resource_scope(|world, mut spawner: Mut<SceneSpawner>| {
+            spawner.despawn_instance_sync(world, despawned);
+            spawner.despawn_instance_sync(world, despawned);
+            spawner.despawn_instance_recursive_sync(world, InstanceId::new());
+            spawner.despawn_instance(despawned);
+            spawner.despawn_instance(InstanceId::new());
+        });
+        app.app.update();
+
+        let world = &mut app.app.world;
+        let spawner = world.get_resource::<SceneSpawner>().unwrap();
+        assert!(spawner.instance_is_ready(kept));
+        assert!(!spawner.instance_is_ready(despawned));
+        assert_eq!(spawner.iter_instance_entities(kept).unwrap().count(), 1);
+        assert_eq!(world.query::<&Door>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn ready_once_the_scene_is_loaded() {
+        let mut app = app();
+        // a handle to a scene that isn't loaded yet
+        let scene: Handle<Scene> = Handle::weak(HandleId::random::<Scene>());
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        let instance_id = spawner.spawn(scene.clone());
+        let mut reader = ManualEventReader::<SceneInstanceReady>::default();
+        let mut ready = |app: &mut AppBuilder| -> Vec<InstanceId> {
+            let events = app
+                .world()
+                .get_resource::<Events<SceneInstanceReady>>()
+                .unwrap();
+            reader.iter(events).map(|event| event.instance_id).collect()
+        };
+        app.app.update();
+        app.app.update();
+        assert!(ready(&mut app).is_empty());
+        assert!(!app
+            .world()
+            .get_resource::<SceneSpawner>()
+            .unwrap()
+            .instance_is_ready(instance_id));
+
+        let mut scene_world = World::default();
+        scene_world.spawn().insert(Door);
+        app.world_mut()
+            .get_resource_mut::<Assets<Scene>>()
+            .unwrap()
+            .set_untracked(&scene, Scene::new(scene_world));
+        app.app.update();
+        assert_eq!(ready(&mut app), vec![instance_id]);
+        app.app.update();
+        assert!(ready(&mut app).is_empty());
+        let world = app.world_mut();
+        assert_eq!(world.query::<&Door>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn patch_instances() {
+        let mut app = app();
+        let lamps = |on: bool| DynamicScene {
+            entities: (0..2)
+                .map(|entity| crate::Entity {
+                    entity,
+                    components: vec![Box::new(Lamp { on })],
+                    prefab: None,
+                })
+                .collect(),
+            resources: Vec::new(),
+        };
+        let scene = app
+            .world_mut()
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap()
+            .add(lamps(false));
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        spawner.set_update_mode(SceneUpdateMode::Patch);
+        let instance_id = spawner.spawn_dynamic(scene.clone());
+        app.app.update();
+
+        // despawning an entity of the instance doesn't stop the rest from being patched
+        let world = app.world_mut();
+        let entity_map = &world
+            .get_resource::<SceneSpawner>()
+            .unwrap()
+            .spawned_instances[&instance_id]
+            .entity_map;
+        let entities: Vec<Entity> = (0..2)
+            .map(|entity| entity_map.get(Entity::new(entity)).unwrap())
+            .collect();
+        world.despawn(entities[0]);
+        *world
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap()
+            .get_mut(&scene)
+            .unwrap() = lamps(true);
+        // the modification is seen by the spawner the frame after its event is sent
+        app.app.update();
+        app.app.update();
+        let world = &mut app.app.world;
+        assert!(world.get_entity(entities[0]).is_none());
+        assert!(world.get::<Lamp>(entities[1]).unwrap().on);
+        assert_eq!(world.query::<&Lamp>().iter(world).count(), 1);
+
+        // components that can't be written fail the patch instead of being dropped
+        #[derive(Reflect, Default)]
+        struct Unregistered;
+        let mut modified = lamps(false);
+        modified.entities[1].components.push(Box::new(Unregistered));
+        world
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap()
+            .set_untracked(&scene, modified);
+        world.resource_scope(|world, mut spawner: Mut<SceneSpawner>| {
+            let result = spawner.patch_spawned_scenes(world, scene.id);
+            assert!(matches!(
+                result,
+                Err(SceneSpawnError::UnregisteredType { .. })
+            ));
+        });
+    }
+
+    #[test]
+    fn respawn_level_with_prefab() {
+        let mut app = app();
+        let mut scenes = app
+            .world_mut()
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap();
+        let lamp = scenes.add(DynamicScene {
+            entities: vec![entity(0, vec![Box::new(Lamp { on: false })], None)],
+            resources: Vec::new(),
+        });
+        let level = |on: bool| DynamicScene {
+            entities: vec![
+                entity(0, vec![Box::new(Door)], Some(lamp.clone_untyped())),
+                entity(
+                    1,
+                    vec![Box::new(Lamp { on }), Box::new(Parent(Entity::new(0)))],
+                    None,
+                ),
+            ],
+            resources: Vec::new(),
+        };
+        let level_handle = scenes.add(level(false));
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        let instance_id = spawner.spawn_dynamic(level_handle.clone());
+        app.app.update();
+
+        let world = app.world_mut();
+        let spawner = world.get_resource::<SceneSpawner>().unwrap();
+        let instance = &spawner.spawned_instances[&instance_id];
+        let door = instance.entity_map.get(Entity::new(0)).unwrap();
+        let level_lamp = instance.entity_map.get(Entity::new(1)).unwrap();
+        let prefab_lamp = spawner.spawned_instances[&instance.prefab_instances[0]]
+            .entity_map
+            .get(Entity::new(0))
+            .unwrap();
+        assert_eq!(world.get::<Parent>(prefab_lamp), Some(&Parent(door)));
+        // the children the hierarchy systems would add, which aren't part of the level
+        world
+            .entity_mut(door)
+            .insert(Children::with(&[level_lamp, prefab_lamp]));
+
+        *world
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap()
+            .get_mut(&level_handle)
+            .unwrap() = level(true);
+        app.app.update();
+        app.app.update();
+
+        let world = app.world_mut();
+        assert_eq!(
+            world.get::<Lamp>(level_lamp).map(|lamp| lamp.on),
+            Some(true)
+        );
+        assert_eq!(world.get::<Parent>(level_lamp), Some(&Parent(door)));
+        // the prefab didn't change, so its instance is kept
+        assert!(world.get::<Lamp>(prefab_lamp).is_some());
+        assert_eq!(lamps(world).len(), 2);
+    }
+
+    #[test]
+    fn nested_prefabs() {
+        let mut app = app();
+        let mut scenes = app
+            .world_mut()
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap();
+        let lamp_scene = |on: bool| DynamicScene {
+            entities: vec![entity(0, vec![Box::new(Lamp { on })], None)],
+            resources: Vec::new(),
+        };
+        let lamp = scenes.add(lamp_scene(false));
+        let door = scenes.add(DynamicScene {
+            entities: vec![entity(0, vec![Box::new(Door)], Some(lamp.clone_untyped()))],
+            resources: Vec::new(),
+        });
+        let level = scenes.add(DynamicScene {
+            entities: vec![entity(0, Vec::new(), Some(door.clone_untyped()))],
+            resources: Vec::new(),
+        });
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        let instance_id = spawner.spawn_dynamic(level);
+        app.app.update();
+
+        let world = app.world_mut();
+        let level_entity = world
+            .get_resource::<SceneSpawner>()
+            .unwrap()
+            .spawned_instances[&instance_id]
+            .entity_map
+            .get(Entity::new(0))
+            .unwrap();
+        let door_entity = world
+            .query_filtered::<Entity, bevy_ecs::query::With<Door>>()
+            .iter(world)
+            .next()
+            .unwrap();
+        assert_eq!(
+            world.get::<Parent>(door_entity),
+            Some(&Parent(level_entity))
+        );
+        let lamp_entity = match lamps(world)[..] {
+            [(lamp_entity, false)] => lamp_entity,
+            ref lamps => panic!("expected one lamp that is off, got {:?}", lamps),
+        };
+        assert_eq!(world.get::<Parent>(lamp_entity), Some(&Parent(door_entity)));
+
+        // changing the innermost prefab spawns its instance again
+        *world
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap()
+            .get_mut(&lamp)
+            .unwrap() = lamp_scene(true);
+        app.app.update();
+        app.app.update();
+        let world = app.world_mut();
+        let lamp_entity = match lamps(world)[..] {
+            [(lamp_entity, true)] => lamp_entity,
+            ref lamps => panic!("expected one lamp that is on, got {:?}", lamps),
+        };
+        assert_eq!(world.get::<Parent>(lamp_entity), Some(&Parent(door_entity)));
+        assert_eq!(world.query::<&Door>().iter(world).count(), 1);
+    }
+
+    #[test]
+    fn scene_prefab() {
+        let mut app = app();
+        let lamp_scene = |on: bool| {
+            let mut world = World::default();
+            world.spawn().insert(Lamp { on });
+            Scene::new(world)
+        };
+        let lamp = app
+            .world_mut()
+            .get_resource_mut::<Assets<Scene>>()
+            .unwrap()
+            .add(lamp_scene(false));
+        let level = app
+            .world_mut()
+            .get_resource_mut::<Assets<DynamicScene>>()
+            .unwrap()
+            .add(DynamicScene {
+                entities: vec![entity(0, vec![Box::new(Door)], Some(lamp.clone_untyped()))],
+                resources: Vec::new(),
+            });
+        let mut spawner = app.world_mut().get_resource_mut::<SceneSpawner>().unwrap();
+        spawner.spawn_dynamic(level);
+        app.app.update();
+
+        let world = app.world_mut();
+        let door = world
+            .query_filtered::<Entity, bevy_ecs::query::With<Door>>()
+            .iter(world)
+            .next()
+            .unwrap();
+        let lamp_entity = match lamps(world)[..] {
+            [(lamp_entity, false)] => lamp_entity,
+            ref lamps => panic!("expected one lamp that is off, got {:?}", lamps),
+        };
+        assert_eq!(world.get::<Parent>(lamp_entity), Some(&Parent(door)));
+
+        *world
+            .get_resource_mut::<Assets<Scene>>()
+            .unwrap()
+            .get_mut(&lamp)
+            .unwrap() = lamp_scene(true);
+        app.app.update();
+        app.app.update();
+        let world = app.world_mut();
+        assert!(matches!(lamps(world)[..], [(_, true)]));
+    }
+}
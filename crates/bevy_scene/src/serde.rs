@@ -1,5 +1,6 @@
-use crate::{DynamicScene, Entity};
+use crate::{DynamicScene, Entity, EntityPatch, ScenePatch, ScenePrefab};
 use anyhow::Result;
+use bevy_asset::{AssetPath, HandleUntyped};
 use bevy_reflect::{
     serde::{ReflectDeserializer, ReflectSerializer},
     Reflect, TypeRegistry, TypeRegistryArc,
@@ -21,13 +22,45 @@ impl<'a> SceneSerializer<'a> {
     }
 }
 
+/// Scenes without resources are serialized as a list of entities, and scenes with resources as a
+/// struct of both.
 impl<'a> Serialize for SceneSerializer<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_seq(Some(self.scene.entities.len()))?;
-        for entity in self.scene.entities.iter() {
+        let entities = EntitiesSerializer {
+            entities: &self.scene.entities,
+            registry: self.registry,
+        };
+        if self.scene.resources.is_empty() {
+            return entities.serialize(serializer);
+        }
+        let mut state = serializer.serialize_struct(SCENE_STRUCT, 2)?;
+        state.serialize_field(
+            SCENE_FIELD_RESOURCES,
+            &ComponentsSerializer {
+                components: &self.scene.resources,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(SCENE_FIELD_ENTITIES, &entities)?;
+        state.end()
+    }
+}
+
+pub struct EntitiesSerializer<'a> {
+    pub entities: &'a [Entity],
+    pub registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for EntitiesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.entities.len()))?;
+        for entity in self.entities.iter() {
             state.serialize_element(&EntitySerializer {
                 entity,
                 registry: self.registry,
@@ -47,7 +80,8 @@ impl<'a> Serialize for EntitySerializer<'a> {
     where
         S: serde::Serializer,
     {
-        let mut state = serializer.serialize_struct(ENTITY_STRUCT, 2)?;
+        let len = if self.entity.prefab.is_some() { 3 } else { 2 };
+        let mut state = serializer.serialize_struct(ENTITY_STRUCT, len)?;
         state.serialize_field(ENTITY_FIELD_ENTITY, &self.entity.entity)?;
         state.serialize_field(
             ENTITY_FIELD_COMPONENTS,
@@ -56,6 +90,122 @@ impl<'a> Serialize for EntitySerializer<'a> {
                 registry: self.registry,
             },
         )?;
+        if let Some(prefab) = &self.entity.prefab {
+            state.serialize_field(
+                ENTITY_FIELD_PREFAB,
+                &PrefabSerializer {
+                    prefab,
+                    registry: self.registry,
+                },
+            )?;
+        }
+        state.end()
+    }
+}
+
+pub struct PrefabSerializer<'a> {
+    pub prefab: &'a ScenePrefab,
+    pub registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for PrefabSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(PREFAB_STRUCT, 2)?;
+        state.serialize_field(PREFAB_FIELD_SCENE, &self.prefab.path.to_string())?;
+        state.serialize_field(
+            PREFAB_FIELD_OVERRIDES,
+            &EntitiesSerializer {
+                entities: &self.prefab.overrides,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+pub struct ScenePatchSerializer<'a> {
+    pub patch: &'a ScenePatch,
+    pub registry: &'a TypeRegistryArc,
+}
+
+impl<'a> ScenePatchSerializer<'a> {
+    pub fn new(patch: &'a ScenePatch, registry: &'a TypeRegistryArc) -> Self {
+        ScenePatchSerializer { patch, registry }
+    }
+}
+
+impl<'a> Serialize for ScenePatchSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(PATCH_STRUCT, 3)?;
+        state.serialize_field(
+            PATCH_FIELD_ADDED,
+            &EntitiesSerializer {
+                entities: &self.patch.added,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(PATCH_FIELD_REMOVED, &self.patch.removed)?;
+        state.serialize_field(
+            PATCH_FIELD_CHANGED,
+            &EntityPatchesSerializer {
+                patches: &self.patch.changed,
+                registry: self.registry,
+            },
+        )?;
+        state.end()
+    }
+}
+
+pub struct EntityPatchesSerializer<'a> {
+    pub patches: &'a [EntityPatch],
+    pub registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for EntityPatchesSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_seq(Some(self.patches.len()))?;
+        for patch in self.patches.iter() {
+            state.serialize_element(&EntityPatchSerializer {
+                patch,
+                registry: self.registry,
+            })?;
+        }
+        state.end()
+    }
+}
+
+pub struct EntityPatchSerializer<'a> {
+    pub patch: &'a EntityPatch,
+    pub registry: &'a TypeRegistryArc,
+}
+
+impl<'a> Serialize for EntityPatchSerializer<'a> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let mut state = serializer.serialize_struct(ENTITY_PATCH_STRUCT, 3)?;
+        state.serialize_field(ENTITY_FIELD_ENTITY, &self.patch.entity)?;
+        state.serialize_field(
+            ENTITY_FIELD_COMPONENTS,
+            &ComponentsSerializer {
+                components: &self.patch.components,
+                registry: self.registry,
+            },
+        )?;
+        state.serialize_field(
+            ENTITY_PATCH_FIELD_REMOVED_COMPONENTS,
+            &self.patch.removed_components,
+        )?;
         state.end()
     }
 }
@@ -91,11 +241,95 @@ impl<'a, 'de> DeserializeSeed<'de> for SceneDeserializer<'a> {
     fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
     where
         D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_any(SceneVisitor {
+            type_registry: self.type_registry,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum SceneField {
+    Resources,
+    Entities,
+}
+
+pub const SCENE_STRUCT: &str = "Scene";
+pub const SCENE_FIELD_RESOURCES: &str = "resources";
+pub const SCENE_FIELD_ENTITIES: &str = "entities";
+
+struct SceneVisitor<'a> {
+    pub type_registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for SceneVisitor<'a> {
+    type Value = DynamicScene;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("scene")
+    }
+
+    fn visit_seq<A>(self, seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
     {
         Ok(DynamicScene {
-            entities: deserializer.deserialize_seq(SceneEntitySeqVisitor {
+            entities: SceneEntitySeqVisitor {
                 type_registry: self.type_registry,
-            })?,
+            }
+            .visit_seq(seq)?,
+            resources: Vec::new(),
+        })
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut resources = None;
+        let mut entities = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                SceneField::Resources => {
+                    if resources.is_some() {
+                        return Err(Error::duplicate_field(SCENE_FIELD_RESOURCES));
+                    }
+                    resources = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.type_registry,
+                    })?);
+                }
+                SceneField::Entities => {
+                    if entities.is_some() {
+                        return Err(Error::duplicate_field(SCENE_FIELD_ENTITIES));
+                    }
+                    entities = Some(map.next_value_seed(SceneEntitiesDeserializer {
+                        type_registry: self.type_registry,
+                    })?);
+                }
+            }
+        }
+
+        Ok(DynamicScene {
+            entities: entities.ok_or_else(|| Error::missing_field(SCENE_FIELD_ENTITIES))?,
+            resources: resources.unwrap_or_default(),
+        })
+    }
+}
+
+pub struct SceneEntitiesDeserializer<'a> {
+    pub type_registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for SceneEntitiesDeserializer<'a> {
+    type Value = Vec<Entity>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(SceneEntitySeqVisitor {
+            type_registry: self.type_registry,
         })
     }
 }
@@ -139,7 +373,11 @@ impl<'a, 'de> DeserializeSeed<'de> for SceneEntityDeserializer<'a> {
     {
         deserializer.deserialize_struct(
             ENTITY_STRUCT,
-            &[ENTITY_FIELD_ENTITY, ENTITY_FIELD_COMPONENTS],
+            &[
+                ENTITY_FIELD_ENTITY,
+                ENTITY_FIELD_COMPONENTS,
+                ENTITY_FIELD_PREFAB,
+            ],
             SceneEntityVisitor {
                 registry: self.type_registry,
             },
@@ -152,11 +390,13 @@ impl<'a, 'de> DeserializeSeed<'de> for SceneEntityDeserializer<'a> {
 enum EntityField {
     Entity,
     Components,
+    Prefab,
 }
 
 pub const ENTITY_STRUCT: &str = "Entity";
 pub const ENTITY_FIELD_ENTITY: &str = "entity";
 pub const ENTITY_FIELD_COMPONENTS: &str = "components";
+pub const ENTITY_FIELD_PREFAB: &str = "prefab";
 
 struct SceneEntityVisitor<'a> {
     pub registry: &'a TypeRegistry,
@@ -175,6 +415,7 @@ impl<'a, 'de> Visitor<'de> for SceneEntityVisitor<'a> {
     {
         let mut id = None;
         let mut components = None;
+        let mut prefab = None;
         while let Some(key) = map.next_key()? {
             match key {
                 EntityField::Entity => {
@@ -192,6 +433,14 @@ impl<'a, 'de> Visitor<'de> for SceneEntityVisitor<'a> {
                         registry: self.registry,
                     })?);
                 }
+                EntityField::Prefab => {
+                    if prefab.is_some() {
+                        return Err(Error::duplicate_field(ENTITY_FIELD_PREFAB));
+                    }
+                    prefab = Some(map.next_value_seed(PrefabDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
             }
         }
 
@@ -205,6 +454,307 @@ impl<'a, 'de> Visitor<'de> for SceneEntityVisitor<'a> {
         Ok(Entity {
             entity: *entity,
             components,
+            prefab,
+        })
+    }
+}
+
+/// Deserializes a [ScenePrefab]. Its handle is weak, until the [SceneLoader](crate::SceneLoader)
+/// loads the prefab.
+pub struct PrefabDeserializer<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for PrefabDeserializer<'a> {
+    type Value = ScenePrefab;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            PREFAB_STRUCT,
+            &[PREFAB_FIELD_SCENE, PREFAB_FIELD_OVERRIDES],
+            PrefabVisitor {
+                registry: self.registry,
+            },
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum PrefabField {
+    Scene,
+    Overrides,
+}
+
+pub const PREFAB_STRUCT: &str = "Prefab";
+pub const PREFAB_FIELD_SCENE: &str = "scene";
+pub const PREFAB_FIELD_OVERRIDES: &str = "overrides";
+
+struct PrefabVisitor<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for PrefabVisitor<'a> {
+    type Value = ScenePrefab;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("prefab")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut scene = None;
+        let mut overrides = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                PrefabField::Scene => {
+                    if scene.is_some() {
+                        return Err(Error::duplicate_field(PREFAB_FIELD_SCENE));
+                    }
+                    scene = Some(map.next_value::<String>()?);
+                }
+                PrefabField::Overrides => {
+                    if overrides.is_some() {
+                        return Err(Error::duplicate_field(PREFAB_FIELD_OVERRIDES));
+                    }
+                    overrides = Some(map.next_value_seed(SceneEntitiesDeserializer {
+                        type_registry: self.registry,
+                    })?);
+                }
+            }
+        }
+
+        let scene = scene.ok_or_else(|| Error::missing_field(PREFAB_FIELD_SCENE))?;
+        let path = AssetPath::from(scene.as_str()).to_owned();
+        Ok(ScenePrefab {
+            handle: HandleUntyped::weak(path.get_id().into()),
+            path,
+            overrides: overrides.unwrap_or_default(),
+        })
+    }
+}
+
+pub struct ScenePatchDeserializer<'a> {
+    pub type_registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for ScenePatchDeserializer<'a> {
+    type Value = ScenePatch;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            PATCH_STRUCT,
+            &[PATCH_FIELD_ADDED, PATCH_FIELD_REMOVED, PATCH_FIELD_CHANGED],
+            ScenePatchVisitor {
+                registry: self.type_registry,
+            },
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "lowercase")]
+enum PatchField {
+    Added,
+    Removed,
+    Changed,
+}
+
+pub const PATCH_STRUCT: &str = "ScenePatch";
+pub const PATCH_FIELD_ADDED: &str = "added";
+pub const PATCH_FIELD_REMOVED: &str = "removed";
+pub const PATCH_FIELD_CHANGED: &str = "changed";
+
+struct ScenePatchVisitor<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for ScenePatchVisitor<'a> {
+    type Value = ScenePatch;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("scene patch")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut added = None;
+        let mut removed = None;
+        let mut changed = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                PatchField::Added => {
+                    if added.is_some() {
+                        return Err(Error::duplicate_field(PATCH_FIELD_ADDED));
+                    }
+                    added = Some(map.next_value_seed(SceneEntitiesDeserializer {
+                        type_registry: self.registry,
+                    })?);
+                }
+                PatchField::Removed => {
+                    if removed.is_some() {
+                        return Err(Error::duplicate_field(PATCH_FIELD_REMOVED));
+                    }
+                    removed = Some(map.next_value::<Vec<u32>>()?);
+                }
+                PatchField::Changed => {
+                    if changed.is_some() {
+                        return Err(Error::duplicate_field(PATCH_FIELD_CHANGED));
+                    }
+                    changed = Some(map.next_value_seed(EntityPatchesDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+            }
+        }
+
+        Ok(ScenePatch {
+            added: added.unwrap_or_default(),
+            removed: removed.unwrap_or_default(),
+            changed: changed.unwrap_or_default(),
+        })
+    }
+}
+
+pub struct EntityPatchesDeserializer<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for EntityPatchesDeserializer<'a> {
+    type Value = Vec<EntityPatch>;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_seq(EntityPatchSeqVisitor {
+            registry: self.registry,
+        })
+    }
+}
+
+struct EntityPatchSeqVisitor<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for EntityPatchSeqVisitor<'a> {
+    type Value = Vec<EntityPatch>;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("list of entity patches")
+    }
+
+    fn visit_seq<A>(self, mut seq: A) -> Result<Self::Value, A::Error>
+    where
+        A: SeqAccess<'de>,
+    {
+        let mut patches = Vec::new();
+        while let Some(patch) = seq.next_element_seed(EntityPatchDeserializer {
+            registry: self.registry,
+        })? {
+            patches.push(patch);
+        }
+
+        Ok(patches)
+    }
+}
+
+pub struct EntityPatchDeserializer<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> DeserializeSeed<'de> for EntityPatchDeserializer<'a> {
+    type Value = EntityPatch;
+
+    fn deserialize<D>(self, deserializer: D) -> Result<Self::Value, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        deserializer.deserialize_struct(
+            ENTITY_PATCH_STRUCT,
+            &[
+                ENTITY_FIELD_ENTITY,
+                ENTITY_FIELD_COMPONENTS,
+                ENTITY_PATCH_FIELD_REMOVED_COMPONENTS,
+            ],
+            EntityPatchVisitor {
+                registry: self.registry,
+            },
+        )
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(field_identifier, rename_all = "snake_case")]
+enum EntityPatchField {
+    Entity,
+    Components,
+    RemovedComponents,
+}
+
+pub const ENTITY_PATCH_STRUCT: &str = "EntityPatch";
+pub const ENTITY_PATCH_FIELD_REMOVED_COMPONENTS: &str = "removed_components";
+
+struct EntityPatchVisitor<'a> {
+    pub registry: &'a TypeRegistry,
+}
+
+impl<'a, 'de> Visitor<'de> for EntityPatchVisitor<'a> {
+    type Value = EntityPatch;
+
+    fn expecting(&self, formatter: &mut std::fmt::Formatter) -> std::fmt::Result {
+        formatter.write_str("entity patch")
+    }
+
+    fn visit_map<A>(self, mut map: A) -> Result<Self::Value, A::Error>
+    where
+        A: MapAccess<'de>,
+    {
+        let mut id = None;
+        let mut components = None;
+        let mut removed_components = None;
+        while let Some(key) = map.next_key()? {
+            match key {
+                EntityPatchField::Entity => {
+                    if id.is_some() {
+                        return Err(Error::duplicate_field(ENTITY_FIELD_ENTITY));
+                    }
+                    id = Some(map.next_value::<u32>()?);
+                }
+                EntityPatchField::Components => {
+                    if components.is_some() {
+                        return Err(Error::duplicate_field(ENTITY_FIELD_COMPONENTS));
+                    }
+                    components = Some(map.next_value_seed(ComponentVecDeserializer {
+                        registry: self.registry,
+                    })?);
+                }
+                EntityPatchField::RemovedComponents => {
+                    if removed_components.is_some() {
+                        return Err(Error::duplicate_field(
+                            ENTITY_PATCH_FIELD_REMOVED_COMPONENTS,
+                        ));
+                    }
+                    removed_components = Some(map.next_value::<Vec<String>>()?);
+                }
+            }
+        }
+
+        let entity = id.ok_or_else(|| Error::missing_field(ENTITY_FIELD_ENTITY))?;
+        Ok(EntityPatch {
+            entity,
+            components: components.unwrap_or_default(),
+            removed_components: removed_components.unwrap_or_default(),
         })
     }
 }
@@ -249,3 +799,69 @@ impl<'a, 'de> Visitor<'de> for ComponentSeqVisitor<'a> {
         Ok(dynamic_properties)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialize_ron;
+
+    #[derive(Reflect, Default)]
+    struct Door {
+        width: u32,
+        open: bool,
+    }
+
+    #[test]
+    fn prefab_round_trip() {
+        let registry = TypeRegistryArc::default();
+        {
+            let mut registry = registry.write();
+            registry.register::<Door>();
+            registry.register::<u32>();
+            registry.register::<bool>();
+        }
+        let path = AssetPath::from("props/door.scn");
+        let prefab = ScenePrefab {
+            handle: HandleUntyped::weak(path.get_id().into()),
+            path,
+            overrides: Vec::new(),
+        }
+        .with_override(
+            0,
+            &Door {
+                width: 100,
+                open: false,
+            },
+            &Door {
+                width: 100,
+                open: true,
+            },
+        )
+        .with_override(
+            2,
+            &Door::default(),
+            &Door {
+                width: 80,
+                open: true,
+            },
+        );
+
+        let ron = serialize_ron(PrefabSerializer {
+            prefab: &prefab,
+            registry: &registry,
+        })
+        .unwrap();
+        let mut deserializer = ron::de::Deserializer::from_str(&ron).unwrap();
+        let deserialized = PrefabDeserializer {
+            registry: &registry.read(),
+        }
+        .deserialize(&mut deserializer)
+        .unwrap();
+
+        assert_eq!(deserialized.path.to_string(), prefab.path.to_string());
+        assert!(deserialized.reflect_eq(&prefab));
+        assert_eq!(deserialized.overrides.len(), 2);
+        // only the overridden field is stored
+        assert!(!ron.contains("width: 100"));
+    }
+}
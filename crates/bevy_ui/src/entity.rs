@@ -7,11 +7,15 @@ use crate::{
 use bevy_asset::Handle;
 use bevy_ecs::bundle::Bundle;
 use bevy_render::{
-    camera::{Camera, DepthCalculation, OrthographicProjection, VisibleEntities, WindowOrigin},
+    camera::{
+        Camera, DepthCalculation, NoFrustumCulling, OrthographicProjection, VisibleEntities,
+        WindowOrigin,
+    },
     draw::Draw,
     mesh::Mesh,
     pipeline::{RenderPipeline, RenderPipelines},
     prelude::Visible,
+    primitives::Frustum,
 };
 use bevy_sprite::{ColorMaterial, QUAD_HANDLE};
 use bevy_text::Text;
@@ -22,6 +26,7 @@ pub struct NodeBundle {
     pub node: Node,
     pub style: Style,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub draw: Draw,
     pub visible: Visible,
@@ -34,6 +39,7 @@ impl Default for NodeBundle {
     fn default() -> Self {
         NodeBundle {
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 UI_PIPELINE_HANDLE.typed(),
             )]),
@@ -58,6 +64,7 @@ pub struct ImageBundle {
     pub image: Image,
     pub calculated_size: CalculatedSize,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub draw: Draw,
     pub visible: Visible,
@@ -70,6 +77,7 @@ impl Default for ImageBundle {
     fn default() -> Self {
         ImageBundle {
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 UI_PIPELINE_HANDLE.typed(),
             )]),
@@ -131,6 +139,7 @@ pub struct ButtonBundle {
     pub interaction: Interaction,
     pub focus_policy: FocusPolicy,
     pub mesh: Handle<Mesh>, // TODO: maybe abstract this out
+    pub no_frustum_culling: NoFrustumCulling,
     pub material: Handle<ColorMaterial>,
     pub draw: Draw,
     pub visible: Visible,
@@ -144,6 +153,7 @@ impl Default for ButtonBundle {
         ButtonBundle {
             button: Button,
             mesh: QUAD_HANDLE.typed(),
+            no_frustum_culling: NoFrustumCulling,
             render_pipelines: RenderPipelines::from_pipelines(vec![RenderPipeline::new(
                 UI_PIPELINE_HANDLE.typed(),
             )]),
@@ -168,6 +178,7 @@ pub struct UiCameraBundle {
     pub camera: Camera,
     pub orthographic_projection: OrthographicProjection,
     pub visible_entities: VisibleEntities,
+    pub frustum: Frustum,
     pub transform: Transform,
     pub global_transform: GlobalTransform,
 }
@@ -189,6 +200,7 @@ impl Default for UiCameraBundle {
                 ..Default::default()
             },
             visible_entities: Default::default(),
+            frustum: Default::default(),
             transform: Transform::from_xyz(0.0, 0.0, far - 0.1),
             global_transform: Default::default(),
         }
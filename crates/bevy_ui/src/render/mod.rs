@@ -149,6 +149,19 @@ pub(crate) fn add_ui_graph(world: &mut World) {
     graph
         .add_node_edge(base::node::MAIN_PASS, node::UI_PASS)
         .unwrap();
+    // and after the main pass has been tonemapped into the swap chain, and the 2d camera drawn
+    // over it
+    for pass in [base::node::TONEMAPPING, base::node::MAIN_PASS_2D].iter() {
+        if graph.get_node_state(*pass).is_ok() {
+            graph.add_node_edge(*pass, node::UI_PASS).unwrap();
+        }
+    }
+    // and before the swap chain is read back
+    if graph.get_node_state(base::node::READBACK).is_ok() {
+        graph
+            .add_node_edge(node::UI_PASS, base::node::READBACK)
+            .unwrap();
+    }
 
     // setup ui camera
     graph.add_system_node(node::CAMERA_UI, CameraNode::new(camera::CAMERA_UI));
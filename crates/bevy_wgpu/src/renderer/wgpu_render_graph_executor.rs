@@ -65,9 +65,8 @@ impl WgpuRenderGraphExecutor {
                                     panic!("Node inputs not set.")
                                 };
 
-                                let output_resource =
-                                    outputs.get(*output_index).expect("Output should be set.");
-                                input_slot.resource = Some(output_resource);
+                                // an output that isn't set leaves the input unset
+                                input_slot.resource = outputs.get(*output_index);
                             } else {
                                 panic!("No edge connected to input.")
                             }
@@ -1,4 +1,4 @@
-use crate::{wgpu_type_converter::WgpuInto, WgpuBindGroupInfo, WgpuResources};
+use crate::{wgpu_type_converter::WgpuInto, PendingBufferRead, WgpuBindGroupInfo, WgpuResources};
 
 use crate::wgpu_type_converter::OwnedWgpuVertexBufferLayout;
 use bevy_asset::{Assets, Handle, HandleUntyped};
@@ -7,13 +7,13 @@ use bevy_render::{
         BindGroupDescriptor, BindGroupDescriptorId, BindingShaderStage, PipelineDescriptor,
     },
     renderer::{
-        BindGroup, BufferId, BufferInfo, BufferMapMode, RenderResourceBinding,
+        BindGroup, BufferId, BufferInfo, BufferMapMode, BufferReadCallback, RenderResourceBinding,
         RenderResourceContext, RenderResourceId, SamplerId, TextureId,
     },
     shader::{glsl_to_spirv, Shader, ShaderError, ShaderSource},
     texture::{Extent3d, SamplerDescriptor, TextureDescriptor},
 };
-use bevy_utils::tracing::trace;
+use bevy_utils::tracing::{error, trace};
 use bevy_window::{Window, WindowId};
 use futures_lite::future;
 use std::{borrow::Cow, num::NonZeroU64, ops::Range, sync::Arc};
@@ -660,6 +660,39 @@ impl RenderResourceContext for WgpuRenderResourceContext {
         buffer.unmap();
     }
 
+    fn read_buffer_async(&self, id: BufferId, range: Range<u64>, read: BufferReadCallback) {
+        let buffer = {
+            let buffers = self.resources.buffers.read();
+            buffers.get(&id).unwrap().clone()
+        };
+        let mut buffer_read = PendingBufferRead(Box::pin(async move {
+            let buffer_slice = buffer.slice(range);
+            if buffer_slice.map_async(wgpu::MapMode::Read).await.is_err() {
+                error!("Failed to map buffer {:?} to host.", id);
+                return;
+            }
+            read(&buffer_slice.get_mapped_range());
+            buffer.unmap();
+        }));
+        // the first poll requests the mapping, which is finished by a later device poll
+        if future::block_on(future::poll_once(&mut buffer_read.0)).is_none() {
+            self.resources.buffer_reads.lock().push(buffer_read);
+        }
+    }
+
+    fn poll_buffer_reads(&self) {
+        self.device.poll(wgpu::Maintain::Poll);
+        // the reads are polled without holding the lock, so their callbacks can start new reads
+        let buffer_reads = std::mem::take(&mut *self.resources.buffer_reads.lock());
+        let mut pending_reads = Vec::new();
+        for mut buffer_read in buffer_reads {
+            if future::block_on(future::poll_once(&mut buffer_read.0)).is_none() {
+                pending_reads.push(buffer_read);
+            }
+        }
+        self.resources.buffer_reads.lock().extend(pending_reads);
+    }
+
     fn get_aligned_texture_size(&self, size: usize) -> usize {
         (size + COPY_BYTES_PER_ROW_ALIGNMENT - 1) & !(COPY_BYTES_PER_ROW_ALIGNMENT - 1)
     }
@@ -687,3 +720,97 @@ impl RenderResourceContext for WgpuRenderResourceContext {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::renderer::WgpuRenderContext;
+    use bevy_app::App;
+    use bevy_asset::{AddAsset, AssetPlugin};
+    use bevy_render::{
+        pipeline::{PipelineCompiler, PipelineDescriptor},
+        readback::{ReadbackNode, ReadbackSource, Readbacks},
+        render_graph::{Node, ResourceSlots},
+        renderer::BufferUsage,
+        texture::{Texture, TextureFormat},
+    };
+    use bevy_tasks::{IoTaskPool, TaskPool};
+
+    #[test]
+    fn readback_node_reads_buffers_back_without_blocking() {
+        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
+        let adapter =
+            match future::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                power_preference: wgpu::PowerPreference::default(),
+                compatible_surface: None,
+            })) {
+                Some(adapter) => adapter,
+                // there is nothing to copy on without a gpu
+                None => return,
+            };
+        let (device, queue) =
+            future::block_on(adapter.request_device(&wgpu::DeviceDescriptor::default(), None))
+                .unwrap();
+        let device = Arc::new(device);
+        let render_resource_context = WgpuRenderResourceContext::new(device.clone());
+
+        let data = (0..16).collect::<Vec<u8>>();
+        let buffer = render_resource_context.create_buffer_with_data(
+            BufferInfo {
+                size: data.len(),
+                buffer_usage: BufferUsage::COPY_SRC,
+                ..Default::default()
+            },
+            &data,
+        );
+
+        let mut app = App::build();
+        app.insert_resource(IoTaskPool(TaskPool::new()))
+            .add_plugin(AssetPlugin)
+            .add_asset::<Shader>()
+            .add_asset::<PipelineDescriptor>()
+            .add_asset::<Texture>()
+            .init_resource::<PipelineCompiler>()
+            .init_resource::<Readbacks>()
+            .insert_resource::<Box<dyn RenderResourceContext>>(Box::new(
+                render_resource_context.clone(),
+            ));
+        let handle = {
+            let world = app.world_mut().cell();
+            let textures = world.get_resource::<Assets<Texture>>().unwrap();
+            let mut readbacks = world.get_resource_mut::<Readbacks>().unwrap();
+            readbacks.read(
+                &textures,
+                ReadbackSource::Buffer {
+                    buffer,
+                    size: Extent3d::new(2, 2, 1),
+                    format: TextureFormat::Rgba8Unorm,
+                },
+            )
+        };
+
+        // the copy is made in the first frame, mapped in the second and added in the third
+        let mut node = ReadbackNode::new(WindowId::primary());
+        let input = ResourceSlots::from(node.input());
+        for _ in 0..3 {
+            let world = app.world_mut();
+            node.prepare(world);
+            let mut render_context =
+                WgpuRenderContext::new(device.clone(), render_resource_context.clone());
+            node.update(
+                world,
+                &mut render_context,
+                &input,
+                &mut ResourceSlots::default(),
+            );
+            queue.submit(render_context.finish());
+            device.poll(wgpu::Maintain::Wait);
+            render_resource_context.poll_buffer_reads();
+        }
+
+        let textures = app.world().get_resource::<Assets<Texture>>().unwrap();
+        let texture = textures.get(&handle).unwrap();
+        assert_eq!(texture.data, data);
+        assert_eq!(texture.format, TextureFormat::Rgba8Unorm);
+    }
+}
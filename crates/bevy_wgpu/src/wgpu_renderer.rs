@@ -125,5 +125,6 @@ impl WgpuRenderer {
             .unwrap();
         render_resource_context.drop_all_swap_chain_textures();
         render_resource_context.remove_stale_bind_groups();
+        render_resource_context.poll_buffer_reads();
     }
 }
@@ -8,8 +8,8 @@ use bevy_render::{
 use bevy_utils::HashMap;
 use bevy_window::WindowId;
 use crossbeam_channel::{Receiver, Sender, TryRecvError};
-use parking_lot::{RwLock, RwLockReadGuard};
-use std::sync::Arc;
+use parking_lot::{Mutex, RwLock, RwLockReadGuard};
+use std::{fmt::Debug, future::Future, pin::Pin, sync::Arc};
 
 #[derive(Debug, Default)]
 pub struct WgpuBindGroupInfo {
@@ -79,6 +79,16 @@ pub struct WgpuResourceRefs<'a> {
     pub used_bind_group_sender: &'a Sender<BindGroupId>,
 }
 
+/// A buffer read that waits for its buffer to be mapped. Polling it to completion calls the read
+/// callback and unmaps the buffer.
+pub struct PendingBufferRead(pub Pin<Box<dyn Future<Output = ()> + Send>>);
+
+impl Debug for PendingBufferRead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("PendingBufferRead")
+    }
+}
+
 #[derive(Default, Clone, Debug)]
 pub struct WgpuResources {
     pub buffer_infos: Arc<RwLock<HashMap<BufferId, BufferInfo>>>,
@@ -96,6 +106,7 @@ pub struct WgpuResources {
     pub bind_group_layouts: Arc<RwLock<HashMap<BindGroupDescriptorId, wgpu::BindGroupLayout>>>,
     pub asset_resources: Arc<RwLock<HashMap<(HandleUntyped, u64), RenderResourceId>>>,
     pub bind_group_counter: BindGroupCounter,
+    pub buffer_reads: Arc<Mutex<Vec<PendingBufferRead>>>,
 }
 
 impl WgpuResources {
@@ -0,0 +1,122 @@
+use bevy::prelude::*;
+
+/// This example shows emissive spheres that are brighter than the window can show, tonemapped and
+/// with bloom. Press space to switch tonemapping operators, up and down to change the exposure and
+/// B to toggle bloom.
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(update_settings.system())
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // ground
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 20.0 })),
+        material: materials.add(Color::rgb(0.2, 0.2, 0.25).into()),
+        ..Default::default()
+    });
+    // spheres that glow brighter and brighter
+    let sphere = meshes.add(Mesh::from(shape::Icosphere {
+        radius: 0.5,
+        subdivisions: 4,
+    }));
+    for i in 0..5 {
+        let brightness = 2.0f32.powi(i);
+        commands.spawn_bundle(PbrBundle {
+            mesh: sphere.clone(),
+            material: materials.add(StandardMaterial {
+                base_color: Color::BLACK,
+                emissive: Color::rgb_linear(brightness, brightness * 0.4, brightness * 0.1),
+                ..Default::default()
+            }),
+            transform: Transform::from_xyz(i as f32 * 1.5 - 3.0, 0.5, 0.0),
+            ..Default::default()
+        });
+    }
+    // light
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(0.0, 4.0, 4.0),
+        ..Default::default()
+    });
+    // camera
+    commands
+        .spawn_bundle(PerspectiveCameraBundle {
+            transform: Transform::from_xyz(0.0, 3.0, 7.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..Default::default()
+        })
+        .insert(BloomSettings::default());
+    // ui
+    commands.spawn_bundle(UiCameraBundle::default());
+    commands.spawn_bundle(TextBundle {
+        text: Text::with_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 30.0,
+                color: Color::WHITE,
+            },
+            Default::default(),
+        ),
+        style: Style {
+            position_type: PositionType::Absolute,
+            position: Rect {
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+}
+
+fn update_settings(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut cameras: Query<(Entity, &mut Tonemapping, Option<&BloomSettings>)>,
+    mut texts: Query<&mut Text>,
+) {
+    for (entity, mut tonemapping, bloom) in cameras.iter_mut() {
+        if keyboard_input.just_pressed(KeyCode::Space) {
+            tonemapping.operator = match tonemapping.operator {
+                TonemappingOperator::None => TonemappingOperator::Reinhard,
+                TonemappingOperator::Reinhard => TonemappingOperator::ReinhardLuminance,
+                TonemappingOperator::ReinhardLuminance => TonemappingOperator::Aces,
+                TonemappingOperator::Aces => TonemappingOperator::None,
+            };
+        }
+        if keyboard_input.just_pressed(KeyCode::Up) {
+            tonemapping.exposure += 0.5;
+        }
+        if keyboard_input.just_pressed(KeyCode::Down) {
+            tonemapping.exposure -= 0.5;
+        }
+        let mut bloom_enabled = bloom.is_some();
+        if keyboard_input.just_pressed(KeyCode::B) {
+            if bloom_enabled {
+                commands.entity(entity).remove::<BloomSettings>();
+            } else {
+                commands.entity(entity).insert(BloomSettings::default());
+            }
+            bloom_enabled = !bloom_enabled;
+        }
+
+        for mut text in texts.iter_mut() {
+            text.sections[0].value = format!(
+                "Tonemapping: {:?}\nExposure: {}\nBloom: {}",
+                tonemapping.operator,
+                tonemapping.exposure,
+                if bloom_enabled { "on" } else { "off" }
+            );
+        }
+    }
+}
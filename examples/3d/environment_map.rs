@@ -0,0 +1,57 @@
+use bevy::{prelude::*, render::camera::PerspectiveProjection};
+
+/// This example lights metallic and rough spheres with an environment map prefiltered from an
+/// equirectangular `.hdr` image. Run it with `--features hdr`.
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(orbit_camera.system())
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    commands.insert_resource(EnvironmentMap::new(
+        asset_server.load("textures/spiaggia_di_mondello_1k.hdr"),
+    ));
+
+    // vary metallic along y and roughness along x
+    let sphere = meshes.add(Mesh::from(shape::Icosphere {
+        radius: 0.45,
+        subdivisions: 32,
+    }));
+    for y in -2..=2 {
+        for x in -5..=5 {
+            commands.spawn_bundle(PbrBundle {
+                mesh: sphere.clone(),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::hex("ffd891").unwrap(),
+                    metallic: (y + 2) as f32 / 4.0,
+                    roughness: (x + 5) as f32 / 10.0,
+                    ..Default::default()
+                }),
+                transform: Transform::from_xyz(x as f32, y as f32, 0.0),
+                ..Default::default()
+            });
+        }
+    }
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 0.0, 8.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..Default::default()
+    });
+}
+
+fn orbit_camera(time: Res<Time>, mut query: Query<&mut Transform, With<PerspectiveProjection>>) {
+    for mut transform in query.iter_mut() {
+        let angle = (time.seconds_since_startup() as f32 * 0.3).sin() * 0.6;
+        *transform = Transform::from_xyz(angle.sin() * 8.0, 0.0, angle.cos() * 8.0)
+            .looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
@@ -0,0 +1,93 @@
+use bevy::{
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    prelude::*,
+    render::{camera::PerspectiveProjection, instancing::InstancedMesh},
+};
+
+/// This example renders a forest of ten thousand trees. The trees share a mesh and a material, so
+/// they are batched into a single instanced draw. The rocks are drawn with an [InstancedMesh]
+/// instead, which places the instances of a single entity explicitly.
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_plugin(FrameTimeDiagnosticsPlugin::default())
+        .add_plugin(LogDiagnosticsPlugin::default())
+        .add_startup_system(setup.system())
+        .add_system(rotate_camera.system())
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // ground
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 220.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..Default::default()
+    });
+
+    // trees, batched automatically
+    let tree = meshes.add(Mesh::from(shape::Box::new(0.5, 3.0, 0.5)));
+    let tree_material = materials.add(Color::rgb(0.1, 0.4, 0.1).into());
+    for x in -50..50 {
+        for z in -50..50 {
+            commands.spawn_bundle(PbrBundle {
+                mesh: tree.clone(),
+                material: tree_material.clone(),
+                transform: Transform::from_xyz(x as f32 * 2.0, 1.5, z as f32 * 2.0),
+                ..Default::default()
+            });
+        }
+    }
+
+    // rocks, instanced explicitly
+    let instances = (0..500)
+        .map(|i| {
+            let angle = i as f32 * 0.61;
+            let distance = 5.0 + i as f32 * 0.2;
+            Transform {
+                translation: Vec3::new(angle.cos() * distance, 0.2, angle.sin() * distance),
+                rotation: Quat::from_rotation_y(angle),
+                ..Default::default()
+            }
+        })
+        .collect();
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 0.4,
+                subdivisions: 1,
+            })),
+            material: materials.add(Color::rgb(0.5, 0.5, 0.5).into()),
+            ..Default::default()
+        })
+        .insert(InstancedMesh { instances });
+
+    // light
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(4.0, 30.0, 4.0),
+        light: Light {
+            range: 200.0,
+            intensity: 10000.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 30.0, 60.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..Default::default()
+    });
+}
+
+fn rotate_camera(time: Res<Time>, mut query: Query<&mut Transform, With<PerspectiveProjection>>) {
+    for mut transform in query.iter_mut() {
+        let rotation = Quat::from_rotation_y(time.delta_seconds() * 0.2);
+        transform.translation = rotation * transform.translation;
+        transform.look_at(Vec3::ZERO, Vec3::Y);
+    }
+}
@@ -0,0 +1,91 @@
+use bevy::{prelude::*, render::camera::PerspectiveProjection};
+use rand::Rng;
+
+/// This example lights a scene with hundreds of small point lights and a few spot lights. Each
+/// light is only shaded where its range reaches, so the frame rate stays steady as lights are
+/// added.
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(move_camera.system())
+        .run();
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // ground
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 100.0 })),
+        material: materials.add(Color::rgb(0.5, 0.5, 0.5).into()),
+        ..Default::default()
+    });
+
+    // a grid of small point lights, each marked by an unlit sphere
+    let mut rng = rand::thread_rng();
+    let sphere = meshes.add(Mesh::from(shape::Icosphere {
+        radius: 0.05,
+        subdivisions: 2,
+    }));
+    for x in -20..20 {
+        for z in -20..20 {
+            let color = Color::rgb(rng.gen(), rng.gen(), rng.gen());
+            commands
+                .spawn_bundle(LightBundle {
+                    light: Light {
+                        color,
+                        intensity: 5.0,
+                        range: 2.0,
+                        ..Default::default()
+                    },
+                    transform: Transform::from_xyz(x as f32 * 2.0 + 1.0, 0.3, z as f32 * 2.0 + 1.0),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(PbrBundle {
+                        mesh: sphere.clone(),
+                        material: materials.add(StandardMaterial {
+                            base_color: color,
+                            unlit: true,
+                            ..Default::default()
+                        }),
+                        ..Default::default()
+                    });
+                });
+        }
+    }
+
+    // spot lights pointing down at the ground
+    for x in -1..=1 {
+        commands.spawn_bundle(SpotLightBundle {
+            spot_light: SpotLight {
+                intensity: 400.0,
+                range: 15.0,
+                inner_angle: 0.3,
+                outer_angle: 0.5,
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(x as f32 * 10.0, 8.0, 0.0)
+                .looking_at(Vec3::new(x as f32 * 10.0, 0.0, 0.0), Vec3::Z),
+            ..Default::default()
+        });
+    }
+
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 15.0, 30.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..Default::default()
+    });
+}
+
+fn move_camera(time: Res<Time>, mut query: Query<&mut Transform, With<PerspectiveProjection>>) {
+    for mut transform in query.iter_mut() {
+        let angle = time.seconds_since_startup() as f32 * 0.1;
+        *transform = Transform::from_xyz(angle.sin() * 30.0, 15.0, angle.cos() * 30.0)
+            .looking_at(Vec3::ZERO, Vec3::Y);
+    }
+}
@@ -0,0 +1,92 @@
+use bevy::{
+    prelude::*,
+    render::{
+        camera::{ActiveCameras, RenderLayers, RenderTarget},
+        render_graph::{base, RenderGraph},
+        texture::{Extent3d, TextureFormat},
+    },
+};
+
+/// This example shows a camera that renders a rotating cube into a texture, which is then shown
+/// on the faces of another cube in the main pass
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(rotate.system())
+        .run();
+}
+
+const TEXTURE_CAMERA: &str = "texture_camera";
+
+/// Rotates the cubes in the texture and in the main pass
+struct Rotates;
+
+fn setup(
+    mut commands: Commands,
+    msaa: Res<Msaa>,
+    mut render_graph: ResMut<RenderGraph>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    // The pipelines of the texture camera's pass are specialized for the format of its texture
+    let texture = textures.add(Texture::new_render_target(
+        Extent3d::new(512, 512, 1),
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    base::add_texture_target_camera(
+        &mut render_graph,
+        &mut active_cameras,
+        &msaa,
+        TEXTURE_CAMERA,
+    );
+
+    // The cube in the texture is on its own render layer, so it's only seen by the texture camera
+    // and the texture camera doesn't see the cube that shows its texture
+    let texture_layer = RenderLayers::layer(1);
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+            material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+            ..Default::default()
+        })
+        .insert(Rotates)
+        .insert(texture_layer);
+    let mut texture_camera = PerspectiveCameraBundle::with_name(TEXTURE_CAMERA);
+    texture_camera.camera.target = RenderTarget::Texture(texture.clone());
+    texture_camera.transform = Transform::from_xyz(0.0, 0.0, 3.0).looking_at(Vec3::ZERO, Vec3::Y);
+    commands.spawn_bundle(texture_camera).insert(texture_layer);
+
+    // cube showing the texture
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Cube { size: 2.0 })),
+            material: materials.add(StandardMaterial {
+                base_color_texture: Some(texture),
+                unlit: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .insert(Rotates);
+    // light
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..Default::default()
+    });
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(-3.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..Default::default()
+    });
+}
+
+fn rotate(time: Res<Time>, mut query: Query<&mut Transform, With<Rotates>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate(Quat::from_rotation_y(time.delta_seconds() * 0.5));
+        transform.rotate(Quat::from_rotation_x(time.delta_seconds() * 0.3));
+    }
+}
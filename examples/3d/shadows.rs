@@ -0,0 +1,96 @@
+use bevy::prelude::*;
+
+/// This example shows shadows cast by a point light and a directional light, and how entities can
+/// opt out of casting or receiving them
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(rotate_sun.system())
+        .run();
+}
+
+struct Sun;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // ground
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 20.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..Default::default()
+    });
+    // a row of cubes
+    let cube = meshes.add(Mesh::from(shape::Cube { size: 1.0 }));
+    let material = materials.add(Color::rgb(0.8, 0.7, 0.6).into());
+    for x in -2..=2 {
+        commands.spawn_bundle(PbrBundle {
+            mesh: cube.clone(),
+            material: material.clone(),
+            transform: Transform::from_xyz(x as f32 * 2.0, 0.5, 0.0),
+            ..Default::default()
+        });
+    }
+    // this sphere doesn't cast a shadow
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 0.5,
+                subdivisions: 4,
+            })),
+            material: materials.add(Color::rgb(0.6, 0.6, 0.9).into()),
+            transform: Transform::from_xyz(-2.0, 0.5, 2.5),
+            ..Default::default()
+        })
+        .insert(NotShadowCaster);
+    // and this one isn't shadowed
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Icosphere {
+                radius: 0.5,
+                subdivisions: 4,
+            })),
+            material: materials.add(Color::rgb(0.9, 0.6, 0.6).into()),
+            transform: Transform::from_xyz(2.0, 0.5, -2.5),
+            ..Default::default()
+        })
+        .insert(NotShadowReceiver);
+    // point light
+    commands.spawn_bundle(LightBundle {
+        light: Light {
+            shadows_enabled: true,
+            ..Default::default()
+        },
+        transform: Transform::from_xyz(0.0, 3.0, 2.0),
+        ..Default::default()
+    });
+    // sun
+    commands
+        .spawn_bundle(DirectionalLightBundle {
+            directional_light: DirectionalLight {
+                illuminance: 2.0,
+                shadows_enabled: true,
+                shadow_max_distance: 40.0,
+                ..Default::default()
+            },
+            transform: Transform::from_xyz(0.0, 10.0, 0.0)
+                .looking_at(Vec3::new(2.0, 0.0, 1.0), Vec3::Y),
+            ..Default::default()
+        })
+        .insert(Sun);
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(-6.0, 6.0, 10.0).looking_at(Vec3::ZERO, Vec3::Y),
+        ..Default::default()
+    });
+}
+
+fn rotate_sun(time: Res<Time>, mut query: Query<&mut Transform, With<Sun>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotate(Quat::from_rotation_y(time.delta_seconds() * 0.2));
+    }
+}
@@ -0,0 +1,110 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, VertexAttributeValues},
+        pipeline::PrimitiveTopology,
+        skinning::{SkinnedMesh, SkinnedMeshInverseBindposes},
+    },
+};
+
+/// This example skins a mesh with two joints by hand, and bends it by rotating the upper joint.
+/// Skinned meshes from gltf files are set up the same way by their scene.
+fn main() {
+    App::build()
+        .insert_resource(Msaa { samples: 4 })
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(bend_joints.system())
+        .run();
+}
+
+/// Marks the joints that [bend_joints] rotates
+struct BendingJoint;
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut inverse_bindposes: ResMut<Assets<SkinnedMeshInverseBindposes>>,
+) {
+    // a strip of quads that is 2 units high, with a joint at its bottom and one in its middle
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+    let rows = [0.0, 0.5, 1.0, 1.5, 2.0];
+    let mut positions = Vec::new();
+    let mut joint_weights = Vec::new();
+    for y in rows.iter() {
+        for x in [-0.5, 0.5].iter() {
+            positions.push([*x, *y, 0.0]);
+            // the vertices around the middle joint follow both joints
+            let upper_weight = (*y - 0.5f32).max(0.0).min(1.0);
+            joint_weights.push([1.0 - upper_weight, upper_weight, 0.0, 0.0]);
+        }
+    }
+    let vertex_count = positions.len();
+    mesh.set_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    mesh.set_attribute(Mesh::ATTRIBUTE_NORMAL, vec![[0.0, 0.0, 1.0]; vertex_count]);
+    mesh.set_attribute(Mesh::ATTRIBUTE_UV_0, vec![[0.0, 0.0]; vertex_count]);
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_JOINT_INDEX,
+        VertexAttributeValues::Uint4(vec![[0, 1, 0, 0]; vertex_count]),
+    );
+    mesh.set_attribute(Mesh::ATTRIBUTE_JOINT_WEIGHT, joint_weights);
+    let indices = (0..rows.len() as u32 - 1)
+        .flat_map(|row| {
+            let bottom = row * 2;
+            let top = bottom + 2;
+            vec![bottom, bottom + 1, top + 1, bottom, top + 1, top]
+        })
+        .collect();
+    mesh.set_indices(Some(Indices::U32(indices)));
+
+    // the inverse bind matrices move the vertices into the space of each joint in the rest pose
+    let inverse_bindposes = inverse_bindposes.add(SkinnedMeshInverseBindposes(vec![
+        Mat4::IDENTITY,
+        Mat4::from_translation(Vec3::new(0.0, -1.0, 0.0)),
+    ]));
+
+    let lower_joint = commands
+        .spawn_bundle((Transform::identity(), GlobalTransform::identity()))
+        .id();
+    let upper_joint = commands
+        .spawn_bundle((
+            Transform::from_xyz(0.0, 1.0, 0.0),
+            GlobalTransform::identity(),
+            BendingJoint,
+        ))
+        .id();
+    commands.entity(lower_joint).push_children(&[upper_joint]);
+
+    commands
+        .spawn_bundle(PbrBundle {
+            mesh: meshes.add(mesh),
+            material: materials.add(StandardMaterial {
+                base_color: Color::rgb(0.8, 0.4, 0.2),
+                double_sided: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+        .insert(SkinnedMesh {
+            inverse_bindposes,
+            joints: vec![lower_joint, upper_joint],
+        });
+
+    // light
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(2.0, 3.0, 4.0),
+        ..Default::default()
+    });
+    // camera
+    commands.spawn_bundle(PerspectiveCameraBundle {
+        transform: Transform::from_xyz(0.0, 1.0, 5.0).looking_at(Vec3::new(0.0, 1.0, 0.0), Vec3::Y),
+        ..Default::default()
+    });
+}
+
+fn bend_joints(time: Res<Time>, mut query: Query<&mut Transform, With<BendingJoint>>) {
+    for mut transform in query.iter_mut() {
+        transform.rotation = Quat::from_rotation_z(time.seconds_since_startup().sin() as f32);
+    }
+}
@@ -0,0 +1,124 @@
+use bevy::{
+    app::{AppExit, ScheduleRunnerPlugin, ScheduleRunnerSettings},
+    asset::AssetSaver,
+    prelude::*,
+    render::{
+        camera::{ActiveCameras, RenderTarget},
+        readback::{ReadbackSource, Readbacks},
+        render_graph::{base, RenderGraph},
+        texture::{Extent3d, ImageTextureSaver, TextureFormat},
+    },
+    utils::Duration,
+    winit::WinitPlugin,
+};
+
+/// This example renders a scene without a window and saves a frame as a PNG, like a golden image
+/// test on CI would. The scene is drawn into a texture by a camera, which is then read back from
+/// the gpu.
+///
+/// CI machines usually don't have a gpu, but a software adapter like lavapipe or SwiftShader can
+/// be used with `BEVY_WGPU_BACKEND=vulkan` and their Vulkan ICD selected through
+/// `VK_ICD_FILENAMES`.
+fn main() {
+    App::build()
+        .insert_resource(ScheduleRunnerSettings::run_loop(Duration::from_secs_f64(
+            1.0 / 60.0,
+        )))
+        .add_plugins_with(DefaultPlugins, |group| group.disable::<WinitPlugin>())
+        .add_plugin(ScheduleRunnerPlugin::default())
+        .add_startup_system(setup.system())
+        .add_system(save_frame.system())
+        .run();
+}
+
+const TEXTURE_CAMERA: &str = "texture_camera";
+
+/// The number of frames to render before the frame is read back, so the scene is fully set up
+const WARMUP_FRAMES: u32 = 3;
+
+#[derive(Default)]
+struct Capture {
+    frame: u32,
+    target: Handle<Texture>,
+    readback: Option<Handle<Texture>>,
+}
+
+fn setup(
+    mut commands: Commands,
+    msaa: Res<Msaa>,
+    mut render_graph: ResMut<RenderGraph>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut textures: ResMut<Assets<Texture>>,
+) {
+    let target = textures.add(Texture::new_render_target(
+        Extent3d::new(512, 512, 1),
+        TextureFormat::Rgba8UnormSrgb,
+    ));
+    base::add_texture_target_camera(
+        &mut render_graph,
+        &mut active_cameras,
+        &msaa,
+        TEXTURE_CAMERA,
+    );
+
+    // plane
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 5.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..Default::default()
+    });
+    // cube
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+        material: materials.add(Color::rgb(0.8, 0.7, 0.6).into()),
+        transform: Transform::from_xyz(0.0, 0.5, 0.0),
+        ..Default::default()
+    });
+    // light
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..Default::default()
+    });
+    // camera
+    let mut camera = PerspectiveCameraBundle::with_name(TEXTURE_CAMERA);
+    camera.camera.target = RenderTarget::Texture(target.clone());
+    camera.transform = Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y);
+    commands.spawn_bundle(camera);
+
+    commands.insert_resource(Capture {
+        target,
+        ..Default::default()
+    });
+}
+
+/// Reads the texture back once the scene is set up, and saves it when its bytes have arrived
+fn save_frame(
+    mut capture: ResMut<Capture>,
+    mut readbacks: ResMut<Readbacks>,
+    textures: Res<Assets<Texture>>,
+    mut app_exit_events: EventWriter<AppExit>,
+) {
+    capture.frame += 1;
+    if capture.frame == WARMUP_FRAMES {
+        let source = ReadbackSource::Texture(capture.target.clone());
+        capture.readback = Some(readbacks.read(&textures, source));
+    }
+
+    let frame = match capture
+        .readback
+        .as_ref()
+        .and_then(|handle| textures.get(handle))
+    {
+        Some(frame) => frame,
+        None => return,
+    };
+    let path = "headless_rendering.png";
+    let png = ImageTextureSaver
+        .save(frame, path.as_ref())
+        .expect("The frame can be encoded as a PNG");
+    std::fs::write(path, png).unwrap();
+    info!("Saved the frame to {}", path);
+    app_exit_events.send(AppExit);
+}
@@ -0,0 +1,119 @@
+use bevy::{
+    prelude::*,
+    render::{
+        hdr::HDR_TEXTURE_FORMAT,
+        render_graph::{base, PostProcessNode, RenderGraph},
+        renderer::RenderResources,
+    },
+    window::WindowId,
+};
+
+/// This example shows how to add full screen effects that run after the main pass. The camera
+/// has a vignette and a saturation effect, each drawn by a post process node with its own shader.
+/// Press space to toggle the vignette.
+fn main() {
+    App::build()
+        .add_plugins(DefaultPlugins)
+        .add_startup_system(setup.system())
+        .add_system(animate_saturation.system())
+        .add_system(toggle_vignette.system())
+        .run();
+}
+
+/// The settings of the vignette shader. Each field is bound to the shader as a uniform named
+/// after the struct and the field, like `Vignette_color`.
+#[derive(RenderResources)]
+struct Vignette {
+    color: Color,
+    intensity: f32,
+}
+
+#[derive(RenderResources)]
+struct Saturation {
+    saturation: f32,
+}
+
+fn setup(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut render_graph: ResMut<RenderGraph>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    // Add a post process node for each effect. They run in the order they were added, each
+    // drawing over the result of the one before it, before the main pass is tonemapped.
+    let mut vignette_node = PostProcessNode::<Vignette>::new(
+        WindowId::primary(),
+        HDR_TEXTURE_FORMAT,
+        asset_server.load("shaders/vignette.frag"),
+    );
+    vignette_node.add_camera(base::camera::CAMERA_3D);
+    base::add_main_post_process_node(&mut render_graph, "vignette", vignette_node).unwrap();
+
+    let mut saturation_node = PostProcessNode::<Saturation>::new(
+        WindowId::primary(),
+        HDR_TEXTURE_FORMAT,
+        asset_server.load("shaders/saturation.frag"),
+    );
+    saturation_node.add_camera(base::camera::CAMERA_3D);
+    base::add_main_post_process_node(&mut render_graph, "saturation", saturation_node).unwrap();
+
+    // plane
+    commands.spawn_bundle(PbrBundle {
+        mesh: meshes.add(Mesh::from(shape::Plane { size: 5.0 })),
+        material: materials.add(Color::rgb(0.3, 0.5, 0.3).into()),
+        ..Default::default()
+    });
+    // cubes
+    for (x, color) in [Color::RED, Color::GREEN, Color::BLUE].iter().enumerate() {
+        commands.spawn_bundle(PbrBundle {
+            mesh: meshes.add(Mesh::from(shape::Cube { size: 1.0 })),
+            material: materials.add((*color).into()),
+            transform: Transform::from_xyz(x as f32 * 1.5 - 1.5, 0.5, 0.0),
+            ..Default::default()
+        });
+    }
+    // light
+    commands.spawn_bundle(LightBundle {
+        transform: Transform::from_xyz(4.0, 8.0, 4.0),
+        ..Default::default()
+    });
+    // camera
+    commands
+        .spawn_bundle(PerspectiveCameraBundle {
+            transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+            ..Default::default()
+        })
+        .insert(Vignette {
+            color: Color::BLACK,
+            intensity: 0.8,
+        })
+        .insert(Saturation { saturation: 1.0 });
+}
+
+fn animate_saturation(time: Res<Time>, mut query: Query<&mut Saturation>) {
+    for mut saturation in query.iter_mut() {
+        saturation.saturation = time.seconds_since_startup().sin() as f32 * 0.5 + 0.5;
+    }
+}
+
+/// Without a [Vignette] on the camera, its node passes the main pass through as it is
+fn toggle_vignette(
+    mut commands: Commands,
+    keyboard_input: Res<Input<KeyCode>>,
+    query: Query<(Entity, Option<&Vignette>), With<Saturation>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::Space) {
+        return;
+    }
+    for (entity, vignette) in query.iter() {
+        if vignette.is_some() {
+            commands.entity(entity).remove::<Vignette>();
+        } else {
+            commands.entity(entity).insert(Vignette {
+                color: Color::BLACK,
+                intensity: 0.8,
+            });
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use bevy::{
     prelude::*,
     render::{
-        camera::{ActiveCameras, Camera},
+        camera::{ActiveCameras, Camera, RenderTarget},
         pass::*,
         render_graph::{
             base::MainPass, CameraNode, PassNode, RenderGraph, WindowSwapChainNode,
@@ -200,7 +200,7 @@ fn setup_pipeline(
     commands.spawn_bundle(PerspectiveCameraBundle {
         camera: Camera {
             name: Some("Secondary".to_string()),
-            window: window_id,
+            target: RenderTarget::Window(window_id),
             ..Default::default()
         },
         transform: Transform::from_xyz(6.0, 0.0, 0.0).looking_at(Vec3::ZERO, Vec3::Y),
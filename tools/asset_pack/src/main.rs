@@ -0,0 +1,38 @@
+use bevy_asset::{PackBuilder, PackCompression};
+use std::{env, fs::File, io::BufWriter, process};
+
+const USAGE: &str = "usage: asset_pack [--compress] <assets directory> <output pack>";
+
+// Packs every file in an assets directory into a single file that can be served by
+// `bevy_asset::PackAssetIo`.
+//
+// cargo run -p asset_pack -- --compress assets assets.pack
+fn main() {
+    let mut compression = PackCompression::None;
+    let mut paths = Vec::new();
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--compress" => compression = PackCompression::Deflate,
+            "-h" | "--help" => {
+                println!("{}", USAGE);
+                return;
+            }
+            _ => paths.push(arg),
+        }
+    }
+    if paths.len() != 2 {
+        eprintln!("{}", USAGE);
+        process::exit(1);
+    }
+
+    let mut builder = PackBuilder::default();
+    if let Err(err) = builder.add_directory(&paths[0], compression) {
+        eprintln!("failed to read {}: {}", paths[0], err);
+        process::exit(1);
+    }
+    let result = File::create(&paths[1]).and_then(|file| builder.write(&mut BufWriter::new(file)));
+    if let Err(err) = result {
+        eprintln!("failed to write {}: {}", paths[1], err);
+        process::exit(1);
+    }
+}
@@ -23,6 +23,7 @@ crates=(
     bevy_pbr
     bevy_gltf
     bevy_scene
+    bevy_inspector
     bevy_sprite
     bevy_text
     bevy_ui